@@ -40,7 +40,8 @@ pub fn decode_bc1_block(data: &[u8], outbuf: &mut [u32]) {
             ((b0 + b1) / 2) as u8,
             255,
         );
-        c[3] = color(0, 0, 0, 255);
+        // the fourth color is transparent black in three color mode
+        c[3] = color(0, 0, 0, 0);
     }
     let mut d: usize = u32::from_le_bytes(data[4..8].try_into().unwrap()) as usize;
     (0..16).for_each(|i| {
@@ -48,3 +49,34 @@ pub fn decode_bc1_block(data: &[u8], outbuf: &mut [u32]) {
         d >>= 2;
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_three_color_block() {
+        // The first endpoint is smaller than the second, so this is a three color block
+        let mut block = [0x00, 0x00, 0xFF, 0xFF, 0, 0, 0, 0];
+        let mut outbuf = [0; 16];
+
+        // Every pixel uses the fourth color, which is transparent black
+        block[4..8].fill(0xFF);
+        decode_bc1_block(&block, &mut outbuf);
+        assert_eq!(outbuf, [color(0, 0, 0, 0); 16]);
+
+        // And the third color is halfway between the endpoints
+        block[4..8].fill(0xAA);
+        decode_bc1_block(&block, &mut outbuf);
+        assert_eq!(outbuf, [color(127, 127, 127, 255); 16]);
+    }
+
+    #[test]
+    fn test_four_color_block() {
+        // The fourth color is opaque when the first endpoint is larger
+        let block = [0xFF, 0xFF, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF];
+        let mut outbuf = [0; 16];
+        decode_bc1_block(&block, &mut outbuf);
+        assert_eq!(outbuf, [color(85, 85, 85, 255); 16]);
+    }
+}
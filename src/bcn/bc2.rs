@@ -0,0 +1,16 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::bc1::decode_bc1_block;
+
+#[inline]
+pub fn decode_bc2_block(data: &[u8], outbuf: &mut [u32]) {
+    decode_bc1_block(&data[8..], outbuf);
+
+    // alpha is stored explicitly as 4 bits per pixel
+    let alpha = u64::from_le_bytes(data[..8].try_into().unwrap());
+    outbuf.iter_mut().take(16).enumerate().for_each(|(i, p)| {
+        let a = ((alpha >> (i * 4)) & 0xF) as u32 * 17;
+        *p = (*p & 0x00FFFFFF) | (a << 24);
+    });
+}
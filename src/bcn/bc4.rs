@@ -0,0 +1,10 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::bc3::decode_bc3_alpha;
+
+#[inline]
+pub fn decode_bc4_block(data: &[u8], outbuf: &mut [u32]) {
+    // BC4 is the same as the alpha of BC3, but stored in red
+    decode_bc3_alpha(data, outbuf, 2);
+}
@@ -0,0 +1,257 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Decoder for signed BC6H, following the bit layouts in the D3D11 documentation.
+
+use half::f16;
+
+use super::consts::{S_BPTC_A2, S_BPTC_FACTORS, S_BPTC_P2};
+
+// Endpoint channels, which are numbered by endpoint and then by red, green and blue
+const RW: usize = 0;
+const GW: usize = 1;
+const BW: usize = 2;
+const RX: usize = 3;
+const GX: usize = 4;
+const BX: usize = 5;
+const RY: usize = 6;
+const GY: usize = 7;
+const BY: usize = 8;
+const RZ: usize = 9;
+const GZ: usize = 10;
+const BZ: usize = 11;
+
+struct Bc6hModeInfo {
+    /// Value of the mode bits.
+    value: u32,
+    /// Whether there are two regions, each with their own pair of endpoints.
+    two_regions: bool,
+    /// Whether endpoints besides the first one are stored as deltas.
+    transformed: bool,
+    /// Bits of the first endpoint, and then of the other endpoints in red, green and blue.
+    bits: [u32; 4],
+    /// Runs of endpoint bits in the order they are stored, as the channel and its bit range.
+    /// Bits are read starting from the second number, moving towards the first.
+    layout: &'static [(usize, u32, u32)],
+}
+
+#[rustfmt::skip]
+static S_BC6H_MODE_INFO: [Bc6hModeInfo; 14] = [
+    Bc6hModeInfo { value: 0b00, two_regions: true, transformed: true, bits: [10, 5, 5, 5], layout: &[
+        (GY, 4, 4), (BY, 4, 4), (BZ, 4, 4), (RW, 9, 0), (GW, 9, 0), (BW, 9, 0), (RX, 4, 0), (GZ, 4, 4),
+        (GY, 3, 0), (GX, 4, 0), (BZ, 0, 0), (GZ, 3, 0), (BX, 4, 0), (BZ, 1, 1), (BY, 3, 0), (RY, 4, 0),
+        (BZ, 2, 2), (RZ, 4, 0), (BZ, 3, 3),
+    ] },
+    Bc6hModeInfo { value: 0b01, two_regions: true, transformed: true, bits: [7, 6, 6, 6], layout: &[
+        (GY, 5, 5), (GZ, 4, 4), (GZ, 5, 5), (RW, 6, 0), (BZ, 0, 0), (BZ, 1, 1), (BY, 4, 4), (GW, 6, 0),
+        (BY, 5, 5), (BZ, 2, 2), (GY, 4, 4), (BW, 6, 0), (BZ, 3, 3), (BZ, 5, 5), (BZ, 4, 4), (RX, 5, 0),
+        (GY, 3, 0), (GX, 5, 0), (GZ, 3, 0), (BX, 5, 0), (BY, 3, 0), (RY, 5, 0), (RZ, 5, 0),
+    ] },
+    Bc6hModeInfo { value: 0b00010, two_regions: true, transformed: true, bits: [11, 5, 4, 4], layout: &[
+        (RW, 9, 0), (GW, 9, 0), (BW, 9, 0), (RX, 4, 0), (RW, 10, 10), (GY, 3, 0), (GX, 3, 0), (GW, 10, 10),
+        (BZ, 0, 0), (GZ, 3, 0), (BX, 3, 0), (BW, 10, 10), (BZ, 1, 1), (BY, 3, 0), (RY, 4, 0), (BZ, 2, 2),
+        (RZ, 4, 0), (BZ, 3, 3),
+    ] },
+    Bc6hModeInfo { value: 0b00110, two_regions: true, transformed: true, bits: [11, 4, 5, 4], layout: &[
+        (RW, 9, 0), (GW, 9, 0), (BW, 9, 0), (RX, 3, 0), (RW, 10, 10), (GZ, 4, 4), (GY, 3, 0), (GX, 4, 0),
+        (GW, 10, 10), (GZ, 3, 0), (BX, 3, 0), (BW, 10, 10), (BZ, 1, 1), (BY, 3, 0), (RY, 3, 0), (BZ, 0, 0),
+        (BZ, 2, 2), (RZ, 3, 0), (GY, 4, 4), (BZ, 3, 3),
+    ] },
+    Bc6hModeInfo { value: 0b01010, two_regions: true, transformed: true, bits: [11, 4, 4, 5], layout: &[
+        (RW, 9, 0), (GW, 9, 0), (BW, 9, 0), (RX, 3, 0), (RW, 10, 10), (BY, 4, 4), (GY, 3, 0), (GX, 3, 0),
+        (GW, 10, 10), (BZ, 0, 0), (GZ, 3, 0), (BX, 4, 0), (BW, 10, 10), (BY, 3, 0), (RY, 3, 0), (BZ, 1, 1),
+        (BZ, 2, 2), (RZ, 3, 0), (BZ, 4, 4), (BZ, 3, 3),
+    ] },
+    Bc6hModeInfo { value: 0b01110, two_regions: true, transformed: true, bits: [9, 5, 5, 5], layout: &[
+        (RW, 8, 0), (BY, 4, 4), (GW, 8, 0), (GY, 4, 4), (BW, 8, 0), (BZ, 4, 4), (RX, 4, 0), (GZ, 4, 4),
+        (GY, 3, 0), (GX, 4, 0), (BZ, 0, 0), (GZ, 3, 0), (BX, 4, 0), (BZ, 1, 1), (BY, 3, 0), (RY, 4, 0),
+        (BZ, 2, 2), (RZ, 4, 0), (BZ, 3, 3),
+    ] },
+    Bc6hModeInfo { value: 0b10010, two_regions: true, transformed: true, bits: [8, 6, 5, 5], layout: &[
+        (RW, 7, 0), (GZ, 4, 4), (BY, 4, 4), (GW, 7, 0), (BZ, 2, 2), (GY, 4, 4), (BW, 7, 0), (BZ, 3, 3),
+        (BZ, 4, 4), (RX, 5, 0), (GY, 3, 0), (GX, 4, 0), (BZ, 0, 0), (GZ, 3, 0), (BX, 4, 0), (BZ, 1, 1),
+        (BY, 3, 0), (RY, 5, 0), (RZ, 5, 0),
+    ] },
+    Bc6hModeInfo { value: 0b10110, two_regions: true, transformed: true, bits: [8, 5, 6, 5], layout: &[
+        (RW, 7, 0), (BZ, 0, 0), (BY, 4, 4), (GW, 7, 0), (GY, 5, 5), (GY, 4, 4), (BW, 7, 0), (GZ, 5, 5),
+        (BZ, 4, 4), (RX, 4, 0), (GZ, 4, 4), (GY, 3, 0), (GX, 5, 0), (GZ, 3, 0), (BX, 4, 0), (BZ, 1, 1),
+        (BY, 3, 0), (RY, 4, 0), (BZ, 2, 2), (RZ, 4, 0), (BZ, 3, 3),
+    ] },
+    Bc6hModeInfo { value: 0b11010, two_regions: true, transformed: true, bits: [8, 5, 5, 6], layout: &[
+        (RW, 7, 0), (BZ, 1, 1), (BY, 4, 4), (GW, 7, 0), (BY, 5, 5), (GY, 4, 4), (BW, 7, 0), (BZ, 5, 5),
+        (BZ, 4, 4), (RX, 4, 0), (GZ, 4, 4), (GY, 3, 0), (GX, 4, 0), (BZ, 0, 0), (GZ, 3, 0), (BX, 5, 0),
+        (BY, 3, 0), (RY, 4, 0), (BZ, 2, 2), (RZ, 4, 0), (BZ, 3, 3),
+    ] },
+    Bc6hModeInfo { value: 0b11110, two_regions: true, transformed: false, bits: [6, 6, 6, 6], layout: &[
+        (RW, 5, 0), (GZ, 4, 4), (BZ, 0, 0), (BZ, 1, 1), (BY, 4, 4), (GW, 5, 0), (GY, 5, 5), (BY, 5, 5),
+        (BZ, 2, 2), (GY, 4, 4), (BW, 5, 0), (GZ, 5, 5), (BZ, 3, 3), (BZ, 5, 5), (BZ, 4, 4), (RX, 5, 0),
+        (GY, 3, 0), (GX, 5, 0), (GZ, 3, 0), (BX, 5, 0), (BY, 3, 0), (RY, 5, 0), (RZ, 5, 0),
+    ] },
+    Bc6hModeInfo { value: 0b00011, two_regions: false, transformed: false, bits: [10, 10, 10, 10], layout: &[
+        (RW, 9, 0), (GW, 9, 0), (BW, 9, 0), (RX, 9, 0), (GX, 9, 0), (BX, 9, 0),
+    ] },
+    Bc6hModeInfo { value: 0b00111, two_regions: false, transformed: true, bits: [11, 9, 9, 9], layout: &[
+        (RW, 9, 0), (GW, 9, 0), (BW, 9, 0), (RX, 8, 0), (RW, 10, 10), (GX, 8, 0), (GW, 10, 10), (BX, 8, 0),
+        (BW, 10, 10),
+    ] },
+    Bc6hModeInfo { value: 0b01011, two_regions: false, transformed: true, bits: [12, 8, 8, 8], layout: &[
+        (RW, 9, 0), (GW, 9, 0), (BW, 9, 0), (RX, 7, 0), (RW, 10, 11), (GX, 7, 0), (GW, 10, 11), (BX, 7, 0),
+        (BW, 10, 11),
+    ] },
+    Bc6hModeInfo { value: 0b01111, two_regions: false, transformed: true, bits: [16, 4, 4, 4], layout: &[
+        (RW, 9, 0), (GW, 9, 0), (BW, 9, 0), (RX, 3, 0), (RW, 10, 15), (GX, 3, 0), (GW, 10, 15), (BX, 3, 0),
+        (BW, 10, 15),
+    ] },
+];
+
+struct BitReader {
+    bits: u128,
+    position: u32,
+}
+
+impl BitReader {
+    fn read(&mut self, count: u32) -> i32 {
+        let value = (self.bits >> self.position) & ((1 << count) - 1);
+        self.position += count;
+        value as i32
+    }
+}
+
+fn sign_extend(value: i32, bits: u32) -> i32 {
+    let shift = 32 - bits;
+    (value << shift) >> shift
+}
+
+fn unquantize(value: i32, bits: u32) -> i32 {
+    if bits >= 16 {
+        return value;
+    }
+
+    let magnitude = value.abs();
+    let unquantized = if magnitude == 0 {
+        0
+    } else if magnitude >= (1 << (bits - 1)) - 1 {
+        0x7FFF
+    } else {
+        ((magnitude << 15) + 0x4000) >> (bits - 1)
+    };
+
+    if value < 0 { -unquantized } else { unquantized }
+}
+
+/// Scales the interpolated value back to the range of a half float.
+fn finish_unquantize(value: i32) -> f32 {
+    let magnitude = ((value.abs() * 31) >> 5) as u16;
+    let sign = if value < 0 { 0x8000 } else { 0 };
+    f16::from_bits(sign | magnitude).to_f32()
+}
+
+pub fn decode_bc6h_block(data: &[u8], outbuf: &mut [[f32; 4]]) {
+    let mut bit = BitReader {
+        bits: u128::from_le_bytes(data[..16].try_into().unwrap()),
+        position: 0,
+    };
+
+    let mut mode = bit.read(2) as u32;
+    if mode > 1 {
+        mode |= (bit.read(3) as u32) << 2;
+    }
+
+    // reserved modes decode to black
+    let Some(mi) = S_BC6H_MODE_INFO.iter().find(|mi| mi.value == mode) else {
+        outbuf[..16].fill([0.0, 0.0, 0.0, 1.0]);
+        return;
+    };
+
+    let mut endpoints = [0i32; 12];
+    for (channel, first, last) in mi.layout {
+        if first >= last {
+            for bit_index in *last..=*first {
+                endpoints[*channel] |= bit.read(1) << bit_index;
+            }
+        } else {
+            for bit_index in (*first..=*last).rev() {
+                endpoints[*channel] |= bit.read(1) << bit_index;
+            }
+        }
+    }
+
+    let partition = if mi.two_regions {
+        bit.read(5) as usize
+    } else {
+        0
+    };
+    let num_endpoints = if mi.two_regions { 4 } else { 2 };
+
+    for endpoint in &mut endpoints[..3] {
+        *endpoint = sign_extend(*endpoint, mi.bits[0]);
+    }
+    for i in 3..num_endpoints * 3 {
+        endpoints[i] = sign_extend(endpoints[i], mi.bits[1 + i % 3]);
+        if mi.transformed {
+            let mask = (1 << mi.bits[0]) - 1;
+            endpoints[i] = sign_extend((endpoints[i % 3] + endpoints[i]) & mask, mi.bits[0]);
+        }
+    }
+
+    let endpoints = endpoints.map(|x| unquantize(x, mi.bits[0]));
+
+    let index_bits: u32 = if mi.two_regions { 3 } else { 4 };
+    let factors = &S_BPTC_FACTORS[index_bits as usize - 2];
+
+    (0..16).for_each(|i| {
+        let subset = if mi.two_regions {
+            (S_BPTC_P2[partition] >> i) & 1
+        } else {
+            0
+        };
+        let anchor = i == 0 || (mi.two_regions && i == S_BPTC_A2[partition]);
+
+        let weight = i32::from(factors[bit.read(index_bits - anchor as u32) as usize]);
+        let [r, g, b] = std::array::from_fn(|c| {
+            let e0 = endpoints[subset * 6 + c];
+            let e1 = endpoints[subset * 6 + 3 + c];
+            finish_unquantize((e0 * (64 - weight) + e1 * weight + 32) >> 6)
+        });
+        outbuf[i] = [r, g, b, 1.0];
+    });
+}
+
+/// Decodes a BC6H texture into RGBA floats.
+pub fn decode_bc6h(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    image: &mut [f32],
+) -> Result<(), &'static str> {
+    let num_blocks_x = width.div_ceil(4);
+    let num_blocks_y = height.div_ceil(4);
+
+    if data.len() < num_blocks_x * num_blocks_y * 16 {
+        return Err("Not enough data to decode image!");
+    }
+
+    if image.len() < width * height * 4 {
+        return Err("Image buffer is too small!");
+    }
+
+    let mut buffer = [[0.0f32; 4]; 16];
+    for (i, block) in data
+        .chunks_exact(16)
+        .take(num_blocks_x * num_blocks_y)
+        .enumerate()
+    {
+        decode_bc6h_block(block, &mut buffer);
+
+        let (bx, by) = (i % num_blocks_x, i / num_blocks_x);
+        for (j, pixel) in buffer.iter().enumerate() {
+            let (x, y) = (bx * 4 + j % 4, by * 4 + j / 4);
+            if x < width && y < height {
+                let offset = (y * width + x) * 4;
+                image[offset..offset + 4].copy_from_slice(pixel);
+            }
+        }
+    }
+
+    Ok(())
+}
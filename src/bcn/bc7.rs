@@ -6,19 +6,19 @@ use super::color::color;
 use super::consts::{S_BPTC_A2, S_BPTC_A3, S_BPTC_FACTORS, S_BPTC_P2, S_BPTC_P3};
 use core::mem::swap;
 
-struct Bc7ModeInfo {
-    num_subsets: usize,
-    partition_bits: usize,
-    rotation_bits: usize,
-    index_selection_bits: usize,
-    color_bits: usize,
-    alpha_bits: usize,
-    endpoint_pbits: usize,
-    shared_pbits: usize,
-    index_bits: [usize; 2],
+pub(super) struct Bc7ModeInfo {
+    pub(super) num_subsets: usize,
+    pub(super) partition_bits: usize,
+    pub(super) rotation_bits: usize,
+    pub(super) index_selection_bits: usize,
+    pub(super) color_bits: usize,
+    pub(super) alpha_bits: usize,
+    pub(super) endpoint_pbits: usize,
+    pub(super) shared_pbits: usize,
+    pub(super) index_bits: [usize; 2],
 }
 
-static S_BP7_MODE_INFO: [Bc7ModeInfo; 8] = [
+pub(super) static S_BP7_MODE_INFO: [Bc7ModeInfo; 8] = [
     //  +---------------------------- num subsets
     //  |  +------------------------- partition bits
     //  |  |  +---------------------- rotation bits
@@ -127,7 +127,7 @@ static S_BP7_MODE_INFO: [Bc7ModeInfo; 8] = [
 ];
 
 #[inline]
-fn expand_quantized(v: u8, bits: usize) -> u8 {
+pub(super) fn expand_quantized(v: u8, bits: usize) -> u8 {
     let s = ((v as u16) << (8 - bits as u16)) as u8;
     s | s.overflowing_shr(bits as u32).0
 }
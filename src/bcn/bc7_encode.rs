@@ -0,0 +1,368 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! BC7 block encoder. Only the modes without channel rotation (0-3, 6 and 7) are used.
+
+use super::bc7::{Bc7ModeInfo, S_BP7_MODE_INFO, expand_quantized};
+use super::consts::{S_BPTC_A2, S_BPTC_A3, S_BPTC_FACTORS, S_BPTC_P2, S_BPTC_P3};
+use super::encode::{
+    Block, CompressionQuality, bounding_box, encode_blocks, least_squares, line_error,
+    principal_endpoints, refinement_passes,
+};
+
+pub fn encode_bc7(
+    rgba: &[u8],
+    width: usize,
+    height: usize,
+    quality: CompressionQuality,
+) -> Result<Vec<u8>, &'static str> {
+    encode_blocks(rgba, width, height, 16, quality, encode_bc7_block)
+}
+
+fn encode_bc7_block(block: &Block, quality: CompressionQuality, output: &mut [u8]) {
+    let opaque = block.iter().all(|pixel| pixel[3] == 255);
+
+    // Mode 6 handles both color and alpha, so it's always a reasonable fallback
+    let mut best = encode_mode(block, 6, 0, quality);
+
+    let (modes, partition_count): (&[usize], usize) = match quality {
+        CompressionQuality::Fast => (&[], 0),
+        CompressionQuality::Normal if opaque => (&[1], 4),
+        CompressionQuality::Normal => (&[7], 4),
+        CompressionQuality::High if opaque => (&[0, 1, 2, 3, 7], 16),
+        CompressionQuality::High => (&[7], 16),
+    };
+
+    for mode in modes {
+        let mi = &S_BP7_MODE_INFO[*mode];
+
+        // Only try the partitions that look the most promising
+        let mut partitions: Vec<(f32, usize)> = (0..1 << mi.partition_bits)
+            .map(|partition| (partition_error(block, mi, partition), partition))
+            .collect();
+        partitions.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        for (_, partition) in partitions.into_iter().take(partition_count) {
+            if best.error == 0 {
+                break;
+            }
+
+            let candidate = encode_mode(block, *mode, partition, quality);
+            if candidate.error < best.error {
+                best = candidate;
+            }
+        }
+    }
+
+    output.copy_from_slice(&best.data);
+}
+
+fn subset_of(mi: &Bc7ModeInfo, partition: usize, pixel: usize) -> usize {
+    match mi.num_subsets {
+        2 => (S_BPTC_P2[partition] >> pixel) & 1,
+        3 => (S_BPTC_P3[partition] >> (2 * pixel)) & 3,
+        _ => 0,
+    }
+}
+
+/// The pixel in `subset` whose index has an implied zero top bit.
+fn anchor_of(mi: &Bc7ModeInfo, partition: usize, subset: usize) -> usize {
+    match (mi.num_subsets, subset) {
+        (_, 0) => 0,
+        (2, _) => S_BPTC_A2[partition],
+        (_, subset) => S_BPTC_A3[subset - 1][partition],
+    }
+}
+
+fn partition_error(block: &Block, mi: &Bc7ModeInfo, partition: usize) -> f32 {
+    (0..mi.num_subsets)
+        .map(|subset| {
+            let pixels: Vec<[f32; 4]> = (0..16)
+                .filter(|i| subset_of(mi, partition, *i) == subset)
+                .map(|i| block[i].map(f32::from))
+                .collect();
+            line_error(&pixels)
+        })
+        .sum()
+}
+
+fn pbit_count(mi: &Bc7ModeInfo) -> usize {
+    if mi.endpoint_pbits != 0 {
+        mi.endpoint_pbits
+    } else {
+        mi.shared_pbits
+    }
+}
+
+/// Bits stored for `channel`, where zero means it's always 255.
+fn channel_bits(mi: &Bc7ModeInfo, channel: usize) -> usize {
+    if channel == 3 {
+        mi.alpha_bits
+    } else {
+        mi.color_bits
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Endpoints {
+    /// Values as they are stored in the block, without the p-bit.
+    stored: [[u8; 4]; 2],
+    pbits: [u8; 2],
+}
+
+/// Expands a stored channel back to 8 bits, the same way the decoder does.
+fn unquantize_channel(mi: &Bc7ModeInfo, channel: usize, stored: u8, pbit: u8) -> u8 {
+    let bits = channel_bits(mi, channel);
+    if bits == 0 {
+        return 255;
+    }
+
+    let pbits = pbit_count(mi);
+    expand_quantized((stored << pbits) | pbit, bits + pbits)
+}
+
+fn quantize_endpoint(mi: &Bc7ModeInfo, endpoint: &[f32; 4], pbit: u8) -> ([u8; 4], f32) {
+    let pbits = pbit_count(mi);
+
+    let mut stored = [0; 4];
+    let mut error = 0.0;
+    for c in 0..4 {
+        let bits = channel_bits(mi, c);
+        if bits == 0 {
+            continue;
+        }
+
+        let scale = ((1 << (bits + pbits)) - 1) as f32;
+        let target = endpoint[c].clamp(0.0, 255.0) / 255.0 * scale;
+        stored[c] = ((target - f32::from(pbit)) / (1 << pbits) as f32)
+            .round()
+            .clamp(0.0, ((1 << bits) - 1) as f32) as u8;
+        error += (f32::from(unquantize_channel(mi, c, stored[c], pbit)) - endpoint[c]).powi(2);
+    }
+
+    (stored, error)
+}
+
+fn quantize(mi: &Bc7ModeInfo, endpoints: &[[f32; 4]; 2]) -> Endpoints {
+    let pbit_options: &[u8] = if pbit_count(mi) == 0 { &[0] } else { &[0, 1] };
+
+    if mi.shared_pbits != 0 {
+        // Both endpoints have to agree on the p-bit
+        pbit_options
+            .iter()
+            .map(|pbit| {
+                let (start, start_error) = quantize_endpoint(mi, &endpoints[0], *pbit);
+                let (end, end_error) = quantize_endpoint(mi, &endpoints[1], *pbit);
+                (
+                    start_error + end_error,
+                    Endpoints {
+                        stored: [start, end],
+                        pbits: [*pbit; 2],
+                    },
+                )
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .unwrap()
+            .1
+    } else {
+        let mut result = Endpoints::default();
+        for (i, endpoint) in endpoints.iter().enumerate() {
+            let (pbit, (stored, _)) = pbit_options
+                .iter()
+                .map(|pbit| (*pbit, quantize_endpoint(mi, endpoint, *pbit)))
+                .min_by(|a, b| a.1.1.total_cmp(&b.1.1))
+                .unwrap();
+            result.stored[i] = stored;
+            result.pbits[i] = pbit;
+        }
+        result
+    }
+}
+
+struct SubsetFit {
+    error: u32,
+    endpoints: Endpoints,
+    /// Indices for each pixel in the subset, in order.
+    indices: Vec<u8>,
+}
+
+/// Picks the closest palette entry for each pixel in `members`.
+fn fit_subset(
+    mi: &Bc7ModeInfo,
+    block: &Block,
+    members: &[usize],
+    endpoints: Endpoints,
+) -> SubsetFit {
+    let decoded: [[u16; 4]; 2] = std::array::from_fn(|i| {
+        std::array::from_fn(|c| {
+            u16::from(unquantize_channel(
+                mi,
+                c,
+                endpoints.stored[i][c],
+                endpoints.pbits[i],
+            ))
+        })
+    });
+
+    let factors = &S_BPTC_FACTORS[mi.index_bits[0] - 2];
+    let palette: Vec<[u8; 4]> = factors[..1 << mi.index_bits[0]]
+        .iter()
+        .map(|factor| {
+            let factor = u16::from(*factor);
+            std::array::from_fn(|c| {
+                ((decoded[0][c] * (64 - factor) + decoded[1][c] * factor + 32) >> 6) as u8
+            })
+        })
+        .collect();
+
+    let mut error = 0;
+    let mut indices = Vec::with_capacity(members.len());
+    for member in members {
+        let (index, pixel_error) = palette
+            .iter()
+            .enumerate()
+            .map(|(index, color)| {
+                let error: u32 = color
+                    .iter()
+                    .zip(&block[*member])
+                    .map(|(a, b)| (i32::from(*a) - i32::from(*b)).pow(2) as u32)
+                    .sum();
+                (index, error)
+            })
+            .min_by_key(|(_, error)| *error)
+            .unwrap();
+        indices.push(index as u8);
+        error += pixel_error;
+    }
+
+    SubsetFit {
+        error,
+        endpoints,
+        indices,
+    }
+}
+
+struct Candidate {
+    error: u32,
+    data: [u8; 16],
+}
+
+fn encode_mode(
+    block: &Block,
+    mode: usize,
+    partition: usize,
+    quality: CompressionQuality,
+) -> Candidate {
+    let mi = &S_BP7_MODE_INFO[mode];
+    let index_count = 1 << mi.index_bits[0];
+    let factors = &S_BPTC_FACTORS[mi.index_bits[0] - 2];
+
+    let mut error = 0;
+    let mut endpoints = [Endpoints::default(); 3];
+    let mut indices = [0u8; 16];
+    for (subset, subset_endpoints) in endpoints.iter_mut().enumerate().take(mi.num_subsets) {
+        let members: Vec<usize> = (0..16)
+            .filter(|i| subset_of(mi, partition, *i) == subset)
+            .collect();
+        let pixels: Vec<[f32; 4]> = members.iter().map(|i| block[*i].map(f32::from)).collect();
+
+        let (start, end) = match quality {
+            CompressionQuality::Fast => bounding_box(&pixels),
+            _ => principal_endpoints(&pixels),
+        };
+        let mut best = fit_subset(mi, block, &members, quantize(mi, &[start, end]));
+
+        for _ in 0..refinement_passes(quality) {
+            let weights: Vec<f32> = best
+                .indices
+                .iter()
+                .map(|index| f32::from(factors[*index as usize]) / 64.0)
+                .collect();
+            let Some((start, end)) = least_squares(&pixels, &weights) else {
+                break;
+            };
+
+            let fit = fit_subset(mi, block, &members, quantize(mi, &[start, end]));
+            if fit.error >= best.error {
+                break;
+            }
+            best = fit;
+        }
+
+        // The anchor can't use the top half of the palette, so flip the subset around if it does
+        let anchor = anchor_of(mi, partition, subset);
+        let anchor_member = members.iter().position(|i| *i == anchor).unwrap();
+        if best.indices[anchor_member] >= index_count / 2 {
+            best.endpoints.stored.swap(0, 1);
+            best.endpoints.pbits.swap(0, 1);
+            for index in &mut best.indices {
+                *index = index_count - 1 - *index;
+            }
+        }
+
+        for (member, index) in members.iter().zip(&best.indices) {
+            indices[*member] = *index;
+        }
+        *subset_endpoints = best.endpoints;
+        error += best.error;
+    }
+
+    Candidate {
+        error,
+        data: pack(mi, mode, partition, &endpoints, &indices),
+    }
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bits: u128,
+    position: usize,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: usize, count: usize) {
+        let mask = (1u128 << count) - 1;
+        self.bits |= (value as u128 & mask) << self.position;
+        self.position += count;
+    }
+}
+
+fn pack(
+    mi: &Bc7ModeInfo,
+    mode: usize,
+    partition: usize,
+    endpoints: &[Endpoints; 3],
+    indices: &[u8; 16],
+) -> [u8; 16] {
+    let mut writer = BitWriter::default();
+
+    // The mode is the number of zero bits before the first one
+    writer.write(1 << mode, mode + 1);
+    writer.write(partition, mi.partition_bits);
+
+    for c in 0..4 {
+        let bits = channel_bits(mi, c);
+        for endpoints in &endpoints[..mi.num_subsets] {
+            for stored in endpoints.stored {
+                writer.write(usize::from(stored[c]), bits);
+            }
+        }
+    }
+
+    for endpoints in &endpoints[..mi.num_subsets] {
+        if mi.endpoint_pbits != 0 {
+            writer.write(usize::from(endpoints.pbits[0]), 1);
+            writer.write(usize::from(endpoints.pbits[1]), 1);
+        } else if mi.shared_pbits != 0 {
+            writer.write(usize::from(endpoints.pbits[0]), 1);
+        }
+    }
+
+    for (i, index) in indices.iter().enumerate() {
+        let anchor = anchor_of(mi, partition, subset_of(mi, partition, i)) == i;
+        writer.write(usize::from(*index), mi.index_bits[0] - anchor as usize);
+    }
+
+    debug_assert_eq!(writer.position, 128);
+    writer.bits.to_le_bytes()
+}
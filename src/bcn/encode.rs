@@ -0,0 +1,552 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Block encoders for BC1, BC3, BC4 and BC5, and the helpers shared with the BC7 encoder.
+
+use super::color::rgb565_le;
+
+/// How much effort the block encoders spend searching for a good fit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompressionQuality {
+    /// Fits endpoints to the bounding box of each block, without refining them.
+    Fast,
+    /// Fits endpoints along the principal axis of each block, and refines them once.
+    #[default]
+    Normal,
+    /// Refines endpoints further, and searches more BC7 modes and partitions. Much slower.
+    High,
+}
+
+/// A 4x4 block of RGBA pixels, in row-major order.
+pub(super) type Block = [[u8; 4]; 16];
+
+type EncodeFunction = fn(&Block, CompressionQuality, &mut [u8]);
+
+/// Splits `rgba` into blocks and encodes each of them with `encode_block`.
+pub(super) fn encode_blocks(
+    rgba: &[u8],
+    width: usize,
+    height: usize,
+    block_size: usize,
+    quality: CompressionQuality,
+    encode_block: EncodeFunction,
+) -> Result<Vec<u8>, &'static str> {
+    if width == 0 || height == 0 {
+        return Err("Image is empty!");
+    }
+
+    if rgba.len() < width * height * 4 {
+        return Err("Image buffer is too small!");
+    }
+
+    let num_blocks_x = width.div_ceil(4);
+    let num_blocks_y = height.div_ceil(4);
+    let mut output = vec![0; num_blocks_x * num_blocks_y * block_size];
+
+    let mut block: Block = [[0; 4]; 16];
+    for by in 0..num_blocks_y {
+        for bx in 0..num_blocks_x {
+            // Blocks hanging off the edge repeat the last row or column
+            for (i, pixel) in block.iter_mut().enumerate() {
+                let x = (bx * 4 + i % 4).min(width - 1);
+                let y = (by * 4 + i / 4).min(height - 1);
+                let offset = (y * width + x) * 4;
+                pixel.copy_from_slice(&rgba[offset..offset + 4]);
+            }
+
+            let offset = (by * num_blocks_x + bx) * block_size;
+            encode_block(&block, quality, &mut output[offset..offset + block_size]);
+        }
+    }
+
+    Ok(output)
+}
+
+pub fn encode_bc1(
+    rgba: &[u8],
+    width: usize,
+    height: usize,
+    quality: CompressionQuality,
+) -> Result<Vec<u8>, &'static str> {
+    encode_blocks(rgba, width, height, 8, quality, encode_bc1_block)
+}
+
+pub fn encode_bc3(
+    rgba: &[u8],
+    width: usize,
+    height: usize,
+    quality: CompressionQuality,
+) -> Result<Vec<u8>, &'static str> {
+    encode_blocks(rgba, width, height, 16, quality, encode_bc3_block)
+}
+
+pub fn encode_bc4(
+    rgba: &[u8],
+    width: usize,
+    height: usize,
+    quality: CompressionQuality,
+) -> Result<Vec<u8>, &'static str> {
+    encode_blocks(rgba, width, height, 8, quality, encode_bc4_block)
+}
+
+pub fn encode_bc5(
+    rgba: &[u8],
+    width: usize,
+    height: usize,
+    quality: CompressionQuality,
+) -> Result<Vec<u8>, &'static str> {
+    encode_blocks(rgba, width, height, 16, quality, encode_bc5_block)
+}
+
+fn encode_bc1_block(block: &Block, quality: CompressionQuality, output: &mut [u8]) {
+    encode_color_block(block, quality, true, output);
+}
+
+fn encode_bc3_block(block: &Block, quality: CompressionQuality, output: &mut [u8]) {
+    encode_channel_block(&channel(block, 3), quality, &mut output[..8]);
+    encode_color_block(block, quality, false, &mut output[8..]);
+}
+
+fn encode_bc4_block(block: &Block, quality: CompressionQuality, output: &mut [u8]) {
+    encode_channel_block(&channel(block, 0), quality, output);
+}
+
+fn encode_bc5_block(block: &Block, quality: CompressionQuality, output: &mut [u8]) {
+    encode_channel_block(&channel(block, 0), quality, &mut output[..8]);
+    encode_channel_block(&channel(block, 1), quality, &mut output[8..]);
+}
+
+fn channel(block: &Block, channel: usize) -> [u8; 16] {
+    std::array::from_fn(|i| block[i][channel])
+}
+
+/// How many times the endpoints are refined with least squares.
+pub(super) fn refinement_passes(quality: CompressionQuality) -> usize {
+    match quality {
+        CompressionQuality::Fast => 0,
+        CompressionQuality::Normal => 1,
+        CompressionQuality::High => 4,
+    }
+}
+
+/// Endpoints spanning the bounding box of `pixels`, along the diagonal that best follows them.
+pub(super) fn bounding_box<const N: usize>(pixels: &[[f32; N]]) -> ([f32; N], [f32; N]) {
+    let mut min = [f32::MAX; N];
+    let mut max = [f32::MIN; N];
+    for pixel in pixels {
+        for c in 0..N {
+            min[c] = min[c].min(pixel[c]);
+            max[c] = max[c].max(pixel[c]);
+        }
+    }
+
+    // Channels that decrease while the widest one increases need to be flipped
+    let widest = (0..N)
+        .max_by(|a, b| (max[*a] - min[*a]).total_cmp(&(max[*b] - min[*b])))
+        .unwrap_or(0);
+    let mean = mean(pixels);
+    for c in 0..N {
+        let covariance: f32 = pixels
+            .iter()
+            .map(|p| (p[c] - mean[c]) * (p[widest] - mean[widest]))
+            .sum();
+        if covariance < 0.0 {
+            std::mem::swap(&mut min[c], &mut max[c]);
+        }
+    }
+
+    (min, max)
+}
+
+fn mean<const N: usize>(pixels: &[[f32; N]]) -> [f32; N] {
+    let mut mean = [0.0; N];
+    for pixel in pixels {
+        for c in 0..N {
+            mean[c] += pixel[c];
+        }
+    }
+    mean.map(|x| x / pixels.len().max(1) as f32)
+}
+
+/// Returns the mean of `pixels` and the direction they vary the most in, which is zero if they don't.
+pub(super) fn principal_axis<const N: usize>(pixels: &[[f32; N]]) -> ([f32; N], [f32; N]) {
+    let mean = mean(pixels);
+
+    let mut covariance = [[0.0f32; N]; N];
+    for pixel in pixels {
+        for a in 0..N {
+            for b in 0..N {
+                covariance[a][b] += (pixel[a] - mean[a]) * (pixel[b] - mean[b]);
+            }
+        }
+    }
+
+    // Power iteration converges quickly enough for such a small matrix
+    let mut axis = [1.0f32; N];
+    for _ in 0..8 {
+        let mut next = [0.0f32; N];
+        for a in 0..N {
+            for b in 0..N {
+                next[a] += covariance[a][b] * axis[b];
+            }
+        }
+
+        let length = next.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if length < f32::EPSILON {
+            return (mean, [0.0; N]);
+        }
+        axis = next.map(|x| x / length);
+    }
+
+    (mean, axis)
+}
+
+/// Endpoints at the extremes of `pixels` projected onto their principal axis.
+pub(super) fn principal_endpoints<const N: usize>(pixels: &[[f32; N]]) -> ([f32; N], [f32; N]) {
+    let (mean, axis) = principal_axis(pixels);
+
+    let mut min = 0.0f32;
+    let mut max = 0.0f32;
+    for pixel in pixels {
+        let t: f32 = (0..N).map(|c| (pixel[c] - mean[c]) * axis[c]).sum();
+        min = min.min(t);
+        max = max.max(t);
+    }
+
+    (
+        std::array::from_fn(|c| mean[c] + axis[c] * min),
+        std::array::from_fn(|c| mean[c] + axis[c] * max),
+    )
+}
+
+/// How far `pixels` stray from the line along their principal axis, used to rank partitions.
+pub(super) fn line_error<const N: usize>(pixels: &[[f32; N]]) -> f32 {
+    let (mean, axis) = principal_axis(pixels);
+
+    pixels
+        .iter()
+        .map(|pixel| {
+            let delta: [f32; N] = std::array::from_fn(|c| pixel[c] - mean[c]);
+            let t: f32 = (0..N).map(|c| delta[c] * axis[c]).sum();
+            delta.iter().map(|x| x * x).sum::<f32>() - t * t
+        })
+        .sum()
+}
+
+/// Solves for the endpoints that best reproduce `pixels`, given how far along the line each one is.
+pub(super) fn least_squares<const N: usize>(
+    pixels: &[[f32; N]],
+    weights: &[f32],
+) -> Option<([f32; N], [f32; N])> {
+    let mut aa = 0.0;
+    let mut ab = 0.0;
+    let mut bb = 0.0;
+    let mut ax = [0.0f32; N];
+    let mut bx = [0.0f32; N];
+    for (pixel, b) in pixels.iter().zip(weights) {
+        let a = 1.0 - b;
+        aa += a * a;
+        ab += a * b;
+        bb += b * b;
+        for c in 0..N {
+            ax[c] += a * pixel[c];
+            bx[c] += b * pixel[c];
+        }
+    }
+
+    let determinant = aa * bb - ab * ab;
+    if determinant.abs() < 1e-6 {
+        return None;
+    }
+
+    Some((
+        std::array::from_fn(|c| (ax[c] * bb - bx[c] * ab) / determinant),
+        std::array::from_fn(|c| (bx[c] * aa - ax[c] * ab) / determinant),
+    ))
+}
+
+fn squared_error(a: &[u8], b: &[u8]) -> u32 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (i32::from(*a) - i32::from(*b)).pow(2) as u32)
+        .sum()
+}
+
+fn pack_565(color: [f32; 3]) -> u16 {
+    let quantize = |x: f32, max: f32| (x.clamp(0.0, 255.0) * max / 255.0).round() as u16;
+    (quantize(color[0], 31.0) << 11) | (quantize(color[1], 63.0) << 5) | quantize(color[2], 31.0)
+}
+
+/// The colors a BC1 block can pick from, the same way the decoder calculates them.
+fn color_palette(q0: u16, q1: u16) -> [[u8; 3]; 4] {
+    let (r0, g0, b0) = rgb565_le(q0);
+    let (r1, g1, b1) = rgb565_le(q1);
+    let c0 = [r0, g0, b0].map(u16::from);
+    let c1 = [r1, g1, b1].map(u16::from);
+
+    let mix = |w0: u16, w1: u16, divisor: u16| -> [u8; 3] {
+        std::array::from_fn(|c| ((c0[c] * w0 + c1[c] * w1) / divisor) as u8)
+    };
+
+    if q0 > q1 {
+        [[r0, g0, b0], [r1, g1, b1], mix(2, 1, 3), mix(1, 2, 3)]
+    } else {
+        [[r0, g0, b0], [r1, g1, b1], mix(1, 1, 2), [0, 0, 0]]
+    }
+}
+
+/// Position of each BC1 index between the two endpoints.
+const FOUR_COLOR_WEIGHTS: [f32; 4] = [0.0, 1.0, 1.0 / 3.0, 2.0 / 3.0];
+const THREE_COLOR_WEIGHTS: [f32; 4] = [0.0, 1.0, 0.5, 0.0];
+
+#[derive(Clone, Copy)]
+struct ColorFit {
+    error: u32,
+    q0: u16,
+    q1: u16,
+    indices: [u8; 16],
+}
+
+/// Picks the closest palette entry for each pixel, ordering the endpoints for the requested mode.
+fn fit_color(
+    block: &Block,
+    transparent: &[bool; 16],
+    three_color: bool,
+    mut q0: u16,
+    mut q1: u16,
+) -> ColorFit {
+    // Four color mode is selected by the first endpoint being larger, and three color mode otherwise
+    if (three_color && q0 > q1) || (!three_color && q0 < q1) {
+        std::mem::swap(&mut q0, &mut q1);
+    }
+
+    let palette = color_palette(q0, q1);
+    // Identical endpoints can't use four color mode, so stick to the first color
+    let usable = if three_color {
+        3
+    } else if q0 == q1 {
+        1
+    } else {
+        4
+    };
+
+    let mut error = 0;
+    let mut indices = [0; 16];
+    for (i, pixel) in block.iter().enumerate() {
+        if transparent[i] {
+            indices[i] = 3;
+            continue;
+        }
+
+        let (index, pixel_error) = (0..usable)
+            .map(|index| (index, squared_error(&palette[index], &pixel[..3])))
+            .min_by_key(|(_, error)| *error)
+            .unwrap();
+        indices[i] = index as u8;
+        error += pixel_error;
+    }
+
+    ColorFit {
+        error,
+        q0,
+        q1,
+        indices,
+    }
+}
+
+/// Encodes the color part of a BC1 or BC3 block. Only BC1 can make pixels transparent.
+fn encode_color_block(
+    block: &Block,
+    quality: CompressionQuality,
+    punch_through: bool,
+    output: &mut [u8],
+) {
+    let transparent: [bool; 16] = std::array::from_fn(|i| punch_through && block[i][3] < 128);
+    let three_color = transparent.contains(&true);
+
+    let pixels: Vec<[f32; 3]> = block
+        .iter()
+        .zip(&transparent)
+        .filter(|(_, transparent)| !**transparent)
+        .map(|(pixel, _)| [pixel[0], pixel[1], pixel[2]].map(f32::from))
+        .collect();
+
+    let (start, end) = match quality {
+        CompressionQuality::Fast => bounding_box(&pixels),
+        _ => principal_endpoints(&pixels),
+    };
+    let mut best = fit_color(
+        block,
+        &transparent,
+        three_color,
+        pack_565(start),
+        pack_565(end),
+    );
+
+    let weights = if three_color {
+        THREE_COLOR_WEIGHTS
+    } else {
+        FOUR_COLOR_WEIGHTS
+    };
+    for _ in 0..refinement_passes(quality) {
+        let pixel_weights: Vec<f32> = best
+            .indices
+            .iter()
+            .zip(&transparent)
+            .filter(|(_, transparent)| !**transparent)
+            .map(|(index, _)| weights[*index as usize])
+            .collect();
+        let Some((start, end)) = least_squares(&pixels, &pixel_weights) else {
+            break;
+        };
+
+        let fit = fit_color(
+            block,
+            &transparent,
+            three_color,
+            pack_565(start),
+            pack_565(end),
+        );
+        if fit.error >= best.error {
+            break;
+        }
+        best = fit;
+    }
+
+    if quality == CompressionQuality::High {
+        // Nudge each channel of each endpoint, since rounding to 565 isn't always the best choice
+        const STEPS: [u16; 6] = [1 << 11, 1 << 5, 1, 1 << 11, 1 << 5, 1];
+        const MASKS: [u16; 6] = [0xF800, 0x07E0, 0x001F, 0xF800, 0x07E0, 0x001F];
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for (step, mask) in STEPS.iter().zip(MASKS) {
+                for endpoint in 0..2 {
+                    for up in [true, false] {
+                        let value = if endpoint == 0 { best.q0 } else { best.q1 };
+                        let channel = value & mask;
+                        let Some(channel) = (if up {
+                            channel.checked_add(*step).filter(|x| *x & !mask == 0)
+                        } else {
+                            channel.checked_sub(*step)
+                        }) else {
+                            continue;
+                        };
+
+                        let value = (value & !mask) | channel;
+                        let (q0, q1) = if endpoint == 0 {
+                            (value, best.q1)
+                        } else {
+                            (best.q0, value)
+                        };
+                        let fit = fit_color(block, &transparent, three_color, q0, q1);
+                        if fit.error < best.error {
+                            best = fit;
+                            improved = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let indices = best
+        .indices
+        .iter()
+        .rev()
+        .fold(0u32, |bits, index| (bits << 2) | u32::from(*index));
+    output[0..2].copy_from_slice(&best.q0.to_le_bytes());
+    output[2..4].copy_from_slice(&best.q1.to_le_bytes());
+    output[4..8].copy_from_slice(&indices.to_le_bytes());
+}
+
+/// The values a BC4 block can pick from, the same way the decoder calculates them.
+fn channel_palette(a0: u8, a1: u8) -> [u8; 8] {
+    let a0 = u16::from(a0);
+    let a1 = u16::from(a1);
+
+    let mut palette = [a0, a1, 0, 0, 0, 0, 0, 0];
+    if a0 > a1 {
+        for i in 1..7u16 {
+            palette[usize::from(i) + 1] = (a0 * (7 - i) + a1 * i) / 7;
+        }
+    } else {
+        for i in 1..5u16 {
+            palette[usize::from(i) + 1] = (a0 * (5 - i) + a1 * i) / 5;
+        }
+        palette[6] = 0;
+        palette[7] = 255;
+    }
+
+    palette.map(|x| x as u8)
+}
+
+/// Returns the error and encoded block for the given endpoints.
+fn fit_channel(values: &[u8; 16], a0: u8, a1: u8) -> (u32, [u8; 8]) {
+    let palette = channel_palette(a0, a1);
+
+    let mut error = 0;
+    let mut indices = 0u64;
+    for (i, value) in values.iter().enumerate() {
+        let (index, value_error) = palette
+            .iter()
+            .enumerate()
+            .map(|(index, x)| (index, squared_error(&[*x], &[*value])))
+            .min_by_key(|(_, error)| *error)
+            .unwrap();
+        error += value_error;
+        indices |= (index as u64) << (i * 3);
+    }
+
+    let mut block = [0; 8];
+    block[0] = a0;
+    block[1] = a1;
+    block[2..].copy_from_slice(&indices.to_le_bytes()[..6]);
+
+    (error, block)
+}
+
+/// Encodes a single channel, like BC4 or the alpha of BC3.
+fn encode_channel_block(values: &[u8; 16], quality: CompressionQuality, output: &mut [u8]) {
+    let min = *values.iter().min().unwrap();
+    let max = *values.iter().max().unwrap();
+
+    // Eight value mode is selected by the first endpoint being larger
+    let mut candidates = vec![(max, min)];
+    if quality != CompressionQuality::Fast {
+        // Six value mode has exact 0 and 255 entries, which leaves the endpoints for everything in between
+        let inner = values.iter().filter(|x| **x != 0 && **x != 255);
+        if let (Some(inner_min), Some(inner_max)) = (inner.clone().min(), inner.max()) {
+            candidates.push((*inner_min, *inner_max));
+        }
+    }
+
+    let mut best = candidates
+        .iter()
+        .map(|(a0, a1)| fit_channel(values, *a0, *a1))
+        .min_by_key(|(error, _)| *error)
+        .unwrap();
+
+    if quality == CompressionQuality::High {
+        for (a0, a1) in candidates {
+            let eight_value = a0 > a1;
+            for d0 in -2i16..=2 {
+                for d1 in -2i16..=2 {
+                    let a0 = (i16::from(a0) + d0).clamp(0, 255) as u8;
+                    let a1 = (i16::from(a1) + d1).clamp(0, 255) as u8;
+                    // Don't let the nudge flip the block into the other mode
+                    if (a0 > a1) != eight_value {
+                        continue;
+                    }
+
+                    let fit = fit_channel(values, a0, a1);
+                    if fit.0 < best.0 {
+                        best = fit;
+                    }
+                }
+            }
+        }
+    }
+
+    output[..8].copy_from_slice(&best.1);
+}
@@ -4,20 +4,32 @@
 extern crate alloc;
 
 mod bc1;
+mod bc2;
 mod bc3;
+mod bc4;
 mod bc5;
+mod bc6h;
 mod bc7;
+mod bc7_encode;
 mod bitreader;
 mod color;
 mod consts;
+mod encode;
 mod macros;
 
 pub use bc1::decode_bc1_block;
+pub use bc2::decode_bc2_block;
 pub use bc3::decode_bc3_block;
+pub use bc4::decode_bc4_block;
 pub use bc5::decode_bc5_block;
+pub use bc6h::decode_bc6h;
 pub use bc7::decode_bc7_block;
+pub use bc7_encode::encode_bc7;
+pub use encode::{CompressionQuality, encode_bc1, encode_bc3, encode_bc4, encode_bc5};
 
 macros::block_decoder!(decode_bc1, 4, 4, 8, decode_bc1_block);
+macros::block_decoder!(decode_bc2, 4, 4, 16, decode_bc2_block);
 macros::block_decoder!(decode_bc3, 4, 4, 16, decode_bc3_block);
+macros::block_decoder!(decode_bc4, 4, 4, 8, decode_bc4_block);
 macros::block_decoder!(decode_bc5, 4, 4, 16, decode_bc5_block);
 macros::block_decoder!(decode_bc7, 4, 4, 16, decode_bc7_block);
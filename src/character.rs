@@ -0,0 +1,609 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::HashMap;
+
+use crate::equipment::{CharacterCategory, EquipSlot, EquipSlotCategory};
+use crate::model::MDL;
+use crate::mtrl::Material;
+use crate::race::{Race, get_fallback_race_id, get_race_from_id, get_race_id};
+use crate::resource::ResourceResolver;
+use crate::savedata::chardat::CustomizeData;
+use crate::skeleton::Skeleton;
+
+/// A piece of gear equipped in an [EquipSlot], as found in the model columns of the Item sheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EquippedGear {
+    /// The model id, e.g. 38 for `e0038`.
+    pub model_id: u16,
+    /// The material variant, e.g. 1 for `v0001`.
+    pub variant: u16,
+}
+
+impl EquippedGear {
+    /// The smallclothes worn in empty body, hands, legs and feet slots.
+    pub const SMALLCLOTHES: EquippedGear = EquippedGear {
+        model_id: 0,
+        variant: 1,
+    };
+}
+
+/// Which part of the character a [CharacterModel] is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharacterPart {
+    /// Gear equipped in this slot.
+    Equipment(EquipSlot),
+    /// The face.
+    Face,
+    /// The hair.
+    Hair,
+    /// The tail of Miqo'te, Au Ra and Hrothgar.
+    Tail,
+    /// The ears of Viera.
+    Ears,
+}
+
+/// A model that's part of a [Character].
+#[derive(Debug)]
+pub struct CharacterModel {
+    /// Which part of the character this is.
+    pub part: CharacterPart,
+    /// The game path this was loaded from, which may be for another race if this one had no model of its own.
+    pub path: String,
+    /// The model itself.
+    pub model: MDL,
+    /// Indices into [Character::materials] for each of the model's `material_names`, or [None] if it couldn't be found.
+    pub materials: Vec<Option<usize>>,
+}
+
+/// A material used by one or more models of a [Character].
+#[derive(Debug)]
+pub struct CharacterMaterial {
+    /// The game path this was loaded from.
+    pub path: String,
+    /// The material itself.
+    pub material: Material,
+}
+
+/// A skeleton used by a [Character].
+#[derive(Debug)]
+pub struct CharacterSkeleton {
+    /// The game path this was loaded from.
+    pub path: String,
+    /// The skeleton itself.
+    pub skeleton: Skeleton,
+}
+
+/// Everything needed to render a character, assembled from their [CustomizeData] and equipment.
+#[derive(Debug, Default)]
+pub struct Character {
+    /// The base skeleton first, followed by any for the face, hair and headgear.
+    pub skeletons: Vec<CharacterSkeleton>,
+    /// The models for each part of the character.
+    pub models: Vec<CharacterModel>,
+    /// The materials used by the models, without duplicates.
+    pub materials: Vec<CharacterMaterial>,
+    /// Material names referenced by a model but not found, in any race.
+    pub missing_materials: Vec<String>,
+}
+
+impl Character {
+    /// Loads the skeletons, models and materials for a character looking like `customize` and wearing `equipment`.
+    ///
+    /// Empty body, hands, legs and feet slots are filled with smallclothes. Weapons, the waist and soul crystal aren't part of the character and are ignored.
+    /// Models and skin materials fall back to other races like the game does, so a Miqo'te can wear gear that only has Hyur models.
+    pub fn assemble(
+        resolver: &mut ResourceResolver,
+        customize: &CustomizeData,
+        equipment: &HashMap<EquipSlot, EquippedGear>,
+    ) -> crate::Result<Character> {
+        let race_id = get_race_id(customize.race, customize.tribe, customize.gender).ok_or(
+            crate::Error::InvalidData {
+                reason: format!(
+                    "{:?} {:?} is not a playable combination",
+                    customize.race, customize.tribe
+                ),
+            },
+        )?;
+
+        let mut character = Character::default();
+
+        let base_path = Skeleton::path(customize.race, customize.tribe, customize.gender);
+        character.skeletons.push(CharacterSkeleton {
+            skeleton: resolver.parsed(&base_path)?,
+            path: base_path,
+        });
+
+        // These only exist for parts with extra bones, like long hair
+        let mut extra_skeletons = vec![
+            skeleton_path(race_id, "face", 'f', customize.face as u16),
+            skeleton_path(race_id, "hair", 'h', customize.hair as u16),
+        ];
+        if let Some(gear) = equipment.get(&EquipSlot::Head) {
+            extra_skeletons.push(skeleton_path(race_id, "met", 'm', gear.model_id));
+        }
+        if let Some(gear) = equipment.get(&EquipSlot::Body) {
+            extra_skeletons.push(skeleton_path(race_id, "top", 't', gear.model_id));
+        }
+        for path in extra_skeletons {
+            if resolver.exists(&path) {
+                character.skeletons.push(CharacterSkeleton {
+                    skeleton: resolver.parsed(&path)?,
+                    path,
+                });
+            }
+        }
+
+        character.load_customize(resolver, race_id, customize)?;
+
+        for slot in [
+            EquipSlot::Head,
+            EquipSlot::Body,
+            EquipSlot::Hands,
+            EquipSlot::Legs,
+            EquipSlot::Feet,
+            EquipSlot::Ears,
+            EquipSlot::Neck,
+            EquipSlot::Wrists,
+            EquipSlot::RightRing,
+            EquipSlot::LeftRing,
+        ] {
+            let gear = match equipment.get(&slot) {
+                Some(gear) => *gear,
+                None if matches!(
+                    slot,
+                    EquipSlot::Body | EquipSlot::Hands | EquipSlot::Legs | EquipSlot::Feet
+                ) =>
+                {
+                    EquippedGear::SMALLCLOTHES
+                }
+                None => continue,
+            };
+
+            character.load_equipment(resolver, race_id, slot, gear)?;
+        }
+
+        Ok(character)
+    }
+
+    /// Loads the face, hair and tail or ears.
+    fn load_customize(
+        &mut self,
+        resolver: &mut ResourceResolver,
+        race_id: i32,
+        customize: &CustomizeData,
+    ) -> crate::Result<()> {
+        let mut parts = vec![
+            (CharacterPart::Face, CharacterCategory::Face, customize.face),
+            (CharacterPart::Hair, CharacterCategory::Hair, customize.hair),
+        ];
+        if customize.race_feature_type != 0 {
+            match customize.race {
+                Race::Miqote | Race::AuRa | Race::Hrothgar => parts.push((
+                    CharacterPart::Tail,
+                    CharacterCategory::Tail,
+                    customize.race_feature_type,
+                )),
+                Race::Viera => parts.push((
+                    CharacterPart::Ears,
+                    CharacterCategory::Ear,
+                    customize.race_feature_type,
+                )),
+                _ => {}
+            }
+        }
+
+        for (part, category, id) in parts {
+            let path = find_for_race(resolver, race_id, |race, tribe, gender| {
+                MDL::character_path(category, id as i32, race, tribe, gender)
+            })?;
+            self.load_model(resolver, race_id, part, path, 1)?;
+        }
+
+        Ok(())
+    }
+
+    /// Loads the gear in `slot`.
+    fn load_equipment(
+        &mut self,
+        resolver: &mut ResourceResolver,
+        race_id: i32,
+        slot: EquipSlot,
+        gear: EquippedGear,
+    ) -> crate::Result<()> {
+        let category = match slot {
+            EquipSlot::Head => EquipSlotCategory::Head,
+            EquipSlot::Body => EquipSlotCategory::Body,
+            EquipSlot::Hands => EquipSlotCategory::Hands,
+            EquipSlot::Legs => EquipSlotCategory::Legs,
+            EquipSlot::Feet => EquipSlotCategory::Feet,
+            EquipSlot::Ears => EquipSlotCategory::Earring,
+            EquipSlot::Neck => EquipSlotCategory::Neck,
+            EquipSlot::Wrists => EquipSlotCategory::Wrists,
+            EquipSlot::RightRing | EquipSlot::LeftRing => EquipSlotCategory::Rings,
+            _ => return Ok(()),
+        };
+
+        let path = find_for_race(resolver, race_id, |race, tribe, gender| {
+            let path =
+                MDL::equipment_path(gear.model_id as i32, race, tribe, gender, category.clone());
+            // The right ring uses its own models
+            if slot == EquipSlot::RightRing {
+                path.replace("_ril.mdl", "_rir.mdl")
+            } else {
+                path
+            }
+        })?;
+        self.load_model(
+            resolver,
+            race_id,
+            CharacterPart::Equipment(slot),
+            path,
+            gear.variant as i32,
+        )
+    }
+
+    /// Loads the model at `path` and its materials, in `variant` if it's gear.
+    fn load_model(
+        &mut self,
+        resolver: &mut ResourceResolver,
+        race_id: i32,
+        part: CharacterPart,
+        path: String,
+        variant: i32,
+    ) -> crate::Result<()> {
+        let model: MDL = resolver.parsed(&path)?;
+
+        let mut materials = Vec::with_capacity(model.material_names.len());
+        for name in &model.material_names {
+            let candidates = material_paths(name, race_id, variant);
+            let Some(material_path) = candidates.into_iter().find(|x| resolver.exists(x)) else {
+                if !self.missing_materials.contains(name) {
+                    self.missing_materials.push(name.clone());
+                }
+                materials.push(None);
+                continue;
+            };
+
+            let index = match self.materials.iter().position(|x| x.path == material_path) {
+                Some(index) => index,
+                None => {
+                    self.materials.push(CharacterMaterial {
+                        material: resolver.parsed(&material_path)?,
+                        path: material_path,
+                    });
+                    self.materials.len() - 1
+                }
+            };
+            materials.push(Some(index));
+        }
+
+        self.models.push(CharacterModel {
+            part,
+            path,
+            model,
+            materials,
+        });
+
+        Ok(())
+    }
+}
+
+/// Returns the first path built by `build_path` that exists, starting with `race_id` and then its fallbacks.
+fn find_for_race(
+    resolver: &mut ResourceResolver,
+    race_id: i32,
+    build_path: impl Fn(Race, crate::race::Tribe, crate::race::Gender) -> String,
+) -> crate::Result<String> {
+    let mut first_path = None;
+    let mut current = Some(race_id);
+    while let Some(race_id) = current {
+        if let Some((race, tribe, gender)) = get_race_from_id(race_id) {
+            let path = build_path(race, tribe, gender);
+            if resolver.exists(&path) {
+                return Ok(path);
+            }
+            first_path.get_or_insert(path);
+        }
+        current = get_fallback_race_id(race_id);
+    }
+
+    Err(crate::Error::FileNotFound {
+        path: first_path.unwrap_or_default(),
+    })
+}
+
+/// Builds a game path to the skeleton of a face, hair or piece of gear.
+fn skeleton_path(race_id: i32, folder: &str, prefix: char, id: u16) -> String {
+    format!(
+        "chara/human/c{race_id:04}/skeleton/{folder}/{prefix}{id:04}/skl_c{race_id:04}{prefix}{id:04}.sklb"
+    )
+}
+
+/// Splits a material name like `/mt_c0101e0038_top_a.mtrl` into its race id, kind and id.
+fn parse_material_name(name: &str) -> Option<(i32, char, i32)> {
+    let code = name.strip_prefix("/mt_c")?;
+    let race_id = code.get(0..4)?.parse().ok()?;
+    let kind = code.get(4..5)?.chars().next()?;
+    let id = code.get(5..9)?.parse().ok()?;
+
+    Some((race_id, kind, id))
+}
+
+/// Returns the game paths that the material `name` could be at, in the order they should be tried.
+///
+/// Skin materials are swapped to the character's own race, because gear only references the skin of the race it was made for.
+fn material_paths(name: &str, race_id: i32, variant: i32) -> Vec<String> {
+    let Some((name_race_id, kind, id)) = parse_material_name(name) else {
+        return Vec::new();
+    };
+
+    match kind {
+        'b' => {
+            let mut paths = Vec::new();
+            let mut current = Some(race_id);
+            while let Some(race_id) = current {
+                let name =
+                    name.replacen(&format!("c{name_race_id:04}"), &format!("c{race_id:04}"), 1);
+                paths.push(Material::skin_material_path(race_id, id, &name));
+                current = get_fallback_race_id(race_id);
+            }
+            paths
+        }
+        'e' => vec![
+            Material::gear_material_path(id, variant, name),
+            Material::gear_material_path(id, 1, name),
+        ],
+        'a' => vec![
+            Material::accessory_material_path(id, variant, name),
+            Material::accessory_material_path(id, 1, name),
+        ],
+        'f' => vec![Material::face_material_path(name_race_id, id, name)],
+        'h' => vec![Material::hair_material_path(name_race_id, id, name)],
+        't' => vec![Material::tail_material_path(name_race_id, id, name)],
+        'z' => vec![Material::ear_material_path(name_race_id, id, name)],
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read;
+    use std::path::PathBuf;
+
+    use crate::race::{Gender, Tribe};
+    use crate::resource::MemoryResource;
+
+    use super::*;
+
+    #[test]
+    fn test_material_paths() {
+        assert_eq!(
+            parse_material_name("/mt_c0201e0038_top_a.mtrl"),
+            Some((201, 'e', 38))
+        );
+        assert_eq!(parse_material_name("mt_c0201e0038_top_a.mtrl"), None);
+        assert_eq!(parse_material_name("/mt_c02"), None);
+
+        assert_eq!(
+            material_paths("/mt_c0201e0038_top_a.mtrl", 801, 2),
+            vec![
+                "chara/equipment/e0038/material/v0002/mt_c0201e0038_top_a.mtrl",
+                "chara/equipment/e0038/material/v0001/mt_c0201e0038_top_a.mtrl",
+            ]
+        );
+        assert_eq!(
+            material_paths("/mt_c0201b0001_a.mtrl", 801, 2),
+            vec![
+                "chara/human/c0801/obj/body/b0001/material/v0001/mt_c0801b0001_a.mtrl",
+                "chara/human/c0201/obj/body/b0001/material/v0001/mt_c0201b0001_a.mtrl",
+                "chara/human/c0101/obj/body/b0001/material/v0001/mt_c0101b0001_a.mtrl",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_racial_fallback() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/tests");
+        d.push("c0201e0038_top_zeroed.mdl");
+
+        let mut resource = MemoryResource::default();
+        resource.add_file(
+            "chara/equipment/e0038/model/c0201e0038_top.mdl",
+            read(d).unwrap(),
+        );
+        let mut resolver = ResourceResolver::new();
+        resolver.add_source(resource);
+
+        // A Miqo'te wearing gear that only has a Midlander female model
+        let mut character = Character::default();
+        character
+            .load_equipment(
+                &mut resolver,
+                801,
+                EquipSlot::Body,
+                EquippedGear {
+                    model_id: 38,
+                    variant: 1,
+                },
+            )
+            .unwrap();
+
+        let model = &character.models[0];
+        assert_eq!(model.part, CharacterPart::Equipment(EquipSlot::Body));
+        assert_eq!(model.path, "chara/equipment/e0038/model/c0201e0038_top.mdl");
+        assert_eq!(model.materials.len(), model.model.material_names.len());
+        assert!(model.materials.iter().all(Option::is_none));
+        assert_eq!(character.missing_materials, model.model.material_names);
+
+        // Nothing to fall back to for the legs
+        assert!(matches!(
+            character.load_equipment(
+                &mut resolver,
+                801,
+                EquipSlot::Legs,
+                EquippedGear {
+                    model_id: 38,
+                    variant: 1,
+                },
+            ),
+            Err(crate::Error::FileNotFound { path }) if path == "chara/equipment/e0038/model/c0801e0038_dwn.mdl"
+        ));
+    }
+
+    fn test_file(name: &str) -> Vec<u8> {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/tests");
+        d.push(name);
+
+        read(d).unwrap()
+    }
+
+    #[test]
+    fn test_assemble() {
+        let mdl = test_file("c0201e0038_top_zeroed.mdl");
+        // A material without any textures or shader values
+        let material = test_file("character.mtrl");
+
+        // A Miqo'te with only some of their models, so the rest fall back to Midlanders
+        let mut resource = MemoryResource::default();
+        resource.add_file(
+            "chara/human/c0801/skeleton/base/b0001/skl_c0801b0001.sklb",
+            test_file("skl_c0801b0001.sklb"),
+        );
+        resource.add_file(
+            "chara/human/c0801/skeleton/hair/h0003/skl_c0801h0003.sklb",
+            test_file("skl_c0801h0003.sklb"),
+        );
+        for path in [
+            "chara/human/c0801/obj/face/f0001/model/c0801f0001_fac.mdl",
+            "chara/human/c0801/obj/hair/h0003/model/c0801h0003_hir.mdl",
+            "chara/human/c0801/obj/tail/t0002/model/c0801t0002_til.mdl",
+            "chara/equipment/e0038/model/c0201e0038_top.mdl",
+            "chara/equipment/e0000/model/c0801e0000_glv.mdl",
+            "chara/equipment/e0000/model/c0801e0000_dwn.mdl",
+            "chara/equipment/e0000/model/c0201e0000_sho.mdl",
+        ] {
+            resource.add_file(path, mdl.clone());
+        }
+        resource.add_file(
+            "chara/equipment/e0038/material/v0001/mt_c0201e0038_top_a.mtrl",
+            material.clone(),
+        );
+        resource.add_file(
+            "chara/human/c0801/obj/body/b0001/material/v0001/mt_c0801b0001_a.mtrl",
+            material.clone(),
+        );
+        let mut resolver = ResourceResolver::new();
+        resolver.add_source(resource);
+
+        let customize = CustomizeData {
+            race: Race::Miqote,
+            tribe: Tribe::Seeker,
+            gender: Gender::Female,
+            face: 1,
+            hair: 3,
+            race_feature_type: 2,
+            ..Default::default()
+        };
+        let equipment = HashMap::from([(
+            EquipSlot::Body,
+            EquippedGear {
+                model_id: 38,
+                variant: 2,
+            },
+        )]);
+
+        let character = Character::assemble(&mut resolver, &customize, &equipment).unwrap();
+
+        // The face has no skeleton, but the hair does
+        assert_eq!(
+            character
+                .skeletons
+                .iter()
+                .map(|x| x.path.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "chara/human/c0801/skeleton/base/b0001/skl_c0801b0001.sklb",
+                "chara/human/c0801/skeleton/hair/h0003/skl_c0801h0003.sklb",
+            ]
+        );
+        assert_eq!(character.skeletons[0].skeleton.bones[1].name, "j_kosi");
+        assert_eq!(
+            character.skeletons[1].skeleton.bones[0].name,
+            "j_ex_h0003_ke_f"
+        );
+
+        // Empty slots get smallclothes, and accessories are left out
+        assert_eq!(
+            character
+                .models
+                .iter()
+                .map(|x| (x.part, x.path.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    CharacterPart::Face,
+                    "chara/human/c0801/obj/face/f0001/model/c0801f0001_fac.mdl"
+                ),
+                (
+                    CharacterPart::Hair,
+                    "chara/human/c0801/obj/hair/h0003/model/c0801h0003_hir.mdl"
+                ),
+                (
+                    CharacterPart::Tail,
+                    "chara/human/c0801/obj/tail/t0002/model/c0801t0002_til.mdl"
+                ),
+                (
+                    CharacterPart::Equipment(EquipSlot::Body),
+                    "chara/equipment/e0038/model/c0201e0038_top.mdl"
+                ),
+                (
+                    CharacterPart::Equipment(EquipSlot::Hands),
+                    "chara/equipment/e0000/model/c0801e0000_glv.mdl"
+                ),
+                (
+                    CharacterPart::Equipment(EquipSlot::Legs),
+                    "chara/equipment/e0000/model/c0801e0000_dwn.mdl"
+                ),
+                (
+                    CharacterPart::Equipment(EquipSlot::Feet),
+                    "chara/equipment/e0000/model/c0201e0000_sho.mdl"
+                ),
+            ]
+        );
+
+        // Variant 2 falls back to the first one, and the skin is swapped to the Miqo'te one
+        assert_eq!(
+            character
+                .materials
+                .iter()
+                .map(|x| x.path.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "chara/equipment/e0038/material/v0001/mt_c0201e0038_top_a.mtrl",
+                "chara/human/c0801/obj/body/b0001/material/v0001/mt_c0801b0001_a.mtrl",
+            ]
+        );
+        assert!(
+            character
+                .models
+                .iter()
+                .all(|x| x.materials == [Some(0), Some(1)])
+        );
+        assert!(character.missing_materials.is_empty());
+
+        // Without a base skeleton there's nothing to build on
+        let customize = CustomizeData {
+            race: Race::Viera,
+            tribe: Tribe::Rava,
+            gender: Gender::Female,
+            ..Default::default()
+        };
+        assert!(matches!(
+            Character::assemble(&mut resolver, &customize, &HashMap::new()),
+            Err(crate::Error::FileNotFound { .. })
+        ));
+    }
+}
@@ -208,7 +208,7 @@ pub fn pass_random_invalid<T: ReadableFile>() {
 
 /// Helper to ensure that type `T` is written to `EXPECTED_SIZE`.
 #[cfg(test)]
-pub fn ensure_size<T: BinWrite + Default, const EXPECTED_SIZE: usize>()
+pub fn ensure_size<T, const EXPECTED_SIZE: usize>()
 where
     for<'a> T: BinWrite<Args<'a> = ()> + 'a + Default,
 {
@@ -80,16 +80,55 @@ pub fn header_decompress(in_data: &mut [u8], out_data: &mut [u8]) -> crate::Resu
         }
 
         strm.next_in = in_data.as_mut_ptr();
-        strm.avail_out = out_data.len() as u32;
         strm.next_out = out_data.as_mut_ptr();
 
-        let ret = inflate(&mut strm, Z_NO_FLUSH);
+        // zlib only takes 32-bit sizes, so bigger buffers are given to it in pieces
+        let mut remaining_in = in_data.len();
+        let mut remaining_out = out_data.len();
+        let ret = loop {
+            let avail_in = remaining_in.min(u32::MAX as usize) as u32;
+            let avail_out = remaining_out.min(u32::MAX as usize) as u32;
+            strm.avail_in = avail_in;
+            strm.avail_out = avail_out;
+
+            let ret = inflate(&mut strm, Z_NO_FLUSH);
+            remaining_in -= (avail_in - strm.avail_in) as usize;
+            remaining_out -= (avail_out - strm.avail_out) as usize;
+
+            if ret != Z_OK || remaining_in == 0 || remaining_out == 0 {
+                break ret;
+            }
+        };
+
+        inflateEnd(&mut strm);
+
         if ret != Z_STREAM_END && ret != Z_OK {
             return Err(crate::Error::Zlib(ret));
         }
 
-        inflateEnd(&mut strm);
+        Ok(out_data.len() - remaining_out)
+    }
+}
+
+/// Compress data with a zlib header.
+pub fn header_compress(in_data: &[u8]) -> crate::Result<Vec<u8>> {
+    unsafe {
+        let mut out_len = compressBound(in_data.len() as _);
+        let mut out_data = vec![0u8; out_len as usize];
+
+        let ret = compress2(
+            out_data.as_mut_ptr(),
+            &mut out_len,
+            in_data.as_ptr(),
+            in_data.len() as _,
+            Z_DEFAULT_COMPRESSION,
+        );
+        if ret != Z_OK {
+            return Err(crate::Error::Zlib(ret));
+        }
+
+        out_data.truncate(out_len as usize);
 
-        Ok(out_data.len() - strm.avail_out as usize)
+        Ok(out_data)
     }
 }
@@ -0,0 +1,295 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! DirectDraw Surface (`.dds`) headers, used for importing and exporting textures.
+//!
+//! See <https://learn.microsoft.com/en-us/windows/win32/direct3ddds/dds-header> for the layout.
+
+use binrw::binrw;
+
+use crate::tex::TextureFormat;
+
+pub(crate) const DDSD_CAPS: u32 = 0x1;
+pub(crate) const DDSD_HEIGHT: u32 = 0x2;
+pub(crate) const DDSD_WIDTH: u32 = 0x4;
+pub(crate) const DDSD_PITCH: u32 = 0x8;
+pub(crate) const DDSD_PIXELFORMAT: u32 = 0x1000;
+pub(crate) const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+pub(crate) const DDSD_LINEARSIZE: u32 = 0x80000;
+pub(crate) const DDSD_DEPTH: u32 = 0x800000;
+
+pub(crate) const DDSCAPS_COMPLEX: u32 = 0x8;
+pub(crate) const DDSCAPS_TEXTURE: u32 = 0x1000;
+pub(crate) const DDSCAPS_MIPMAP: u32 = 0x400000;
+
+pub(crate) const DDSCAPS2_CUBEMAP: u32 = 0x200;
+/// Every face of the cubemap is present.
+pub(crate) const DDSCAPS2_CUBEMAP_ALLFACES: u32 = 0xFC00;
+pub(crate) const DDSCAPS2_VOLUME: u32 = 0x200000;
+
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_ALPHA: u32 = 0x2;
+pub(crate) const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDPF_LUMINANCE: u32 = 0x20000;
+
+pub(crate) const DIMENSION_TEXTURE1D: u32 = 2;
+pub(crate) const DIMENSION_TEXTURE2D: u32 = 3;
+pub(crate) const DIMENSION_TEXTURE3D: u32 = 4;
+
+pub(crate) const MISC_TEXTURECUBE: u32 = 0x4;
+
+pub(crate) const FOURCC_DX10: u32 = u32::from_le_bytes(*b"DX10");
+
+/// Written into the reserved area, so formats that share a DXGI format (like `L8_UNORM` and `R8_UNORM`) survive a round-trip.
+pub(crate) const FORMAT_HINT_MAGIC: u32 = u32::from_le_bytes(*b"PHYS");
+
+#[binrw]
+#[derive(Debug, Default)]
+pub(crate) struct DdsPixelFormat {
+    #[br(assert(size == 32, "pixel format size is {size}, not 32"))]
+    pub size: u32,
+    pub flags: u32,
+    pub four_cc: u32,
+    pub rgb_bit_count: u32,
+    pub r_bit_mask: u32,
+    pub g_bit_mask: u32,
+    pub b_bit_mask: u32,
+    pub a_bit_mask: u32,
+}
+
+#[binrw]
+#[derive(Debug)]
+pub(crate) struct Dx10Header {
+    pub dxgi_format: u32,
+    pub resource_dimension: u32,
+    pub misc_flag: u32,
+    pub array_size: u32,
+    pub misc_flags2: u32,
+}
+
+#[binrw]
+#[brw(little, magic = b"DDS ")]
+#[derive(Debug)]
+pub(crate) struct DdsHeader {
+    #[br(assert(size == 124, "header size is {size}, not 124"))]
+    pub size: u32,
+    pub flags: u32,
+    pub height: u32,
+    pub width: u32,
+    pub pitch_or_linear_size: u32,
+    pub depth: u32,
+    pub mip_map_count: u32,
+    pub reserved1: [u32; 11],
+    pub pixel_format: DdsPixelFormat,
+    pub caps: u32,
+    pub caps2: u32,
+    pub caps3: u32,
+    pub caps4: u32,
+    pub reserved2: u32,
+
+    #[br(if(pixel_format.flags & DDPF_FOURCC != 0 && pixel_format.four_cc == FOURCC_DX10))]
+    pub dx10: Option<Dx10Header>,
+}
+
+/// Returns the DXGI format with the same layout as `format`.
+pub(crate) fn dxgi_format(format: TextureFormat) -> u32 {
+    match format {
+        TextureFormat::L8_UNORM | TextureFormat::R8_UNORM => 61,
+        TextureFormat::A8_UNORM => 65,
+        TextureFormat::R8_UINT => 62,
+        TextureFormat::R16_UINT => 57,
+        TextureFormat::R32_UINT => 42,
+        TextureFormat::R8G8_UNORM => 49,
+        TextureFormat::B4G4R4A4_UNORM => 115,
+        TextureFormat::B5G5R5A1_UNORM => 86,
+        TextureFormat::B8G8R8A8_UNORM => 87,
+        TextureFormat::B8G8R8X8_UNORM => 88,
+        TextureFormat::R16_FLOAT => 54,
+        TextureFormat::R32_FLOAT => 41,
+        TextureFormat::R16G16_FLOAT => 34,
+        TextureFormat::R32G32_FLOAT => 16,
+        TextureFormat::R11G11B10_FLOAT => 26,
+        TextureFormat::R16G16B16A16_FLOAT => 10,
+        TextureFormat::R32G32B32A32_FLOAT => 2,
+        TextureFormat::BC1_UNORM => 71,
+        TextureFormat::BC2_UNORM => 74,
+        TextureFormat::BC3_UNORM => 77,
+        TextureFormat::D16_UNORM | TextureFormat::D16_UNORM_2 => 55,
+        TextureFormat::D24_UNORM_S8_UINT
+        | TextureFormat::D24_UNORM_S8_UINT_2
+        | TextureFormat::D24_UNORM_S8_UINT_3 => 45,
+        TextureFormat::BC4_UNORM => 80,
+        TextureFormat::BC5_UNORM => 83,
+        TextureFormat::BC6H_SF16 => 96,
+        TextureFormat::BC7_UNORM => 98,
+        TextureFormat::R16_UNORM => 56,
+        TextureFormat::R16G16_UNORM => 35,
+        TextureFormat::R10G10B10A2_UNORM | TextureFormat::R10G10B10A2_UNORM_2 => 24,
+    }
+}
+
+/// Returns the texture format for a DXGI format. The typeless and sRGB variants are accepted too, since the data is identical.
+pub(crate) fn texture_format(dxgi_format: u32) -> Option<TextureFormat> {
+    Some(match dxgi_format {
+        1 | 2 => TextureFormat::R32G32B32A32_FLOAT,
+        9 | 10 => TextureFormat::R16G16B16A16_FLOAT,
+        15 | 16 => TextureFormat::R32G32_FLOAT,
+        23 | 24 => TextureFormat::R10G10B10A2_UNORM,
+        26 => TextureFormat::R11G11B10_FLOAT,
+        34 => TextureFormat::R16G16_FLOAT,
+        33 | 35 => TextureFormat::R16G16_UNORM,
+        39 | 41 => TextureFormat::R32_FLOAT,
+        42 => TextureFormat::R32_UINT,
+        44 | 45 => TextureFormat::D24_UNORM_S8_UINT,
+        48 | 49 => TextureFormat::R8G8_UNORM,
+        54 => TextureFormat::R16_FLOAT,
+        55 => TextureFormat::D16_UNORM,
+        53 | 56 => TextureFormat::R16_UNORM,
+        57 => TextureFormat::R16_UINT,
+        60 | 61 => TextureFormat::R8_UNORM,
+        62 => TextureFormat::R8_UINT,
+        65 => TextureFormat::A8_UNORM,
+        70..=72 => TextureFormat::BC1_UNORM,
+        73..=75 => TextureFormat::BC2_UNORM,
+        76..=78 => TextureFormat::BC3_UNORM,
+        79 | 80 => TextureFormat::BC4_UNORM,
+        82 | 83 => TextureFormat::BC5_UNORM,
+        86 => TextureFormat::B5G5R5A1_UNORM,
+        87 | 90 | 91 => TextureFormat::B8G8R8A8_UNORM,
+        88 | 92 | 93 => TextureFormat::B8G8R8X8_UNORM,
+        94 | 96 => TextureFormat::BC6H_SF16,
+        97..=99 => TextureFormat::BC7_UNORM,
+        115 => TextureFormat::B4G4R4A4_UNORM,
+        _ => return None,
+    })
+}
+
+/// Returns the texture format for files without a DX10 header, which describe it with a FourCC or bit masks instead.
+pub(crate) fn legacy_texture_format(pixel_format: &DdsPixelFormat) -> Option<TextureFormat> {
+    if pixel_format.flags & DDPF_FOURCC != 0 {
+        return Some(match &pixel_format.four_cc.to_le_bytes() {
+            b"DXT1" => TextureFormat::BC1_UNORM,
+            b"DXT2" | b"DXT3" => TextureFormat::BC2_UNORM,
+            b"DXT4" | b"DXT5" => TextureFormat::BC3_UNORM,
+            b"ATI1" | b"BC4U" => TextureFormat::BC4_UNORM,
+            b"ATI2" | b"BC5U" => TextureFormat::BC5_UNORM,
+            // The rest are D3DFORMAT values
+            [111, 0, 0, 0] => TextureFormat::R16_FLOAT,
+            [112, 0, 0, 0] => TextureFormat::R16G16_FLOAT,
+            [113, 0, 0, 0] => TextureFormat::R16G16B16A16_FLOAT,
+            [114, 0, 0, 0] => TextureFormat::R32_FLOAT,
+            [115, 0, 0, 0] => TextureFormat::R32G32_FLOAT,
+            [116, 0, 0, 0] => TextureFormat::R32G32B32A32_FLOAT,
+            _ => return None,
+        });
+    }
+
+    let masks = (
+        pixel_format.rgb_bit_count,
+        pixel_format.r_bit_mask,
+        pixel_format.g_bit_mask,
+        pixel_format.b_bit_mask,
+    );
+    let alpha = if pixel_format.flags & DDPF_ALPHAPIXELS != 0 {
+        pixel_format.a_bit_mask
+    } else {
+        0
+    };
+
+    if pixel_format.flags & DDPF_RGB != 0 {
+        return match (masks, alpha) {
+            ((32, 0xFF0000, 0xFF00, 0xFF), 0xFF000000) => Some(TextureFormat::B8G8R8A8_UNORM),
+            ((32, 0xFF0000, 0xFF00, 0xFF), 0) => Some(TextureFormat::B8G8R8X8_UNORM),
+            ((16, 0x7C00, 0x3E0, 0x1F), 0x8000) => Some(TextureFormat::B5G5R5A1_UNORM),
+            ((16, 0xF00, 0xF0, 0xF), 0xF000) => Some(TextureFormat::B4G4R4A4_UNORM),
+            ((32, 0x3FF, 0xFFC00, 0x3FF00000), 0xC0000000) => {
+                Some(TextureFormat::R10G10B10A2_UNORM)
+            }
+            ((32, 0xFFFF, 0xFFFF0000, 0), 0) => Some(TextureFormat::R16G16_UNORM),
+            _ => None,
+        };
+    }
+    if pixel_format.flags & DDPF_LUMINANCE != 0 && masks == (8, 0xFF, 0, 0) {
+        return Some(TextureFormat::L8_UNORM);
+    }
+    if pixel_format.flags & DDPF_ALPHA != 0 && pixel_format.rgb_bit_count == 8 {
+        return Some(TextureFormat::A8_UNORM);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dxgi_round_trip() {
+        // Every format, except the ones that alias another
+        let formats = [
+            TextureFormat::A8_UNORM,
+            TextureFormat::R8_UNORM,
+            TextureFormat::R8_UINT,
+            TextureFormat::R16_UINT,
+            TextureFormat::R32_UINT,
+            TextureFormat::R8G8_UNORM,
+            TextureFormat::B4G4R4A4_UNORM,
+            TextureFormat::B5G5R5A1_UNORM,
+            TextureFormat::B8G8R8A8_UNORM,
+            TextureFormat::B8G8R8X8_UNORM,
+            TextureFormat::R16_FLOAT,
+            TextureFormat::R32_FLOAT,
+            TextureFormat::R16G16_FLOAT,
+            TextureFormat::R32G32_FLOAT,
+            TextureFormat::R11G11B10_FLOAT,
+            TextureFormat::R16G16B16A16_FLOAT,
+            TextureFormat::R32G32B32A32_FLOAT,
+            TextureFormat::BC1_UNORM,
+            TextureFormat::BC2_UNORM,
+            TextureFormat::BC3_UNORM,
+            TextureFormat::D16_UNORM,
+            TextureFormat::D24_UNORM_S8_UINT,
+            TextureFormat::BC4_UNORM,
+            TextureFormat::BC5_UNORM,
+            TextureFormat::BC6H_SF16,
+            TextureFormat::BC7_UNORM,
+            TextureFormat::R16_UNORM,
+            TextureFormat::R16G16_UNORM,
+            TextureFormat::R10G10B10A2_UNORM,
+        ];
+        for format in formats {
+            assert_eq!(texture_format(dxgi_format(format)), Some(format));
+        }
+    }
+
+    #[test]
+    fn test_legacy_formats() {
+        let four_cc = |code: &[u8; 4]| DdsPixelFormat {
+            size: 32,
+            flags: DDPF_FOURCC,
+            four_cc: u32::from_le_bytes(*code),
+            ..Default::default()
+        };
+        assert_eq!(
+            legacy_texture_format(&four_cc(b"DXT5")),
+            Some(TextureFormat::BC3_UNORM)
+        );
+        assert_eq!(legacy_texture_format(&four_cc(b"ABCD")), None);
+
+        let bgra = DdsPixelFormat {
+            size: 32,
+            flags: DDPF_RGB | DDPF_ALPHAPIXELS,
+            rgb_bit_count: 32,
+            r_bit_mask: 0xFF0000,
+            g_bit_mask: 0xFF00,
+            b_bit_mask: 0xFF,
+            a_bit_mask: 0xFF000000,
+            ..Default::default()
+        };
+        assert_eq!(
+            legacy_texture_format(&bgra),
+            Some(TextureFormat::B8G8R8A8_UNORM)
+        );
+    }
+}
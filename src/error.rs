@@ -27,6 +27,11 @@ pub enum Error {
     InvalidFilename { path: PathBuf },
     /// Right now is this a catch-all error when a resolver function fails.
     ResolverFailed,
+    /// The data given to us is inconsistent, e.g. an Excel field doesn't match the type of its column.
+    InvalidData {
+        /// Why the data was rejected.
+        reason: String,
+    },
 }
 
 impl std::fmt::Display for Error {
@@ -41,6 +46,7 @@ impl std::fmt::Display for Error {
             Error::TargetInfoMissing => write!(f, "target info missing"),
             Error::HashNotFound { hash } => write!(f, "hash {hash:?} not found"),
             Error::InvalidFilename { path } => write!(f, "invalid filename: {path:?}"),
+            Error::InvalidData { reason } => write!(f, "invalid data: {reason}"),
         }
     }
 }
@@ -0,0 +1,619 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::BTreeMap;
+
+use crate::{
+    ByteBuffer, Error, Language, Platform, WritableFile,
+    excel::{Entry, Field, Page, Row, Sheet},
+    exd::EXD,
+    exh::{ColumnDataType, EXH, ExcelColumnDefinition, ExcelDataPagination, SheetRowKind},
+    exl::EXL,
+};
+
+/// The maximum amount of rows put in a page by default, which matches most retail sheets.
+const DEFAULT_PAGE_SIZE: u32 = 500;
+
+/// Rows for a single language, keyed by row ID and then subrow ID.
+type LanguageRows = BTreeMap<u32, BTreeMap<u16, Row>>;
+
+/// Creates new Excel sheets, or edits existing ones.
+///
+/// Unlike editing [Page::entries] directly, this takes care of laying out columns, splitting rows into pages and writing the header. Rows are kept separately for each language, although most of the time you want to insert the same row for all of them with [insert_row](Self::insert_row).
+///
+/// # Example
+///
+/// ```
+/// # use physis::excel::{Field, Row, SheetBuilder};
+/// # use physis::exh::{ColumnDataType, SheetRowKind};
+/// # use physis::exl::EXL;
+/// # use physis::Language;
+/// let mut builder = SheetBuilder::new("CustomThing", SheetRowKind::SingleRow);
+/// builder.set_languages(&[Language::English, Language::Japanese]);
+/// builder.add_column(ColumnDataType::String);
+/// builder.add_column(ColumnDataType::UInt32);
+///
+/// builder.insert_row(1, Row { columns: vec![Field::String("Hello".to_string()), Field::UInt32(5)] })?;
+///
+/// let mut root_exl = EXL { version: 2, entries: Vec::new() };
+/// let files = builder.write(&mut root_exl)?;
+/// for (path, data) in files {
+///     println!("{path}: {} bytes", data.len());
+/// }
+/// # Ok::<(), physis::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct SheetBuilder {
+    name: String,
+    exh: EXH,
+    /// The ID used when adding this sheet to the root list.
+    list_id: i32,
+    page_size: u32,
+    rows: Vec<(Language, LanguageRows)>,
+}
+
+impl SheetBuilder {
+    /// Creates a new, empty sheet named `name` with no columns.
+    ///
+    /// The sheet is language-agnostic ([Language::None]) until you call [set_languages](Self::set_languages).
+    pub fn new(name: &str, row_kind: SheetRowKind) -> Self {
+        let mut exh = EXH::new();
+        exh.header.version = 3;
+        exh.header.row_kind = row_kind;
+        exh.languages = vec![Language::None];
+
+        Self {
+            name: name.to_string(),
+            exh,
+            list_id: -1,
+            page_size: DEFAULT_PAGE_SIZE,
+            rows: vec![(Language::None, BTreeMap::new())],
+        }
+    }
+
+    /// Creates a builder from an existing sheet, so it can be edited.
+    ///
+    /// `sheets` should contain the sheet read in each language listed in `exh`.
+    pub fn from_sheets(name: &str, exh: &EXH, sheets: &[(Language, Sheet)]) -> Self {
+        let rows = sheets
+            .iter()
+            .map(|(language, sheet)| {
+                let mut rows = LanguageRows::new();
+                for (row_id, subrows) in sheet {
+                    rows.insert(row_id, subrows.iter().cloned().collect());
+                }
+                (*language, rows)
+            })
+            .collect();
+
+        Self {
+            name: name.to_string(),
+            exh: exh.clone(),
+            list_id: -1,
+            page_size: DEFAULT_PAGE_SIZE,
+            rows,
+        }
+    }
+
+    /// Sets the ID used for this sheet in the root list. By default this is `-1`, which is what most sheets use.
+    pub fn set_list_id(&mut self, id: i32) {
+        self.list_id = id;
+    }
+
+    /// Sets the maximum amount of rows in each page.
+    pub fn set_page_size(&mut self, page_size: u32) {
+        self.page_size = page_size.max(1);
+    }
+
+    /// The columns of this sheet.
+    pub fn columns(&self) -> &[ExcelColumnDefinition] {
+        &self.exh.column_definitions
+    }
+
+    /// The languages this sheet is written in.
+    pub fn languages(&self) -> &[Language] {
+        &self.exh.languages
+    }
+
+    /// Changes which languages this sheet is written in.
+    ///
+    /// Newly added languages start out as a copy of the first existing language, and removed languages lose their rows.
+    pub fn set_languages(&mut self, languages: &[Language]) {
+        let template = self
+            .rows
+            .first()
+            .map(|(_, rows)| rows.clone())
+            .unwrap_or_default();
+
+        self.rows = languages
+            .iter()
+            .map(|language| {
+                let rows = self
+                    .rows
+                    .iter()
+                    .find(|(existing, _)| existing == language)
+                    .map(|(_, rows)| rows.clone())
+                    .unwrap_or_else(|| template.clone());
+                (*language, rows)
+            })
+            .collect();
+        self.exh.languages = languages.to_vec();
+    }
+
+    /// Adds a new column of `data_type` at the end of the row, and returns its index.
+    ///
+    /// Existing rows are given the [default value](Field::default_for) for this column. Packed booleans are placed in an existing byte if that bit is free.
+    pub fn add_column(&mut self, data_type: ColumnDataType) -> usize {
+        let offset = self.next_offset(data_type);
+        self.exh
+            .column_definitions
+            .push(ExcelColumnDefinition { data_type, offset });
+
+        for row in self.all_rows_mut() {
+            row.columns.push(Field::default_for(data_type));
+        }
+
+        self.exh.column_definitions.len() - 1
+    }
+
+    /// Removes the column at `index` from the header and every row.
+    ///
+    /// The other columns keep their offsets, so this may leave unused space in the row.
+    pub fn remove_column(&mut self, index: usize) {
+        if index >= self.exh.column_definitions.len() {
+            return;
+        }
+
+        self.exh.column_definitions.remove(index);
+
+        for row in self.all_rows_mut() {
+            if index < row.columns.len() {
+                row.columns.remove(index);
+            }
+        }
+    }
+
+    /// Inserts (or replaces) the row at `row_id` for every language.
+    ///
+    /// For subrow sheets, this is inserted as subrow 0.
+    pub fn insert_row(&mut self, row_id: u32, row: Row) -> crate::Result<()> {
+        self.insert_subrow(row_id, 0, row)
+    }
+
+    /// Inserts (or replaces) subrow `subrow_id` of `row_id` for every language.
+    pub fn insert_subrow(&mut self, row_id: u32, subrow_id: u16, row: Row) -> crate::Result<()> {
+        Self::check_row(&self.exh, row_id, subrow_id, &row)?;
+
+        for (_, rows) in &mut self.rows {
+            rows.entry(row_id)
+                .or_default()
+                .insert(subrow_id, row.clone());
+        }
+
+        Ok(())
+    }
+
+    /// Inserts (or replaces) the row at `row_id`, but only for `language`.
+    ///
+    /// This is useful for setting translated strings. Other languages that don't have this row yet will fall back to this one when written.
+    pub fn insert_localized_row(
+        &mut self,
+        language: Language,
+        row_id: u32,
+        subrow_id: u16,
+        row: Row,
+    ) -> crate::Result<()> {
+        Self::check_row(&self.exh, row_id, subrow_id, &row)?;
+
+        let Some((_, rows)) = self.rows.iter_mut().find(|(x, _)| *x == language) else {
+            return Err(Error::InvalidData {
+                reason: format!("{language:?} isn't a language of this sheet"),
+            });
+        };
+
+        rows.entry(row_id).or_default().insert(subrow_id, row);
+
+        Ok(())
+    }
+
+    /// Removes the row at `row_id` (including all of its subrows) from every language. Returns true if it existed.
+    pub fn remove_row(&mut self, row_id: u32) -> bool {
+        let mut removed = false;
+        for (_, rows) in &mut self.rows {
+            removed |= rows.remove(&row_id).is_some();
+        }
+        removed
+    }
+
+    /// Removes subrow `subrow_id` of `row_id` from every language. Returns true if it existed.
+    ///
+    /// If this was the last subrow, the row is removed too.
+    pub fn remove_subrow(&mut self, row_id: u32, subrow_id: u16) -> bool {
+        let mut removed = false;
+        for (_, rows) in &mut self.rows {
+            if let Some(subrows) = rows.get_mut(&row_id) {
+                removed |= subrows.remove(&subrow_id).is_some();
+                if subrows.is_empty() {
+                    rows.remove(&row_id);
+                }
+            }
+        }
+        removed
+    }
+
+    /// Returns the row at `row_id` in `language`, if it exists.
+    pub fn row(&self, language: Language, row_id: u32, subrow_id: u16) -> Option<&Row> {
+        let (_, rows) = self.rows.iter().find(|(x, _)| *x == language)?;
+        rows.get(&row_id)?.get(&subrow_id)
+    }
+
+    /// Builds the header, with the row size and pages calculated from the current columns and rows.
+    pub fn build_header(&self) -> EXH {
+        let mut exh = self.exh.clone();
+
+        exh.header.row_size = Self::calculate_row_size(&exh.column_definitions);
+        exh.header.column_count = exh.column_definitions.len() as u16;
+        exh.header.language_count = exh.languages.len() as u16;
+
+        let row_ids = self.row_ids();
+        exh.header.row_count = row_ids.len() as u32;
+
+        // The page's row count is the range of IDs it covers, not how many rows are actually in it.
+        exh.pages = row_ids
+            .chunks(self.page_size as usize)
+            .map(|ids| ExcelDataPagination {
+                start_id: ids[0],
+                row_count: ids[ids.len() - 1] - ids[0] + 1,
+            })
+            .collect();
+        exh.header.page_count = exh.pages.len() as u16;
+
+        exh
+    }
+
+    /// Builds the header and a [Sheet] for each language.
+    pub fn build(&self) -> (EXH, Vec<(Language, Sheet)>) {
+        let exh = self.build_header();
+        let row_ids = self.row_ids();
+
+        let sheets = self
+            .rows
+            .iter()
+            .map(|(language, rows)| {
+                let pages = exh
+                    .pages
+                    .iter()
+                    .map(|page| {
+                        // The IDs are sorted, so each page covers a contiguous range of them
+                        let first = row_ids.partition_point(|id| *id < page.start_id);
+                        let last =
+                            row_ids.partition_point(|id| *id < page.start_id + page.row_count);
+
+                        let entries = row_ids[first..last]
+                            .iter()
+                            .map(|&id| {
+                                // Fall back to the first language with this row, for languages that were never given one.
+                                let subrows = rows
+                                    .get(&id)
+                                    .or_else(|| self.rows.iter().find_map(|(_, x)| x.get(&id)))
+                                    .unwrap();
+
+                                Entry {
+                                    id,
+                                    subrows: subrows
+                                        .iter()
+                                        .map(|(subrow_id, row)| (*subrow_id, row.clone()))
+                                        .collect(),
+                                }
+                            })
+                            .collect();
+
+                        Page { entries }
+                    })
+                    .collect();
+
+                (
+                    *language,
+                    Sheet {
+                        exh: exh.clone(),
+                        pages,
+                    },
+                )
+            })
+            .collect();
+
+        (exh, sheets)
+    }
+
+    /// Writes the header and the pages for every language, and adds this sheet to `root_exl`.
+    ///
+    /// Returns a list of files with their game paths (e.g. `exd/customthing_0_en.exd`), including the updated `exd/root.exl`.
+    pub fn write(&self, root_exl: &mut EXL) -> crate::Result<Vec<(String, ByteBuffer)>> {
+        let (exh, sheets) = self.build();
+        let filename = self.name.to_lowercase();
+
+        let mut files = vec![(
+            format!("exd/{filename}.exh"),
+            exh.write_to_buffer(Platform::Win32)?,
+        )];
+
+        for (language, sheet) in &sheets {
+            for (page, pagination) in sheet.pages.iter().zip(&exh.pages) {
+                let data = page.write_to_buffer(&exh).ok_or(Error::InvalidFile)?;
+                files.push((
+                    format!(
+                        "exd/{}",
+                        EXD::calculate_filename(&filename, *language, pagination)
+                    ),
+                    data,
+                ));
+            }
+        }
+
+        root_exl.insert(&self.name, self.list_id);
+        files.push((
+            "exd/root.exl".to_string(),
+            root_exl.write_to_buffer(Platform::Win32)?,
+        ));
+
+        Ok(files)
+    }
+
+    /// Every row ID across all languages, sorted.
+    fn row_ids(&self) -> Vec<u32> {
+        let mut ids: Vec<u32> = self
+            .rows
+            .iter()
+            .flat_map(|(_, rows)| rows.keys().copied())
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    fn all_rows_mut(&mut self) -> impl Iterator<Item = &mut Row> {
+        self.rows
+            .iter_mut()
+            .flat_map(|(_, rows)| rows.values_mut())
+            .flat_map(|subrows| subrows.values_mut())
+    }
+
+    /// Ensures `row` can be inserted into a sheet described by `exh`.
+    pub(super) fn check_row(
+        exh: &EXH,
+        row_id: u32,
+        subrow_id: u16,
+        row: &Row,
+    ) -> crate::Result<()> {
+        if exh.header.row_kind == SheetRowKind::SingleRow && subrow_id != 0 {
+            return Err(Error::InvalidData {
+                reason: format!(
+                    "can't insert subrow {row_id}.{subrow_id} into a sheet without subrows"
+                ),
+            });
+        }
+
+        if row.columns.len() != exh.column_definitions.len() {
+            return Err(Error::InvalidData {
+                reason: format!(
+                    "row {row_id} has {} columns, but the sheet has {}",
+                    row.columns.len(),
+                    exh.column_definitions.len()
+                ),
+            });
+        }
+
+        for (i, (field, definition)) in row.columns.iter().zip(&exh.column_definitions).enumerate()
+        {
+            if !field.matches(definition.data_type) {
+                return Err(Error::InvalidData {
+                    reason: format!(
+                        "column {i} of row {row_id} is {field:?}, but should be {:?}",
+                        definition.data_type
+                    ),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finds where a new column of `data_type` should go.
+    fn next_offset(&self, data_type: ColumnDataType) -> u16 {
+        let columns = &self.exh.column_definitions;
+
+        // Try to fit it into a byte that already has other packed booleans
+        if data_type.is_packed_bool()
+            && let Some(existing) = columns.iter().find(|x| {
+                x.data_type.is_packed_bool()
+                    && !columns
+                        .iter()
+                        .any(|y| y.offset == x.offset && y.data_type == data_type)
+            })
+        {
+            return existing.offset;
+        }
+
+        let end = columns
+            .iter()
+            .map(|x| x.offset + x.data_type.size())
+            .max()
+            .unwrap_or_default();
+
+        // Align it to its own size, like a C struct would
+        end.div_ceil(data_type.size()) * data_type.size()
+    }
+
+    fn calculate_row_size(columns: &[ExcelColumnDefinition]) -> u16 {
+        let end = columns
+            .iter()
+            .map(|x| x.offset + x.data_type.size())
+            .max()
+            .unwrap_or_default();
+
+        (end.div_ceil(4) * 4).max(4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read;
+    use std::path::PathBuf;
+
+    use crate::{ReadableFile, exd::EXD};
+
+    use super::*;
+
+    fn read_test_sheet(exh_name: &str, exd_name: &str) -> (EXH, Sheet) {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/tests");
+        d.push(exh_name);
+        let exh = EXH::from_existing(Platform::Win32, &read(&d).unwrap()).unwrap();
+
+        d.pop();
+        d.push(exd_name);
+        let exd = EXD::from_existing(Platform::Win32, &read(&d).unwrap()).unwrap();
+
+        let sheet = Sheet {
+            exh: exh.clone(),
+            pages: vec![Page::from_exd(&exh, exd)],
+        };
+
+        (exh, sheet)
+    }
+
+    #[test]
+    fn test_column_layout() {
+        let mut builder = SheetBuilder::new("Test", SheetRowKind::SingleRow);
+        builder.add_column(ColumnDataType::UInt8);
+        builder.add_column(ColumnDataType::UInt32);
+        builder.add_column(ColumnDataType::PackedBool0);
+        builder.add_column(ColumnDataType::PackedBool1);
+        builder.add_column(ColumnDataType::PackedBool0);
+
+        let offsets: Vec<u16> = builder.columns().iter().map(|x| x.offset).collect();
+        assert_eq!(offsets, vec![0, 4, 8, 8, 9]);
+
+        assert_eq!(builder.build_header().header.row_size, 12);
+    }
+
+    #[test]
+    fn test_roundtrip_existing() {
+        let (exh, sheet) =
+            read_test_sheet("territorytypetransient.exh", "territorytypetransient_1.exd");
+
+        let mut builder = SheetBuilder::from_sheets(
+            "TerritoryTypeTransient",
+            &exh,
+            &[(Language::None, sheet.clone())],
+        );
+        builder.set_page_size(u32::MAX);
+
+        let (new_exh, sheets) = builder.build();
+        assert_eq!(new_exh.pages[0].start_id, exh.pages[0].start_id);
+        assert_eq!(new_exh.pages[0].row_count, exh.pages[0].row_count);
+        assert_eq!(new_exh.header.row_count, exh.header.row_count);
+        assert_eq!(new_exh.header.row_size, exh.header.row_size);
+        assert_eq!(sheets[0].1.pages[0].entries, sheet.pages[0].entries);
+    }
+
+    #[test]
+    fn test_insert_remove_rows() {
+        let mut builder = SheetBuilder::new("Test", SheetRowKind::SubRows);
+        builder.set_languages(&[Language::English, Language::German]);
+        builder.add_column(ColumnDataType::String);
+        builder.add_column(ColumnDataType::Int16);
+        builder.set_page_size(2);
+
+        let row = |text: &str, value: i16| Row {
+            columns: vec![Field::String(text.to_string()), Field::Int16(value)],
+        };
+
+        builder.insert_row(0, row("zero", 0)).unwrap();
+        builder.insert_subrow(5, 0, row("five", 5)).unwrap();
+        builder.insert_subrow(5, 1, row("five.one", 51)).unwrap();
+        builder.insert_row(10, row("ten", 10)).unwrap();
+        builder
+            .insert_localized_row(Language::German, 10, 0, row("zehn", 10))
+            .unwrap();
+
+        // a (String, Int16) row is accepted, but a row missing a column or with the columns swapped is not
+        assert!(builder.insert_row(1, row("one", 1)).is_ok());
+        assert!(
+            builder
+                .insert_row(
+                    2,
+                    Row {
+                        columns: vec![Field::Int16(2)]
+                    }
+                )
+                .is_err()
+        );
+        assert!(
+            builder
+                .insert_row(
+                    2,
+                    Row {
+                        columns: vec![Field::Int16(2), Field::String(String::new())]
+                    }
+                )
+                .is_err()
+        );
+
+        assert!(builder.remove_row(1));
+        assert!(!builder.remove_row(1));
+
+        // rows 0, 5 (with two subrows) and 10 are left, and with two rows per page 0 and 5 share the first page
+        let exh = builder.build_header();
+        assert_eq!(exh.header.row_count, 3);
+        assert_eq!(exh.pages.len(), 2);
+        assert_eq!(exh.pages[0].start_id, 0);
+        assert_eq!(exh.pages[0].row_count, 6);
+        assert_eq!(exh.pages[1].start_id, 10);
+        assert_eq!(exh.pages[1].row_count, 1);
+
+        let mut root_exl = EXL {
+            version: 2,
+            entries: Vec::new(),
+        };
+        let files = builder.write(&mut root_exl).unwrap();
+        let paths: Vec<&str> = files.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "exd/test.exh",
+                "exd/test_0_en.exd",
+                "exd/test_10_en.exd",
+                "exd/test_0_de.exd",
+                "exd/test_10_de.exd",
+                "exd/root.exl"
+            ]
+        );
+        assert!(root_exl.contains("Test"));
+
+        // read the files back in
+        let written_exh = EXH::from_existing(Platform::Win32, &files[0].1).unwrap();
+        assert_eq!(written_exh.pages.len(), 2);
+
+        let english = Page::from_exd(
+            &written_exh,
+            EXD::from_existing(Platform::Win32, &files[2].1).unwrap(),
+        );
+        let german = Page::from_exd(
+            &written_exh,
+            EXD::from_existing(Platform::Win32, &files[4].1).unwrap(),
+        );
+        assert_eq!(english.entries[0].subrows[0].1, row("ten", 10));
+        assert_eq!(german.entries[0].subrows[0].1, row("zehn", 10));
+
+        let first_page = Page::from_exd(
+            &written_exh,
+            EXD::from_existing(Platform::Win32, &files[1].1).unwrap(),
+        );
+        assert_eq!(first_page.entries.len(), 2);
+        assert_eq!(first_page.entries[1].subrows.len(), 2);
+        assert_eq!(first_page.entries[1].subrows[1].0, 1);
+        assert_eq!(first_page.entries[1].subrows[0].1, row("five", 5));
+        assert_eq!(first_page.entries[1].subrows[1].1, row("five.one", 51));
+    }
+}
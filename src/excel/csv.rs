@@ -0,0 +1,307 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{
+    Error,
+    excel::{Field, Row, Sheet},
+    exh::{ColumnDataType, EXH, SheetRowKind},
+};
+
+impl Sheet {
+    /// Exports this sheet as CSV, which can be opened in most spreadsheet software.
+    ///
+    /// The first line contains the column names, taken from `column_names` (e.g. from [EXDSchema](https://github.com/xivdev/EXDSchema)) or the column index if not given. The second line contains the [type of each column](ColumnDataType::name). The first column is the row ID, which is written as `row.subrow` for sheets with subrows.
+    ///
+    /// Use [from_csv](Self::from_csv) to import it again.
+    pub fn to_csv(&self, column_names: Option<&[&str]>) -> String {
+        let mut output = String::new();
+
+        let mut names = vec!["key".to_string()];
+        names.extend(self.column_names(column_names));
+        write_line(&mut output, &names);
+
+        let mut types = vec!["type".to_string()];
+        types.extend(
+            self.exh
+                .column_definitions
+                .iter()
+                .map(|x| x.data_type.name().to_string()),
+        );
+        write_line(&mut output, &types);
+
+        for (row_id, subrows) in self {
+            for (subrow_id, row) in subrows {
+                let key = match self.exh.header.row_kind {
+                    SheetRowKind::SingleRow => row_id.to_string(),
+                    SheetRowKind::SubRows => format!("{row_id}.{subrow_id}"),
+                };
+
+                let mut values = vec![key];
+                values.extend(row.columns.iter().map(|x| x.to_string()));
+                write_line(&mut output, &values);
+            }
+        }
+
+        output
+    }
+
+    /// Imports a sheet from CSV, in the format written by [to_csv](Self::to_csv).
+    ///
+    /// The column types in the CSV must match the ones in `exh`, and every value is checked against them. Rows are placed in the pages described by `exh`, so if you added new rows use [SheetBuilder](crate::excel::SheetBuilder) instead.
+    pub fn from_csv(exh: &EXH, text: &str) -> crate::Result<Self> {
+        let mut lines = parse(text)?.into_iter();
+
+        let (Some(_names), Some(types)) = (lines.next(), lines.next()) else {
+            return Err(Error::InvalidData {
+                reason: "CSV is missing the name and type lines".to_string(),
+            });
+        };
+
+        let types: Vec<Option<ColumnDataType>> = types
+            .iter()
+            .skip(1)
+            .map(|x| ColumnDataType::from_name(x))
+            .collect();
+        let expected_types: Vec<Option<ColumnDataType>> = exh
+            .column_definitions
+            .iter()
+            .map(|x| Some(x.data_type))
+            .collect();
+        if types != expected_types {
+            return Err(Error::InvalidData {
+                reason: "CSV column types don't match the sheet".to_string(),
+            });
+        }
+
+        let mut rows = Vec::new();
+        for (line_number, values) in lines.enumerate() {
+            // Account for the two header lines
+            let line_number = line_number + 3;
+
+            let invalid = |reason: String| Error::InvalidData {
+                reason: format!("{reason} on line {line_number}"),
+            };
+
+            let Some((key, values)) = values.split_first() else {
+                continue;
+            };
+
+            let (row_id, subrow_id) = match key.split_once('.') {
+                Some((row_id, subrow_id)) => (row_id.parse(), subrow_id.parse()),
+                None => (key.parse(), Ok(0)),
+            };
+            let (Ok(row_id), Ok(subrow_id)) = (row_id, subrow_id) else {
+                return Err(invalid(format!("invalid key {key}")));
+            };
+
+            if values.len() != exh.column_definitions.len() {
+                return Err(invalid(format!(
+                    "expected {} columns but found {}",
+                    exh.column_definitions.len(),
+                    values.len()
+                )));
+            }
+
+            let columns = values
+                .iter()
+                .zip(&exh.column_definitions)
+                .map(|(value, definition)| {
+                    Field::parse(value, definition.data_type).ok_or_else(|| {
+                        invalid(format!(
+                            "{value:?} isn't a valid {}",
+                            definition.data_type.name()
+                        ))
+                    })
+                })
+                .collect::<crate::Result<Vec<Field>>>()?;
+
+            rows.push((row_id, subrow_id, Row { columns }));
+        }
+
+        Self::from_rows(exh, rows)
+    }
+}
+
+fn write_line(output: &mut String, values: &[String]) {
+    for (i, value) in values.iter().enumerate() {
+        if i != 0 {
+            output.push(',');
+        }
+
+        if value.contains([',', '"', '\n', '\r']) || value.starts_with(' ') || value.ends_with(' ')
+        {
+            output.push('"');
+            output.push_str(&value.replace('"', "\"\""));
+            output.push('"');
+        } else {
+            output.push_str(value);
+        }
+    }
+    output.push_str("\r\n");
+}
+
+/// Splits CSV `text` into lines of values, handling quoted values.
+fn parse(text: &str) -> crate::Result<Vec<Vec<String>>> {
+    let mut lines = Vec::new();
+    let mut values = Vec::new();
+    let mut value = String::new();
+
+    let mut chars = text.chars().peekable();
+    let mut in_quotes = false;
+    let mut line_has_data = false;
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    value.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                value.push(c);
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_quotes = true;
+                line_has_data = true;
+            }
+            ',' => {
+                values.push(std::mem::take(&mut value));
+                line_has_data = true;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                if line_has_data || !value.is_empty() {
+                    values.push(std::mem::take(&mut value));
+                    lines.push(std::mem::take(&mut values));
+                }
+                line_has_data = false;
+            }
+            c => value.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(Error::InvalidData {
+            reason: "CSV has an unterminated quote".to_string(),
+        });
+    }
+
+    if line_has_data || !value.is_empty() {
+        values.push(value);
+        lines.push(values);
+    }
+
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read;
+    use std::path::PathBuf;
+
+    use crate::{
+        Language, ReadableFile,
+        common::Platform,
+        excel::{Page, SheetBuilder},
+        exd::EXD,
+    };
+
+    use super::*;
+
+    fn read_test_sheet(exh_name: &str, exd_name: &str) -> Sheet {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/tests");
+        d.push(exh_name);
+        let exh = EXH::from_existing(Platform::Win32, &read(&d).unwrap()).unwrap();
+
+        d.pop();
+        d.push(exd_name);
+        let exd = EXD::from_existing(Platform::Win32, &read(&d).unwrap()).unwrap();
+
+        Sheet {
+            pages: vec![Page::from_exd(&exh, exd)],
+            exh,
+        }
+    }
+
+    #[test]
+    fn test_export() {
+        let sheet = read_test_sheet("openingsystemdefine.exh", "openingsystemdefine_0.exd");
+
+        let csv = sheet.to_csv(Some(&["Name"]));
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("key,Name,1"));
+        assert_eq!(lines.next(), Some("type,str,uint32"));
+        assert_eq!(lines.next(), Some("0,HOWTO_MOVE_AND_CAMERA,1"));
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for (exh, exd) in [
+            ("openingsystemdefine.exh", "openingsystemdefine_0.exd"),
+            ("physicsgroup.exh", "physicsgroup_1.exd"),
+            ("territorytypetransient.exh", "territorytypetransient_1.exd"),
+        ] {
+            let sheet = read_test_sheet(exh, exd);
+
+            let imported = Sheet::from_csv(&sheet.exh, &sheet.to_csv(None)).unwrap();
+            assert_eq!(imported.pages[0].entries, sheet.pages[0].entries);
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_subrows() {
+        let mut builder = SheetBuilder::new("Test", SheetRowKind::SubRows);
+        builder.add_column(ColumnDataType::String);
+        builder.add_column(ColumnDataType::PackedBool3);
+        builder.add_column(ColumnDataType::Float32);
+
+        let row = |text: &str, flag: bool, value: f32| Row {
+            columns: vec![
+                Field::String(text.to_string()),
+                Field::Bool(flag),
+                Field::Float32(value),
+            ],
+        };
+        builder
+            .insert_subrow(1, 0, row("a, \"b\"\nc", true, 0.1))
+            .unwrap();
+        builder
+            .insert_subrow(1, 1, row(" ", false, -1e-30))
+            .unwrap();
+        builder
+            .insert_subrow(2, 0, row("", true, f32::MAX))
+            .unwrap();
+
+        let (exh, sheets) = builder.build();
+        let (language, sheet) = &sheets[0];
+        assert_eq!(*language, Language::None);
+
+        let imported = Sheet::from_csv(&exh, &sheet.to_csv(None)).unwrap();
+        assert_eq!(imported.pages[0].entries, sheet.pages[0].entries);
+    }
+
+    #[test]
+    fn test_type_checking() {
+        let sheet = read_test_sheet("openingsystemdefine.exh", "openingsystemdefine_0.exd");
+
+        // wrong column type
+        assert!(Sheet::from_csv(&sheet.exh, "key,0,1\ntype,str,int32\n").is_err());
+        // value doesn't fit the type
+        assert!(Sheet::from_csv(&sheet.exh, "key,0,1\ntype,str,uint32\n0,abc,-1\n").is_err());
+        // missing a column
+        assert!(Sheet::from_csv(&sheet.exh, "key,0,1\ntype,str,uint32\n0,abc\n").is_err());
+        // subrows in a sheet that doesn't have them
+        assert!(Sheet::from_csv(&sheet.exh, "key,0,1\ntype,str,uint32\n0.1,abc,1\n").is_err());
+        // outside of any page
+        assert!(Sheet::from_csv(&sheet.exh, "key,0,1\ntype,str,uint32\n100,abc,1\n").is_err());
+
+        assert!(Sheet::from_csv(&sheet.exh, "key,0,1\ntype,str,uint32\n0,abc,1\n").is_ok());
+    }
+}
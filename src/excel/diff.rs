@@ -0,0 +1,464 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+use crate::{
+    Language,
+    excel::{Field, Row, Sheet},
+    exh::{ColumnDataType, EXH},
+    resource::{
+        Resource, generic_get_all_sheet_names, generic_read_excel_sheet,
+        generic_read_excel_sheet_header,
+    },
+};
+
+/// A change to the column layout of a sheet.
+///
+/// Columns are matched by their index, which is how they're usually referred to (e.g. in [EXDSchema](https://github.com/xivdev/EXDSchema).)
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnChange {
+    /// A column was added at `index`.
+    Added {
+        /// Index of the column.
+        index: usize,
+        /// Type of the new column.
+        data_type: ColumnDataType,
+    },
+    /// The column at `index` was removed.
+    Removed {
+        /// Index of the column.
+        index: usize,
+        /// Type of the old column.
+        data_type: ColumnDataType,
+    },
+    /// The column at `index` changed type.
+    TypeChanged {
+        /// Index of the column.
+        index: usize,
+        /// The old type.
+        old: ColumnDataType,
+        /// The new type.
+        new: ColumnDataType,
+    },
+    /// The column at `index` kept its type, but moved to a different place in the row.
+    OffsetChanged {
+        /// Index of the column.
+        index: usize,
+        /// The old offset, in bytes.
+        old: u16,
+        /// The new offset, in bytes.
+        new: u16,
+    },
+}
+
+/// A field that changed between two versions of a row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    /// Index of the column.
+    pub column: usize,
+    /// The old value, or [None] if this column didn't exist.
+    pub old: Option<Field>,
+    /// The new value, or [None] if this column no longer exists.
+    pub new: Option<Field>,
+}
+
+/// A row that exists in both versions of a sheet, but has different fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowChange {
+    /// The row ID.
+    pub row_id: u32,
+    /// The subrow ID, which is always 0 for sheets without subrows.
+    pub subrow_id: u16,
+    /// The fields that changed.
+    pub fields: Vec<FieldChange>,
+}
+
+/// The differences between two versions of the same [Sheet].
+///
+/// # Example
+///
+/// ```no_run
+/// # use physis::excel::SheetDiff;
+/// # use physis::resource::{ResourceResolver, SqPackResource};
+/// # use physis::Language;
+/// # let mut old_resolver = ResourceResolver::new();
+/// # let mut new_resolver = ResourceResolver::new();
+/// let old_exh = old_resolver.read_excel_sheet_header("Item")?;
+/// let old_sheet = old_resolver.read_excel_sheet(&old_exh, "Item", Language::English)?;
+///
+/// let new_exh = new_resolver.read_excel_sheet_header("Item")?;
+/// let new_sheet = new_resolver.read_excel_sheet(&new_exh, "Item", Language::English)?;
+///
+/// let diff = SheetDiff::compare(&old_sheet, &new_sheet);
+/// for (row_id, subrow_id) in &diff.added_rows {
+///     println!("New item: {row_id}");
+/// }
+/// # Ok::<(), physis::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SheetDiff {
+    /// Changes to the column layout.
+    pub column_changes: Vec<ColumnChange>,
+    /// Rows (and subrows) that only exist in the new sheet, as `(row ID, subrow ID)`.
+    pub added_rows: Vec<(u32, u16)>,
+    /// Rows (and subrows) that only exist in the old sheet, as `(row ID, subrow ID)`.
+    pub removed_rows: Vec<(u32, u16)>,
+    /// Rows that exist in both sheets, but whose fields changed.
+    pub changed_rows: Vec<RowChange>,
+}
+
+impl SheetDiff {
+    /// Compares the `old` and `new` versions of a sheet. They may have different column layouts.
+    pub fn compare(old: &Sheet, new: &Sheet) -> Self {
+        let column_changes = Self::compare_columns(&old.exh, &new.exh);
+
+        let old_rows = Self::collect_rows(old);
+        let new_rows = Self::collect_rows(new);
+
+        let mut diff = SheetDiff {
+            column_changes,
+            ..Default::default()
+        };
+
+        for (key, old_row) in &old_rows {
+            match new_rows.get(key) {
+                Some(new_row) => {
+                    let fields = Self::compare_fields(old_row, new_row);
+                    if !fields.is_empty() {
+                        diff.changed_rows.push(RowChange {
+                            row_id: key.0,
+                            subrow_id: key.1,
+                            fields,
+                        });
+                    }
+                }
+                None => diff.removed_rows.push(*key),
+            }
+        }
+
+        diff.added_rows = new_rows
+            .keys()
+            .filter(|key| !old_rows.contains_key(key))
+            .copied()
+            .collect();
+
+        diff
+    }
+
+    /// Returns true if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.column_changes.is_empty()
+            && self.added_rows.is_empty()
+            && self.removed_rows.is_empty()
+            && self.changed_rows.is_empty()
+    }
+
+    fn compare_columns(old: &EXH, new: &EXH) -> Vec<ColumnChange> {
+        let old_columns = &old.column_definitions;
+        let new_columns = &new.column_definitions;
+
+        let mut changes = Vec::new();
+        for index in 0..old_columns.len().max(new_columns.len()) {
+            match (old_columns.get(index), new_columns.get(index)) {
+                (Some(old), Some(new)) => {
+                    if old.data_type != new.data_type {
+                        changes.push(ColumnChange::TypeChanged {
+                            index,
+                            old: old.data_type,
+                            new: new.data_type,
+                        });
+                    } else if old.offset != new.offset {
+                        changes.push(ColumnChange::OffsetChanged {
+                            index,
+                            old: old.offset,
+                            new: new.offset,
+                        });
+                    }
+                }
+                (Some(old), None) => changes.push(ColumnChange::Removed {
+                    index,
+                    data_type: old.data_type,
+                }),
+                (None, Some(new)) => changes.push(ColumnChange::Added {
+                    index,
+                    data_type: new.data_type,
+                }),
+                (None, None) => unreachable!(),
+            }
+        }
+
+        changes
+    }
+
+    fn collect_rows(sheet: &Sheet) -> BTreeMap<(u32, u16), &Row> {
+        let mut rows = BTreeMap::new();
+        for (row_id, subrows) in sheet {
+            for (subrow_id, row) in subrows {
+                rows.insert((row_id, *subrow_id), row);
+            }
+        }
+        rows
+    }
+
+    fn compare_fields(old: &Row, new: &Row) -> Vec<FieldChange> {
+        (0..old.columns.len().max(new.columns.len()))
+            .filter_map(|column| {
+                let old = old.columns.get(column);
+                let new = new.columns.get(column);
+                (old != new).then(|| FieldChange {
+                    column,
+                    old: old.cloned(),
+                    new: new.cloned(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// The differences between every Excel sheet in two versions of the game.
+///
+/// # Example
+///
+/// ```no_run
+/// # use physis::excel::ExcelDiff;
+/// # use physis::resource::SqPackResource;
+/// # use physis::Language;
+/// let mut old_game = SqPackResource::from_existing("old/game");
+/// let mut new_game = SqPackResource::from_existing("new/game");
+///
+/// let diff = ExcelDiff::compare(&mut old_game, &mut new_game, Language::English)?;
+/// println!("{}", diff.summary());
+/// # Ok::<(), physis::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ExcelDiff {
+    /// Sheets that only exist in the new version.
+    pub added_sheets: Vec<String>,
+    /// Sheets that only exist in the old version.
+    pub removed_sheets: Vec<String>,
+    /// Sheets that exist in both versions but changed, with their differences.
+    pub changed_sheets: Vec<(String, SheetDiff)>,
+    /// Sheets that exist in both versions, but couldn't be read from at least one of them.
+    pub unreadable_sheets: Vec<String>,
+}
+
+impl ExcelDiff {
+    /// Compares every sheet listed in the root list of `old` and `new`.
+    ///
+    /// Sheets are read in `language` if they support it, otherwise the first language they do support (usually [Language::None].)
+    pub fn compare<A: Resource + ?Sized, B: Resource + ?Sized>(
+        old: &mut A,
+        new: &mut B,
+        language: Language,
+    ) -> crate::Result<Self> {
+        let old_names = generic_get_all_sheet_names(old)?;
+        let new_names = generic_get_all_sheet_names(new)?;
+
+        let old_set: HashSet<&String> = old_names.iter().collect();
+        let new_set: HashSet<&String> = new_names.iter().collect();
+
+        let mut diff = ExcelDiff {
+            added_sheets: new_names
+                .iter()
+                .filter(|name| !old_set.contains(name))
+                .cloned()
+                .collect(),
+            removed_sheets: old_names
+                .iter()
+                .filter(|name| !new_set.contains(name))
+                .cloned()
+                .collect(),
+            ..Default::default()
+        };
+
+        for name in old_names.iter().filter(|name| new_set.contains(name)) {
+            let (Some(old_sheet), Some(new_sheet)) = (
+                read_sheet(old, name, language),
+                read_sheet(new, name, language),
+            ) else {
+                diff.unreadable_sheets.push(name.clone());
+                continue;
+            };
+
+            let sheet_diff = SheetDiff::compare(&old_sheet, &new_sheet);
+            if !sheet_diff.is_empty() {
+                diff.changed_sheets.push((name.clone(), sheet_diff));
+            }
+        }
+
+        Ok(diff)
+    }
+
+    /// Returns a human-readable summary of what changed, suitable for patch notes.
+    pub fn summary(&self) -> String {
+        let mut output = String::new();
+
+        for name in &self.added_sheets {
+            writeln!(output, "Added sheet {name}").unwrap();
+        }
+        for name in &self.removed_sheets {
+            writeln!(output, "Removed sheet {name}").unwrap();
+        }
+
+        for (name, diff) in &self.changed_sheets {
+            let mut parts = Vec::new();
+
+            let mut count = |amount: usize, what: &str| {
+                if amount > 0 {
+                    parts.push(format!(
+                        "{amount} {what}{}",
+                        if amount == 1 { "" } else { "s" }
+                    ));
+                }
+            };
+            count(diff.added_rows.len(), "added row");
+            count(diff.removed_rows.len(), "removed row");
+            count(diff.changed_rows.len(), "changed row");
+            count(diff.column_changes.len(), "column change");
+
+            writeln!(output, "Changed sheet {name}: {}", parts.join(", ")).unwrap();
+        }
+
+        for name in &self.unreadable_sheets {
+            writeln!(output, "Couldn't compare sheet {name}").unwrap();
+        }
+
+        output
+    }
+}
+
+fn read_sheet<R: Resource + ?Sized>(
+    resource: &mut R,
+    name: &str,
+    language: Language,
+) -> Option<Sheet> {
+    let exh = generic_read_excel_sheet_header(resource, name).ok()?;
+
+    let language = if exh.languages.contains(&language) {
+        language
+    } else {
+        *exh.languages.first()?
+    };
+
+    generic_read_excel_sheet(resource, &exh, name, language).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{excel::SheetBuilder, exh::SheetRowKind, resource::MemoryResource};
+
+    use super::*;
+
+    fn row(text: &str, value: u32) -> Row {
+        Row {
+            columns: vec![Field::String(text.to_string()), Field::UInt32(value)],
+        }
+    }
+
+    fn old_builder() -> SheetBuilder {
+        let mut builder = SheetBuilder::new("Item", SheetRowKind::SingleRow);
+        builder.add_column(ColumnDataType::String);
+        builder.add_column(ColumnDataType::UInt32);
+        builder.insert_row(0, row("Gil", 1)).unwrap();
+        builder.insert_row(1, row("Potion", 5)).unwrap();
+        builder.insert_row(2, row("Ether", 10)).unwrap();
+        builder
+    }
+
+    #[test]
+    fn test_sheet_diff() {
+        let old = old_builder();
+
+        let mut new = old.clone();
+        new.add_column(ColumnDataType::Int8);
+        new.remove_row(0);
+        new.insert_row(
+            1,
+            Row {
+                columns: vec![
+                    Field::String("Hi-Potion".to_string()),
+                    Field::UInt32(5),
+                    Field::Int8(1),
+                ],
+            },
+        )
+        .unwrap();
+        new.insert_row(
+            3,
+            Row {
+                columns: vec![
+                    Field::String("Elixir".to_string()),
+                    Field::UInt32(100),
+                    Field::Int8(0),
+                ],
+            },
+        )
+        .unwrap();
+
+        let (_, old_sheets) = old.build();
+        let (_, new_sheets) = new.build();
+
+        let diff = SheetDiff::compare(&old_sheets[0].1, &new_sheets[0].1);
+        assert_eq!(
+            diff.column_changes,
+            vec![ColumnChange::Added {
+                index: 2,
+                data_type: ColumnDataType::Int8
+            }]
+        );
+        assert_eq!(diff.added_rows, vec![(3, 0)]);
+        assert_eq!(diff.removed_rows, vec![(0, 0)]);
+
+        // row 2 only gained the new column
+        assert_eq!(diff.changed_rows.len(), 2);
+        assert_eq!(
+            diff.changed_rows[0].fields,
+            vec![
+                FieldChange {
+                    column: 0,
+                    old: Some(Field::String("Potion".to_string())),
+                    new: Some(Field::String("Hi-Potion".to_string()))
+                },
+                FieldChange {
+                    column: 2,
+                    old: None,
+                    new: Some(Field::Int8(1))
+                }
+            ]
+        );
+        assert_eq!(diff.changed_rows[1].row_id, 2);
+
+        assert!(SheetDiff::compare(&old_sheets[0].1, &old_sheets[0].1).is_empty());
+    }
+
+    #[test]
+    fn test_excel_diff() {
+        let item = old_builder();
+
+        let mut changed_item = item.clone();
+        changed_item.insert_row(3, row("Elixir", 100)).unwrap();
+
+        let mut unchanged = SheetBuilder::new("Unchanged", SheetRowKind::SingleRow);
+        unchanged.add_column(ColumnDataType::UInt8);
+
+        let removed = SheetBuilder::new("Removed", SheetRowKind::SingleRow);
+        let added = SheetBuilder::new("Added", SheetRowKind::SubRows);
+
+        let mut old = MemoryResource::from_sheets(&[item, unchanged.clone(), removed]);
+        let mut new = MemoryResource::from_sheets(&[changed_item, unchanged, added]);
+
+        let diff = ExcelDiff::compare(&mut old, &mut new, Language::English).unwrap();
+        assert_eq!(diff.added_sheets, vec!["Added".to_string()]);
+        assert_eq!(diff.removed_sheets, vec!["Removed".to_string()]);
+        assert_eq!(diff.changed_sheets.len(), 1);
+        assert_eq!(diff.changed_sheets[0].0, "Item");
+        assert!(diff.unreadable_sheets.is_empty());
+
+        assert_eq!(
+            diff.summary(),
+            "Added sheet Added\nRemoved sheet Removed\nChanged sheet Item: 1 added row\n"
+        );
+    }
+}
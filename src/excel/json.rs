@@ -0,0 +1,297 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{
+    Error,
+    excel::{Field, Row, Sheet},
+    exh::{ColumnDataType, EXH, SheetRowKind},
+    json::JsonValue,
+};
+
+impl Sheet {
+    /// Exports this sheet as JSON.
+    ///
+    /// The output contains a `columns` array with the name and [type](ColumnDataType::name) of each column, and a `rows` array where each row has an `id`, `subrow` (only for sheets with subrows) and `fields` object keyed by column name. Column names are taken from `column_names` (e.g. from [EXDSchema](https://github.com/xivdev/EXDSchema)) or the column index if not given.
+    ///
+    /// Use [from_json](Self::from_json) to import it again.
+    pub fn to_json(&self, column_names: Option<&[&str]>) -> String {
+        let names = self.column_names(column_names);
+
+        let columns = names
+            .iter()
+            .zip(&self.exh.column_definitions)
+            .map(|(name, definition)| {
+                JsonValue::Object(vec![
+                    ("name".to_string(), JsonValue::String(name.clone())),
+                    (
+                        "type".to_string(),
+                        JsonValue::String(definition.data_type.name().to_string()),
+                    ),
+                ])
+            })
+            .collect();
+
+        let mut rows = Vec::new();
+        for (row_id, subrows) in self {
+            for (subrow_id, row) in subrows {
+                let mut object = vec![("id".to_string(), JsonValue::number(row_id))];
+                if self.exh.header.row_kind == SheetRowKind::SubRows {
+                    object.push(("subrow".to_string(), JsonValue::number(subrow_id)));
+                }
+
+                let fields = names
+                    .iter()
+                    .zip(&row.columns)
+                    .map(|(name, field)| (name.clone(), field_to_json(field)))
+                    .collect();
+                object.push(("fields".to_string(), JsonValue::Object(fields)));
+
+                rows.push(JsonValue::Object(object));
+            }
+        }
+
+        JsonValue::Object(vec![
+            ("columns".to_string(), JsonValue::Array(columns)),
+            ("rows".to_string(), JsonValue::Array(rows)),
+        ])
+        .to_pretty_string()
+    }
+
+    /// Imports a sheet from JSON, in the format written by [to_json](Self::to_json).
+    ///
+    /// The column types in the JSON must match the ones in `exh`, and every value is checked against them. Rows are placed in the pages described by `exh`, so if you added new rows use [SheetBuilder](crate::excel::SheetBuilder) instead.
+    pub fn from_json(exh: &EXH, text: &str) -> crate::Result<Self> {
+        let invalid = |reason: String| Error::InvalidData { reason };
+
+        let root = JsonValue::parse(text)?;
+
+        let columns = root
+            .get("columns")
+            .and_then(|x| x.as_array())
+            .ok_or_else(|| invalid("JSON is missing the columns array".to_string()))?;
+
+        let mut names = Vec::with_capacity(columns.len());
+        let mut types = Vec::with_capacity(columns.len());
+        for column in columns {
+            names.push(
+                column
+                    .get("name")
+                    .and_then(|x| x.as_str())
+                    .unwrap_or_default(),
+            );
+            types.push(
+                column
+                    .get("type")
+                    .and_then(|x| x.as_str())
+                    .and_then(ColumnDataType::from_name),
+            );
+        }
+
+        let expected_types: Vec<Option<ColumnDataType>> = exh
+            .column_definitions
+            .iter()
+            .map(|x| Some(x.data_type))
+            .collect();
+        if types != expected_types {
+            return Err(invalid(
+                "JSON column types don't match the sheet".to_string(),
+            ));
+        }
+
+        let json_rows = root
+            .get("rows")
+            .and_then(|x| x.as_array())
+            .ok_or_else(|| invalid("JSON is missing the rows array".to_string()))?;
+
+        let mut rows = Vec::with_capacity(json_rows.len());
+        for json_row in json_rows {
+            let row_id: u32 = json_row
+                .get("id")
+                .and_then(|x| x.as_number())
+                .ok_or_else(|| invalid("row is missing its id".to_string()))?;
+            let subrow_id: u16 = match json_row.get("subrow") {
+                Some(subrow) => subrow
+                    .as_number()
+                    .ok_or_else(|| invalid(format!("row {row_id} has an invalid subrow")))?,
+                None => 0,
+            };
+
+            let fields = json_row
+                .get("fields")
+                .ok_or_else(|| invalid(format!("row {row_id} is missing its fields")))?;
+
+            let columns = names
+                .iter()
+                .zip(&exh.column_definitions)
+                .map(|(name, definition)| {
+                    let value = fields
+                        .get(name)
+                        .ok_or_else(|| invalid(format!("row {row_id} is missing column {name}")))?;
+
+                    field_from_json(value, definition.data_type).ok_or_else(|| {
+                        invalid(format!(
+                            "column {name} of row {row_id} isn't a valid {}",
+                            definition.data_type.name()
+                        ))
+                    })
+                })
+                .collect::<crate::Result<Vec<Field>>>()?;
+
+            rows.push((row_id, subrow_id, Row { columns }));
+        }
+
+        Self::from_rows(exh, rows)
+    }
+}
+
+fn field_to_json(field: &Field) -> JsonValue {
+    match field {
+        Field::String(value) => JsonValue::String(value.clone()),
+        Field::Bool(value) => JsonValue::Bool(*value),
+        // JSON doesn't have a way to express these as numbers
+        Field::Float32(value) if !value.is_finite() => JsonValue::String(value.to_string()),
+        field => JsonValue::number(field),
+    }
+}
+
+fn field_from_json(value: &JsonValue, data_type: ColumnDataType) -> Option<Field> {
+    match data_type {
+        ColumnDataType::String => Some(Field::String(value.as_str()?.to_string())),
+        ColumnDataType::Bool => Some(Field::Bool(value.as_bool()?)),
+        data_type if data_type.is_packed_bool() => Some(Field::Bool(value.as_bool()?)),
+        data_type => match value {
+            JsonValue::Number(text) => Field::parse(text, data_type),
+            // Non-finite floats are written as strings
+            JsonValue::String(text) if data_type == ColumnDataType::Float32 => {
+                Field::parse(text, data_type)
+            }
+            _ => None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read;
+    use std::path::PathBuf;
+
+    use crate::{
+        ReadableFile,
+        common::Platform,
+        excel::{Page, SheetBuilder},
+        exd::EXD,
+    };
+
+    use super::*;
+
+    fn read_test_sheet(exh_name: &str, exd_name: &str) -> Sheet {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/tests");
+        d.push(exh_name);
+        let exh = EXH::from_existing(Platform::Win32, &read(&d).unwrap()).unwrap();
+
+        d.pop();
+        d.push(exd_name);
+        let exd = EXD::from_existing(Platform::Win32, &read(&d).unwrap()).unwrap();
+
+        Sheet {
+            pages: vec![Page::from_exd(&exh, exd)],
+            exh,
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for (exh, exd) in [
+            ("gcshop.exh", "gcshop_1441792.exd"),
+            ("openingsystemdefine.exh", "openingsystemdefine_0.exd"),
+            ("physicsgroup.exh", "physicsgroup_1.exd"),
+        ] {
+            let sheet = read_test_sheet(exh, exd);
+
+            let imported = Sheet::from_json(&sheet.exh, &sheet.to_json(None)).unwrap();
+            assert_eq!(imported.pages[0].entries, sheet.pages[0].entries);
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_subrows() {
+        let mut builder = SheetBuilder::new("Test", SheetRowKind::SubRows);
+        builder.add_column(ColumnDataType::String);
+        builder.add_column(ColumnDataType::PackedBool7);
+        builder.add_column(ColumnDataType::Float32);
+        builder.add_column(ColumnDataType::UInt64);
+
+        let row = |text: &str, flag: bool, value: f32| Row {
+            columns: vec![
+                Field::String(text.to_string()),
+                Field::Bool(flag),
+                Field::Float32(value),
+                Field::UInt64(u64::MAX),
+            ],
+        };
+        builder
+            .insert_subrow(1, 0, row("\u{2}\u{13}\"quoted\"", true, f32::INFINITY))
+            .unwrap();
+        builder.insert_subrow(1, 3, row("", false, 0.3)).unwrap();
+
+        let (exh, sheets) = builder.build();
+        let sheet = &sheets[0].1;
+
+        let json = sheet.to_json(Some(&["Text", "Flag", "Value", "Big"]));
+        let imported = Sheet::from_json(&exh, &json).unwrap();
+        assert_eq!(imported.pages[0].entries, sheet.pages[0].entries);
+    }
+
+    #[test]
+    fn test_type_checking() {
+        let sheet = read_test_sheet("openingsystemdefine.exh", "openingsystemdefine_0.exd");
+
+        let columns =
+            r#""columns": [{"name": "a", "type": "str"}, {"name": "b", "type": "uint32"}]"#;
+
+        assert!(
+            Sheet::from_json(
+                &sheet.exh,
+                &format!(
+                    r#"{{ {columns}, "rows": [{{"id": 0, "fields": {{"a": "x", "b": 1}}}}] }}"#
+                )
+            )
+            .is_ok()
+        );
+        // value doesn't fit the type
+        assert!(
+            Sheet::from_json(
+                &sheet.exh,
+                &format!(
+                    r#"{{ {columns}, "rows": [{{"id": 0, "fields": {{"a": "x", "b": -1}}}}] }}"#
+                )
+            )
+            .is_err()
+        );
+        // wrong value type
+        assert!(
+            Sheet::from_json(
+                &sheet.exh,
+                &format!(r#"{{ {columns}, "rows": [{{"id": 0, "fields": {{"a": 5, "b": 1}}}}] }}"#)
+            )
+            .is_err()
+        );
+        // missing a column
+        assert!(
+            Sheet::from_json(
+                &sheet.exh,
+                &format!(r#"{{ {columns}, "rows": [{{"id": 0, "fields": {{"a": "x"}}}}] }}"#)
+            )
+            .is_err()
+        );
+        // column types don't match
+        assert!(
+            Sheet::from_json(
+                &sheet.exh,
+                r#"{ "columns": [{"name": "a", "type": "str"}], "rows": [] }"#
+            )
+            .is_err()
+        );
+    }
+}
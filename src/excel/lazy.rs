@@ -0,0 +1,304 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::io::{Cursor, Seek, SeekFrom};
+
+use binrw::BinRead;
+
+use crate::{
+    Language,
+    excel::{Field, Row},
+    exd::{DataSectionHeader, EXD, EXDHeader, SubRowHeader},
+    exd_file_operations::read_row,
+    exh::{EXH, SheetRowKind},
+    resource::{Resource, generic_read_excel_exd},
+};
+
+/// An Excel sheet that only reads pages when they're needed.
+///
+/// Unlike [Sheet](crate::excel::Sheet), this doesn't parse every page upfront. When a row is requested, only the page containing it is read from the [Resource] and its columns are decoded on demand. This is useful for large sheets like `Quest` or `Item` when you only need a handful of rows.
+///
+/// To create one, use [ResourceResolver::read_lazy_excel_sheet](crate::resource::ResourceResolver::read_lazy_excel_sheet) or [SqPackResource::read_lazy_excel_sheet](crate::resource::SqPackResource::read_lazy_excel_sheet).
+///
+/// # Example
+///
+/// ```no_run
+/// # use physis::resource::{UnpackedResource, ResourceResolver};
+/// # use physis::Language;
+/// # use physis::Error;
+/// # let resource = UnpackedResource::from_existing(".");
+/// # let mut resolver = ResourceResolver::new();
+/// # resolver.add_source(resource);
+/// let exh = resolver.read_excel_sheet_header("Item")?;
+/// let mut sheet = resolver.read_lazy_excel_sheet(&exh, "Item", Language::English);
+/// if let Some(row) = sheet.row(1)? {
+///     println!("{:#?}", row.column(9)); // Only reads this column
+/// }
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Clone)]
+pub struct LazySheet {
+    resource: Box<dyn Resource>,
+    exh: EXH,
+    name: String,
+    language: Language,
+    /// Pages that have been read so far, indexed the same as [EXH::pages].
+    pages: Vec<Option<EXD>>,
+}
+
+impl LazySheet {
+    /// Creates a new lazy sheet, which reads pages from `resource` as they're requested.
+    ///
+    /// No data is read until you ask for a row.
+    pub fn new(resource: impl Resource, exh: &EXH, name: &str, language: Language) -> Self {
+        Self {
+            resource: Box::new(resource),
+            exh: exh.clone(),
+            name: name.to_string(),
+            language,
+            pages: vec![None; exh.pages.len()],
+        }
+    }
+
+    /// The EXH for this sheet.
+    pub fn exh(&self) -> &EXH {
+        &self.exh
+    }
+
+    /// Returns whether the page at `index` has been read yet.
+    pub fn is_page_loaded(&self, index: usize) -> bool {
+        self.pages.get(index).is_some_and(|page| page.is_some())
+    }
+
+    /// Reads the page at `index` if it hasn't been already.
+    fn load_page(&mut self, index: usize) -> crate::Result<()> {
+        if self.pages[index].is_none() {
+            let exd = generic_read_excel_exd(
+                self.resource.as_mut(),
+                &self.name,
+                &self.exh,
+                self.language,
+                index,
+            )?;
+            self.pages[index] = Some(exd);
+        }
+
+        Ok(())
+    }
+
+    /// Finds a row matching `row_id` and returns it, otherwise returns [None]. Reads the page containing this row if needed.
+    ///
+    /// For sheets that have subrows, this will always return subrow 0. It's recommended to use [subrow](Self::subrow) instead.
+    pub fn row(&mut self, row_id: u32) -> crate::Result<Option<LazyRow<'_>>> {
+        match self.exh.header.row_kind {
+            SheetRowKind::SingleRow => {
+                let Some(page_index) = self.exh.find_page(row_id) else {
+                    return Ok(None);
+                };
+
+                self.load_page(page_index)?;
+                let exd = self.pages[page_index].as_ref().unwrap();
+
+                let Some(offset) = data_offset(exd, row_id) else {
+                    return Ok(None);
+                };
+
+                Ok(Some(LazyRow {
+                    exh: &self.exh,
+                    exd,
+                    offset: offset + DataSectionHeader::SIZE as u64,
+                }))
+            }
+            SheetRowKind::SubRows => self.subrow(row_id, 0),
+        }
+    }
+
+    /// Finds a row matching `row_id` and `subrow_id` and returns it, otherwise returns [None]. Reads the page containing this row if needed.
+    ///
+    /// For sheets that don't have subrows, this will always return [None].
+    pub fn subrow(&mut self, row_id: u32, subrow_id: u16) -> crate::Result<Option<LazyRow<'_>>> {
+        // Grabbing a subrow here never makes sense, and is just misuse of the API.
+        if self.exh.header.row_kind == SheetRowKind::SingleRow {
+            return Ok(None);
+        }
+
+        let Some(page_index) = self.exh.find_page(row_id) else {
+            return Ok(None);
+        };
+
+        self.load_page(page_index)?;
+        let exd = self.pages[page_index].as_ref().unwrap();
+
+        let Some(offset) = data_offset(exd, row_id) else {
+            return Ok(None);
+        };
+
+        let mut cursor = Cursor::new(&exd.remaining_data);
+        cursor.seek(SeekFrom::Start(offset))?;
+
+        let row_header = DataSectionHeader::read(&mut cursor)?;
+        let data_offset = cursor.stream_position()?;
+
+        for i in 0..row_header.row_count {
+            let subrow_offset = data_offset + i as u64 * (2 + self.exh.header.row_size as u64);
+            cursor.seek(SeekFrom::Start(subrow_offset))?;
+
+            let subrow_header = SubRowHeader::read(&mut cursor)?;
+            if subrow_header.subrow_id == subrow_id {
+                return Ok(Some(LazyRow {
+                    exh: &self.exh,
+                    exd,
+                    offset: subrow_offset + 2,
+                }));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Returns the offset of `row_id`'s data section, relative to [EXD::remaining_data].
+fn data_offset(exd: &EXD, row_id: u32) -> Option<u64> {
+    let header_offset = EXDHeader::SIZE as u64 + exd.header.data_offset_size as u64;
+
+    exd.data_offsets
+        .iter()
+        .find(|offset| offset.row_id == row_id)
+        .map(|offset| offset.offset as u64 - header_offset)
+}
+
+/// A row (or subrow) in a [LazySheet], whose columns are read on demand.
+#[derive(Clone, Copy)]
+pub struct LazyRow<'a> {
+    exh: &'a EXH,
+    exd: &'a EXD,
+    /// Offset to the beginning of this row's column data, relative to [EXD::remaining_data].
+    offset: u64,
+}
+
+impl LazyRow<'_> {
+    /// The number of columns in this row.
+    pub fn column_count(&self) -> usize {
+        self.exh.column_definitions.len()
+    }
+
+    /// Reads the column at `index`, otherwise returns [None] if it's out of bounds or couldn't be read.
+    pub fn column(&self, index: usize) -> Option<Field> {
+        let definition = self.exh.column_definitions.get(index)?;
+
+        let mut cursor = Cursor::new(&self.exd.remaining_data);
+        cursor
+            .seek(SeekFrom::Start(self.offset + definition.offset as u64))
+            .ok()?;
+
+        EXD::read_column(&mut cursor, self.exh, self.offset, definition)
+    }
+
+    /// Reads every column, and returns them as a regular [Row].
+    pub fn read(&self) -> Option<Row> {
+        let mut cursor = Cursor::new(&self.exd.remaining_data);
+        read_row(&mut cursor, self.exh, self.offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use crate::{ByteBuffer, ReadableFile, common::Platform};
+
+    use super::*;
+
+    /// Reads from `resources/tests`, but ignores the `exd/` prefix and counts how many reads happened.
+    #[derive(Clone)]
+    struct TestResource {
+        reads: Arc<AtomicUsize>,
+    }
+
+    impl Resource for TestResource {
+        fn read(&mut self, path: &str) -> crate::Result<ByteBuffer> {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+
+            Ok(std::fs::read(Self::path(path))?)
+        }
+
+        fn exists(&mut self, path: &str) -> bool {
+            Self::path(path).exists()
+        }
+    }
+
+    impl TestResource {
+        /// Where `path` is in the test resources, which aren't split up into folders.
+        fn path(path: &str) -> PathBuf {
+            let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            d.push("resources/tests");
+            d.push(path.trim_start_matches("exd/"));
+            d
+        }
+    }
+
+    fn read_exh(name: &str) -> EXH {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/tests");
+        d.push(name);
+
+        EXH::from_existing(Platform::Win32, &std::fs::read(d).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_lazy_read() {
+        let exh = read_exh("gcshop.exh");
+        let resource = TestResource {
+            reads: Default::default(),
+        };
+        let reads = resource.reads.clone();
+
+        let mut sheet = LazySheet::new(resource, &exh, "gcshop", Language::None);
+
+        // nothing should be read yet
+        assert!(!sheet.is_page_loaded(0));
+        assert_eq!(reads.load(Ordering::Relaxed), 0);
+
+        let row = sheet.row(1441794).unwrap().unwrap();
+        assert_eq!(row.column_count(), 1);
+        assert_eq!(row.column(0), Some(Field::Int8(2)));
+        assert_eq!(row.column(1), None);
+        assert_eq!(
+            row.read(),
+            Some(Row {
+                columns: vec![Field::Int8(2)]
+            })
+        );
+
+        assert!(sheet.is_page_loaded(0));
+
+        // the page is only read once
+        assert!(sheet.row(1441795).unwrap().is_some());
+        assert_eq!(reads.load(Ordering::Relaxed), 1);
+
+        // non-existent rows
+        assert!(sheet.row(0).unwrap().is_none());
+        assert!(sheet.subrow(1441794, 0).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_lazy_read_strings() {
+        let exh = read_exh("openingsystemdefine.exh");
+        let resource = TestResource {
+            reads: Default::default(),
+        };
+
+        let mut sheet = LazySheet::new(resource, &exh, "openingsystemdefine", Language::None);
+
+        let row = sheet.row(4).unwrap().unwrap();
+        assert_eq!(
+            row.column(0),
+            Some(Field::String("ITEM_INITIAL_RING_A".to_string()))
+        );
+        assert_eq!(row.column(1), Some(Field::UInt32(4423)));
+    }
+}
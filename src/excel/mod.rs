@@ -72,15 +72,31 @@ use std::io::{Cursor, Seek, SeekFrom};
 use binrw::{BinRead, BinWrite};
 
 use crate::{
-    ByteBuffer,
+    ByteBuffer, Error,
     exd::{DataSectionHeader, EXD, EXDHeader, ExcelDataOffset, SubRowHeader},
-    exd_file_operations::{read_row, write_row},
-    exh::{EXH, SheetRowKind},
+    exd_file_operations::{read_row, row_strings, write_row},
+    exh::{ColumnDataType, EXH, SheetRowKind},
 };
 
+mod builder;
+pub use builder::SheetBuilder;
+
+mod csv;
+
+mod diff;
+pub use diff::{ColumnChange, ExcelDiff, FieldChange, RowChange, SheetDiff};
+
 mod iterators;
 pub use iterators::*;
 
+mod json;
+
+mod lazy;
+pub use lazy::{LazyRow, LazySheet};
+
+mod multilanguage;
+pub use multilanguage::{MultiLanguageRow, MultiLanguageSheet};
+
 /// Contains a single column's data, which can be various underlying types.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Field {
@@ -196,6 +212,76 @@ impl Field {
         }
         None
     }
+
+    /// Returns the default (zero or empty) value for a column of `data_type`.
+    pub fn default_for(data_type: ColumnDataType) -> Self {
+        match data_type {
+            ColumnDataType::String => Field::String(String::new()),
+            ColumnDataType::Int8 => Field::Int8(0),
+            ColumnDataType::UInt8 => Field::UInt8(0),
+            ColumnDataType::Int16 => Field::Int16(0),
+            ColumnDataType::UInt16 => Field::UInt16(0),
+            ColumnDataType::Int32 => Field::Int32(0),
+            ColumnDataType::UInt32 => Field::UInt32(0),
+            ColumnDataType::Float32 => Field::Float32(0.0),
+            ColumnDataType::Int64 => Field::Int64(0),
+            ColumnDataType::UInt64 => Field::UInt64(0),
+            _ => Field::Bool(false), // bools and packed bools
+        }
+    }
+
+    /// Parses `text` as a value for a column of `data_type`, which is the inverse of this type's [Display](std::fmt::Display) implementation.
+    pub fn parse(text: &str, data_type: ColumnDataType) -> Option<Self> {
+        Some(match data_type {
+            ColumnDataType::String => Field::String(text.to_string()),
+            ColumnDataType::Int8 => Field::Int8(text.parse().ok()?),
+            ColumnDataType::UInt8 => Field::UInt8(text.parse().ok()?),
+            ColumnDataType::Int16 => Field::Int16(text.parse().ok()?),
+            ColumnDataType::UInt16 => Field::UInt16(text.parse().ok()?),
+            ColumnDataType::Int32 => Field::Int32(text.parse().ok()?),
+            ColumnDataType::UInt32 => Field::UInt32(text.parse().ok()?),
+            ColumnDataType::Float32 => Field::Float32(text.parse().ok()?),
+            ColumnDataType::Int64 => Field::Int64(text.parse().ok()?),
+            ColumnDataType::UInt64 => Field::UInt64(text.parse().ok()?),
+            _ => Field::Bool(text.parse().ok()?), // bools and packed bools
+        })
+    }
+
+    /// Returns true if this field can be stored in a column of `data_type`.
+    pub fn matches(&self, data_type: ColumnDataType) -> bool {
+        match self {
+            Field::String(_) => data_type == ColumnDataType::String,
+            Field::Bool(_) => data_type == ColumnDataType::Bool || data_type.is_packed_bool(),
+            Field::Int8(_) => data_type == ColumnDataType::Int8,
+            Field::UInt8(_) => data_type == ColumnDataType::UInt8,
+            Field::Int16(_) => data_type == ColumnDataType::Int16,
+            Field::UInt16(_) => data_type == ColumnDataType::UInt16,
+            Field::Int32(_) => data_type == ColumnDataType::Int32,
+            Field::UInt32(_) => data_type == ColumnDataType::UInt32,
+            Field::Float32(_) => data_type == ColumnDataType::Float32,
+            Field::Int64(_) => data_type == ColumnDataType::Int64,
+            Field::UInt64(_) => data_type == ColumnDataType::UInt64,
+        }
+    }
+}
+
+impl std::fmt::Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Field::String(value) => write!(f, "{value}"),
+            Field::Bool(value) => write!(f, "{value}"),
+            Field::Int8(value) => write!(f, "{value}"),
+            Field::UInt8(value) => write!(f, "{value}"),
+            Field::Int16(value) => write!(f, "{value}"),
+            Field::UInt16(value) => write!(f, "{value}"),
+            Field::Int32(value) => write!(f, "{value}"),
+            Field::UInt32(value) => write!(f, "{value}"),
+            // This is the shortest representation that still round-trips
+            Field::Float32(value) => write!(f, "{value}"),
+            Field::Int64(value) => write!(f, "{value}"),
+            Field::UInt64(value) => write!(f, "{value}"),
+        }
+    }
 }
 
 /// A single row of data.
@@ -316,14 +402,28 @@ impl Page {
             // write column data
             match &exh.header.row_kind {
                 SheetRowKind::SingleRow => {
-                    write_row(&mut cursor, exh, &row.subrows.first().unwrap().1)
+                    write_row(&mut cursor, exh, &row.subrows.first().unwrap().1, 0)
                 }
                 SheetRowKind::SubRows => {
-                    for (id, row) in &row.subrows {
+                    // Strings of every subrow are stored together after the last one, but their offsets are relative to the end of each subrow.
+                    let subrow_size = exh.header.row_size as u32 + 2;
+                    let mut strings_len = 0;
+                    for (i, (id, subrow)) in row.subrows.iter().enumerate() {
                         let subrow_header = SubRowHeader { subrow_id: *id };
                         subrow_header.write_ne(&mut cursor).ok()?;
 
-                        write_row(&mut cursor, exh, row);
+                        let remaining_subrows = (row.subrows.len() - i - 1) as u32;
+                        write_row(
+                            &mut cursor,
+                            exh,
+                            subrow,
+                            remaining_subrows * subrow_size + strings_len,
+                        );
+
+                        strings_len += row_strings(exh, subrow)
+                            .iter()
+                            .map(|x| x.len() as u32 + 1)
+                            .sum::<u32>();
                     }
                 }
             }
@@ -331,14 +431,11 @@ impl Page {
             // write strings at the end of column data
             {
                 let mut write_row_strings = |row: &Row| {
-                    for column in &row.columns {
-                        if let Field::String(val) = column {
-                            let bytes = val.as_bytes();
-                            bytes.write(&mut cursor).unwrap();
-
-                            // nul terminator
-                            0u8.write_ne(&mut cursor).unwrap();
-                        }
+                    for string in row_strings(exh, row) {
+                        string.as_bytes().write(&mut cursor).unwrap();
+
+                        // nul terminator
+                        0u8.write_ne(&mut cursor).unwrap();
                     }
                 };
 
@@ -361,7 +458,7 @@ impl Page {
 
             let row_header = DataSectionHeader {
                 size: (new_pos - old_pos) as u32,
-                row_count: 1, // TODO: hardcoded
+                row_count: row.subrows.len() as u16,
             };
             row_header.write(&mut cursor).unwrap();
 
@@ -409,6 +506,63 @@ pub struct Sheet {
 }
 
 impl Sheet {
+    /// Creates a sheet from a list of `(row ID, subrow ID, row)`, split into pages the same way as `exh`.
+    ///
+    /// Every row is checked against the column types of `exh`.
+    pub(crate) fn from_rows(exh: &EXH, rows: Vec<(u32, u16, Row)>) -> crate::Result<Self> {
+        let mut pages = vec![
+            Page {
+                entries: Vec::new()
+            };
+            exh.pages.len()
+        ];
+
+        for (row_id, subrow_id, row) in rows {
+            SheetBuilder::check_row(exh, row_id, subrow_id, &row)?;
+
+            let Some(page_index) = exh.find_page(row_id) else {
+                return Err(Error::InvalidData {
+                    reason: format!(
+                        "row {row_id} isn't in any page, use SheetBuilder to add new rows"
+                    ),
+                });
+            };
+
+            let entries = &mut pages[page_index].entries;
+            match entries.iter_mut().find(|entry| entry.id == row_id) {
+                Some(entry) => entry.subrows.push((subrow_id, row)),
+                None => entries.push(Entry {
+                    id: row_id,
+                    subrows: vec![(subrow_id, row)],
+                }),
+            }
+        }
+
+        for page in &mut pages {
+            page.entries.sort_by_key(|entry| entry.id);
+            for entry in &mut page.entries {
+                entry.subrows.sort_by_key(|(id, _)| *id);
+            }
+        }
+
+        Ok(Self {
+            exh: exh.clone(),
+            pages,
+        })
+    }
+
+    /// Returns the name of each column, using `column_names` where given and the column index otherwise.
+    pub(crate) fn column_names(&self, column_names: Option<&[&str]>) -> Vec<String> {
+        (0..self.exh.column_definitions.len())
+            .map(|i| {
+                column_names
+                    .and_then(|names| names.get(i))
+                    .map(|name| name.to_string())
+                    .unwrap_or_else(|| i.to_string())
+            })
+            .collect()
+    }
+
     /// Returns a reference to the entry matching `row_id` and returns a reference to it, otherwise returns [None].
     ///
     /// This is only useful if you need to discriminate between single row and subrow sheets. In most cases, you want to use [row](Self::row) or [subrow](Self::subrow).
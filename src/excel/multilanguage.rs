@@ -0,0 +1,279 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::HashMap;
+
+use crate::{
+    Language,
+    excel::{Field, Row, Sheet},
+    exh::{ColumnDataType, EXH, SheetRowKind},
+};
+
+/// String columns of each row, keyed by `(row ID, subrow ID)`.
+type LocalizedStrings = HashMap<(u32, u16), Vec<String>>;
+
+/// An Excel sheet read in every language it supports at once.
+///
+/// Only strings differ between languages, so the other columns are shared and only stored once. This is much easier than reading a [Sheet] for each language and zipping them by hand.
+///
+/// To read one, use [ResourceResolver::read_excel_sheet_all_languages](crate::resource::ResourceResolver::read_excel_sheet_all_languages) or [SqPackResource::read_excel_sheet_all_languages](crate::resource::SqPackResource::read_excel_sheet_all_languages).
+///
+/// # Example
+///
+/// ```no_run
+/// # use physis::resource::{UnpackedResource, ResourceResolver};
+/// # use physis::Language;
+/// # use physis::Error;
+/// # let resource = UnpackedResource::from_existing(".");
+/// # let mut resolver = ResourceResolver::new();
+/// # resolver.add_source(resource);
+/// let exh = resolver.read_excel_sheet_header("Item")?;
+/// let sheet = resolver.read_excel_sheet_all_languages(&exh, "Item")?;
+/// if let Some(row) = sheet.row(1) {
+///     for (language, name) in row.strings(9) {
+///         println!("{language:?}: {name}");
+///     }
+/// }
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct MultiLanguageSheet {
+    /// The sheet in the first language, which is also where the non-string columns are read from.
+    base: Sheet,
+    /// The language of `base`.
+    base_language: Language,
+    /// Strings for the rest of the languages, in the same order as `string_columns`.
+    strings: Vec<(Language, LocalizedStrings)>,
+    /// Indices of the string columns.
+    string_columns: Vec<usize>,
+}
+
+impl MultiLanguageSheet {
+    /// Merges sheets of the same `exh` read in different languages. The first sheet is used for non-string columns.
+    ///
+    /// Returns [None] if `sheets` is empty.
+    pub fn from_sheets(exh: &EXH, sheets: Vec<(Language, Sheet)>) -> Option<Self> {
+        let string_columns: Vec<usize> = exh
+            .column_definitions
+            .iter()
+            .enumerate()
+            .filter(|(_, definition)| definition.data_type == ColumnDataType::String)
+            .map(|(i, _)| i)
+            .collect();
+
+        let mut sheets = sheets.into_iter();
+        let (base_language, base) = sheets.next()?;
+
+        let strings = sheets
+            .map(|(language, sheet)| {
+                let mut strings = HashMap::new();
+                for (row_id, subrows) in &sheet {
+                    for (subrow_id, row) in subrows {
+                        strings.insert(
+                            (row_id, *subrow_id),
+                            string_columns
+                                .iter()
+                                .map(|i| match row.columns.get(*i) {
+                                    Some(Field::String(value)) => value.clone(),
+                                    _ => String::new(),
+                                })
+                                .collect(),
+                        );
+                    }
+                }
+                (language, strings)
+            })
+            .collect();
+
+        Some(Self {
+            base,
+            base_language,
+            strings,
+            string_columns,
+        })
+    }
+
+    /// The EXH for this sheet.
+    pub fn exh(&self) -> &EXH {
+        &self.base.exh
+    }
+
+    /// The languages that were read. For language-agnostic sheets, this is only [Language::None].
+    pub fn languages(&self) -> Vec<Language> {
+        std::iter::once(self.base_language)
+            .chain(self.strings.iter().map(|(language, _)| *language))
+            .collect()
+    }
+
+    /// Finds a row matching `row_id`, otherwise returns [None].
+    ///
+    /// For sheets that have subrows, this will always return subrow 0. It's recommended to use [subrow](Self::subrow) instead.
+    pub fn row(&self, row_id: u32) -> Option<MultiLanguageRow<'_>> {
+        let entry = self.base.entry(row_id)?;
+        let (subrow_id, row) = entry.subrows.first()?;
+
+        Some(MultiLanguageRow {
+            sheet: self,
+            row_id,
+            subrow_id: *subrow_id,
+            row,
+        })
+    }
+
+    /// Finds a row matching `row_id` and `subrow_id`, otherwise returns [None].
+    ///
+    /// For sheets that don't have subrows, this will always return [None].
+    pub fn subrow(&self, row_id: u32, subrow_id: u16) -> Option<MultiLanguageRow<'_>> {
+        if self.base.exh.header.row_kind == SheetRowKind::SingleRow {
+            return None;
+        }
+
+        Some(MultiLanguageRow {
+            sheet: self,
+            row_id,
+            subrow_id,
+            row: self.base.subrow(row_id, subrow_id)?,
+        })
+    }
+
+    /// Iterates over every row and subrow in this sheet.
+    pub fn iter(&self) -> impl Iterator<Item = MultiLanguageRow<'_>> {
+        self.base.into_iter().flat_map(move |(row_id, subrows)| {
+            subrows
+                .iter()
+                .map(move |(subrow_id, row)| MultiLanguageRow {
+                    sheet: self,
+                    row_id,
+                    subrow_id: *subrow_id,
+                    row,
+                })
+        })
+    }
+}
+
+/// A row in a [MultiLanguageSheet].
+#[derive(Debug, Clone, Copy)]
+pub struct MultiLanguageRow<'a> {
+    sheet: &'a MultiLanguageSheet,
+    /// The row ID.
+    pub row_id: u32,
+    /// The subrow ID, which is always 0 for sheets without subrows.
+    pub subrow_id: u16,
+    row: &'a Row,
+}
+
+impl<'a> MultiLanguageRow<'a> {
+    /// Returns the field at `column`. String columns are returned in the first language of the sheet, use [string](Self::string) to choose one.
+    pub fn field(&self, column: usize) -> Option<&'a Field> {
+        self.row.columns.get(column)
+    }
+
+    /// Returns the string at `column` in `language`, otherwise returns [None] if it isn't a string column or the language wasn't read.
+    ///
+    /// Language-agnostic sheets return the same string for every language.
+    pub fn string(&self, column: usize, language: Language) -> Option<&'a str> {
+        if language == self.sheet.base_language || self.sheet.base_language == Language::None {
+            return self.field(column)?.into_string().map(|x| x.as_str());
+        }
+
+        let index = self
+            .sheet
+            .string_columns
+            .iter()
+            .position(|x| *x == column)?;
+        let (_, strings) = self.sheet.strings.iter().find(|(x, _)| *x == language)?;
+
+        strings
+            .get(&(self.row_id, self.subrow_id))?
+            .get(index)
+            .map(|x| x.as_str())
+    }
+
+    /// Returns the string at `column` in every language that has this row.
+    pub fn strings(&self, column: usize) -> Vec<(Language, &'a str)> {
+        self.sheet
+            .languages()
+            .into_iter()
+            .filter_map(|language| Some((language, self.string(column, language)?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        excel::SheetBuilder,
+        resource::{MemoryResource, generic_read_excel_sheet_all_languages},
+    };
+
+    use super::*;
+
+    fn row(text: &str, value: u32) -> Row {
+        Row {
+            columns: vec![Field::UInt32(value), Field::String(text.to_string())],
+        }
+    }
+
+    #[test]
+    fn test_all_languages() {
+        let mut builder = SheetBuilder::new("Item", SheetRowKind::SingleRow);
+        builder.set_languages(&[Language::Japanese, Language::English, Language::German]);
+        builder.add_column(ColumnDataType::UInt32);
+        builder.add_column(ColumnDataType::String);
+        builder.insert_row(1, row("ギル", 1)).unwrap();
+        builder
+            .insert_localized_row(Language::English, 1, 0, row("Gil", 1))
+            .unwrap();
+        builder
+            .insert_localized_row(Language::German, 1, 0, row("Gil (de)", 1))
+            .unwrap();
+        builder.insert_row(2, row("ポーション", 5)).unwrap();
+
+        let mut resource = MemoryResource::from_sheets(&[builder.clone()]);
+        let (exh, _) = builder.build();
+
+        let sheet = generic_read_excel_sheet_all_languages(&mut resource, &exh, "Item").unwrap();
+        assert_eq!(
+            sheet.languages(),
+            vec![Language::Japanese, Language::English, Language::German]
+        );
+
+        let gil = sheet.row(1).unwrap();
+        assert_eq!(gil.field(0), Some(&Field::UInt32(1)));
+        assert_eq!(gil.string(1, Language::English), Some("Gil"));
+        assert_eq!(
+            gil.strings(1),
+            vec![
+                (Language::Japanese, "ギル"),
+                (Language::English, "Gil"),
+                (Language::German, "Gil (de)")
+            ]
+        );
+
+        // not a string column
+        assert_eq!(gil.string(0, Language::English), None);
+        // not a language of this sheet
+        assert_eq!(gil.string(1, Language::French), None);
+
+        assert_eq!(sheet.iter().count(), 2);
+        assert!(sheet.row(3).is_none());
+    }
+
+    #[test]
+    fn test_language_agnostic() {
+        let mut builder = SheetBuilder::new("Thing", SheetRowKind::SubRows);
+        builder.add_column(ColumnDataType::UInt32);
+        builder.add_column(ColumnDataType::String);
+        builder.insert_subrow(0, 2, row("Internal", 8)).unwrap();
+
+        let mut resource = MemoryResource::from_sheets(&[builder.clone()]);
+        let (exh, _) = builder.build();
+
+        let sheet = generic_read_excel_sheet_all_languages(&mut resource, &exh, "Thing").unwrap();
+        assert_eq!(sheet.languages(), vec![Language::None]);
+
+        let row = sheet.subrow(0, 2).unwrap();
+        assert_eq!(row.string(1, Language::English), Some("Internal"));
+        assert_eq!(row.strings(1), vec![(Language::None, "Internal")]);
+    }
+}
@@ -38,7 +38,13 @@ pub(crate) fn read_row<T: Read + Seek>(reader: &mut T, exh: &EXH, row_offset: u6
     Some(subrow)
 }
 
-pub(crate) fn write_row<T: Write + Seek>(writer: &mut T, exh: &EXH, row: &Row) {
+/// Writes the column data of `row`. String offsets start at `strings_offset`, which is relative to the end of this row.
+pub(crate) fn write_row<T: Write + Seek>(
+    writer: &mut T,
+    exh: &EXH,
+    row: &Row,
+    strings_offset: u32,
+) {
     let mut column_definitions: Vec<(ExcelColumnDefinition, Field)> = exh
         .column_definitions
         .clone()
@@ -78,15 +84,9 @@ pub(crate) fn write_row<T: Write + Seek>(writer: &mut T, exh: &EXH, row: &Row) {
         };
     }
 
-    let largest_offset = column_definitions.iter().map(|x| x.0.offset).max().unwrap();
-
-    // Rows must be at least 4 bytes long and live along this boundary too
-    let padded_length = (largest_offset.div_ceil(4) * 4).max(4);
-    let padding = padded_length - largest_offset;
-
     let mut current_offset = 0;
 
-    let mut strings_len = 0;
+    let mut strings_len = strings_offset;
     for (definition, column) in &column_definitions {
         //  Seen in PlaceName. Why does sqex do this?!
         let expected_offset = definition.offset as u64;
@@ -112,16 +112,36 @@ pub(crate) fn write_row<T: Write + Seek>(writer: &mut T, exh: &EXH, row: &Row) {
         let written_length = new_pos - original_pos;
 
         current_offset += written_length;
+    }
 
-        // Add any needed padding at the end
-        if definition.offset == largest_offset {
-            for _ in 0..(padding as u64).saturating_sub(written_length) {
-                0u8.write_ne(writer).unwrap();
-            }
-        }
+    // Rows must be at least 4 bytes long and live along this boundary too
+    let padded_length = (exh.header.row_size as u64)
+        .max(current_offset.div_ceil(4) * 4)
+        .max(4);
+    for _ in current_offset..padded_length {
+        0u8.write_ne(writer).unwrap();
     }
 }
 
+/// Returns the strings in `row`, in the order they are written after the column data.
+pub(crate) fn row_strings<'a>(exh: &EXH, row: &'a Row) -> Vec<&'a str> {
+    let mut strings: Vec<(u16, &str)> = exh
+        .column_definitions
+        .iter()
+        .zip(&row.columns)
+        .filter_map(|(definition, column)| {
+            column
+                .into_string()
+                .map(|x| (definition.offset, x.as_str()))
+        })
+        .collect();
+
+    // write_row assigns string offsets sorted by column offset, so they have to be in the same order
+    strings.sort_by_key(|(offset, _)| *offset);
+
+    strings.into_iter().map(|(_, x)| x).collect()
+}
+
 impl EXD {
     fn read_data_raw<T: Read + Seek, Z: BinRead<Args<'static> = ()>>(
         cursor: &mut T,
@@ -196,8 +216,8 @@ impl EXD {
                 Self::write_data_raw(cursor, &string_offset);
                 *strings_len += val.len() as u32 + 1;
             }
-            Field::Bool(_) => match column_definition.data_type {
-                ColumnDataType::Bool => todo!(),
+            Field::Bool(val) => match column_definition.data_type {
+                ColumnDataType::Bool => Self::write_data_raw(cursor, &(*val as u8)),
                 // packed bools are handled in write_rows
                 ColumnDataType::PackedBool0
                 | ColumnDataType::PackedBool1
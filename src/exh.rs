@@ -105,6 +105,98 @@ pub enum ColumnDataType {
     PackedBool7 = 0x20,
 }
 
+impl ColumnDataType {
+    /// The size of this type in a row, in bytes. Strings are stored as an offset.
+    pub fn size(&self) -> u16 {
+        match self {
+            ColumnDataType::String => 4,
+            ColumnDataType::Bool => 1,
+            ColumnDataType::Int8 | ColumnDataType::UInt8 => 1,
+            ColumnDataType::Int16 | ColumnDataType::UInt16 => 2,
+            ColumnDataType::Int32 | ColumnDataType::UInt32 | ColumnDataType::Float32 => 4,
+            ColumnDataType::Int64 | ColumnDataType::UInt64 => 8,
+            ColumnDataType::PackedBool0
+            | ColumnDataType::PackedBool1
+            | ColumnDataType::PackedBool2
+            | ColumnDataType::PackedBool3
+            | ColumnDataType::PackedBool4
+            | ColumnDataType::PackedBool5
+            | ColumnDataType::PackedBool6
+            | ColumnDataType::PackedBool7 => 1,
+        }
+    }
+
+    /// Returns a short name for this type, used when importing and exporting sheets.
+    ///
+    /// For example, `ColumnDataType::UInt32` becomes "uint32".
+    pub fn name(&self) -> &'static str {
+        // NOTE: Keep in sync with `from_name`.
+        match self {
+            ColumnDataType::String => "str",
+            ColumnDataType::Bool => "bool",
+            ColumnDataType::Int8 => "int8",
+            ColumnDataType::UInt8 => "uint8",
+            ColumnDataType::Int16 => "int16",
+            ColumnDataType::UInt16 => "uint16",
+            ColumnDataType::Int32 => "int32",
+            ColumnDataType::UInt32 => "uint32",
+            ColumnDataType::Float32 => "float32",
+            ColumnDataType::Int64 => "int64",
+            ColumnDataType::UInt64 => "uint64",
+            ColumnDataType::PackedBool0 => "packedbool0",
+            ColumnDataType::PackedBool1 => "packedbool1",
+            ColumnDataType::PackedBool2 => "packedbool2",
+            ColumnDataType::PackedBool3 => "packedbool3",
+            ColumnDataType::PackedBool4 => "packedbool4",
+            ColumnDataType::PackedBool5 => "packedbool5",
+            ColumnDataType::PackedBool6 => "packedbool6",
+            ColumnDataType::PackedBool7 => "packedbool7",
+        }
+    }
+
+    /// Returns the type for a given short name, if valid.
+    pub fn from_name(name: &str) -> Option<Self> {
+        // NOTE: Keep in sync with `name`.
+        Some(match name {
+            "str" => ColumnDataType::String,
+            "bool" => ColumnDataType::Bool,
+            "int8" => ColumnDataType::Int8,
+            "uint8" => ColumnDataType::UInt8,
+            "int16" => ColumnDataType::Int16,
+            "uint16" => ColumnDataType::UInt16,
+            "int32" => ColumnDataType::Int32,
+            "uint32" => ColumnDataType::UInt32,
+            "float32" => ColumnDataType::Float32,
+            "int64" => ColumnDataType::Int64,
+            "uint64" => ColumnDataType::UInt64,
+            "packedbool0" => ColumnDataType::PackedBool0,
+            "packedbool1" => ColumnDataType::PackedBool1,
+            "packedbool2" => ColumnDataType::PackedBool2,
+            "packedbool3" => ColumnDataType::PackedBool3,
+            "packedbool4" => ColumnDataType::PackedBool4,
+            "packedbool5" => ColumnDataType::PackedBool5,
+            "packedbool6" => ColumnDataType::PackedBool6,
+            "packedbool7" => ColumnDataType::PackedBool7,
+            _ => return None,
+        })
+    }
+
+    /// Returns true if this is one of the packed boolean types.
+    pub fn is_packed_bool(&self) -> bool {
+        matches!(
+            self,
+            ColumnDataType::PackedBool0
+                | ColumnDataType::PackedBool1
+                | ColumnDataType::PackedBool2
+                | ColumnDataType::PackedBool3
+                | ColumnDataType::PackedBool4
+                | ColumnDataType::PackedBool5
+                | ColumnDataType::PackedBool6
+                | ColumnDataType::PackedBool7
+        )
+    }
+}
+
 /// A column in an Excel sheet.
 #[binrw]
 #[brw(big)]
@@ -193,13 +285,14 @@ impl EXH {
 
     /// Returns the page that contains this `row_id`.
     pub(crate) fn get_page(&self, row_id: u32) -> usize {
-        for (i, page) in self.pages.iter().enumerate() {
-            if row_id >= page.start_id && row_id < page.start_id + page.row_count {
-                return i;
-            }
-        }
+        self.find_page(row_id).unwrap_or_default()
+    }
 
-        0
+    /// Returns the page that contains this `row_id`, or [None] if it's outside of every page.
+    pub(crate) fn find_page(&self, row_id: u32) -> Option<usize> {
+        self.pages
+            .iter()
+            .position(|page| row_id >= page.start_id && row_id < page.start_id + page.row_count)
     }
 }
 
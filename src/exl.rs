@@ -83,6 +83,21 @@ impl EXL {
     pub fn contains(&self, key: &str) -> bool {
         self.entries.iter().any(|t| t.0 == key)
     }
+
+    /// Adds `key` to the list with `value`, or updates its value if it's already listed.
+    pub fn insert(&mut self, key: &str, value: i32) {
+        match self.entries.iter_mut().find(|t| t.0 == key) {
+            Some(entry) => entry.1 = value,
+            None => self.entries.push((key.to_string(), value)),
+        }
+    }
+
+    /// Removes `key` from the list. Returns true if it was listed.
+    pub fn remove(&mut self, key: &str) -> bool {
+        let old_len = self.entries.len();
+        self.entries.retain(|t| t.0 != key);
+        self.entries.len() != old_len
+    }
 }
 
 #[cfg(test)]
@@ -124,6 +139,26 @@ mod tests {
         assert!(!exl.contains("foo"));
     }
 
+    #[test]
+    fn insert_remove() {
+        let mut exl = common_setup();
+
+        exl.insert("Foo", 5);
+        exl.insert("Baz", -1);
+        assert_eq!(
+            exl.entries,
+            vec![
+                ("Foo".to_string(), 5),
+                ("Bar".to_string(), -1),
+                ("Baz".to_string(), -1)
+            ]
+        );
+
+        assert!(exl.remove("Bar"));
+        assert!(!exl.remove("Bar"));
+        assert!(!exl.contains("Bar"));
+    }
+
     #[test]
     fn test_write() {
         let existing_exl = common_setup();
@@ -7,5 +7,6 @@ use crate::havok::transform::HavokTransform;
 
 pub trait HavokAnimation {
     fn duration(&self) -> f32;
-    fn sample(&self, time: f32) -> Vec<HavokTransform>;
+    /// Samples every track at `time` milliseconds. Returns [None] if the animation data is malformed.
+    fn sample(&self, time: f32) -> Option<Vec<HavokTransform>>;
 }
@@ -15,24 +15,24 @@ pub struct HavokAnimationContainer {
 }
 
 impl HavokAnimationContainer {
-    pub fn new(object: Arc<RefCell<HavokObject>>) -> Self {
+    pub fn new(object: Arc<RefCell<HavokObject>>) -> Option<Self> {
         let root = object.borrow();
 
-        let raw_skeletons = root.get("skeletons").as_array();
+        let raw_skeletons = root.get("skeletons")?.as_array()?;
         let skeletons = raw_skeletons
             .iter()
-            .map(|x| HavokSkeleton::new(x.as_object()))
-            .collect::<Vec<_>>();
+            .map(|x| HavokSkeleton::new(x.as_object()?))
+            .collect::<Option<Vec<_>>>()?;
 
-        let raw_bindings = root.get("bindings").as_array();
+        let raw_bindings = root.get("bindings")?.as_array()?;
         let bindings = raw_bindings
             .iter()
-            .map(|x| HavokAnimationBinding::new(x.as_object()))
-            .collect::<Vec<_>>();
+            .map(|x| HavokAnimationBinding::new(x.as_object()?))
+            .collect::<Option<Vec<_>>>()?;
 
-        Self {
+        Some(Self {
             skeletons,
             bindings,
-        }
+        })
     }
 }
@@ -27,18 +27,18 @@ enum HavokTagType {
 }
 
 impl HavokTagType {
-    pub fn from_raw(raw: u8) -> Self {
+    pub fn from_raw(raw: u8) -> Option<Self> {
         match raw {
-            255 => HavokTagType::Eof,
-            0 => HavokTagType::Invalid,
-            1 => HavokTagType::FileInfo,
-            2 => HavokTagType::Type,
-            3 => HavokTagType::Object,
-            4 => HavokTagType::ObjectRemember,
-            5 => HavokTagType::Backref,
-            6 => HavokTagType::ObjectNull,
-            7 => HavokTagType::FileEnd,
-            _ => panic!(),
+            255 => Some(HavokTagType::Eof),
+            0 => Some(HavokTagType::Invalid),
+            1 => Some(HavokTagType::FileInfo),
+            2 => Some(HavokTagType::Type),
+            3 => Some(HavokTagType::Object),
+            4 => Some(HavokTagType::ObjectRemember),
+            5 => Some(HavokTagType::Backref),
+            6 => Some(HavokTagType::ObjectNull),
+            7 => Some(HavokTagType::FileEnd),
+            _ => None,
         }
     }
 }
@@ -53,7 +53,8 @@ pub struct HavokBinaryTagFileReader<'a> {
 }
 
 impl<'a> HavokBinaryTagFileReader<'a> {
-    pub fn read(data: &'a [u8]) -> HavokRootObject {
+    /// Reads the root object of a tag file. Returns [None] if the data is malformed or uses features we don't support.
+    pub fn read(data: &'a [u8]) -> Option<HavokRootObject> {
         let mut reader = Self::new(ByteReader::new(data));
 
         reader.do_read()
@@ -80,19 +81,22 @@ impl<'a> HavokBinaryTagFileReader<'a> {
         }
     }
 
-    fn do_read(&mut self) -> HavokRootObject {
-        let signature1 = self.reader.read_bytes(4).to_int_le::<u32>();
-        let signature2 = self.reader.read_bytes(4).to_int_le::<u32>();
+    fn do_read(&mut self) -> Option<HavokRootObject> {
+        let signature1 = self.reader.read_bytes(4)?.to_int_le::<u32>();
+        let signature2 = self.reader.read_bytes(4)?.to_int_le::<u32>();
         if signature1 != 0xCAB0_0D1E || signature2 != 0xD011_FACE {
-            panic!()
+            return None;
         }
 
         loop {
-            let tag_type = HavokTagType::from_raw(self.read_packed_int() as u8);
+            let tag_type = HavokTagType::from_raw(self.read_packed_int()? as u8)?;
             match tag_type {
                 HavokTagType::FileInfo => {
-                    self.file_version = self.read_packed_int() as u8;
-                    assert_eq!(self.file_version, 3, "Unimplemented version");
+                    self.file_version = self.read_packed_int()? as u8;
+                    // Unimplemented version
+                    if self.file_version != 3 {
+                        return None;
+                    }
                     self.remembered_objects
                         .push(Arc::new(RefCell::new(HavokObject::new(
                             self.remembered_types[0].clone(),
@@ -100,12 +104,11 @@ impl<'a> HavokBinaryTagFileReader<'a> {
                         ))))
                 }
                 HavokTagType::Type => {
-                    let object_type = self.read_type();
+                    let object_type = self.read_type()?;
                     self.remembered_types.push(Arc::new(object_type));
                 }
-                HavokTagType::Backref => panic!(),
                 HavokTagType::ObjectRemember => {
-                    let object = Arc::new(RefCell::new(self.read_object()));
+                    let object = Arc::new(RefCell::new(self.read_object()?));
 
                     self.remembered_objects.push(object.clone());
                     self.objects.push(object);
@@ -113,61 +116,71 @@ impl<'a> HavokBinaryTagFileReader<'a> {
                 HavokTagType::FileEnd => {
                     break;
                 }
-                _ => panic!(),
+                // Backrefs aren't implemented
+                _ => return None,
             }
         }
 
         // fill object references
         for object in &self.objects {
-            self.fill_object_reference(&mut object.borrow_mut());
+            self.fill_object_reference(&mut object.borrow_mut())?;
         }
 
-        HavokRootObject::new(self.remembered_objects[1].clone())
+        Some(HavokRootObject::new(
+            self.remembered_objects.get(1)?.clone(),
+        ))
     }
 
-    fn read_object(&mut self) -> HavokObject {
-        let object_type_index = self.read_packed_int();
-        let object_type = self.remembered_types[object_type_index as usize].clone();
+    fn read_object(&mut self) -> Option<HavokObject> {
+        let object_type_index = self.read_packed_int()?;
+        let object_type = self
+            .remembered_types
+            .get(usize::try_from(object_type_index).ok()?)?
+            .clone();
 
         let members = object_type.members();
-        let data_existence = self.read_bit_field(members.len());
+        let data_existence = self.read_bit_field(members.len())?;
 
         let data = members
             .into_iter()
             .enumerate()
             .map(|(index, member)| {
                 let value = if data_existence[index] {
-                    self.read_object_member_value(member)
+                    self.read_object_member_value(member)?
                 } else {
-                    Self::default_value(member.type_)
+                    Self::default_value(member.type_)?
                 };
-                (index, value)
+                Some((index, value))
             })
-            .collect::<HashMap<_, _>>();
+            .collect::<Option<HashMap<_, _>>>()?;
 
-        HavokObject::new(object_type.clone(), data)
+        Some(HavokObject::new(object_type.clone(), data))
     }
 
-    fn read_object_member_value(&mut self, member: &HavokObjectTypeMember) -> HavokValue {
+    fn read_object_member_value(&mut self, member: &HavokObjectTypeMember) -> Option<HavokValue> {
         if member.type_.is_array() {
-            let array_len = self.read_packed_int();
+            let array_len = usize::try_from(self.read_packed_int()?).ok()?;
             if member.type_.base_type() == HavokValueType::OBJECT && member.class_name.is_none() {
-                panic!()
+                return None;
+            }
+            // Every element takes up at least a byte, so don't trust lengths that can't fit
+            if array_len > self.reader.remaining() {
+                return None;
             }
 
-            HavokValue::Array(self.read_array(member, array_len as usize))
+            Some(HavokValue::Array(self.read_array(member, array_len)?))
         } else {
-            match member.type_ {
-                HavokValueType::BYTE => HavokValue::Integer(self.reader.read() as i32),
-                HavokValueType::INT => HavokValue::Integer(self.read_packed_int()),
-                HavokValueType::REAL => HavokValue::Real(self.reader.read_f32_le()),
-                HavokValueType::STRING => HavokValue::String(self.read_string()),
+            Some(match member.type_ {
+                HavokValueType::BYTE => HavokValue::Integer(self.reader.read()? as i32),
+                HavokValueType::INT => HavokValue::Integer(self.read_packed_int()?),
+                HavokValueType::REAL => HavokValue::Real(self.reader.read_f32_le()?),
+                HavokValueType::STRING => HavokValue::String(self.read_string()?),
                 HavokValueType::OBJECT => {
-                    HavokValue::ObjectReference(self.read_packed_int() as usize)
+                    HavokValue::ObjectReference(usize::try_from(self.read_packed_int()?).ok()?)
                 }
                 HavokValueType::STRUCT => {
-                    let target_type = self.find_type(member.class_name.as_ref().unwrap());
-                    let data_existence = self.read_bit_field(target_type.member_count());
+                    let target_type = self.find_type(member.class_name.as_ref()?)?;
+                    let data_existence = self.read_bit_field(target_type.member_count())?;
 
                     let object = Arc::new(RefCell::new(HavokObject::new(
                         target_type.clone(),
@@ -176,13 +189,18 @@ impl<'a> HavokBinaryTagFileReader<'a> {
                     self.objects.push(object.clone());
 
                     for (member_index, member) in target_type.members().into_iter().enumerate() {
-                        if data_existence[member_index] {
+                        if data_existence
+                            .get(member_index)
+                            .copied()
+                            .unwrap_or_default()
+                        {
+                            // Tuples aren't implemented
                             if member.type_.is_tuple() {
-                                panic!()
-                            } else {
-                                let item = self.read_object_member_value(member);
-                                object.borrow_mut().set(member_index, item);
+                                return None;
                             }
+
+                            let item = self.read_object_member_value(member)?;
+                            object.borrow_mut().set(member_index, item);
                         }
                     }
 
@@ -196,23 +214,27 @@ impl<'a> HavokBinaryTagFileReader<'a> {
                     HavokValue::Vec(
                         (0..vec_size)
                             .map(|_| self.reader.read_f32_le())
-                            .collect::<Vec<_>>(),
+                            .collect::<Option<Vec<_>>>()?,
                     )
                 }
-                _ => panic!("unimplemented {}", member.type_.bits()),
-            }
+                _ => return None,
+            })
         }
     }
 
-    fn read_array(&mut self, member: &HavokObjectTypeMember, array_len: usize) -> Vec<HavokValue> {
+    fn read_array(
+        &mut self,
+        member: &HavokObjectTypeMember,
+        array_len: usize,
+    ) -> Option<Vec<HavokValue>> {
         let base_type = member.type_.base_type();
         match base_type {
             HavokValueType::STRING => (0..array_len)
-                .map(|_| HavokValue::String(self.read_string()))
-                .collect::<Vec<_>>(),
+                .map(|_| Some(HavokValue::String(self.read_string()?)))
+                .collect(),
             HavokValueType::STRUCT => {
-                let target_type = self.find_type(member.class_name.as_ref().unwrap());
-                let data_existence = self.read_bit_field(target_type.member_count());
+                let target_type = self.find_type(member.class_name.as_ref()?)?;
+                let data_existence = self.read_bit_field(target_type.member_count())?;
 
                 let mut result_objects = Vec::new();
                 for _ in 0..array_len {
@@ -227,44 +249,51 @@ impl<'a> HavokBinaryTagFileReader<'a> {
 
                 // struct of array
                 for (member_index, member) in target_type.members().into_iter().enumerate() {
-                    if data_existence[member_index] {
+                    if data_existence
+                        .get(member_index)
+                        .copied()
+                        .unwrap_or_default()
+                    {
+                        // Tuples aren't implemented
                         if member.type_.is_tuple() {
-                            panic!()
-                        } else {
-                            let data = self.read_array(member, array_len);
-                            for (index, item) in data.into_iter().enumerate() {
-                                result_objects[index].borrow_mut().set(member_index, item);
-                            }
+                            return None;
+                        }
+
+                        let data = self.read_array(member, array_len)?;
+                        for (index, item) in data.into_iter().enumerate() {
+                            result_objects[index].borrow_mut().set(member_index, item);
                         }
                     }
                 }
 
-                result_objects
-                    .into_iter()
-                    .map(HavokValue::Object)
-                    .collect::<Vec<_>>()
+                Some(
+                    result_objects
+                        .into_iter()
+                        .map(HavokValue::Object)
+                        .collect::<Vec<_>>(),
+                )
             }
             HavokValueType::OBJECT => (0..array_len)
                 .map(|_| {
-                    let object_index = self.read_packed_int();
+                    let object_index = usize::try_from(self.read_packed_int()?).ok()?;
 
-                    HavokValue::ObjectReference(object_index as usize)
+                    Some(HavokValue::ObjectReference(object_index))
                 })
-                .collect::<Vec<_>>(),
+                .collect(),
             HavokValueType::BYTE => (0..array_len)
-                .map(|_| HavokValue::Integer(self.reader.read() as HavokInteger))
-                .collect::<Vec<_>>(),
+                .map(|_| Some(HavokValue::Integer(self.reader.read()? as HavokInteger)))
+                .collect(),
             HavokValueType::INT => {
                 if self.file_version >= 3 {
-                    self.read_packed_int(); // type?
+                    self.read_packed_int()?; // type?
                 }
                 (0..array_len)
-                    .map(|_| HavokValue::Integer(self.read_packed_int()))
-                    .collect::<Vec<_>>()
+                    .map(|_| Some(HavokValue::Integer(self.read_packed_int()?)))
+                    .collect()
             }
             HavokValueType::REAL => (0..array_len)
-                .map(|_| HavokValue::Real(self.reader.read_f32_le()))
-                .collect::<Vec<_>>(),
+                .map(|_| Some(HavokValue::Real(self.reader.read_f32_le()?)))
+                .collect(),
             HavokValueType::VEC4
             | HavokValueType::VEC8
             | HavokValueType::VEC12
@@ -272,73 +301,80 @@ impl<'a> HavokBinaryTagFileReader<'a> {
                 let vec_size = member.type_.base_type().vec_size() as usize;
                 (0..array_len)
                     .map(|_| {
-                        HavokValue::Vec(
+                        Some(HavokValue::Vec(
                             (0..vec_size)
                                 .map(|_| self.reader.read_f32_le())
-                                .collect::<Vec<_>>(),
-                        )
+                                .collect::<Option<Vec<_>>>()?,
+                        ))
                     })
-                    .collect::<Vec<_>>()
+                    .collect()
             }
-            _ => panic!(
-                "unimplemented {} {}",
-                member.type_.bits(),
-                member.type_.base_type().bits()
-            ),
+            _ => None,
         }
     }
 
-    fn read_type(&mut self) -> HavokObjectType {
-        let name = self.read_string();
-        let _version = self.read_packed_int();
-        let parent = self.read_packed_int();
-        let member_count = self.read_packed_int();
+    fn read_type(&mut self) -> Option<HavokObjectType> {
+        let name = self.read_string()?;
+        let _version = self.read_packed_int()?;
+        let parent = self.read_packed_int()?;
+        let member_count = self.read_packed_int()?;
 
-        let parent = self.remembered_types[parent as usize].clone();
+        let parent = self
+            .remembered_types
+            .get(usize::try_from(parent).ok()?)?
+            .clone();
         let members = (0..member_count)
             .map(|_| {
-                let member_name = self.read_string();
-                let type_ = HavokValueType::from_bits(self.read_packed_int() as u32).unwrap();
+                let member_name = self.read_string()?;
+                let type_ = HavokValueType::from_bits(self.read_packed_int()? as u32)?;
 
                 let tuple_size = if type_.is_tuple() {
-                    self.read_packed_int()
+                    self.read_packed_int()?
                 } else {
                     0
                 };
                 let type_name = if type_.base_type() == HavokValueType::OBJECT
                     || type_.base_type() == HavokValueType::STRUCT
                 {
-                    Some(self.read_string())
+                    Some(self.read_string()?)
                 } else {
                     None
                 };
 
-                HavokObjectTypeMember::new(member_name, type_, tuple_size as u32, type_name)
+                Some(HavokObjectTypeMember::new(
+                    member_name,
+                    type_,
+                    tuple_size as u32,
+                    type_name,
+                ))
             })
-            .collect::<Vec<_>>();
+            .collect::<Option<Vec<_>>>()?;
 
-        HavokObjectType::new(name, Some(parent), members)
+        Some(HavokObjectType::new(name, Some(parent), members))
     }
 
-    fn read_string(&mut self) -> Arc<str> {
-        let length = self.read_packed_int();
+    fn read_string(&mut self) -> Option<Arc<str>> {
+        let length = self.read_packed_int()?;
         if length < 0 {
-            return self.remembered_strings[-length as usize].clone();
+            return self
+                .remembered_strings
+                .get(length.unsigned_abs() as usize)
+                .cloned();
         }
 
-        let result = Arc::from(
-            std::str::from_utf8(self.reader.read_bytes(length as usize))
-                .unwrap()
+        let result: Arc<str> = Arc::from(
+            std::str::from_utf8(self.reader.read_bytes(length as usize)?)
+                .ok()?
                 .to_owned(),
         );
         self.remembered_strings.push(Arc::clone(&result));
 
-        result
+        Some(result)
     }
 
-    fn read_bit_field(&mut self, count: usize) -> Vec<bool> {
-        let bytes_to_read = ((count + 7) & 0xffff_fff8) / 8;
-        let bytes = self.reader.read_bytes(bytes_to_read);
+    fn read_bit_field(&mut self, count: usize) -> Option<Vec<bool>> {
+        let bytes_to_read = count.div_ceil(8);
+        let bytes = self.reader.read_bytes(bytes_to_read)?;
 
         let mut result = Vec::with_capacity(count);
         for byte in bytes {
@@ -353,53 +389,52 @@ impl<'a> HavokBinaryTagFileReader<'a> {
             }
         }
 
-        result
+        Some(result)
     }
 
-    fn read_packed_int(&mut self) -> HavokInteger {
-        let mut byte = self.reader.read();
+    fn read_packed_int(&mut self) -> Option<HavokInteger> {
+        let mut byte = self.reader.read()?;
 
         let mut result = ((byte & 0x7f) >> 1) as u32;
         let neg = byte & 1;
 
         let mut shift = 6;
         while byte & 0x80 != 0 {
-            byte = self.reader.read();
+            byte = self.reader.read()?;
 
-            result |= ((byte as u32) & 0xffff_ff7f) << shift;
+            result |= ((byte as u32) & 0xffff_ff7f).checked_shl(shift)?;
             shift += 7;
         }
         if neg == 1 {
-            -(result as HavokInteger)
+            Some((result as HavokInteger).wrapping_neg())
         } else {
-            result as HavokInteger
+            Some(result as HavokInteger)
         }
     }
 
-    fn find_type(&self, type_name: &str) -> Arc<HavokObjectType> {
+    fn find_type(&self, type_name: &str) -> Option<Arc<HavokObjectType>> {
         self.remembered_types
             .iter()
             .find(|&x| &*x.name == type_name)
-            .unwrap()
-            .clone()
+            .cloned()
     }
 
-    fn fill_object_reference(&self, object: &mut HavokObject) {
+    fn fill_object_reference(&self, object: &mut HavokObject) -> Option<()> {
         let mut values_to_update = Vec::new();
         for (index, mut value) in object.members_mut() {
             match &mut value {
                 HavokValue::ObjectReference(x) => {
-                    let object_ref = &self.remembered_objects[*x];
+                    let object_ref = self.remembered_objects.get(*x)?;
                     values_to_update.push((*index, HavokValue::Object(object_ref.clone())));
                 }
                 HavokValue::Array(x) => {
-                    x.iter_mut().for_each(|item| {
+                    for item in x.iter_mut() {
                         if let HavokValue::ObjectReference(x) = item {
-                            let object_ref = &self.remembered_objects[*x];
+                            let object_ref = self.remembered_objects.get(*x)?;
 
                             *item = HavokValue::Object(object_ref.clone())
                         }
-                    });
+                    }
                 }
                 _ => {}
             }
@@ -408,24 +443,26 @@ impl<'a> HavokBinaryTagFileReader<'a> {
         for (index, value) in values_to_update {
             object.set(index, value);
         }
+
+        Some(())
     }
 
-    fn default_value(type_: HavokValueType) -> HavokValue {
+    fn default_value(type_: HavokValueType) -> Option<HavokValue> {
         if type_.is_vec() {
-            HavokValue::Array(
+            Some(HavokValue::Array(
                 (0..type_.vec_size())
                     .map(|_| Self::default_value(type_.base_type()))
-                    .collect::<Vec<_>>(),
-            )
+                    .collect::<Option<Vec<_>>>()?,
+            ))
         } else if type_.is_array() || type_.is_tuple() {
-            HavokValue::Array(Vec::new())
+            Some(HavokValue::Array(Vec::new()))
         } else {
             match type_ {
-                HavokValueType::EMPTY => HavokValue::Integer(HavokInteger::default()),
-                HavokValueType::BYTE => HavokValue::Integer(HavokInteger::default()),
-                HavokValueType::INT => HavokValue::Integer(HavokInteger::default()),
-                HavokValueType::OBJECT => HavokValue::ObjectReference(0),
-                _ => panic!("unimplemented {}", type_.bits()),
+                HavokValueType::EMPTY => Some(HavokValue::Integer(HavokInteger::default())),
+                HavokValueType::BYTE => Some(HavokValue::Integer(HavokInteger::default())),
+                HavokValueType::INT => Some(HavokValue::Integer(HavokInteger::default())),
+                HavokValueType::OBJECT => Some(HavokValue::ObjectReference(0)),
+                _ => None,
             }
         }
     }
@@ -14,37 +14,30 @@ impl<'a> ByteReader<'a> {
         Self { data, cursor: 0 }
     }
 
-    pub fn read(&mut self) -> u8 {
-        let result = self.data[self.cursor];
+    pub fn read(&mut self) -> Option<u8> {
+        let result = *self.data.get(self.cursor)?;
         self.cursor += 1;
 
-        result
+        Some(result)
     }
 
-    pub fn read_u16_le(&mut self) -> u16 {
-        let result = u16::from_le_bytes([self.data[self.cursor], self.data[self.cursor + 1]]);
-        self.cursor += size_of::<u16>();
+    pub fn read_u16_le(&mut self) -> Option<u16> {
+        let result = u16::from_le_bytes(self.read_bytes(size_of::<u16>())?.try_into().ok()?);
 
-        result
+        Some(result)
     }
 
-    pub fn read_f32_le(&mut self) -> f32 {
-        let result = f32::from_le_bytes([
-            self.data[self.cursor],
-            self.data[self.cursor + 1],
-            self.data[self.cursor + 2],
-            self.data[self.cursor + 3],
-        ]);
-        self.cursor += size_of::<f32>();
+    pub fn read_f32_le(&mut self) -> Option<f32> {
+        let result = f32::from_le_bytes(self.read_bytes(size_of::<f32>())?.try_into().ok()?);
 
-        result
+        Some(result)
     }
 
-    pub fn read_bytes(&mut self, size: usize) -> &[u8] {
-        let result = &self.data[self.cursor..self.cursor + size];
+    pub fn read_bytes(&mut self, size: usize) -> Option<&[u8]> {
+        let result = self.data.get(self.cursor..self.cursor.checked_add(size)?)?;
         self.cursor += size;
 
-        result
+        Some(result)
     }
 
     pub fn align(&mut self, align: usize) {
@@ -68,6 +61,10 @@ impl<'a> ByteReader<'a> {
         &self.data[self.cursor..]
     }
 
+    pub fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.cursor)
+    }
+
     pub fn seek(&mut self, offset: usize) {
         self.cursor += offset;
     }
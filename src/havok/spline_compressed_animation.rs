@@ -93,31 +93,31 @@ pub struct HavokSplineCompressedAnimation {
 }
 
 impl HavokSplineCompressedAnimation {
-    pub fn new(object: Arc<RefCell<HavokObject>>) -> Self {
+    pub fn new(object: Arc<RefCell<HavokObject>>) -> Option<Self> {
         let root = object.borrow();
 
-        let duration = root.get("duration").as_real();
-        let number_of_transform_tracks = root.get("numberOfTransformTracks").as_int() as usize;
-        let num_frames = root.get("numFrames").as_int() as usize;
-        let num_blocks = root.get("numBlocks").as_int() as usize;
-        let max_frames_per_block = root.get("maxFramesPerBlock").as_int() as usize;
-        let mask_and_quantization_size = root.get("maskAndQuantizationSize").as_int() as u32;
-        let block_inverse_duration = root.get("blockInverseDuration").as_real();
-        let frame_duration = root.get("frameDuration").as_real();
+        let duration = root.get("duration")?.as_real()?;
+        let number_of_transform_tracks = root.get("numberOfTransformTracks")?.as_int()? as usize;
+        let num_frames = root.get("numFrames")?.as_int()? as usize;
+        let num_blocks = root.get("numBlocks")?.as_int()? as usize;
+        let max_frames_per_block = root.get("maxFramesPerBlock")?.as_int()? as usize;
+        let mask_and_quantization_size = root.get("maskAndQuantizationSize")?.as_int()? as u32;
+        let block_inverse_duration = root.get("blockInverseDuration")?.as_real()?;
+        let frame_duration = root.get("frameDuration")?.as_real()?;
 
-        let raw_block_offsets = root.get("blockOffsets").as_array();
+        let raw_block_offsets = root.get("blockOffsets")?.as_array()?;
         let block_offsets = raw_block_offsets
             .iter()
-            .map(|x| x.as_int() as u32)
-            .collect::<Vec<_>>();
+            .map(|x| Some(x.as_int()? as u32))
+            .collect::<Option<Vec<_>>>()?;
 
-        let raw_data = root.get("data").as_array();
+        let raw_data = root.get("data")?.as_array()?;
         let data = raw_data
             .iter()
-            .map(|x| x.as_int() as u8)
-            .collect::<Vec<_>>();
+            .map(|x| Some(x.as_int()? as u8))
+            .collect::<Option<Vec<_>>>()?;
 
-        Self {
+        Some(Self {
             duration,
             number_of_transform_tracks,
             num_frames,
@@ -128,7 +128,7 @@ impl HavokSplineCompressedAnimation {
             frame_duration,
             block_offsets,
             data,
-        }
+        })
     }
 
     fn get_block_and_time(&self, frame: usize, delta: f32) -> (usize, f32, u8) {
@@ -174,9 +174,9 @@ impl HavokSplineCompressedAnimation {
         data: &mut ByteReader,
         u: u8,
         frame_duration: f32,
-    ) -> (usize, usize, Vec<f32>, usize) {
-        let n = data.read_u16_le() as usize;
-        let p = data.read() as usize;
+    ) -> Option<(usize, usize, Vec<f32>, usize)> {
+        let n = data.read_u16_le()? as usize;
+        let p = data.read()? as usize;
         let raw = data.raw();
         let span = Self::find_span(n, p, u, raw);
 
@@ -190,7 +190,7 @@ impl HavokSplineCompressedAnimation {
 
         data.seek(n + p + 2);
 
-        (n, p, U, span)
+        Some((n, p, U, span))
     }
 
     fn unpack_signed_quaternion_32(data: &[u8]) -> [f32; 4] {
@@ -456,7 +456,7 @@ impl HavokSplineCompressedAnimation {
         quantized_time: u8,
         mask: u8,
         data: &mut ByteReader,
-    ) -> [f32; 4] {
+    ) -> Option<[f32; 4]> {
         let result = if mask != 0 {
             Self::read_nurbs_curve(
                 quantization,
@@ -466,14 +466,14 @@ impl HavokSplineCompressedAnimation {
                 time,
                 mask,
                 [0., 0., 0., 0.],
-            )
+            )?
         } else {
             [0., 0., 0., 0.]
         };
 
         data.align(4);
 
-        result
+        Some(result)
     }
 
     fn sample_rotation(
@@ -483,7 +483,7 @@ impl HavokSplineCompressedAnimation {
         quantized_time: u8,
         mask: u8,
         data: &mut ByteReader,
-    ) -> [f32; 4] {
+    ) -> Option<[f32; 4]> {
         let result = Self::read_nurbs_quaternion(
             quantization,
             data,
@@ -491,11 +491,11 @@ impl HavokSplineCompressedAnimation {
             self.frame_duration,
             time,
             mask,
-        );
+        )?;
 
         data.align(4);
 
-        result
+        Some(result)
     }
 
     fn sample_scale(
@@ -505,7 +505,7 @@ impl HavokSplineCompressedAnimation {
         quantized_time: u8,
         mask: u8,
         data: &mut ByteReader,
-    ) -> [f32; 4] {
+    ) -> Option<[f32; 4]> {
         let result = if mask != 0 {
             Self::read_nurbs_curve(
                 quantization,
@@ -515,14 +515,14 @@ impl HavokSplineCompressedAnimation {
                 time,
                 mask,
                 [1., 1., 1., 1.],
-            )
+            )?
         } else {
             [1., 1., 1., 1.]
         };
 
         data.align(4);
 
-        result
+        Some(result)
     }
 
     #[allow(non_snake_case)]
@@ -534,13 +534,13 @@ impl HavokSplineCompressedAnimation {
         u: f32,
         mask: u8,
         I: [f32; 4],
-    ) -> [f32; 4] {
+    ) -> Option<[f32; 4]> {
         let mut max_p = [0., 0., 0., 1.];
         let mut min_p = [0., 0., 0., 1.];
         let mut S = [0., 0., 0., 1.];
 
         let (n, p, U, span) = if mask & 0xf0 != 0 {
-            Self::read_knots(data, quantized_time, frame_duration)
+            Self::read_knots(data, quantized_time, frame_duration)?
         } else {
             (0, 0, vec![0.; 10], 0)
         };
@@ -548,10 +548,10 @@ impl HavokSplineCompressedAnimation {
 
         for i in 0..3 {
             if (1 << i) & mask != 0 {
-                S[i] = data.read_f32_le();
+                S[i] = data.read_f32_le()?;
             } else if (1 << (i + 4)) & mask != 0 {
-                min_p[i] = data.read_f32_le();
-                max_p[i] = data.read_f32_le();
+                min_p[i] = data.read_f32_le()?;
+                max_p[i] = data.read_f32_le()?;
             }
         }
 
@@ -574,7 +574,7 @@ impl HavokSplineCompressedAnimation {
                         let mut vals = [0; 4];
                         for (j, item) in vals.iter_mut().enumerate().take(3) {
                             if (1 << (j + 4)) & mask != 0 {
-                                *item = new_data.read();
+                                *item = new_data.read()?;
                             }
                         }
 
@@ -584,7 +584,7 @@ impl HavokSplineCompressedAnimation {
                         let mut vals = [0; 4];
                         for (j, item) in vals.iter_mut().enumerate().take(3) {
                             if (1 << (j + 4)) & mask != 0 {
-                                *item = new_data.read_u16_le();
+                                *item = new_data.read_u16_le()?;
                             }
                         }
 
@@ -599,12 +599,12 @@ impl HavokSplineCompressedAnimation {
 
             data.seek(bytes_per_component * size * (n + 1));
 
-            result
+            Some(result)
         } else {
             let mut result = I;
             Self::recompose(stat_mask, dyn_mask, S, I, &mut result);
 
-            result
+            Some(result)
         }
     }
 
@@ -616,19 +616,19 @@ impl HavokSplineCompressedAnimation {
         frame_duration: f32,
         u: f32,
         mask: u8,
-    ) -> [f32; 4] {
+    ) -> Option<[f32; 4]> {
         if mask & 0xf0 != 0 {
-            let (n, p, U, span) = Self::read_knots(data, quantized_time, frame_duration);
+            let (n, p, U, span) = Self::read_knots(data, quantized_time, frame_duration)?;
             let P = Self::read_packed_quaternions(quantization, data, n, p, span);
-            Self::evaluate(u, p, &U, &P)
+            Some(Self::evaluate(u, p, &U, &P))
         } else if mask & 0x0f != 0 {
             data.align(quantization.align());
             let result = Self::unpack_quaternion(&quantization, data.raw());
             data.seek(quantization.bytes_per_quaternion());
 
-            result
+            Some(result)
         } else {
-            [0., 0., 0., 1.]
+            Some([0., 0., 0., 1.])
         }
     }
 }
@@ -638,7 +638,7 @@ impl HavokAnimation for HavokSplineCompressedAnimation {
         self.duration
     }
 
-    fn sample(&self, time: f32) -> Vec<HavokTransform> {
+    fn sample(&self, time: f32) -> Option<Vec<HavokTransform>> {
         let frame_float = ((time / 1000.) / self.duration) * (self.num_frames as f32 - 1.);
         let frame = frame_float as usize;
         let delta = frame_float - frame as f32;
@@ -660,7 +660,7 @@ impl HavokAnimation for HavokSplineCompressedAnimation {
 
         let mut result = Vec::with_capacity(self.number_of_transform_tracks);
         for _ in 0..self.number_of_transform_tracks {
-            let packed_quantization_types = mask.read();
+            let packed_quantization_types = mask.read()?;
 
             let (translation_type, rotation_type, scale_type) =
                 Self::unpack_quantization_types(packed_quantization_types);
@@ -669,27 +669,27 @@ impl HavokAnimation for HavokSplineCompressedAnimation {
                 translation_type,
                 block_time,
                 quantized_time,
-                mask.read(),
+                mask.read()?,
                 &mut data,
-            );
+            )?;
             let rotation = self.sample_rotation(
                 rotation_type,
                 block_time,
                 quantized_time,
-                mask.read(),
+                mask.read()?,
                 &mut data,
-            );
+            )?;
             let scale = self.sample_scale(
                 scale_type,
                 block_time,
                 quantized_time,
-                mask.read(),
+                mask.read()?,
                 &mut data,
-            );
+            )?;
 
             result.push(HavokTransform::from_trs(translation, rotation, scale));
         }
 
-        result
+        Some(result)
     }
 }
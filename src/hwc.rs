@@ -5,7 +5,10 @@ use std::io::{Cursor, Read};
 
 use binrw::BinWrite;
 
-use crate::{ByteBuffer, ByteSpan, ReadableFile, WritableFile, common::Platform};
+use crate::{
+    ByteBuffer, ByteSpan, ReadableFile, WritableFile, common::Platform, png::read_png,
+    png::write_png, tga::read_tga, tga::write_tga,
+};
 
 /// Hardware cursor file, usually with the `.hwc` file extension.
 ///
@@ -47,6 +50,42 @@ impl Hwc {
 
     /// The height of all hardware cursors, in pixels.
     pub const HEIGHT: usize = 64;
+
+    /// Reads a cursor from a PNG image, which has to be exactly [Hwc::WIDTH] by [Hwc::HEIGHT].
+    pub fn from_png(buffer: ByteSpan) -> crate::Result<Self> {
+        let (width, height, rgba) = read_png(buffer)?;
+        Self::from_image(width as usize, height as usize, rgba)
+    }
+
+    /// Reads a cursor from a TGA image, which has to be exactly [Hwc::WIDTH] by [Hwc::HEIGHT].
+    pub fn from_tga(buffer: ByteSpan) -> crate::Result<Self> {
+        let (width, height, rgba) = read_tga(buffer)?;
+        Self::from_image(width as usize, height as usize, rgba)
+    }
+
+    /// Encodes the cursor as a PNG image.
+    pub fn to_png(&self) -> crate::Result<ByteBuffer> {
+        write_png(Self::WIDTH as u32, Self::HEIGHT as u32, &self.rgba)
+    }
+
+    /// Encodes the cursor as a TGA image.
+    pub fn to_tga(&self) -> crate::Result<ByteBuffer> {
+        write_tga(Self::WIDTH as u16, Self::HEIGHT as u16, &self.rgba)
+    }
+
+    fn from_image(width: usize, height: usize, rgba: Vec<u8>) -> crate::Result<Self> {
+        if (width, height) != (Self::WIDTH, Self::HEIGHT) {
+            return Err(crate::Error::InvalidData {
+                reason: format!(
+                    "cursors have to be {}x{}, not {width}x{height}",
+                    Self::WIDTH,
+                    Self::HEIGHT
+                ),
+            });
+        }
+
+        Ok(Self { rgba })
+    }
 }
 
 #[cfg(test)]
@@ -59,4 +98,20 @@ mod tests {
     fn test_invalid() {
         pass_random_invalid::<Hwc>();
     }
+
+    #[test]
+    fn test_image_round_trip() {
+        let cursor = Hwc {
+            rgba: (0..Hwc::WIDTH * Hwc::HEIGHT * 4).map(|x| x as u8).collect(),
+        };
+
+        let png = Hwc::from_png(&cursor.to_png().unwrap()).unwrap();
+        assert_eq!(png.rgba, cursor.rgba);
+        let tga = Hwc::from_tga(&cursor.to_tga().unwrap()).unwrap();
+        assert_eq!(tga.rgba, cursor.rgba);
+
+        // Cursors can only be one size
+        let small = write_png(32, 32, &[0; 32 * 32 * 4]).unwrap();
+        assert!(Hwc::from_png(&small).is_err());
+    }
 }
@@ -0,0 +1,407 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! A tiny JSON reader and writer, so we don't have to depend on serde for the few places we import and export JSON.
+
+use crate::Error;
+
+/// How deep arrays and objects can be nested, so deeply nested input can't overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+/// A parsed JSON value.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum JsonValue {
+    Null,
+    Bool(bool),
+    /// Numbers are kept as their original text, so they can be parsed into the exact type needed without losing precision.
+    Number(String),
+    String(String),
+    Array(Vec<JsonValue>),
+    /// Objects keep the order of their keys.
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// Parses `text` as a single JSON value.
+    pub(crate) fn parse(text: &str) -> crate::Result<Self> {
+        let mut parser = Parser {
+            bytes: text.as_bytes(),
+            position: 0,
+        };
+
+        let value = parser.parse_value(0)?;
+        parser.skip_whitespace();
+        if parser.position != parser.bytes.len() {
+            return Err(parser.error("trailing characters"));
+        }
+
+        Ok(value)
+    }
+
+    /// Creates a number from anything that can be displayed as one.
+    pub(crate) fn number(value: impl std::fmt::Display) -> Self {
+        JsonValue::Number(value.to_string())
+    }
+
+    /// Returns the value of `key`, if this is an object and has it.
+    pub(crate) fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Parses this number as `T`, which also accepts numbers written as strings.
+    pub(crate) fn as_number<T: std::str::FromStr>(&self) -> Option<T> {
+        match self {
+            JsonValue::Number(value) | JsonValue::String(value) => value.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Writes this value as pretty-printed JSON.
+    pub(crate) fn to_pretty_string(&self) -> String {
+        let mut output = String::new();
+        self.write(&mut output, 0);
+        output.push('\n');
+        output
+    }
+
+    fn write(&self, output: &mut String, indent: usize) {
+        const INDENT: &str = "  ";
+
+        match self {
+            JsonValue::Null => output.push_str("null"),
+            JsonValue::Bool(value) => output.push_str(if *value { "true" } else { "false" }),
+            JsonValue::Number(value) => output.push_str(value),
+            JsonValue::String(value) => write_string(output, value),
+            JsonValue::Array(values) => {
+                if values.is_empty() {
+                    output.push_str("[]");
+                    return;
+                }
+
+                // Keep arrays of plain values on one line, they're usually vectors or colors
+                let simple = values
+                    .iter()
+                    .all(|x| !matches!(x, JsonValue::Array(_) | JsonValue::Object(_)));
+
+                output.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    if i != 0 {
+                        output.push(',');
+                    }
+                    if simple {
+                        if i != 0 {
+                            output.push(' ');
+                        }
+                    } else {
+                        output.push('\n');
+                        output.push_str(&INDENT.repeat(indent + 1));
+                    }
+                    value.write(output, indent + 1);
+                }
+                if !simple {
+                    output.push('\n');
+                    output.push_str(&INDENT.repeat(indent));
+                }
+                output.push(']');
+            }
+            JsonValue::Object(entries) => {
+                if entries.is_empty() {
+                    output.push_str("{}");
+                    return;
+                }
+
+                output.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i != 0 {
+                        output.push(',');
+                    }
+                    output.push('\n');
+                    output.push_str(&INDENT.repeat(indent + 1));
+                    write_string(output, key);
+                    output.push_str(": ");
+                    value.write(output, indent + 1);
+                }
+                output.push('\n');
+                output.push_str(&INDENT.repeat(indent));
+                output.push('}');
+            }
+        }
+    }
+}
+
+fn write_string(output: &mut String, value: &str) {
+    output.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 || c as u32 == 0x7F => {
+                output.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, reason: &str) -> Error {
+        Error::InvalidData {
+            reason: format!("{reason} in JSON at byte {}", self.position),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.bytes.get(self.position) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    fn expect(&mut self, literal: &str) -> crate::Result<()> {
+        if self.bytes[self.position..].starts_with(literal.as_bytes()) {
+            self.position += literal.len();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {literal}")))
+        }
+    }
+
+    /// Parses a value that's nested `depth` arrays or objects deep.
+    fn parse_value(&mut self, depth: usize) -> crate::Result<JsonValue> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("too deeply nested"));
+        }
+
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some(b'n') => self.expect("null").map(|_| JsonValue::Null),
+            Some(b't') => self.expect("true").map(|_| JsonValue::Bool(true)),
+            Some(b'f') => self.expect("false").map(|_| JsonValue::Bool(false)),
+            Some(b'"') => self.parse_string().map(JsonValue::String),
+            Some(b'[') => {
+                self.position += 1;
+                let mut values = Vec::new();
+
+                self.skip_whitespace();
+                if self.peek() == Some(b']') {
+                    self.position += 1;
+                    return Ok(JsonValue::Array(values));
+                }
+
+                loop {
+                    values.push(self.parse_value(depth + 1)?);
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(b',') => self.position += 1,
+                        Some(b']') => {
+                            self.position += 1;
+                            return Ok(JsonValue::Array(values));
+                        }
+                        _ => return Err(self.error("expected , or ]")),
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.position += 1;
+                let mut entries = Vec::new();
+
+                self.skip_whitespace();
+                if self.peek() == Some(b'}') {
+                    self.position += 1;
+                    return Ok(JsonValue::Object(entries));
+                }
+
+                loop {
+                    self.skip_whitespace();
+                    let key = self.parse_string()?;
+                    self.skip_whitespace();
+                    self.expect(":")?;
+                    entries.push((key, self.parse_value(depth + 1)?));
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(b',') => self.position += 1,
+                        Some(b'}') => {
+                            self.position += 1;
+                            return Ok(JsonValue::Object(entries));
+                        }
+                        _ => return Err(self.error("expected , or }")),
+                    }
+                }
+            }
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.position;
+                while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.peek() {
+                    self.position += 1;
+                }
+
+                let number = std::str::from_utf8(&self.bytes[start..self.position]).unwrap();
+                if number.parse::<f64>().is_err() {
+                    return Err(self.error("invalid number"));
+                }
+
+                Ok(JsonValue::Number(number.to_string()))
+            }
+            _ => Err(self.error("unexpected character")),
+        }
+    }
+
+    fn parse_hex4(&mut self) -> crate::Result<u32> {
+        let hex = self
+            .bytes
+            .get(self.position..self.position + 4)
+            .and_then(|x| std::str::from_utf8(x).ok())
+            .and_then(|x| u32::from_str_radix(x, 16).ok())
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.position += 4;
+        Ok(hex)
+    }
+
+    fn parse_string(&mut self) -> crate::Result<String> {
+        self.expect("\"")?;
+
+        let mut bytes = Vec::new();
+        loop {
+            let Some(byte) = self.peek() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.position += 1;
+
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let Some(escape) = self.peek() else {
+                        return Err(self.error("unterminated string"));
+                    };
+                    self.position += 1;
+
+                    let c = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut code = self.parse_hex4()?;
+                            // Surrogate pairs
+                            if (0xD800..0xDC00).contains(&code) {
+                                self.expect("\\u")?;
+                                let low = self.parse_hex4()?;
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return Err(self.error("invalid surrogate pair"));
+                                }
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                            }
+                            char::from_u32(code)
+                                .ok_or_else(|| self.error("invalid unicode escape"))?
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    };
+
+                    let mut buffer = [0; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+                }
+                byte => bytes.push(byte),
+            }
+        }
+
+        String::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let value = JsonValue::Object(vec![
+            (
+                "name".to_string(),
+                JsonValue::String("Gil \"\u{2}\" é\n".to_string()),
+            ),
+            (
+                "count".to_string(),
+                JsonValue::number(18446744073709551615u64),
+            ),
+            (
+                "values".to_string(),
+                JsonValue::Array(vec![
+                    JsonValue::number(-1.5f32),
+                    JsonValue::Bool(true),
+                    JsonValue::Null,
+                ]),
+            ),
+            ("empty".to_string(), JsonValue::Object(Vec::new())),
+        ]);
+
+        let text = value.to_pretty_string();
+        assert_eq!(JsonValue::parse(&text).unwrap(), value);
+
+        assert_eq!(
+            value.get("count").unwrap().as_number::<u64>(),
+            Some(18446744073709551615)
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let value = JsonValue::parse(r#" { "a" : [1, 2e3, "😀\/"] } "#).unwrap();
+        let array = value.get("a").unwrap().as_array().unwrap();
+        assert_eq!(array[0].as_number::<i32>(), Some(1));
+        assert_eq!(array[1].as_number::<f32>(), Some(2000.0));
+        assert_eq!(array[2].as_str(), Some("😀/"));
+
+        assert!(JsonValue::parse("{").is_err());
+        assert!(JsonValue::parse("[1,]").is_err());
+        assert!(JsonValue::parse("1 2").is_err());
+        assert!(JsonValue::parse("\"abc").is_err());
+
+        // A high surrogate has to be followed by a low one
+        assert!(JsonValue::parse(r#""\ud83d\u0041""#).is_err());
+        assert!(JsonValue::parse(r#""\ud83d\uffff""#).is_err());
+
+        assert!(JsonValue::parse(&"[".repeat(100_000)).is_err());
+        let nested = format!("{}{}", "[".repeat(MAX_DEPTH), "]".repeat(MAX_DEPTH));
+        assert!(JsonValue::parse(&nested).is_ok());
+    }
+}
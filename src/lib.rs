@@ -31,6 +31,9 @@ pub mod exl;
 /// Dealing with equipment and its data.
 pub mod equipment;
 
+/// Assembling everything needed to render a character.
+pub mod character;
+
 // NOTE: Should be brought up to top-level because these are the most used types.
 mod common;
 #[cfg(test)]
@@ -168,6 +171,24 @@ pub mod excel;
 /// Implementation detail for textures.
 mod bcn;
 
+/// Implementation detail for importing and exporting JSON.
+mod json;
+
+/// Implementation detail for importing and exporting DDS textures.
+mod dds;
+
+/// Implementation detail for importing and exporting PNG images.
+mod png;
+
+/// Implementation detail for importing and exporting TGA images.
+mod tga;
+
+/// Implementation detail for resizing textures.
+mod resample;
+
+/// Implementation detail for console texture layouts.
+mod tiling;
+
 // NOTE: Should be brought up to the top-level because it's a basic error type.
 mod error;
 pub use error::Error;
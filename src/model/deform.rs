@@ -0,0 +1,160 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{
+    Error,
+    model::{MDL, math::normalize},
+    pbd::{PreBoneDeformMatrices, PreBoneDeformer},
+};
+
+impl MDL {
+    /// Deforms a model made for `from_body_id` (like `101` for `c0101`) to fit `to_body_id`, using the racial deformers in `pbd`.
+    ///
+    /// This is how the game fits gear to races it wasn't made for, so this can be used to create race-specific variants of it. Returns an error if either body isn't in `pbd`.
+    pub fn apply_racial_deform(
+        &mut self,
+        pbd: &PreBoneDeformer,
+        from_body_id: u16,
+        to_body_id: u16,
+    ) -> crate::Result<()> {
+        if from_body_id == to_body_id {
+            return Ok(());
+        }
+
+        let matrices = pbd
+            .get_deform_matrices_between(from_body_id, to_body_id)
+            .ok_or_else(|| Error::InvalidData {
+                reason: format!(
+                    "can't deform from body {from_body_id:04} to {to_body_id:04}, as one of them isn't in the pbd"
+                ),
+            })?;
+        self.apply_deform_matrices(&matrices);
+
+        Ok(())
+    }
+
+    /// Deforms the vertices of every part by `matrices`, blended by each vertex's bone weights.
+    ///
+    /// Bones without a deform matrix aren't changed. Positions, normals and bitangents are deformed, including the vertices used by shapes.
+    pub fn apply_deform_matrices(&mut self, matrices: &PreBoneDeformMatrices) {
+        // Look up the matrix of each bone once, instead of for every vertex
+        let bone_matrices: Vec<Option<[f32; 12]>> = self
+            .affected_bone_names
+            .iter()
+            .map(|name| matrices.get(name).copied())
+            .collect();
+
+        for l in 0..self.lods.len() {
+            for p in 0..self.lods[l].parts.len() {
+                let part_matrices: Vec<Option<[f32; 12]>> = self
+                    .bone_table(&self.lods[l].parts[p])
+                    .iter()
+                    .map(|bone| bone_matrices.get(*bone as usize).copied().flatten())
+                    .collect();
+
+                for vertex in &mut self.lods[l].parts[p].vertices {
+                    let sum: f32 = vertex.bone_weight.iter().sum();
+                    if sum <= 0.0 {
+                        continue;
+                    }
+
+                    let mut position = [0.0; 3];
+                    let mut normal = [0.0; 3];
+                    let mut bitangent = [0.0; 3];
+                    for (weight, bone_id) in vertex.bone_weight.iter().zip(vertex.bone_id) {
+                        if *weight <= 0.0 {
+                            continue;
+                        }
+                        let weight = weight / sum;
+
+                        let matrix = part_matrices.get(bone_id as usize).copied().flatten();
+                        let moved = transform(matrix.as_ref(), vertex.position, 1.0);
+                        let turned = transform(matrix.as_ref(), vertex.normal, 0.0);
+                        let bent = transform(
+                            matrix.as_ref(),
+                            [
+                                vertex.bitangent[0],
+                                vertex.bitangent[1],
+                                vertex.bitangent[2],
+                            ],
+                            0.0,
+                        );
+                        for i in 0..3 {
+                            position[i] += moved[i] * weight;
+                            normal[i] += turned[i] * weight;
+                            bitangent[i] += bent[i] * weight;
+                        }
+                    }
+
+                    vertex.position = position;
+                    if let Some(normal) = normalize(normal) {
+                        vertex.normal = normal;
+                    }
+                    if let Some(bitangent) = normalize(bitangent) {
+                        vertex.bitangent[..3].copy_from_slice(&bitangent);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Transforms a point (`w` of 1) or direction (`w` of 0) by a 3x4 row-major matrix, or leaves it unchanged without one.
+fn transform(m: Option<&[f32; 12]>, v: [f32; 3], w: f32) -> [f32; 3] {
+    let Some(m) = m else {
+        return v;
+    };
+
+    [
+        m[0] * v[0] + m[1] * v[1] + m[2] * v[2] + m[3] * w,
+        m[4] * v[0] + m[5] * v[1] + m[6] * v[2] + m[7] * w,
+        m[8] * v[0] + m[9] * v[1] + m[10] * v[2] + m[11] * w,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read;
+    use std::path::PathBuf;
+
+    use crate::{ReadableFile, common::Platform, pbd::PreBoneDeformBone};
+
+    use super::*;
+
+    #[test]
+    fn test_apply_deform_matrices() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/tests");
+        d.push("c0201e0038_top_zeroed.mdl");
+
+        let mut mdl = MDL::from_existing(Platform::Win32, &read(d).unwrap()).unwrap();
+
+        // Fully weight the first two vertices to the first two bones of the part
+        let part = &mut mdl.lods[0].parts[0];
+        for (i, vertex) in part.vertices.iter_mut().take(2).enumerate() {
+            vertex.position = [1.0, 1.0, 1.0];
+            vertex.normal = [0.0, 1.0, 0.0];
+            vertex.bone_weight = [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+            vertex.bone_id = [i as u8, 0, 0, 0, 0, 0, 0, 0];
+        }
+        let bone_table = mdl.bone_table(&mdl.lods[0].parts[0]).to_vec();
+
+        // Only the first bone is deformed, by scaling it twice on X and moving it up
+        let matrices = PreBoneDeformMatrices {
+            bones: vec![PreBoneDeformBone {
+                name: mdl.affected_bone_names[bone_table[0] as usize].clone(),
+                deform: [
+                    2.0, 0.0, 0.0, 0.0, //
+                    0.0, 1.0, 0.0, 1.0, //
+                    0.0, 0.0, 1.0, 0.0,
+                ],
+            }],
+        };
+        mdl.apply_deform_matrices(&matrices);
+
+        let part = &mdl.lods[0].parts[0];
+        assert_eq!(part.vertices[0].position, [2.0, 2.0, 1.0]);
+        assert_eq!(part.vertices[0].normal, [0.0, 1.0, 0.0]);
+        assert_eq!(part.vertices[1].position, [1.0, 1.0, 1.0]);
+    }
+}
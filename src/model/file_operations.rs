@@ -2,7 +2,10 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use crate::ByteSpan;
-use crate::model::MDL;
+use crate::model::vertex_declarations::{
+    VertexElement, VertexType, VertexUsage, get_vertex_type_component_count,
+};
+use crate::model::{MDL, Vertex};
 use binrw::{BinReaderExt, BinResult, BinWriterExt, Endian};
 use half::f16;
 use std::io::Cursor;
@@ -10,6 +13,9 @@ use std::io::Cursor;
 /// Maximum value of byte, used to divide and multiply floats in that space [0.0..1.0] to [0..255]
 const MAX_BYTE_FLOAT: f32 = u8::MAX as f32;
 
+/// Maximum value of a signed short, used for normalized shorts in the range [-1.0..1.0]
+const MAX_SHORT_FLOAT: f32 = i16::MAX as f32;
+
 impl MDL {
     pub(crate) fn read_byte_float4(cursor: &mut Cursor<ByteSpan>) -> BinResult<[f32; 4]> {
         Ok([
@@ -32,18 +38,6 @@ impl MDL {
         ])
     }
 
-    pub(crate) fn write_byte_float42<T: BinWriterExt>(
-        cursor: &mut T,
-        vec: &[f32; 4],
-    ) -> BinResult<()> {
-        cursor.write_ne::<[u8; 4]>(&[
-            (vec[0]).round() as u8,
-            (vec[1]).round() as u8,
-            (vec[2]).round() as u8,
-            (vec[3]).round() as u8,
-        ])
-    }
-
     pub(crate) fn read_tangent(cursor: &mut Cursor<ByteSpan>) -> BinResult<[f32; 4]> {
         Ok([
             (f32::from(cursor.read_ne::<u8>()?) * 2.0 / MAX_BYTE_FLOAT - 1.0),
@@ -122,6 +116,14 @@ impl MDL {
         cursor.write_ne::<[u8; 4]>(vec)
     }
 
+    pub(crate) fn read_byte8(cursor: &mut Cursor<ByteSpan>) -> BinResult<[u8; 8]> {
+        cursor.read_ne::<[u8; 8]>()
+    }
+
+    pub(crate) fn write_byte8<T: BinWriterExt>(cursor: &mut T, vec: &[u8; 8]) -> BinResult<()> {
+        cursor.write_ne::<[u8; 8]>(vec)
+    }
+
     pub(crate) fn read_single3(
         cursor: &mut Cursor<ByteSpan>,
         endian: Endian,
@@ -159,6 +161,91 @@ impl MDL {
         cursor.read_type_args::<[u16; 4]>(endian, ())
     }
 
+    /// Reads an element of any type, with each component converted to a float. Components the type doesn't have are zero.
+    pub(crate) fn read_element(
+        cursor: &mut Cursor<ByteSpan>,
+        endian: Endian,
+        vertex_type: VertexType,
+    ) -> BinResult<[f32; 4]> {
+        match vertex_type {
+            VertexType::Single3 => Ok(MDL::pad_slice(&MDL::read_single3(cursor, endian)?, 0.0)),
+            VertexType::Single4 => MDL::read_single4(cursor, endian),
+            VertexType::Byte4 => Ok(MDL::read_byte4(cursor)?.map(f32::from)),
+            VertexType::ByteFloat4 | VertexType::UnkPS3 => MDL::read_byte_float4(cursor),
+            VertexType::Half2 => Ok(MDL::pad_slice(&MDL::read_half2(cursor, endian)?, 0.0)),
+            VertexType::Half4 => MDL::read_half4(cursor, endian),
+            VertexType::UnsignedShort4 => {
+                Ok(MDL::read_unsigned_short4(cursor, endian)?.map(f32::from))
+            }
+            _ => {
+                let mut values = [0.0; 4];
+                for value in values
+                    .iter_mut()
+                    .take(get_vertex_type_component_count(vertex_type))
+                {
+                    *value = match vertex_type {
+                        VertexType::Short2 | VertexType::Short4 => {
+                            f32::from(cursor.read_type_args::<i16>(endian, ())?)
+                        }
+                        VertexType::Short2n | VertexType::Short4n => {
+                            (f32::from(cursor.read_type_args::<i16>(endian, ())?) / MAX_SHORT_FLOAT)
+                                .max(-1.0)
+                        }
+                        VertexType::UnsignedShort2 => {
+                            f32::from(cursor.read_type_args::<u16>(endian, ())?)
+                        }
+                        _ => cursor.read_type_args::<f32>(endian, ())?,
+                    };
+                }
+
+                Ok(values)
+            }
+        }
+    }
+
+    /// Writes an element of any type, the opposite of [read_element](Self::read_element).
+    pub(crate) fn write_element<T: BinWriterExt>(
+        cursor: &mut T,
+        endian: Endian,
+        vertex_type: VertexType,
+        vec: &[f32; 4],
+    ) -> BinResult<()> {
+        match vertex_type {
+            VertexType::Single3 => MDL::write_single3(cursor, endian, &[vec[0], vec[1], vec[2]]),
+            VertexType::Single4 => MDL::write_single4(cursor, endian, vec),
+            VertexType::Byte4 => MDL::write_byte4(cursor, &vec.map(|x| x.round() as u8)),
+            VertexType::ByteFloat4 | VertexType::UnkPS3 => MDL::write_byte_float4(cursor, vec),
+            VertexType::Half2 => MDL::write_half2(cursor, endian, &[vec[0], vec[1]]),
+            VertexType::Half4 => MDL::write_half4(cursor, endian, vec),
+            VertexType::UnsignedShort4 => {
+                cursor.write_type_args::<[u16; 4]>(&vec.map(|x| x.round() as u16), endian, ())
+            }
+            _ => {
+                for value in vec
+                    .iter()
+                    .take(get_vertex_type_component_count(vertex_type))
+                {
+                    match vertex_type {
+                        VertexType::Short2 | VertexType::Short4 => {
+                            cursor.write_type_args(&(value.round() as i16), endian, ())?
+                        }
+                        VertexType::Short2n | VertexType::Short4n => cursor.write_type_args(
+                            &((value.clamp(-1.0, 1.0) * MAX_SHORT_FLOAT).round() as i16),
+                            endian,
+                            (),
+                        )?,
+                        VertexType::UnsignedShort2 => {
+                            cursor.write_type_args(&(value.round() as u16), endian, ())?
+                        }
+                        _ => cursor.write_type_args(value, endian, ())?,
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    }
+
     pub(crate) fn pad_slice<const N: usize>(small_slice: &[f32; N], fill: f32) -> [f32; 4] {
         let mut bigger_slice: [f32; 4] = [fill, fill, fill, fill];
         bigger_slice[..N].copy_from_slice(&small_slice[..N]);
@@ -166,11 +253,176 @@ impl MDL {
     }
 }
 
+impl Vertex {
+    /// Reads `element` into the matching field of this vertex.
+    pub(crate) fn read_element(
+        &mut self,
+        cursor: &mut Cursor<ByteSpan>,
+        endian: Endian,
+        element: &VertexElement,
+    ) -> BinResult<()> {
+        let vertex_type = element.vertex_type;
+        // Whether this is the second element of this usage, e.g. the second set of UVs
+        let second = element.usage_index > 0;
+
+        match element.vertex_usage {
+            VertexUsage::Position => {
+                let position = MDL::read_element(cursor, endian, vertex_type)?;
+                self.position.copy_from_slice(&position[0..3]);
+            }
+            VertexUsage::BlendWeights => match vertex_type {
+                VertexType::UnsignedShort4 => {
+                    self.bone_weight =
+                        MDL::read_byte8(cursor)?.map(|x| f32::from(x) / MAX_BYTE_FLOAT);
+                }
+                _ => {
+                    // Unlike blend indices, these are always normalized
+                    let weights = match vertex_type {
+                        VertexType::Byte4 => MDL::read_byte_float4(cursor)?,
+                        _ => MDL::read_element(cursor, endian, vertex_type)?,
+                    };
+                    let start = if second { 4 } else { 0 };
+                    self.bone_weight[start..start + 4].copy_from_slice(&weights);
+                }
+            },
+            VertexUsage::BlendIndices => match vertex_type {
+                VertexType::UnsignedShort4 => {
+                    self.bone_id = MDL::read_byte8(cursor)?;
+                }
+                _ => {
+                    let ids = MDL::read_element(cursor, endian, vertex_type)?;
+                    let start = if second { 4 } else { 0 };
+                    for (id, value) in self.bone_id[start..start + 4].iter_mut().zip(ids) {
+                        *id = value as u8;
+                    }
+                }
+            },
+            VertexUsage::Normal => {
+                let normal = MDL::read_element(cursor, endian, vertex_type)?;
+                self.normal.copy_from_slice(&normal[0..3]);
+            }
+            VertexUsage::UV => {
+                let combined = MDL::read_element(cursor, endian, vertex_type)?;
+                let (first, other) = if second {
+                    (&mut self.uv2, &mut self.uv3)
+                } else {
+                    (&mut self.uv0, &mut self.uv1)
+                };
+
+                first.copy_from_slice(&combined[0..2]);
+                if get_vertex_type_component_count(vertex_type) > 2 {
+                    other.copy_from_slice(&combined[2..4]);
+                }
+            }
+            VertexUsage::Tangent | VertexUsage::BiTangent => {
+                let value = match vertex_type {
+                    VertexType::ByteFloat4 => MDL::read_tangent(cursor)?,
+                    _ => MDL::read_element(cursor, endian, vertex_type)?,
+                };
+
+                match (element.vertex_usage, second) {
+                    (VertexUsage::Tangent, _) => self.tangent = value,
+                    (_, false) => self.bitangent = value,
+                    (_, true) => self.bitangent1 = value,
+                }
+            }
+            VertexUsage::Color => {
+                let color = MDL::read_element(cursor, endian, vertex_type)?;
+                if second {
+                    self.color1 = color;
+                } else {
+                    self.color = color;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the field of this vertex matching `element`, the opposite of [read_element](Self::read_element).
+    pub(crate) fn write_element<T: BinWriterExt>(
+        &self,
+        cursor: &mut T,
+        endian: Endian,
+        element: &VertexElement,
+    ) -> BinResult<()> {
+        let vertex_type = element.vertex_type;
+        let second = element.usage_index > 0;
+        let start = if second { 4 } else { 0 };
+
+        match element.vertex_usage {
+            VertexUsage::Position => MDL::write_element(
+                cursor,
+                endian,
+                vertex_type,
+                &MDL::pad_slice(&self.position, 1.0),
+            ),
+            VertexUsage::BlendWeights => match vertex_type {
+                VertexType::UnsignedShort4 => MDL::write_byte8(
+                    cursor,
+                    &self.bone_weight.map(|x| (x * MAX_BYTE_FLOAT).round() as u8),
+                ),
+                VertexType::Byte4 => MDL::write_byte_float4(
+                    cursor,
+                    self.bone_weight[start..start + 4].try_into().unwrap(),
+                ),
+                _ => MDL::write_element(
+                    cursor,
+                    endian,
+                    vertex_type,
+                    self.bone_weight[start..start + 4].try_into().unwrap(),
+                ),
+            },
+            VertexUsage::BlendIndices => match vertex_type {
+                VertexType::UnsignedShort4 => MDL::write_byte8(cursor, &self.bone_id),
+                _ => {
+                    let mut ids = [0.0; 4];
+                    for (value, id) in ids.iter_mut().zip(&self.bone_id[start..start + 4]) {
+                        *value = f32::from(*id);
+                    }
+                    MDL::write_element(cursor, endian, vertex_type, &ids)
+                }
+            },
+            VertexUsage::Normal => MDL::write_element(
+                cursor,
+                endian,
+                vertex_type,
+                &MDL::pad_slice(&self.normal, 0.0),
+            ),
+            VertexUsage::UV => {
+                let combined = if second {
+                    [self.uv2[0], self.uv2[1], self.uv3[0], self.uv3[1]]
+                } else {
+                    [self.uv0[0], self.uv0[1], self.uv1[0], self.uv1[1]]
+                };
+                MDL::write_element(cursor, endian, vertex_type, &combined)
+            }
+            VertexUsage::Tangent | VertexUsage::BiTangent => {
+                let value = match (element.vertex_usage, second) {
+                    (VertexUsage::Tangent, _) => &self.tangent,
+                    (_, false) => &self.bitangent,
+                    (_, true) => &self.bitangent1,
+                };
+
+                match vertex_type {
+                    VertexType::ByteFloat4 => MDL::write_tangent(cursor, value),
+                    _ => MDL::write_element(cursor, endian, vertex_type, value),
+                }
+            }
+            VertexUsage::Color => {
+                let color = if second { &self.color1 } else { &self.color };
+                MDL::write_element(cursor, endian, vertex_type, color)
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use binrw::Endian;
 
-    use crate::model::MDL;
+    use crate::model::vertex_declarations::{VertexElement, VertexType, VertexUsage};
+    use crate::model::{MDL, Vertex};
     use std::io::Cursor;
 
     macro_rules! assert_delta {
@@ -296,4 +548,73 @@ mod tests {
 
         assert_eq!(MDL::pad_slice(&a, 1.0), b);
     }
+
+    #[test]
+    fn short4n() {
+        let a = [1.0, -1.0, 0.5, 0.0];
+
+        let mut v = vec![];
+        let mut cursor = Cursor::new(&mut v);
+
+        MDL::write_element(&mut cursor, Endian::Little, VertexType::Short4n, &a).unwrap();
+        assert_eq!(v.len(), 8);
+
+        let mut read_cursor = Cursor::new(v.as_slice());
+        let b = MDL::read_element(&mut read_cursor, Endian::Little, VertexType::Short4n).unwrap();
+        assert_delta!(b, a, 0.001);
+    }
+
+    #[test]
+    fn eight_influences() {
+        let element = |vertex_usage| VertexElement {
+            stream: 0,
+            offset: 0,
+            vertex_type: VertexType::UnsignedShort4,
+            vertex_usage,
+            usage_index: 0,
+        };
+
+        let a = Vertex {
+            bone_weight: [0.2, 0.2, 0.2, 0.2, 0.1, 0.1, 0.0, 0.0],
+            bone_id: [1, 2, 3, 4, 5, 6, 0, 0],
+            ..Default::default()
+        };
+
+        let mut v = vec![];
+        let mut cursor = Cursor::new(&mut v);
+
+        a.write_element(
+            &mut cursor,
+            Endian::Little,
+            &element(VertexUsage::BlendWeights),
+        )
+        .unwrap();
+        a.write_element(
+            &mut cursor,
+            Endian::Little,
+            &element(VertexUsage::BlendIndices),
+        )
+        .unwrap();
+        assert_eq!(v.len(), 16);
+
+        let mut read_cursor = Cursor::new(v.as_slice());
+        let mut b = Vertex::default();
+        b.read_element(
+            &mut read_cursor,
+            Endian::Little,
+            &element(VertexUsage::BlendWeights),
+        )
+        .unwrap();
+        b.read_element(
+            &mut read_cursor,
+            Endian::Little,
+            &element(VertexUsage::BlendIndices),
+        )
+        .unwrap();
+
+        assert_eq!(b.bone_id, a.bone_id);
+        for (x, y) in b.bone_weight.iter().zip(a.bone_weight) {
+            assert!((x - y).abs() < 0.01);
+        }
+    }
 }
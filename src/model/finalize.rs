@@ -0,0 +1,349 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::model::{
+    BoundingBox, MDL, ModelData, Part, PartType,
+    math::{bitangent, bounding_box, cross, expand, normalize},
+};
+
+/// The version that switched to variable-sized bone tables.
+const BONE_TABLES_V2_VERSION: u32 = 0x1000006;
+
+impl MDL {
+    /// Recalculates everything derived from the vertices and sizes of the model, so it can be written after editing.
+    ///
+    /// This is [recalculate_bounding_boxes](Self::recalculate_bounding_boxes) and [generate_tangents](Self::generate_tangents), followed by updating the counts, sizes and offsets in the headers.
+    pub fn finalize(&mut self) {
+        self.recalculate_bounding_boxes();
+        self.generate_tangents();
+        self.update_counts();
+        self.update_headers();
+    }
+
+    /// Recalculates the bounding boxes and radius of the model, its water and vertical fog, and of each bone from the vertices they influence.
+    ///
+    /// Only the first LOD is used for the model, since the others are smaller versions of it.
+    pub fn recalculate_bounding_boxes(&mut self) {
+        let mut model_bounds = None;
+        let mut water_bounds = None;
+        let mut vertical_fog_bounds = None;
+        let mut bone_bounds = vec![None; self.affected_bone_names.len()];
+
+        for (l, lod) in self.lods.iter().enumerate() {
+            for part in &lod.parts {
+                if l == 0 {
+                    let bounds = match part.part_type {
+                        PartType::Normal => &mut model_bounds,
+                        PartType::Water => &mut water_bounds,
+                        PartType::VerticalFog => &mut vertical_fog_bounds,
+                    };
+                    for vertex in &part.vertices {
+                        expand(bounds, vertex.position);
+                    }
+                }
+
+                let bone_table = self.bone_table(part);
+                for vertex in &part.vertices {
+                    for (weight, bone_id) in vertex.bone_weight.iter().zip(vertex.bone_id) {
+                        if *weight <= 0.0 {
+                            continue;
+                        }
+                        let bone = bone_table.get(bone_id as usize).copied();
+                        if let Some(bounds) = bone.and_then(|x| bone_bounds.get_mut(x as usize)) {
+                            expand(bounds, vertex.position);
+                        }
+                    }
+                }
+            }
+        }
+
+        let (min, max) = model_bounds.unwrap_or_default();
+        self.model_data.header.radius = (0..3)
+            .map(|i| min[i].abs().max(max[i].abs()).powi(2))
+            .sum::<f32>()
+            .sqrt();
+
+        self.model_data.bounding_box = bounding_box(Some((min, max)));
+        self.model_data.model_bounding_box = self.model_data.bounding_box;
+        self.model_data.water_bounding_box = bounding_box(water_bounds);
+        self.model_data.vertical_fog_bounding_box = bounding_box(vertical_fog_bounds);
+        self.model_data.bone_bounding_boxes = bone_bounds.into_iter().map(bounding_box).collect();
+    }
+
+    /// Generates the tangents and bitangents of every vertex from its first UV, replacing the existing ones.
+    ///
+    /// Vertices used by shapes get the same ones as the vertex they replace.
+    pub fn generate_tangents(&mut self) {
+        for l in 0..self.lods.len() {
+            for p in 0..self.lods[l].parts.len() {
+                let replacements = self.shape_replacements(l, &self.lods[l].parts[p]);
+                let part = &mut self.lods[l].parts[p];
+                generate_part_tangents(part);
+
+                for (base, replacing) in replacements.into_iter().flatten() {
+                    if let (Some(base), Some(_)) = (
+                        part.vertices.get(base).copied(),
+                        part.vertices.get(replacing),
+                    ) {
+                        part.vertices[replacing].tangent = base.tangent;
+                        part.vertices[replacing].bitangent = base.bitangent;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the vertex each shape vertex of `part` replaces for each shape, as pairs of indices into its vertices.
+    pub(super) fn shape_replacements(&self, lod: usize, part: &Part) -> Vec<Vec<(usize, usize)>> {
+        let mesh = &self.model_data.meshes[part.mesh_index as usize];
+
+        let mut replacements = Vec::with_capacity(self.model_data.shapes.len());
+        for shape in &self.model_data.shapes {
+            let shape_meshes = self
+                .model_data
+                .shape_meshes
+                .iter()
+                .skip(
+                    shape
+                        .shape_mesh_start_index
+                        .get(lod)
+                        .copied()
+                        .unwrap_or_default() as usize,
+                )
+                .take(shape.shape_mesh_count.get(lod).copied().unwrap_or_default() as usize)
+                .filter(|x| x.mesh_index_offset == mesh.start_index);
+
+            let mut shape_replacements = Vec::new();
+            for shape_mesh in shape_meshes {
+                let values = self
+                    .model_data
+                    .shape_values
+                    .iter()
+                    .skip(shape_mesh.shape_value_offset as usize)
+                    .take(shape_mesh.shape_value_count as usize);
+                for value in values {
+                    // The base index is relative to the whole LOD, not this mesh
+                    let position = (value.base_indices_index as usize)
+                        .checked_sub(mesh.start_index as usize)
+                        .and_then(|x| part.indices.get(x));
+                    if let Some(base) = position {
+                        shape_replacements
+                            .push((*base as usize, value.replacing_vertex_index as usize));
+                    }
+                }
+            }
+            replacements.push(shape_replacements);
+        }
+
+        replacements
+    }
+
+    /// Makes the counts in the headers match what's actually in the model.
+    pub(super) fn update_counts(&mut self) {
+        let data = &mut self.model_data;
+
+        data.header.string_size = data.header.strings.len() as u32;
+        data.header.mesh_count = data.meshes.len() as u16;
+        data.header.attribute_count = data.attribute_name_offsets.len() as u16;
+        data.header.submesh_count = data.submeshes.len() as u16;
+        data.header.material_count = data.material_name_offsets.len() as u16;
+        data.header.bone_count = data.bone_name_offsets.len() as u16;
+        data.header.bone_table_count = if self.file_header.version >= BONE_TABLES_V2_VERSION {
+            data.bone_tables_v2.tables.len() as u16
+        } else {
+            data.bone_tables.len() as u16
+        };
+        data.header.element_id_count = data.element_ids.len() as u16;
+        data.header.terrain_shadow_mesh_count = data.terrain_shadow_meshes.len() as u8;
+        data.header.terrain_shadow_submesh_count = data.terrain_shadow_submeshes.len() as u16;
+        data.header.patch_72_table_size = (data.unknown_72_padding.len() / 16) as u16;
+        data.submesh_bone_map_size = data.submesh_bone_map.len() as u32 * 2;
+        data.update_padding();
+
+        self.file_header.vertex_declaration_count = data.header.vertex_declarations.len() as u16;
+        self.file_header.material_count = data.header.material_count;
+    }
+}
+
+impl ModelData {
+    /// Pads the model data so the bounding boxes start on a 16 byte boundary.
+    pub(super) fn update_padding(&mut self) {
+        let boxes_size = (4 + self.bone_bounding_boxes.len()) * size_of::<BoundingBox>();
+        let position =
+            self.calculate_runtime_size() as usize - self.unknown_padding.len() - boxes_size;
+        let padding = position.next_multiple_of(16) - position;
+
+        self.padding_amount = padding as u8;
+        self.unknown_padding = vec![0; padding];
+    }
+}
+
+/// Generates the tangent and bitangent of each vertex of `part` from the triangles using it.
+fn generate_part_tangents(part: &mut Part) {
+    let mut tangents = vec![[0.0f32; 3]; part.vertices.len()];
+    let mut bitangents = vec![[0.0f32; 3]; part.vertices.len()];
+
+    for triangle in part.indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|x| x as usize);
+        let (Some(va), Some(vb), Some(vc)) = (
+            part.vertices.get(a),
+            part.vertices.get(b),
+            part.vertices.get(c),
+        ) else {
+            continue;
+        };
+
+        let edge1 = sub(vb.position, va.position);
+        let edge2 = sub(vc.position, va.position);
+        let (du1, dv1) = (vb.uv0[0] - va.uv0[0], vb.uv0[1] - va.uv0[1]);
+        let (du2, dv2) = (vc.uv0[0] - va.uv0[0], vc.uv0[1] - va.uv0[1]);
+
+        let determinant = du1 * dv2 - du2 * dv1;
+        if determinant.abs() <= f32::EPSILON {
+            continue;
+        }
+        let r = 1.0 / determinant;
+
+        let tangent: [f32; 3] = std::array::from_fn(|i| (edge1[i] * dv2 - edge2[i] * dv1) * r);
+        let bitangent: [f32; 3] = std::array::from_fn(|i| (edge2[i] * du1 - edge1[i] * du2) * r);
+        for vertex in [a, b, c] {
+            for i in 0..3 {
+                tangents[vertex][i] += tangent[i];
+                bitangents[vertex][i] += bitangent[i];
+            }
+        }
+    }
+
+    for ((vertex, tangent), uv_bitangent) in part.vertices.iter_mut().zip(tangents).zip(bitangents)
+    {
+        // Make it perpendicular to the normal
+        let normal = vertex.normal;
+        let along = dot(normal, tangent);
+        let Some(tangent) = normalize(std::array::from_fn(|i| tangent[i] - normal[i] * along))
+        else {
+            continue;
+        };
+
+        let handedness = if dot(cross(normal, tangent), uv_bitangent) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+
+        vertex.tangent = [tangent[0], tangent[1], tangent[2], handedness];
+        vertex.bitangent = bitangent(tangent, handedness, normal);
+    }
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read;
+    use std::path::PathBuf;
+
+    use crate::{ReadableFile, common::Platform, model::Vertex};
+
+    use super::*;
+
+    fn read_mdl() -> MDL {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/tests");
+        d.push("c0201e0038_top_zeroed.mdl");
+
+        MDL::from_existing(Platform::Win32, &read(d).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_finalize_sizes() {
+        let mut mdl = read_mdl();
+        let old_mdl = mdl.clone();
+
+        mdl.finalize();
+
+        // Only what's derived from the (zeroed) vertices should change
+        assert_eq!(mdl.file_header, old_mdl.file_header);
+        assert_eq!(mdl.model_data.header, {
+            let mut header = old_mdl.model_data.header.clone();
+            header.radius = 0.0;
+            header
+        });
+        assert_eq!(
+            mdl.model_data.padding_amount,
+            old_mdl.model_data.padding_amount
+        );
+        assert_eq!(mdl.model_data.lods, old_mdl.model_data.lods);
+        assert_eq!(
+            mdl.model_data.bone_bounding_boxes.len(),
+            old_mdl.model_data.bone_bounding_boxes.len()
+        );
+    }
+
+    #[test]
+    fn test_bounding_boxes() {
+        let mut mdl = read_mdl();
+
+        let part = &mut mdl.lods[0].parts[0];
+        part.vertices[0].position = [-1.0, 0.0, 2.0];
+        part.vertices[0].bone_weight = [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+        part.vertices[0].bone_id = [1, 0, 0, 0, 0, 0, 0, 0];
+        part.vertices[1].position = [0.0, 3.0, 0.0];
+        let bone = mdl.bone_table(&mdl.lods[0].parts[0])[1] as usize;
+
+        mdl.recalculate_bounding_boxes();
+
+        assert_eq!(mdl.model_data.bounding_box.min, [-1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(mdl.model_data.bounding_box.max, [0.0, 3.0, 2.0, 1.0]);
+        assert_eq!(
+            mdl.model_data.model_bounding_box,
+            mdl.model_data.bounding_box
+        );
+        assert_eq!(mdl.model_data.header.radius, 14.0f32.sqrt());
+        assert_eq!(mdl.model_data.water_bounding_box, BoundingBox::default());
+
+        let bone_box = mdl.model_data.bone_bounding_boxes[bone];
+        // Only the first vertex is weighted to it
+        assert_eq!(bone_box.min, [-1.0, 0.0, 2.0, 1.0]);
+        assert_eq!(bone_box.max, [-1.0, 0.0, 2.0, 1.0]);
+    }
+
+    #[test]
+    fn test_generate_tangents() {
+        // A quad in the XY plane, with U along X and V along Y
+        let vertex = |x: f32, y: f32| Vertex {
+            position: [x, y, 0.0],
+            uv0: [x, y],
+            normal: [0.0, 0.0, 1.0],
+            ..Default::default()
+        };
+        let mut part = read_mdl().lods[0].parts[0].clone();
+        part.vertices = vec![
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(0.0, 1.0),
+            vertex(1.0, 1.0),
+        ];
+        part.indices = vec![0, 1, 2, 1, 3, 2];
+
+        generate_part_tangents(&mut part);
+        for vertex in &part.vertices {
+            assert_eq!(vertex.tangent, [1.0, 0.0, 0.0, 1.0]);
+            assert_eq!(vertex.bitangent, [0.0, 1.0, 0.0, 1.0]);
+        }
+
+        // Mirroring the UVs flips the handedness
+        for vertex in &mut part.vertices {
+            vertex.uv0[0] = 1.0 - vertex.uv0[0];
+        }
+        generate_part_tangents(&mut part);
+        for vertex in &part.vertices {
+            assert_eq!(vertex.tangent, [-1.0, 0.0, 0.0, -1.0]);
+        }
+    }
+}
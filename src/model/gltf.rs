@@ -0,0 +1,959 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::HashMap;
+
+use crate::{
+    ByteBuffer, PHYSIS_VERSION,
+    json::JsonValue,
+    model::{
+        MDL, Part,
+        math::{IDENTITY, Matrix, compose, cross, inverse_affine, multiply, normalize},
+        vertex_declarations::{VertexType, VertexUsage, get_vertex_type_component_count},
+    },
+    png::write_png,
+    skeleton::Skeleton,
+    tex::Texture,
+};
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+const UNSIGNED_SHORT: u32 = 5123;
+const FLOAT: u32 = 5126;
+
+/// Magic for binary glTF files ("glTF").
+pub(super) const GLB_MAGIC: u32 = 0x46546C67;
+/// Chunk type for the JSON chunk ("JSON").
+pub(super) const GLB_JSON_CHUNK: u32 = 0x4E4F534A;
+/// Chunk type for the binary chunk ("BIN").
+pub(super) const GLB_BIN_CHUNK: u32 = 0x004E4942;
+
+/// Options for exporting a [MDL] to glTF.
+#[derive(Debug, Default, Clone)]
+pub struct GltfOptions<'a> {
+    /// The skeleton to bind `affected_bone_names` to. If not given, every joint is placed at the origin.
+    pub skeleton: Option<&'a Skeleton>,
+    /// Textures for each material, in the same order as `material_names`.
+    ///
+    /// Textures that can't be decoded with [Texture::to_rgba] are skipped.
+    pub textures: Vec<GltfMaterialTextures<'a>>,
+}
+
+/// Textures to embed for a material when exporting to glTF.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GltfMaterialTextures<'a> {
+    /// Usually the diffuse texture.
+    pub base_color: Option<&'a Texture>,
+    /// The normal map.
+    pub normal: Option<&'a Texture>,
+}
+
+impl MDL {
+    /// Exports this model as binary glTF (`.glb`), which can be opened in Blender and most web viewers.
+    ///
+    /// Every part of every LOD becomes a mesh, and their submeshes become primitives. Shapes are exported as morph targets and the bones are exported as joints of a skin.
+    pub fn to_glb(&self, options: &GltfOptions) -> crate::Result<ByteBuffer> {
+        let (mut root, mut buffer) = self.build_gltf(options)?;
+        pad_to_four(&mut buffer, 0);
+
+        root.push((
+            "buffers".to_string(),
+            JsonValue::Array(vec![JsonValue::Object(vec![(
+                "byteLength".to_string(),
+                JsonValue::number(buffer.len()),
+            )])]),
+        ));
+
+        let mut json = JsonValue::Object(root).to_pretty_string().into_bytes();
+        pad_to_four(&mut json, b' ');
+
+        let total_length = 12 + 8 + json.len() + 8 + buffer.len();
+
+        let mut output = ByteBuffer::with_capacity(total_length);
+        output.extend_from_slice(&GLB_MAGIC.to_le_bytes());
+        output.extend_from_slice(&2u32.to_le_bytes());
+        output.extend_from_slice(&(total_length as u32).to_le_bytes());
+
+        output.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        output.extend_from_slice(&GLB_JSON_CHUNK.to_le_bytes());
+        output.extend_from_slice(&json);
+
+        output.extend_from_slice(&(buffer.len() as u32).to_le_bytes());
+        output.extend_from_slice(&GLB_BIN_CHUNK.to_le_bytes());
+        output.extend_from_slice(&buffer);
+
+        Ok(output)
+    }
+
+    /// Exports this model as glTF (`.gltf`), with the binary data embedded in the file.
+    ///
+    /// See [to_glb](Self::to_glb) for what is exported. The binary version is smaller and faster to load, so prefer it when possible.
+    pub fn to_gltf(&self, options: &GltfOptions) -> crate::Result<String> {
+        let (mut root, buffer) = self.build_gltf(options)?;
+
+        root.push((
+            "buffers".to_string(),
+            JsonValue::Array(vec![JsonValue::Object(vec![
+                ("byteLength".to_string(), JsonValue::number(buffer.len())),
+                (
+                    "uri".to_string(),
+                    JsonValue::String(format!(
+                        "data:application/octet-stream;base64,{}",
+                        base64(&buffer)
+                    )),
+                ),
+            ])]),
+        ));
+
+        Ok(JsonValue::Object(root).to_pretty_string())
+    }
+
+    /// Builds the entries of the glTF document, without the `buffers` array. Returns the entries and the contents of its only buffer.
+    fn build_gltf(
+        &self,
+        options: &GltfOptions,
+    ) -> crate::Result<(Vec<(String, JsonValue)>, ByteBuffer)> {
+        let mut builder = Builder::default();
+
+        // Joints come first, so their node index is the same as their joint index
+        let joints = Joints::new(&self.affected_bone_names, options.skeleton);
+        let mut nodes: Vec<JsonValue> = joints.nodes();
+        let mut scene_nodes: Vec<usize> = joints.roots();
+
+        let materials = self.gltf_materials(options, &mut builder)?;
+
+        let mut meshes = Vec::new();
+        for (lod_index, lod) in self.lods.iter().enumerate() {
+            let mut children = Vec::new();
+
+            for (part_index, part) in lod.parts.iter().enumerate() {
+                let name = format!("LOD {lod_index} Part {part_index}");
+
+                let Some((mesh, skinned)) = self.gltf_mesh(part, &name, &joints, &mut builder)
+                else {
+                    continue;
+                };
+
+                let mut node = vec![
+                    ("name".to_string(), JsonValue::String(name)),
+                    ("mesh".to_string(), JsonValue::number(meshes.len())),
+                ];
+                if skinned {
+                    node.push(("skin".to_string(), JsonValue::number(0)));
+                }
+
+                meshes.push(mesh);
+                children.push(JsonValue::number(nodes.len()));
+                nodes.push(JsonValue::Object(node));
+            }
+
+            scene_nodes.push(nodes.len());
+            nodes.push(JsonValue::Object(vec![
+                (
+                    "name".to_string(),
+                    JsonValue::String(format!("LOD {lod_index}")),
+                ),
+                ("children".to_string(), JsonValue::Array(children)),
+            ]));
+        }
+
+        let mut root = vec![
+            (
+                "asset".to_string(),
+                JsonValue::Object(vec![
+                    ("version".to_string(), JsonValue::String("2.0".to_string())),
+                    (
+                        "generator".to_string(),
+                        JsonValue::String(format!("physis {PHYSIS_VERSION}")),
+                    ),
+                ]),
+            ),
+            ("scene".to_string(), JsonValue::number(0)),
+            (
+                "scenes".to_string(),
+                JsonValue::Array(vec![JsonValue::Object(vec![(
+                    "nodes".to_string(),
+                    JsonValue::Array(scene_nodes.into_iter().map(JsonValue::number).collect()),
+                )])]),
+            ),
+            ("nodes".to_string(), JsonValue::Array(nodes)),
+            ("meshes".to_string(), JsonValue::Array(meshes)),
+            ("materials".to_string(), JsonValue::Array(materials)),
+        ];
+
+        if !joints.names.is_empty() {
+            let mut skin = vec![(
+                "joints".to_string(),
+                JsonValue::Array((0..joints.names.len()).map(JsonValue::number).collect()),
+            )];
+            if options.skeleton.is_some() {
+                let inverse_bind_matrices = builder.push_matrices(&joints.inverse_bind_matrices());
+                skin.push((
+                    "inverseBindMatrices".to_string(),
+                    JsonValue::number(inverse_bind_matrices),
+                ));
+            }
+            root.push((
+                "skins".to_string(),
+                JsonValue::Array(vec![JsonValue::Object(skin)]),
+            ));
+        }
+
+        if !builder.images.is_empty() {
+            let textures = (0..builder.images.len())
+                .map(|i| JsonValue::Object(vec![("source".to_string(), JsonValue::number(i))]))
+                .collect();
+            root.push(("textures".to_string(), JsonValue::Array(textures)));
+            root.push((
+                "images".to_string(),
+                JsonValue::Array(std::mem::take(&mut builder.images)),
+            ));
+        }
+
+        root.push(("accessors".to_string(), JsonValue::Array(builder.accessors)));
+        root.push((
+            "bufferViews".to_string(),
+            JsonValue::Array(builder.buffer_views),
+        ));
+
+        Ok((root, builder.buffer))
+    }
+
+    fn gltf_materials(
+        &self,
+        options: &GltfOptions,
+        builder: &mut Builder,
+    ) -> crate::Result<Vec<JsonValue>> {
+        let mut materials = Vec::with_capacity(self.material_names.len());
+        for (i, name) in self.material_names.iter().enumerate() {
+            let textures = options.textures.get(i).copied().unwrap_or_default();
+
+            let mut pbr = vec![("metallicFactor".to_string(), JsonValue::number(0))];
+            if let Some(texture) = textures.base_color
+                && let Some(image) = builder.push_texture(texture)?
+            {
+                pbr.push(("baseColorTexture".to_string(), texture_info(image)));
+            }
+
+            let mut material = vec![
+                ("name".to_string(), JsonValue::String(name.clone())),
+                ("pbrMetallicRoughness".to_string(), JsonValue::Object(pbr)),
+            ];
+            if let Some(texture) = textures.normal
+                && let Some(image) = builder.push_texture(texture)?
+            {
+                material.push(("normalTexture".to_string(), texture_info(image)));
+            }
+
+            materials.push(JsonValue::Object(material));
+        }
+
+        Ok(materials)
+    }
+
+    /// Builds a glTF mesh for `part`, and whether it's skinned. Returns [None] if there's nothing to draw.
+    fn gltf_mesh(
+        &self,
+        part: &Part,
+        name: &str,
+        joints: &Joints,
+        builder: &mut Builder,
+    ) -> Option<(JsonValue, bool)> {
+        if part.vertices.is_empty() {
+            return None;
+        }
+
+        let declaration = &self.model_data.header.vertex_declarations[part.mesh_index as usize];
+        let has_usage = |usage: VertexUsage| {
+            declaration
+                .elements
+                .iter()
+                .any(|element| element.vertex_usage == usage)
+        };
+        // Returns the number of components of the first or second element with `usage`, if it exists
+        let components = |usage: VertexUsage, second: bool| {
+            declaration
+                .elements
+                .iter()
+                .find(|element| {
+                    element.vertex_usage == usage && (element.usage_index > 0) == second
+                })
+                .map(|element| get_vertex_type_component_count(element.vertex_type))
+        };
+        // Dawntrail models can have up to 8 influences, either in one element or split across two
+        let influences = if declaration.elements.iter().any(|element| {
+            element.vertex_usage == VertexUsage::BlendWeights
+                && (element.vertex_type == VertexType::UnsignedShort4 || element.usage_index > 0)
+        }) {
+            8
+        } else {
+            4
+        };
+
+        let vertices = &part.vertices;
+        let mut attributes = Vec::new();
+
+        let positions: Vec<f32> = vertices.iter().flat_map(|x| x.position).collect();
+        attributes.push(("POSITION", builder.push_floats(&positions, "VEC3", true)));
+
+        let normals: Vec<[f32; 3]> = vertices
+            .iter()
+            .map(|x| normalize(x.normal).unwrap_or([0.0, 1.0, 0.0]))
+            .collect();
+        if has_usage(VertexUsage::Normal) {
+            let values: Vec<f32> = normals.iter().flatten().copied().collect();
+            attributes.push(("NORMAL", builder.push_floats(&values, "VEC3", false)));
+
+            if has_usage(VertexUsage::BiTangent) {
+                let tangents: Vec<f32> = vertices
+                    .iter()
+                    .zip(&normals)
+                    .flat_map(|(vertex, normal)| tangent(vertex.bitangent, *normal))
+                    .collect();
+                attributes.push(("TANGENT", builder.push_floats(&tangents, "VEC4", false)));
+            }
+        }
+
+        for (second, names) in [
+            (false, ["TEXCOORD_0", "TEXCOORD_1"]),
+            (true, ["TEXCOORD_2", "TEXCOORD_3"]),
+        ] {
+            let Some(count) = components(VertexUsage::UV, second) else {
+                continue;
+            };
+
+            let uvs: [Vec<f32>; 2] = if second {
+                [
+                    vertices.iter().flat_map(|x| x.uv2).collect(),
+                    vertices.iter().flat_map(|x| x.uv3).collect(),
+                ]
+            } else {
+                [
+                    vertices.iter().flat_map(|x| x.uv0).collect(),
+                    vertices.iter().flat_map(|x| x.uv1).collect(),
+                ]
+            };
+            for (uv, name) in uvs.iter().zip(names).take(count / 2) {
+                attributes.push((name, builder.push_floats(uv, "VEC2", false)));
+            }
+        }
+
+        for (second, name) in [(false, "COLOR_0"), (true, "COLOR_1")] {
+            if components(VertexUsage::Color, second).is_some() {
+                let colors: Vec<f32> = vertices
+                    .iter()
+                    .flat_map(|x| if second { x.color1 } else { x.color })
+                    .collect();
+                attributes.push((name, builder.push_floats(&colors, "VEC4", false)));
+            }
+        }
+
+        let skinned = !joints.names.is_empty()
+            && has_usage(VertexUsage::BlendIndices)
+            && has_usage(VertexUsage::BlendWeights);
+        if skinned {
+            let bone_table = self.bone_table(part);
+            let joint_for = |bone_id: u8| -> u16 {
+                bone_table
+                    .get(bone_id as usize)
+                    .and_then(|x| self.affected_bone_names.get(*x as usize))
+                    .and_then(|x| joints.indices.get(x))
+                    .copied()
+                    .unwrap_or_default() as u16
+            };
+
+            let mut joint_indices = Vec::with_capacity(vertices.len() * influences);
+            let mut weights = Vec::with_capacity(vertices.len() * influences);
+            for vertex in vertices {
+                let sum: f32 = vertex.bone_weight.iter().take(influences).sum();
+                for i in 0..influences {
+                    let weight = if sum > 0.0 {
+                        vertex.bone_weight[i] / sum
+                    } else if i == 0 {
+                        1.0
+                    } else {
+                        0.0
+                    };
+
+                    // Unused influences have to point at a valid joint, so keep them at zero
+                    joint_indices.push(if weight > 0.0 {
+                        joint_for(vertex.bone_id[i])
+                    } else {
+                        0
+                    });
+                    weights.push(weight);
+                }
+            }
+
+            // Each set of joints and weights only holds 4 influences
+            for (set, (joints_name, weights_name)) in
+                [("JOINTS_0", "WEIGHTS_0"), ("JOINTS_1", "WEIGHTS_1")]
+                    .into_iter()
+                    .enumerate()
+                    .take(influences / 4)
+            {
+                let joint_set: Vec<u16> = joint_indices
+                    .chunks_exact(influences)
+                    .flat_map(|x| x[set * 4..set * 4 + 4].to_vec())
+                    .collect();
+                let weight_set: Vec<f32> = weights
+                    .chunks_exact(influences)
+                    .flat_map(|x| x[set * 4..set * 4 + 4].to_vec())
+                    .collect();
+
+                attributes.push((joints_name, builder.push_joints(&joint_set)));
+                attributes.push((
+                    weights_name,
+                    builder.push_floats(&weight_set, "VEC4", false),
+                ));
+            }
+        }
+
+        let targets: Vec<JsonValue> = part
+            .shapes
+            .iter()
+            .map(|shape| {
+                let deltas: Vec<f32> = (0..vertices.len())
+                    .flat_map(|i| {
+                        shape
+                            .morphed_vertices
+                            .get(i)
+                            .map(|x| x.position)
+                            .unwrap_or_default()
+                    })
+                    .collect();
+
+                JsonValue::Object(vec![(
+                    "POSITION".to_string(),
+                    JsonValue::number(builder.push_floats(&deltas, "VEC3", true)),
+                )])
+            })
+            .collect();
+
+        let attributes = JsonValue::Object(
+            attributes
+                .into_iter()
+                .map(|(name, accessor)| (name.to_string(), JsonValue::number(accessor)))
+                .collect(),
+        );
+
+        // Submesh offsets are relative to the whole LOD, not the part
+        let start_index = self.model_data.meshes[part.mesh_index as usize].start_index as usize;
+        let ranges: Vec<(usize, usize)> = if part.submeshes.is_empty() {
+            vec![(0, part.indices.len())]
+        } else {
+            part.submeshes
+                .iter()
+                .filter_map(|submesh| {
+                    let offset = (submesh.index_offset as usize).checked_sub(start_index)?;
+                    let count = submesh.index_count as usize;
+                    (offset + count <= part.indices.len()).then_some((offset, count))
+                })
+                .collect()
+        };
+
+        let index_view = builder.push_view(&to_bytes(&part.indices), Some(ELEMENT_ARRAY_BUFFER));
+
+        let primitives: Vec<JsonValue> = ranges
+            .into_iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(offset, count)| {
+                let indices = builder.push_accessor(
+                    index_view,
+                    offset * size_of::<u16>(),
+                    UNSIGNED_SHORT,
+                    count,
+                    "SCALAR",
+                    None,
+                );
+
+                let mut primitive = vec![
+                    ("attributes".to_string(), attributes.clone()),
+                    ("indices".to_string(), JsonValue::number(indices)),
+                ];
+                if (part.material_index as usize) < self.material_names.len() {
+                    primitive.push((
+                        "material".to_string(),
+                        JsonValue::number(part.material_index),
+                    ));
+                }
+                if !targets.is_empty() {
+                    primitive.push(("targets".to_string(), JsonValue::Array(targets.clone())));
+                }
+
+                JsonValue::Object(primitive)
+            })
+            .collect();
+
+        if primitives.is_empty() {
+            return None;
+        }
+
+        let mut mesh = vec![
+            ("name".to_string(), JsonValue::String(name.to_string())),
+            ("primitives".to_string(), JsonValue::Array(primitives)),
+        ];
+        if !part.shapes.is_empty() {
+            // Not part of the spec, but understood by Blender and others
+            mesh.push((
+                "extras".to_string(),
+                JsonValue::Object(vec![(
+                    "targetNames".to_string(),
+                    JsonValue::Array(
+                        part.shapes
+                            .iter()
+                            .map(|x| JsonValue::String(x.name.clone()))
+                            .collect(),
+                    ),
+                )]),
+            ));
+        }
+
+        Some((JsonValue::Object(mesh), skinned))
+    }
+}
+
+/// The joints of the exported skin.
+struct Joints {
+    names: Vec<String>,
+    /// Index of each joint's parent, if it has one.
+    parents: Vec<Option<usize>>,
+    /// Local transform of each joint, as translation, rotation and scale.
+    transforms: Vec<([f32; 3], [f32; 4], [f32; 3])>,
+    /// Looks up a joint index by name.
+    indices: HashMap<String, usize>,
+}
+
+impl Joints {
+    fn new(bone_names: &[String], skeleton: Option<&Skeleton>) -> Self {
+        let mut joints = Self {
+            names: Vec::new(),
+            parents: Vec::new(),
+            transforms: Vec::new(),
+            indices: HashMap::new(),
+        };
+
+        if let Some(skeleton) = skeleton {
+            for bone in &skeleton.bones {
+                joints.indices.insert(bone.name.clone(), joints.names.len());
+                joints.names.push(bone.name.clone());
+                joints.parents.push(
+                    usize::try_from(bone.parent_index)
+                        .ok()
+                        .filter(|x| *x < skeleton.bones.len()),
+                );
+                joints
+                    .transforms
+                    .push((bone.position, bone.rotation, bone.scale));
+            }
+        }
+
+        // Bones missing from the skeleton (or when there isn't one) are placed at the origin
+        for name in bone_names {
+            if !joints.indices.contains_key(name) {
+                joints.indices.insert(name.clone(), joints.names.len());
+                joints.names.push(name.clone());
+                joints.parents.push(None);
+                joints
+                    .transforms
+                    .push(([0.0; 3], [0.0, 0.0, 0.0, 1.0], [1.0; 3]));
+            }
+        }
+
+        joints
+    }
+
+    fn nodes(&self) -> Vec<JsonValue> {
+        (0..self.names.len())
+            .map(|i| {
+                let (translation, rotation, scale) = self.transforms[i];
+
+                let mut node = vec![
+                    ("name".to_string(), JsonValue::String(self.names[i].clone())),
+                    ("translation".to_string(), floats(&translation)),
+                    ("rotation".to_string(), floats(&rotation)),
+                    ("scale".to_string(), floats(&scale)),
+                ];
+
+                let children: Vec<JsonValue> = (0..self.names.len())
+                    .filter(|x| self.parents[*x] == Some(i))
+                    .map(JsonValue::number)
+                    .collect();
+                if !children.is_empty() {
+                    node.push(("children".to_string(), JsonValue::Array(children)));
+                }
+
+                JsonValue::Object(node)
+            })
+            .collect()
+    }
+
+    fn roots(&self) -> Vec<usize> {
+        (0..self.names.len())
+            .filter(|x| self.parents[*x].is_none())
+            .collect()
+    }
+
+    fn inverse_bind_matrices(&self) -> Vec<Matrix> {
+        (0..self.names.len())
+            .map(|i| {
+                // Walk up to the root, so this doesn't depend on the order of the bones
+                let mut world = IDENTITY;
+                let mut current = Some(i);
+                let mut depth = 0;
+                while let Some(index) = current
+                    && depth <= self.names.len()
+                {
+                    let (translation, rotation, scale) = self.transforms[index];
+                    world = multiply(&compose(translation, rotation, scale), &world);
+                    current = self.parents[index];
+                    depth += 1;
+                }
+
+                inverse_affine(&world)
+            })
+            .collect()
+    }
+}
+
+/// Accumulates the binary buffer, and the views and accessors pointing into it.
+#[derive(Default)]
+struct Builder {
+    buffer: ByteBuffer,
+    buffer_views: Vec<JsonValue>,
+    accessors: Vec<JsonValue>,
+    images: Vec<JsonValue>,
+}
+
+impl Builder {
+    fn push_view(&mut self, data: &[u8], target: Option<u32>) -> usize {
+        pad_to_four(&mut self.buffer, 0);
+
+        let mut view = vec![
+            ("buffer".to_string(), JsonValue::number(0)),
+            (
+                "byteOffset".to_string(),
+                JsonValue::number(self.buffer.len()),
+            ),
+            ("byteLength".to_string(), JsonValue::number(data.len())),
+        ];
+        if let Some(target) = target {
+            view.push(("target".to_string(), JsonValue::number(target)));
+        }
+
+        self.buffer.extend_from_slice(data);
+        self.buffer_views.push(JsonValue::Object(view));
+        self.buffer_views.len() - 1
+    }
+
+    fn push_accessor(
+        &mut self,
+        view: usize,
+        byte_offset: usize,
+        component_type: u32,
+        count: usize,
+        kind: &str,
+        bounds: Option<(Vec<f32>, Vec<f32>)>,
+    ) -> usize {
+        let mut accessor = vec![
+            ("bufferView".to_string(), JsonValue::number(view)),
+            ("byteOffset".to_string(), JsonValue::number(byte_offset)),
+            (
+                "componentType".to_string(),
+                JsonValue::number(component_type),
+            ),
+            ("count".to_string(), JsonValue::number(count)),
+            ("type".to_string(), JsonValue::String(kind.to_string())),
+        ];
+        if let Some((min, max)) = bounds {
+            accessor.push(("min".to_string(), floats(&min)));
+            accessor.push(("max".to_string(), floats(&max)));
+        }
+
+        self.accessors.push(JsonValue::Object(accessor));
+        self.accessors.len() - 1
+    }
+
+    /// Pushes a vertex attribute of floats. `bounds` is required for positions.
+    fn push_floats(&mut self, values: &[f32], kind: &str, bounds: bool) -> usize {
+        let components = match kind {
+            "VEC2" => 2,
+            "VEC3" => 3,
+            "VEC4" => 4,
+            _ => 1,
+        };
+
+        let bounds = bounds.then(|| {
+            let mut min = vec![f32::MAX; components];
+            let mut max = vec![f32::MIN; components];
+            for value in values.chunks_exact(components) {
+                for i in 0..components {
+                    min[i] = min[i].min(value[i]);
+                    max[i] = max[i].max(value[i]);
+                }
+            }
+            (min, max)
+        });
+
+        let view = self.push_view(&to_bytes(values), Some(ARRAY_BUFFER));
+        self.push_accessor(view, 0, FLOAT, values.len() / components, kind, bounds)
+    }
+
+    fn push_joints(&mut self, joints: &[u16]) -> usize {
+        let view = self.push_view(&to_bytes(joints), Some(ARRAY_BUFFER));
+        self.push_accessor(view, 0, UNSIGNED_SHORT, joints.len() / 4, "VEC4", None)
+    }
+
+    fn push_matrices(&mut self, matrices: &[Matrix]) -> usize {
+        let values: Vec<f32> = matrices.iter().flatten().copied().collect();
+        let view = self.push_view(&to_bytes(&values), None);
+        self.push_accessor(view, 0, FLOAT, matrices.len(), "MAT4", None)
+    }
+
+    /// Embeds `texture` as a PNG image, and returns the index of the image. Returns [None] if it couldn't be decoded.
+    fn push_texture(&mut self, texture: &Texture) -> crate::Result<Option<usize>> {
+        let Ok(rgba) = texture.to_rgba() else {
+            return Ok(None);
+        };
+        let size = texture.width as usize * texture.height as usize * 4;
+        let png = write_png(
+            texture.width as u32,
+            texture.height as u32,
+            &rgba[..size.min(rgba.len())],
+        )?;
+
+        let view = self.push_view(&png, None);
+        self.images.push(JsonValue::Object(vec![
+            ("bufferView".to_string(), JsonValue::number(view)),
+            (
+                "mimeType".to_string(),
+                JsonValue::String("image/png".to_string()),
+            ),
+        ]));
+
+        Ok(Some(self.images.len() - 1))
+    }
+}
+
+trait LeBytes {
+    fn extend_le_bytes(&self, output: &mut ByteBuffer);
+}
+
+impl LeBytes for f32 {
+    fn extend_le_bytes(&self, output: &mut ByteBuffer) {
+        output.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl LeBytes for u16 {
+    fn extend_le_bytes(&self, output: &mut ByteBuffer) {
+        output.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+/// glTF is always little-endian, regardless of the platform the model came from.
+fn to_bytes<T: LeBytes>(values: &[T]) -> ByteBuffer {
+    let mut output = ByteBuffer::with_capacity(size_of_val(values));
+    for value in values {
+        value.extend_le_bytes(&mut output);
+    }
+    output
+}
+
+fn pad_to_four(buffer: &mut ByteBuffer, padding: u8) {
+    while !buffer.len().is_multiple_of(4) {
+        buffer.push(padding);
+    }
+}
+
+/// JSON can't express non-finite numbers, so those are written as zero.
+fn floats(values: &[f32]) -> JsonValue {
+    JsonValue::Array(
+        values
+            .iter()
+            .map(|x| JsonValue::number(if x.is_finite() { *x } else { 0.0 }))
+            .collect(),
+    )
+}
+
+fn texture_info(image: usize) -> JsonValue {
+    // Each image has a texture of the same index
+    JsonValue::Object(vec![("index".to_string(), JsonValue::number(image))])
+}
+
+/// The game stores the bitangent, but glTF wants the tangent with the handedness in W.
+fn tangent(bitangent: [f32; 4], normal: [f32; 3]) -> [f32; 4] {
+    let handedness = if bitangent[3] < 0.0 { -1.0 } else { 1.0 };
+
+    let tangent = normalize(cross([bitangent[0], bitangent[1], bitangent[2]], normal))
+        .or_else(|| {
+            // Any vector perpendicular to the normal will do
+            let axis = if normal[0].abs() < 0.9 {
+                [1.0, 0.0, 0.0]
+            } else {
+                [0.0, 1.0, 0.0]
+            };
+            normalize(cross(normal, axis))
+        })
+        .unwrap_or([1.0, 0.0, 0.0]);
+
+    [tangent[0], tangent[1], tangent[2], handedness]
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut output = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let combined = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                output.push(ALPHABET[(combined >> (18 - i * 6)) as usize & 0x3F] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read;
+    use std::path::PathBuf;
+
+    use crate::{ReadableFile, common::Platform, skeleton::Bone};
+
+    use super::*;
+
+    fn read_mdl() -> MDL {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/tests");
+        d.push("c0201e0038_top_zeroed.mdl");
+
+        MDL::from_existing(Platform::Win32, &read(d).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_glb() {
+        let mdl = read_mdl();
+        let glb = mdl.to_glb(&GltfOptions::default()).unwrap();
+
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(glb[offset..offset + 4].try_into().unwrap());
+        assert_eq!(u32_at(0), GLB_MAGIC);
+        assert_eq!(u32_at(4), 2);
+        assert_eq!(u32_at(8) as usize, glb.len());
+
+        let json_length = u32_at(12) as usize;
+        assert_eq!(u32_at(16), GLB_JSON_CHUNK);
+        let json = std::str::from_utf8(&glb[20..20 + json_length]).unwrap();
+        let root = JsonValue::parse(json).unwrap();
+
+        let bin_length = u32_at(20 + json_length) as usize;
+        assert_eq!(u32_at(24 + json_length), GLB_BIN_CHUNK);
+        assert_eq!(28 + json_length + bin_length, glb.len());
+
+        let part_count: usize = mdl.lods.iter().map(|x| x.parts.len()).sum();
+        assert_eq!(
+            root.get("meshes").unwrap().as_array().unwrap().len(),
+            part_count
+        );
+        assert_eq!(
+            root.get("materials").unwrap().as_array().unwrap().len(),
+            mdl.material_names.len()
+        );
+        assert_eq!(
+            root.get("skins").unwrap().as_array().unwrap()[0]
+                .get("joints")
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .len(),
+            mdl.affected_bone_names.len()
+        );
+
+        // Every view has to fit in the buffer
+        for view in root.get("bufferViews").unwrap().as_array().unwrap() {
+            let offset: usize = view.get("byteOffset").unwrap().as_number().unwrap();
+            let length: usize = view.get("byteLength").unwrap().as_number().unwrap();
+            assert!(offset + length <= bin_length);
+        }
+    }
+
+    #[test]
+    fn test_gltf() {
+        let mdl = read_mdl();
+        let gltf = mdl.to_gltf(&GltfOptions::default()).unwrap();
+
+        let root = JsonValue::parse(&gltf).unwrap();
+        let buffer = &root.get("buffers").unwrap().as_array().unwrap()[0];
+        assert!(
+            buffer
+                .get("uri")
+                .unwrap()
+                .as_str()
+                .unwrap()
+                .starts_with("data:application/octet-stream;base64,")
+        );
+    }
+
+    #[test]
+    fn test_skeleton() {
+        let skeleton = Skeleton {
+            bones: vec![
+                Bone {
+                    name: "n_root".to_string(),
+                    parent_index: -1,
+                    position: [0.0, 1.0, 0.0],
+                    rotation: [0.0, 0.0, 0.0, 1.0],
+                    scale: [1.0; 3],
+                },
+                Bone {
+                    name: "n_hara".to_string(),
+                    parent_index: 0,
+                    position: [0.0, 0.5, 0.0],
+                    rotation: [0.0, 0.70710677, 0.0, 0.70710677],
+                    scale: [2.0; 3],
+                },
+            ],
+        };
+
+        let joints = Joints::new(
+            &["n_hara".to_string(), "j_kosi".to_string()],
+            Some(&skeleton),
+        );
+        assert_eq!(joints.names, vec!["n_root", "n_hara", "j_kosi"]);
+        assert_eq!(joints.roots(), vec![0, 2]);
+
+        // The inverse bind matrix should undo the joint's world transform
+        let matrices = joints.inverse_bind_matrices();
+        let world = multiply(
+            &compose([0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0], [1.0; 3]),
+            &compose(
+                [0.0, 0.5, 0.0],
+                [0.0, 0.70710677, 0.0, 0.70710677],
+                [2.0; 3],
+            ),
+        );
+        let product = multiply(&world, &matrices[1]);
+        for (value, expected) in product.iter().zip(IDENTITY) {
+            assert!((value - expected).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+}
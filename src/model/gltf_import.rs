@@ -0,0 +1,1425 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::HashMap;
+
+use crate::{
+    ByteBuffer, ByteSpan, Error, ReadableFile, WritableFile,
+    common::Platform,
+    json::JsonValue,
+    model::{
+        BoneTable, BoneTablesV2, BoundingBox, Lod, MDL, Mesh, MeshLod, ModelData, ModelFileHeader,
+        ModelFlags1, ModelFlags2, ModelHeader, Part, PartType, ShapeMesh, ShapeStruct, ShapeValue,
+        SubMesh, Submesh, Vertex,
+        gltf::{GLB_BIN_CHUNK, GLB_JSON_CHUNK, GLB_MAGIC},
+        math::{IDENTITY, Matrix, bitangent, bounding_box, compose, expand, multiply, normalize},
+        vertex_declarations::{
+            MAX_VERTEX_STREAMS, VertexDeclaration, VertexElement, VertexType, VertexUsage,
+            get_vertex_type_size,
+        },
+    },
+};
+
+/// The version of the model files we create, which uses the fixed-size bone tables.
+const MODEL_VERSION: u32 = 0x1000005;
+
+/// The most bones a single mesh can reference.
+const MAX_BONES_PER_MESH: usize = 64;
+
+/// Index data of each mesh starts on a 16 byte boundary.
+pub(super) const MESH_INDEX_ALIGNMENT: u32 = 8;
+
+/// The most bones a single vertex can be influenced by.
+pub(super) const MAX_INFLUENCES: usize = 8;
+
+/// Triangle lists, which is the only primitive mode we support.
+const MODE_TRIANGLES: u32 = 4;
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::InvalidData {
+        reason: reason.into(),
+    }
+}
+
+impl MDL {
+    /// Builds a new model from a glTF (`.gltf`) or binary glTF (`.glb`) file, without needing an existing model to start from.
+    ///
+    /// Meshes under a node named `LOD n` (like the ones written by [to_glb](Self::to_glb)) are placed in that LOD, otherwise they go in the first one. Each mesh becomes a part per material, and their primitives become submeshes. Morph targets become shapes, named after the mesh's `extras.targetNames`. Joints of a skin become bones, named after their nodes.
+    ///
+    /// Buffers have to be embedded, external files aren't supported.
+    pub fn from_gltf(buffer: ByteSpan) -> crate::Result<MDL> {
+        let document = Document::parse(buffer)?;
+
+        let mut importer = Importer::default();
+        for (node, lod, transform) in document.mesh_nodes()? {
+            importer.import_node(&document, node, lod, transform)?;
+        }
+
+        importer.build()
+    }
+}
+
+/// A parsed glTF document and its buffers.
+struct Document {
+    root: JsonValue,
+    buffers: Vec<ByteBuffer>,
+}
+
+impl Document {
+    fn parse(buffer: ByteSpan) -> crate::Result<Self> {
+        let u32_at = |offset: usize| -> crate::Result<u32> {
+            buffer
+                .get(offset..offset + 4)
+                .map(|x| u32::from_le_bytes(x.try_into().unwrap()))
+                .ok_or_else(|| invalid("GLB file is truncated"))
+        };
+
+        let (text, binary) = if buffer.len() >= 4 && u32_at(0)? == GLB_MAGIC {
+            let mut text = None;
+            let mut binary = None;
+
+            let mut offset = 12;
+            while offset + 8 <= buffer.len() {
+                let length = u32_at(offset)? as usize;
+                let kind = u32_at(offset + 4)?;
+                let data = buffer
+                    .get(offset + 8..offset + 8 + length)
+                    .ok_or_else(|| invalid("GLB chunk is truncated"))?;
+
+                match kind {
+                    GLB_JSON_CHUNK => text = Some(data),
+                    GLB_BIN_CHUNK => binary = Some(data.to_vec()),
+                    _ => {}
+                }
+
+                offset += 8 + length;
+            }
+
+            (
+                text.ok_or_else(|| invalid("GLB file is missing its JSON chunk"))?,
+                binary,
+            )
+        } else {
+            (buffer, None)
+        };
+
+        let text = std::str::from_utf8(text).map_err(|_| invalid("glTF isn't valid UTF-8"))?;
+        let root = JsonValue::parse(text)?;
+
+        let mut binary = binary;
+        let buffers = array(&root, "buffers")
+            .iter()
+            .map(|buffer| match buffer.get("uri").and_then(|x| x.as_str()) {
+                Some(uri) => {
+                    let (_, data) = uri
+                        .strip_prefix("data:")
+                        .and_then(|x| x.split_once(";base64,"))
+                        .ok_or_else(|| invalid("external glTF buffers aren't supported"))?;
+                    decode_base64(data).ok_or_else(|| invalid("glTF buffer isn't valid base64"))
+                }
+                None => binary
+                    .take()
+                    .ok_or_else(|| invalid("glTF buffer is missing its data")),
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        Ok(Self { root, buffers })
+    }
+
+    fn get(&self, key: &str, index: usize) -> crate::Result<&JsonValue> {
+        array(&self.root, key)
+            .get(index)
+            .ok_or_else(|| invalid(format!("glTF {key} {index} doesn't exist")))
+    }
+
+    /// Returns every node with a mesh, along with its LOD and world transform.
+    fn mesh_nodes(&self) -> crate::Result<Vec<(usize, usize, Matrix)>> {
+        let nodes = array(&self.root, "nodes");
+
+        let roots: Vec<usize> = match array(&self.root, "scenes").get(
+            self.root
+                .get("scene")
+                .and_then(|x| x.as_number())
+                .unwrap_or(0),
+        ) {
+            Some(scene) => array(scene, "nodes")
+                .iter()
+                .filter_map(|x| x.as_number())
+                .collect(),
+            // Without a scene, use every node that isn't a child of another
+            None => (0..nodes.len())
+                .filter(|i| {
+                    !nodes.iter().any(|node| {
+                        array(node, "children")
+                            .iter()
+                            .any(|x| x.as_number() == Some(*i))
+                    })
+                })
+                .collect(),
+        };
+
+        let mut mesh_nodes = Vec::new();
+        let mut stack: Vec<(usize, Option<usize>, Matrix, usize)> = roots
+            .into_iter()
+            .map(|node| (node, None, IDENTITY, 0))
+            .collect();
+        while let Some((index, lod, parent, depth)) = stack.pop() {
+            if depth > nodes.len() {
+                return Err(invalid("glTF node hierarchy has a cycle"));
+            }
+
+            let node = self.get("nodes", index)?;
+            let lod = node
+                .get("name")
+                .and_then(|x| x.as_str())
+                .and_then(lod_from_name)
+                .or(lod);
+            let transform = multiply(&parent, &node_transform(node));
+
+            if node.get("mesh").is_some() {
+                mesh_nodes.push((index, lod.unwrap_or_default(), transform));
+            }
+
+            for child in array(node, "children").iter().rev() {
+                if let Some(child) = child.as_number() {
+                    stack.push((child, lod, transform, depth + 1));
+                }
+            }
+        }
+
+        Ok(mesh_nodes)
+    }
+
+    /// Reads accessor `index` as floats, and returns them with the number of components per element.
+    fn read_accessor(&self, index: usize) -> crate::Result<(Vec<f32>, usize)> {
+        let accessor = self.get("accessors", index)?;
+        if accessor.get("sparse").is_some() {
+            return Err(invalid("sparse glTF accessors aren't supported"));
+        }
+
+        let count: usize = accessor
+            .get("count")
+            .and_then(|x| x.as_number())
+            .ok_or_else(|| invalid(format!("glTF accessor {index} is missing its count")))?;
+        let components = match accessor.get("type").and_then(|x| x.as_str()) {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") | Some("MAT2") => 4,
+            Some("MAT3") => 9,
+            Some("MAT4") => 16,
+            _ => {
+                return Err(invalid(format!(
+                    "glTF accessor {index} has an invalid type"
+                )));
+            }
+        };
+        let component_type: u32 = accessor
+            .get("componentType")
+            .and_then(|x| x.as_number())
+            .unwrap_or_default();
+        let normalized = accessor
+            .get("normalized")
+            .and_then(|x| x.as_bool())
+            .unwrap_or_default();
+
+        let component_size = match component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            _ => {
+                return Err(invalid(format!(
+                    "glTF accessor {index} has an invalid component type"
+                )));
+            }
+        };
+
+        let Some(view) = accessor.get("bufferView").and_then(|x| x.as_number()) else {
+            // Accessors without a view are all zeroes, which only makes sense for vertex attributes
+            if count > u16::MAX as usize + 1 {
+                return Err(invalid(format!(
+                    "glTF accessor {index} has too many elements"
+                )));
+            }
+            return Ok((vec![0.0; count * components], components));
+        };
+        let view = self.get("bufferViews", view)?;
+
+        let buffer = self
+            .buffers
+            .get(view.get("buffer").and_then(|x| x.as_number()).unwrap_or(0))
+            .ok_or_else(|| invalid(format!("glTF accessor {index} has an invalid buffer")))?;
+        let view_offset: usize = view
+            .get("byteOffset")
+            .and_then(|x| x.as_number())
+            .unwrap_or(0);
+        let view_length: usize = view
+            .get("byteLength")
+            .and_then(|x| x.as_number())
+            .unwrap_or(0);
+        let data = view_offset
+            .checked_add(view_length)
+            .and_then(|end| buffer.get(view_offset..end))
+            .ok_or_else(|| invalid(format!("glTF accessor {index} is out of bounds")))?;
+
+        let offset: usize = accessor
+            .get("byteOffset")
+            .and_then(|x| x.as_number())
+            .unwrap_or(0);
+        // A stride of 0 means the elements are tightly packed, like when there's no stride
+        let element_size = components * component_size;
+        let stride = match view.get("byteStride").and_then(|x| x.as_number::<usize>()) {
+            None | Some(0) => element_size,
+            Some(stride) if stride < element_size => {
+                return Err(invalid(format!(
+                    "glTF accessor {index} has elements overlapping each other"
+                )));
+            }
+            Some(stride) => stride,
+        };
+
+        // Make sure the last element fits before trusting the count
+        if count > 0 {
+            let end = (count - 1)
+                .checked_mul(stride)
+                .and_then(|x| x.checked_add(offset))
+                .and_then(|x| x.checked_add(element_size));
+            if end.is_none_or(|end| end > data.len()) {
+                return Err(invalid(format!("glTF accessor {index} is out of bounds")));
+            }
+        }
+
+        let mut values = Vec::with_capacity(count * components);
+        for i in 0..count {
+            for c in 0..components {
+                let start = offset + i * stride + c * component_size;
+                let bytes = data
+                    .get(start..start + component_size)
+                    .ok_or_else(|| invalid(format!("glTF accessor {index} is out of bounds")))?;
+
+                let value = match component_type {
+                    5120 => {
+                        let value = bytes[0] as i8 as f32;
+                        if normalized {
+                            (value / 127.0).max(-1.0)
+                        } else {
+                            value
+                        }
+                    }
+                    5121 => {
+                        let value = bytes[0] as f32;
+                        if normalized { value / 255.0 } else { value }
+                    }
+                    5122 => {
+                        let value = i16::from_le_bytes([bytes[0], bytes[1]]) as f32;
+                        if normalized {
+                            (value / 32767.0).max(-1.0)
+                        } else {
+                            value
+                        }
+                    }
+                    5123 => {
+                        let value = u16::from_le_bytes([bytes[0], bytes[1]]) as f32;
+                        if normalized { value / 65535.0 } else { value }
+                    }
+                    5125 => u32::from_le_bytes(bytes.try_into().unwrap()) as f32,
+                    _ => f32::from_le_bytes(bytes.try_into().unwrap()),
+                };
+                values.push(value);
+            }
+        }
+
+        Ok((values, components))
+    }
+
+    /// Reads the attribute `name` of a primitive, if it has one.
+    fn read_attribute(
+        &self,
+        attributes: &JsonValue,
+        name: &str,
+    ) -> crate::Result<Option<(Vec<f32>, usize)>> {
+        match attributes.get(name).and_then(|x| x.as_number()) {
+            Some(accessor) => Ok(Some(self.read_accessor(accessor)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// A part being imported, before it's laid out in the model.
+#[derive(Default)]
+pub(super) struct ImportedPart {
+    pub(super) lod: usize,
+    /// Index in `Importer::material_names`.
+    pub(super) material: usize,
+    pub(super) vertices: Vec<Vertex>,
+    /// Index in `Importer::bone_names` for each influence of each vertex.
+    pub(super) bones: Vec<[usize; MAX_INFLUENCES]>,
+    /// Indices of each submesh.
+    pub(super) submeshes: Vec<Vec<u32>>,
+    /// Position offsets of each vertex, for each shape.
+    pub(super) shapes: Vec<(String, Vec<[f32; 3]>)>,
+    pub(super) skinned: bool,
+    /// Whether any vertex is influenced by more than 4 bones.
+    pub(super) has_eight_influences: bool,
+    pub(super) has_color: bool,
+    pub(super) has_color1: bool,
+    pub(super) has_uv1: bool,
+    pub(super) has_uv2: bool,
+    pub(super) has_uv3: bool,
+    /// Attributes that were already read, so primitives sharing them also share vertices.
+    pub(super) read_attributes: Vec<(JsonValue, u32)>,
+}
+
+#[derive(Default)]
+pub(super) struct Importer {
+    pub(super) parts: Vec<ImportedPart>,
+    pub(super) bone_names: Vec<String>,
+    pub(super) material_names: Vec<String>,
+    /// Maps glTF materials to `material_names`.
+    pub(super) materials: HashMap<Option<usize>, usize>,
+}
+
+impl Importer {
+    fn import_node(
+        &mut self,
+        document: &Document,
+        node_index: usize,
+        lod: usize,
+        transform: Matrix,
+    ) -> crate::Result<()> {
+        if lod >= 3 {
+            return Err(invalid(format!(
+                "glTF node {node_index} is in LOD {lod}, but models only have 3 LODs"
+            )));
+        }
+
+        let node = document.get("nodes", node_index)?;
+        let mesh = document.get(
+            "meshes",
+            node.get("mesh").and_then(|x| x.as_number()).unwrap(),
+        )?;
+
+        // The transform of skinned meshes is ignored, as the joints place them instead
+        let joint_names = match node.get("skin").and_then(|x| x.as_number()) {
+            Some(skin) => Some(
+                array(document.get("skins", skin)?, "joints")
+                    .iter()
+                    .map(|joint| {
+                        let joint = joint.as_number().unwrap_or(usize::MAX);
+                        Ok(document
+                            .get("nodes", joint)?
+                            .get("name")
+                            .and_then(|x| x.as_str())
+                            .map(|x| x.to_string())
+                            .unwrap_or_else(|| format!("joint_{joint}")))
+                    })
+                    .collect::<crate::Result<Vec<String>>>()?,
+            ),
+            None => None,
+        };
+
+        let target_names: Vec<&str> = mesh
+            .get("extras")
+            .map(|x| array(x, "targetNames"))
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|x| x.as_str())
+            .collect();
+
+        // Parts of this node, keyed by material
+        let mut parts: Vec<ImportedPart> = Vec::new();
+
+        for (primitive_index, primitive) in array(mesh, "primitives").iter().enumerate() {
+            let mode = primitive
+                .get("mode")
+                .and_then(|x| x.as_number())
+                .unwrap_or(MODE_TRIANGLES);
+            if mode != MODE_TRIANGLES {
+                return Err(invalid(format!(
+                    "primitive {primitive_index} of glTF node {node_index} isn't made of triangles"
+                )));
+            }
+
+            let material = self.material(
+                document,
+                primitive.get("material").and_then(|x| x.as_number()),
+            )?;
+            let part = match parts.iter().position(|x| x.material == material) {
+                Some(part) => &mut parts[part],
+                None => {
+                    parts.push(ImportedPart {
+                        lod,
+                        material,
+                        ..Default::default()
+                    });
+                    parts.last_mut().unwrap()
+                }
+            };
+
+            let attributes = primitive
+                .get("attributes")
+                .ok_or_else(|| invalid("glTF primitive is missing its attributes"))?;
+
+            // Primitives often share the same vertices, so only read them once
+            let key = JsonValue::Array(vec![
+                attributes.clone(),
+                primitive.get("targets").cloned().unwrap_or(JsonValue::Null),
+            ]);
+            let base = match part.read_attributes.iter().find(|(x, _)| *x == key) {
+                Some((_, base)) => *base,
+                None => {
+                    let base = part.vertices.len() as u32;
+                    self.read_vertices(
+                        document,
+                        part,
+                        primitive,
+                        attributes,
+                        joint_names.as_deref(),
+                        &target_names,
+                        &transform,
+                    )?;
+                    part.read_attributes.push((key, base));
+                    base
+                }
+            };
+            let vertex_count = part.vertices.len() as u32 - base;
+
+            let indices: Vec<u32> = match primitive.get("indices").and_then(|x| x.as_number()) {
+                Some(accessor) => document
+                    .read_accessor(accessor)?
+                    .0
+                    .into_iter()
+                    .map(|x| x as u32)
+                    .collect(),
+                None => (0..vertex_count).collect(),
+            };
+            if indices.iter().any(|x| *x >= vertex_count) {
+                return Err(invalid(format!(
+                    "primitive {primitive_index} of glTF node {node_index} has an out of bounds index"
+                )));
+            }
+
+            part.submeshes
+                .push(indices.into_iter().map(|x| x + base).collect());
+        }
+
+        self.parts.extend(parts);
+
+        Ok(())
+    }
+
+    /// Returns the index in `material_names` for a glTF material.
+    fn material(&mut self, document: &Document, material: Option<usize>) -> crate::Result<usize> {
+        if let Some(index) = self.materials.get(&material) {
+            return Ok(*index);
+        }
+
+        let name = match material {
+            Some(material) => document
+                .get("materials", material)?
+                .get("name")
+                .and_then(|x| x.as_str())
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| format!("/material_{material}.mtrl")),
+            None => "/mt_default.mtrl".to_string(),
+        };
+
+        let index = self.material_names.len();
+        self.material_names.push(name);
+        self.materials.insert(material, index);
+        Ok(index)
+    }
+
+    fn bone(&mut self, name: &str) -> usize {
+        match self.bone_names.iter().position(|x| x == name) {
+            Some(index) => index,
+            None => {
+                self.bone_names.push(name.to_string());
+                self.bone_names.len() - 1
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn read_vertices(
+        &mut self,
+        document: &Document,
+        part: &mut ImportedPart,
+        primitive: &JsonValue,
+        attributes: &JsonValue,
+        joint_names: Option<&[String]>,
+        target_names: &[&str],
+        transform: &Matrix,
+    ) -> crate::Result<()> {
+        let (positions, _) = document
+            .read_attribute(attributes, "POSITION")?
+            .ok_or_else(|| invalid("glTF primitive is missing its positions"))?;
+        let count = positions.len() / 3;
+
+        let read = |name: &str, components: usize| -> crate::Result<Option<Vec<f32>>> {
+            Ok(document
+                .read_attribute(attributes, name)?
+                .map(|(values, size)| {
+                    let values = &values;
+                    // Pad or truncate every element to the expected size, e.g. RGB colors
+                    (0..count)
+                        .flat_map(|i| {
+                            (0..components).map(move |c| {
+                                if c < size {
+                                    values.get(i * size + c).copied().unwrap_or_default()
+                                } else {
+                                    1.0
+                                }
+                            })
+                        })
+                        .collect()
+                }))
+        };
+
+        let normals = read("NORMAL", 3)?;
+        let tangents = read("TANGENT", 4)?;
+        let uv0 = read("TEXCOORD_0", 2)?;
+        let uv1 = read("TEXCOORD_1", 2)?;
+        let uv2 = read("TEXCOORD_2", 2)?;
+        let uv3 = read("TEXCOORD_3", 2)?;
+        let colors = read("COLOR_0", 4)?;
+        let colors1 = read("COLOR_1", 4)?;
+
+        // The second set of joints and weights is optional, and holds influences 4 to 8
+        let influences = |first: Option<Vec<f32>>, second: Option<Vec<f32>>| {
+            first.map(|first| {
+                (0..count)
+                    .flat_map(|i| {
+                        let mut values = [0.0; MAX_INFLUENCES];
+                        values[..4].copy_from_slice(&first[i * 4..i * 4 + 4]);
+                        if let Some(second) = &second {
+                            values[4..].copy_from_slice(&second[i * 4..i * 4 + 4]);
+                        }
+                        values
+                    })
+                    .collect::<Vec<f32>>()
+            })
+        };
+        let joints = influences(read("JOINTS_0", 4)?, read("JOINTS_1", 4)?);
+        let weights = influences(read("WEIGHTS_0", 4)?, read("WEIGHTS_1", 4)?);
+
+        let skin = match (joint_names, joints, weights) {
+            (Some(names), Some(joints), Some(weights)) => Some((names, joints, weights)),
+            _ => None,
+        };
+        let rigid = skin.is_none();
+
+        let transform_point = |v: [f32; 3], w: f32| -> [f32; 3] {
+            if !rigid {
+                return v;
+            }
+            let m = transform;
+            [
+                m[0] * v[0] + m[4] * v[1] + m[8] * v[2] + m[12] * w,
+                m[1] * v[0] + m[5] * v[1] + m[9] * v[2] + m[13] * w,
+                m[2] * v[0] + m[6] * v[1] + m[10] * v[2] + m[14] * w,
+            ]
+        };
+
+        let start = part.vertices.len();
+        for i in 0..count {
+            let position = transform_point(
+                [positions[i * 3], positions[i * 3 + 1], positions[i * 3 + 2]],
+                1.0,
+            );
+            let normal = normals
+                .as_ref()
+                .and_then(|x| {
+                    normalize(transform_point([x[i * 3], x[i * 3 + 1], x[i * 3 + 2]], 0.0))
+                })
+                .unwrap_or([0.0, 1.0, 0.0]);
+
+            let bitangent = match &tangents {
+                Some(tangents) => {
+                    let tangent = transform_point(
+                        [tangents[i * 4], tangents[i * 4 + 1], tangents[i * 4 + 2]],
+                        0.0,
+                    );
+                    bitangent(tangent, tangents[i * 4 + 3], normal)
+                }
+                None => bitangent([0.0; 3], 1.0, normal),
+            };
+
+            let color = |colors: &Option<Vec<f32>>| {
+                colors
+                    .as_ref()
+                    .map(|x| [x[i * 4], x[i * 4 + 1], x[i * 4 + 2], x[i * 4 + 3]])
+                    .unwrap_or([1.0; 4])
+            };
+            let uv = |uvs: &Option<Vec<f32>>| {
+                uvs.as_ref()
+                    .map(|x| [x[i * 2], x[i * 2 + 1]])
+                    .unwrap_or_default()
+            };
+
+            let mut vertex = Vertex {
+                position,
+                normal,
+                bitangent,
+                color: color(&colors),
+                color1: color(&colors1),
+                uv0: uv(&uv0),
+                uv1: uv(&uv1),
+                uv2: uv(&uv2),
+                uv3: uv(&uv3),
+                ..Default::default()
+            };
+
+            let mut bones = [usize::MAX; MAX_INFLUENCES];
+            if let Some((names, joints, weights)) = &skin {
+                // Used influences are moved to the front, so 4 or less fit in the smaller elements
+                let mut used = 0;
+                for j in 0..MAX_INFLUENCES {
+                    let weight = weights[i * MAX_INFLUENCES + j];
+                    if weight <= 0.0 {
+                        continue;
+                    }
+
+                    let joint = joints[i * MAX_INFLUENCES + j] as usize;
+                    let name = names.get(joint).ok_or_else(|| {
+                        invalid(format!("glTF vertex references missing joint {joint}"))
+                    })?;
+                    bones[used] = self.bone(name);
+                    vertex.bone_weight[used] = weight;
+                    used += 1;
+                }
+
+                part.has_eight_influences |= used > 4;
+            }
+
+            part.vertices.push(vertex);
+            part.bones.push(bones);
+        }
+
+        part.skinned |= !rigid;
+        part.has_color |= colors.is_some();
+        part.has_color1 |= colors1.is_some();
+        part.has_uv1 |= uv1.is_some();
+        part.has_uv2 |= uv2.is_some();
+        part.has_uv3 |= uv3.is_some();
+
+        // Every shape has an offset for every vertex, even if it's zero
+        for (_, offsets) in &mut part.shapes {
+            offsets.resize(part.vertices.len(), [0.0; 3]);
+        }
+        for (target_index, target) in array(primitive, "targets").iter().enumerate() {
+            let Some((deltas, _)) = document.read_attribute(target, "POSITION")? else {
+                continue;
+            };
+
+            let name = target_names
+                .get(target_index)
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| format!("shape_{target_index}"));
+            let shape = match part.shapes.iter().position(|(x, _)| *x == name) {
+                Some(shape) => shape,
+                None => {
+                    part.shapes
+                        .push((name, vec![[0.0; 3]; part.vertices.len()]));
+                    part.shapes.len() - 1
+                }
+            };
+
+            let offsets = &mut part.shapes[shape].1;
+            for i in 0..count {
+                offsets[start + i] = transform_point(
+                    [
+                        deltas.get(i * 3).copied().unwrap_or_default(),
+                        deltas.get(i * 3 + 1).copied().unwrap_or_default(),
+                        deltas.get(i * 3 + 2).copied().unwrap_or_default(),
+                    ],
+                    0.0,
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lays out the imported parts into a model.
+    pub(super) fn build(mut self) -> crate::Result<MDL> {
+        if self.parts.is_empty() {
+            return Err(invalid("glTF doesn't contain any meshes"));
+        }
+
+        // Meshes are stored in LOD order
+        self.parts.sort_by_key(|x| x.lod);
+        let lod_count = self.parts.iter().map(|x| x.lod).max().unwrap_or_default() + 1;
+
+        let mut shape_names: Vec<String> = Vec::new();
+        for part in &self.parts {
+            for (name, _) in &part.shapes {
+                if !shape_names.contains(name) {
+                    shape_names.push(name.clone());
+                }
+            }
+        }
+
+        let mut meshes = Vec::new();
+        let mut submeshes = Vec::new();
+        let mut vertex_declarations = Vec::new();
+        let mut bone_tables = Vec::new();
+        let mut submesh_bone_map = Vec::new();
+        let mut lods = Vec::new();
+        // (shape, lod, mesh start index, values) for each mesh that has a shape
+        let mut shape_meshes: Vec<(usize, usize, u32, Vec<ShapeValue>)> = Vec::new();
+
+        let mut bone_bounds: Vec<Option<([f32; 3], [f32; 3])>> = vec![None; self.bone_names.len()];
+        let mut model_bounds: Option<([f32; 3], [f32; 3])> = None;
+
+        for lod in 0..lod_count {
+            let mut parts = Vec::new();
+            let mut index_count = 0u32;
+
+            for part in self.parts.iter().filter(|x| x.lod == lod) {
+                let mesh_index = meshes.len() as u16;
+
+                // Bone table of this mesh, which the vertices index into
+                let mut bone_table: Vec<usize> = Vec::new();
+                let mut vertices = part.vertices.clone();
+                for (vertex, bones) in vertices.iter_mut().zip(&part.bones) {
+                    let mut influences = [(usize::MAX, 0.0); MAX_INFLUENCES];
+                    for i in 0..MAX_INFLUENCES {
+                        influences[i] = (bones[i], vertex.bone_weight[i]);
+                    }
+                    // Vertices without any weight are attached to the first bone
+                    if part.skinned && influences.iter().all(|(bone, _)| *bone == usize::MAX) {
+                        influences[0] = (0, 1.0);
+                    }
+
+                    let weights = quantize_weights(influences.map(|(_, weight)| weight));
+                    for i in 0..MAX_INFLUENCES {
+                        let (bone, _) = influences[i];
+                        if weights[i] == 0 || bone == usize::MAX || !part.skinned {
+                            vertex.bone_id[i] = 0;
+                            vertex.bone_weight[i] = 0.0;
+                            continue;
+                        }
+
+                        let local = match bone_table.iter().position(|x| *x == bone) {
+                            Some(local) => local,
+                            None => {
+                                bone_table.push(bone);
+                                bone_table.len() - 1
+                            }
+                        };
+                        vertex.bone_id[i] = local as u8;
+                        vertex.bone_weight[i] = weights[i] as f32 / u8::MAX as f32;
+
+                        if let Some(bounds) = bone_bounds.get_mut(bone) {
+                            expand(bounds, vertex.position);
+                        }
+                    }
+                }
+                if bone_table.len() > MAX_BONES_PER_MESH {
+                    return Err(invalid(format!(
+                        "a mesh in LOD {lod} uses {} bones, but only {MAX_BONES_PER_MESH} are allowed",
+                        bone_table.len()
+                    )));
+                }
+
+                if lod == 0 {
+                    for vertex in &vertices {
+                        expand(&mut model_bounds, vertex.position);
+                    }
+                }
+
+                // Index data of each mesh is aligned
+                let start_index = index_count.next_multiple_of(MESH_INDEX_ALIGNMENT);
+                let indices: Vec<u32> = part.submeshes.iter().flatten().copied().collect();
+
+                // Vertices replaced by each shape are appended after the regular ones
+                for (shape_index, shape_name) in shape_names.iter().enumerate() {
+                    let Some((_, offsets)) = part.shapes.iter().find(|(x, _)| x == shape_name)
+                    else {
+                        continue;
+                    };
+
+                    let mut replacements = HashMap::new();
+                    let mut values = Vec::new();
+                    for (position, index) in indices.iter().enumerate() {
+                        let offset = offsets[*index as usize];
+                        if offset == [0.0; 3] {
+                            continue;
+                        }
+
+                        let replacing = *replacements.entry(*index).or_insert_with(|| {
+                            let mut vertex = vertices[*index as usize];
+                            for (position, offset) in vertex.position.iter_mut().zip(offset) {
+                                *position += offset;
+                            }
+                            vertices.push(vertex);
+                            vertices.len() - 1
+                        });
+
+                        let base_index = start_index as usize + position;
+                        if base_index > u16::MAX as usize || replacing > u16::MAX as usize {
+                            return Err(invalid(format!(
+                                "shape {shape_name} in LOD {lod} has too many indices or vertices"
+                            )));
+                        }
+                        values.push(ShapeValue {
+                            base_indices_index: base_index as u16,
+                            replacing_vertex_index: replacing as u16,
+                        });
+                    }
+
+                    if !values.is_empty() {
+                        shape_meshes.push((shape_index, lod, start_index, values));
+                    }
+                }
+
+                if vertices.len() > u16::MAX as usize {
+                    return Err(invalid(format!(
+                        "a mesh in LOD {lod} has {} vertices, but only {} are allowed",
+                        vertices.len(),
+                        u16::MAX
+                    )));
+                }
+
+                let (declaration, strides) = vertex_declaration(part);
+
+                let submesh_index = submeshes.len() as u16;
+                let mut part_submeshes = Vec::with_capacity(part.submeshes.len());
+                let mut offset = start_index;
+                for submesh_indices in &part.submeshes {
+                    // Bones used by this submesh, in terms of the mesh's bone table
+                    let mut bones: Vec<u16> = Vec::new();
+                    for index in submesh_indices {
+                        let vertex = &vertices[*index as usize];
+                        for i in 0..MAX_INFLUENCES {
+                            if vertex.bone_weight[i] > 0.0
+                                && !bones.contains(&(vertex.bone_id[i] as u16))
+                            {
+                                bones.push(vertex.bone_id[i] as u16);
+                            }
+                        }
+                    }
+
+                    part_submeshes.push(SubMesh {
+                        submesh_index: submeshes.len(),
+                        index_count: submesh_indices.len() as u32,
+                        index_offset: offset,
+                    });
+                    submeshes.push(Submesh {
+                        index_offset: offset,
+                        index_count: submesh_indices.len() as u32,
+                        attribute_index_mask: 0,
+                        bone_start_index: submesh_bone_map.len() as u16,
+                        bone_count: bones.len() as u16,
+                    });
+                    submesh_bone_map.extend(bones);
+
+                    offset += submesh_indices.len() as u32;
+                }
+                index_count = offset;
+
+                let mut bone_indices = [0u16; 64];
+                for (i, bone) in bone_table.iter().enumerate() {
+                    bone_indices[i] = *bone as u16;
+                }
+                bone_tables.push(BoneTable {
+                    bone_indices,
+                    bone_count: bone_table.len() as u8,
+                });
+
+                meshes.push(Mesh {
+                    vertex_count: vertices.len() as u16,
+                    index_count: indices.len() as u32,
+                    material_index: part.material as u16,
+                    submesh_index,
+                    submesh_count: part.submeshes.len() as u16,
+                    bone_table_index: bone_tables.len() as u16 - 1,
+                    start_index,
+                    vertex_buffer_offsets: [0; 3],
+                    vertex_buffer_strides: strides,
+                    vertex_stream_count: 2,
+                });
+                vertex_declarations.push(declaration);
+
+                parts.push(Part {
+                    mesh_index,
+                    vertices,
+                    vertex_streams: Vec::new(),
+                    vertex_stream_strides: Vec::new(),
+                    indices: indices.into_iter().map(|x| x as u16).collect(),
+                    material_index: part.material as u16,
+                    submeshes: part_submeshes,
+                    shapes: Vec::new(),
+                    part_type: PartType::Normal,
+                });
+            }
+
+            lods.push(Lod {
+                parts,
+                terrain_shadow_parts: Vec::new(),
+                edge_geometry: ByteBuffer::new(),
+                model_lod_range: 0.0,
+            });
+        }
+
+        // Strings are stored as bones, materials and then shapes
+        let mut strings = Vec::new();
+        let mut push_string = |name: &str| -> u32 {
+            let offset = strings.len() as u32;
+            strings.extend_from_slice(name.as_bytes());
+            strings.push(0);
+            offset
+        };
+        let bone_name_offsets: Vec<u32> = self.bone_names.iter().map(|x| push_string(x)).collect();
+        let material_name_offsets: Vec<u32> =
+            self.material_names.iter().map(|x| push_string(x)).collect();
+        let shape_name_offsets: Vec<u32> = shape_names.iter().map(|x| push_string(x)).collect();
+        strings.resize(strings.len().next_multiple_of(4), 0);
+
+        let mut shapes: Vec<ShapeStruct> = shape_name_offsets
+            .into_iter()
+            .map(|string_offset| ShapeStruct {
+                string_offset,
+                shape_mesh_start_index: [0; 3],
+                shape_mesh_count: [0; 3],
+            })
+            .collect();
+        let mut model_shape_meshes = Vec::new();
+        let mut shape_values = Vec::new();
+        // Shape meshes of each shape and LOD have to be next to each other
+        shape_meshes.sort_by_key(|(shape, lod, _, _)| (*shape, *lod));
+        for (shape, lod, start_index, values) in shape_meshes {
+            if shapes[shape].shape_mesh_count[lod] == 0 {
+                shapes[shape].shape_mesh_start_index[lod] = model_shape_meshes.len() as u16;
+            }
+            shapes[shape].shape_mesh_count[lod] += 1;
+
+            model_shape_meshes.push(ShapeMesh {
+                mesh_index_offset: start_index,
+                shape_value_count: values.len() as u32,
+                shape_value_offset: shape_values.len() as u32,
+            });
+            shape_values.extend(values);
+        }
+
+        let mut model_lods = Vec::with_capacity(3);
+        let mut mesh_index = 0;
+        for lod in 0..3 {
+            let mesh_count = lods.get(lod).map(|x| x.parts.len()).unwrap_or_default() as u16;
+            let end = mesh_index + mesh_count;
+            model_lods.push(MeshLod {
+                mesh_index,
+                mesh_count,
+                model_lod_range: 0.0,
+                texture_lod_range: 0.0,
+                water_mesh_index: end,
+                water_mesh_count: 0,
+                shadow_mesh_index: end,
+                shadow_mesh_count: 0,
+                terrain_shadow_mesh_index: 0,
+                terrain_shadow_mesh_count: 0,
+                vertical_fog_mesh_index: end,
+                vertical_fog_mesh_count: 0,
+                edge_geometry_size: 0,
+                edge_geometry_data_offset: 0,
+                unk6: 0,
+                unk7: 0,
+                vertex_buffer_size: 0,
+                index_buffer_size: 0,
+                vertex_data_offset: 0,
+                index_data_offset: 0,
+            });
+            mesh_index = end;
+        }
+
+        let (min, max) = model_bounds.unwrap_or_default();
+        let model_bounding_box = bounding_box(Some((min, max)));
+        let radius = (0..3)
+            .map(|i| min[i].abs().max(max[i].abs()).powi(2))
+            .sum::<f32>()
+            .sqrt();
+
+        let bone_count = self.bone_names.len();
+        let mut model_data = ModelData {
+            header: ModelHeader {
+                vertex_declarations,
+                string_count: (bone_count + self.material_names.len() + shapes.len()) as u16,
+                string_size: strings.len() as u32,
+                strings,
+                radius,
+                mesh_count: meshes.len() as u16,
+                attribute_count: 0,
+                submesh_count: submeshes.len() as u16,
+                material_count: self.material_names.len() as u16,
+                bone_count: bone_count as u16,
+                bone_table_count: bone_tables.len() as u16,
+                shape_count: shapes.len() as u16,
+                shape_mesh_count: model_shape_meshes.len() as u16,
+                shape_value_count: shape_values.len() as u16,
+                lod_count: lod_count as u8,
+                flags1: ModelFlags1::None,
+                element_id_count: 0,
+                terrain_shadow_mesh_count: 0,
+                flags2: ModelFlags2::None,
+                model_clip_out_of_distance: 0.0,
+                shadow_clip_out_of_distance: 0.0,
+                furniture_part_bounding_box_count: 0,
+                terrain_shadow_submesh_count: 0,
+                flags3: 0,
+                bg_change_material_index: 0,
+                bg_crest_change_material_index: 0,
+                neck_morph_table_size: 0,
+                bone_set_size: 0,
+                unknown13: 0,
+                patch_72_table_size: 0,
+                unknown15: 0,
+                unknown16: 0,
+                unknown17: 0,
+            },
+            element_ids: Vec::new(),
+            lods: model_lods,
+            extra_lods: Vec::new(),
+            meshes,
+            attribute_name_offsets: Vec::new(),
+            terrain_shadow_meshes: Vec::new(),
+            submeshes,
+            terrain_shadow_submeshes: Vec::new(),
+            material_name_offsets,
+            bone_name_offsets,
+            bone_tables,
+            bone_tables_v2: BoneTablesV2::default(),
+            shapes,
+            shape_meshes: model_shape_meshes,
+            shape_values,
+            submesh_bone_map_size: submesh_bone_map.len() as u32 * 2,
+            submesh_bone_map,
+            unknown_72_padding: Vec::new(),
+            padding_amount: 0,
+            unknown_padding: Vec::new(),
+            bounding_box: model_bounding_box,
+            model_bounding_box,
+            water_bounding_box: BoundingBox::default(),
+            vertical_fog_bounding_box: BoundingBox::default(),
+            bone_bounding_boxes: bone_bounds.into_iter().map(bounding_box).collect(),
+        };
+
+        model_data.update_padding();
+
+        let mut mdl = MDL {
+            file_header: ModelFileHeader {
+                version: MODEL_VERSION,
+                stack_size: 0,
+                runtime_size: 0,
+                vertex_declaration_count: model_data.header.vertex_declarations.len() as u16,
+                material_count: self.material_names.len() as u16,
+                vertex_offsets: [0; 3],
+                index_offsets: [0; 3],
+                vertex_buffer_size: [0; 3],
+                index_buffer_size: [0; 3],
+                lod_count: lod_count as u8,
+                index_buffer_streaming_enabled: false,
+                has_edge_geometry: false,
+            },
+            model_data,
+            lods,
+            affected_bone_names: self.bone_names,
+            material_names: self.material_names,
+        };
+        mdl.update_headers();
+
+        // Read it back, so everything derived from the file (like shapes) is filled in
+        let buffer = mdl.write_to_buffer(Platform::Win32)?;
+        MDL::from_existing(Platform::Win32, &buffer)
+    }
+}
+
+/// Chooses the vertex layout for a part, and returns it along with the stride of each stream.
+fn vertex_declaration(part: &ImportedPart) -> (VertexDeclaration, [u8; MAX_VERTEX_STREAMS]) {
+    let mut elements = Vec::new();
+    // Elements are laid out one after another in each stream
+    let mut offsets = [0u8; MAX_VERTEX_STREAMS];
+    let mut push = |stream: u8, vertex_type, vertex_usage, usage_index| {
+        elements.push(VertexElement {
+            stream,
+            offset: offsets[stream as usize],
+            vertex_type,
+            vertex_usage,
+            usage_index,
+        });
+        offsets[stream as usize] += get_vertex_type_size(vertex_type) as u8;
+    };
+
+    push(0, VertexType::Single3, VertexUsage::Position, 0);
+    if part.skinned {
+        // 8 influences are stored as 8 bytes each, in place of 4 shorts
+        let (weights, indices) = if part.has_eight_influences {
+            (VertexType::UnsignedShort4, VertexType::UnsignedShort4)
+        } else {
+            (VertexType::ByteFloat4, VertexType::Byte4)
+        };
+        push(0, weights, VertexUsage::BlendWeights, 0);
+        push(0, indices, VertexUsage::BlendIndices, 0);
+    }
+
+    push(1, VertexType::Half4, VertexUsage::Normal, 0);
+    push(1, VertexType::ByteFloat4, VertexUsage::BiTangent, 0);
+    if part.has_color || part.has_color1 {
+        push(1, VertexType::ByteFloat4, VertexUsage::Color, 0);
+    }
+    if part.has_color1 {
+        push(1, VertexType::ByteFloat4, VertexUsage::Color, 1);
+    }
+    let uv_type = |four| {
+        if four {
+            VertexType::Half4
+        } else {
+            VertexType::Half2
+        }
+    };
+    push(1, uv_type(part.has_uv1), VertexUsage::UV, 0);
+    if part.has_uv2 || part.has_uv3 {
+        push(1, uv_type(part.has_uv3), VertexUsage::UV, 1);
+    }
+
+    let declaration = VertexDeclaration { elements };
+    let strides = declaration.strides();
+
+    (declaration, strides)
+}
+
+/// Converts weights to bytes that add up to 255, so they aren't lost when written.
+fn quantize_weights<const N: usize>(weights: [f32; N]) -> [u8; N] {
+    let weights = weights.map(|x| if x.is_finite() { x.max(0.0) } else { 0.0 });
+    let sum: f32 = weights.iter().sum();
+    if sum <= 0.0 {
+        return [0; N];
+    }
+
+    let mut bytes = weights.map(|x| (x / sum * u8::MAX as f32).round() as i32);
+
+    // Put any rounding error on the biggest weight
+    let error = u8::MAX as i32 - bytes.iter().sum::<i32>();
+    let biggest = (0..N).max_by_key(|i| bytes[*i]).unwrap_or_default();
+    bytes[biggest] += error;
+
+    bytes.map(|x| x.clamp(0, u8::MAX as i32) as u8)
+}
+
+/// Returns the array at `key`, or an empty one if it doesn't exist.
+fn array<'a>(value: &'a JsonValue, key: &str) -> &'a [JsonValue] {
+    value
+        .get(key)
+        .and_then(|x| x.as_array())
+        .unwrap_or_default()
+}
+
+/// Parses the LOD from names like `LOD 1` or `LOD 1 Part 0`.
+fn lod_from_name(name: &str) -> Option<usize> {
+    let rest = name.strip_prefix("LOD")?.trim_start();
+    let digits: String = rest.chars().take_while(|x| x.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+fn node_transform(node: &JsonValue) -> Matrix {
+    let floats = |key: &str| -> Option<Vec<f32>> {
+        node.get(key)?
+            .as_array()?
+            .iter()
+            .map(|x| x.as_number())
+            .collect()
+    };
+
+    if let Some(matrix) = floats("matrix").and_then(|x| Matrix::try_from(x).ok()) {
+        return matrix;
+    }
+
+    let translation = floats("translation")
+        .and_then(|x| <[f32; 3]>::try_from(x).ok())
+        .unwrap_or([0.0; 3]);
+    let rotation = floats("rotation")
+        .and_then(|x| <[f32; 4]>::try_from(x).ok())
+        .unwrap_or([0.0, 0.0, 0.0, 1.0]);
+    let scale = floats("scale")
+        .and_then(|x| <[f32; 3]>::try_from(x).ok())
+        .unwrap_or([1.0; 3]);
+
+    compose(translation, rotation, scale)
+}
+
+fn decode_base64(text: &str) -> Option<ByteBuffer> {
+    let value = |c: u8| -> Option<u32> {
+        Some(match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        } as u32)
+    };
+
+    let text = text.trim_end_matches('=').as_bytes();
+    let mut output = ByteBuffer::with_capacity(text.len() * 3 / 4);
+    for chunk in text.chunks(4) {
+        let mut combined = 0;
+        for (i, c) in chunk.iter().enumerate() {
+            combined |= value(*c)? << (18 - i * 6);
+        }
+
+        let bytes = combined.to_be_bytes();
+        output.extend_from_slice(&bytes[1..chunk.len()]);
+    }
+
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read;
+    use std::path::PathBuf;
+
+    use crate::model::GltfOptions;
+
+    use super::*;
+
+    fn read_mdl() -> MDL {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/tests");
+        d.push("c0201e0038_top_zeroed.mdl");
+
+        MDL::from_existing(Platform::Win32, &read(d).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let original = read_mdl();
+        let imported = MDL::from_gltf(&original.to_glb(&GltfOptions::default()).unwrap()).unwrap();
+
+        assert_eq!(imported.lods.len(), original.lods.len());
+        assert_eq!(imported.material_names, original.material_names);
+        // The shapes of the test model don't move any vertices, so only their names survive
+        assert_eq!(
+            imported.model_data.shapes.len(),
+            original.model_data.shapes.len()
+        );
+        for bone in &imported.affected_bone_names {
+            assert!(original.affected_bone_names.contains(bone));
+        }
+
+        for (imported_lod, original_lod) in imported.lods.iter().zip(&original.lods) {
+            assert_eq!(imported_lod.parts.len(), original_lod.parts.len());
+
+            for (imported_part, original_part) in imported_lod.parts.iter().zip(&original_lod.parts)
+            {
+                assert_eq!(imported_part.material_index, original_part.material_index);
+                assert_eq!(imported_part.submeshes.len(), original_part.submeshes.len());
+                assert_eq!(
+                    imported_part
+                        .submeshes
+                        .iter()
+                        .map(|x| x.index_count)
+                        .collect::<Vec<_>>(),
+                    original_part
+                        .submeshes
+                        .iter()
+                        .map(|x| x.index_count)
+                        .collect::<Vec<_>>()
+                );
+                // Both should point at the same positions
+                for (imported_index, original_index) in
+                    imported_part.indices.iter().zip(&original_part.indices)
+                {
+                    let imported = imported_part.vertices[*imported_index as usize].position;
+                    let original = original_part.vertices[*original_index as usize].position;
+                    assert_eq!(imported, original);
+                }
+            }
+        }
+
+        // Writing it again shouldn't change anything
+        let mut rewritten = imported.clone();
+        rewritten.update_headers();
+        assert_eq!(rewritten.file_header, imported.file_header);
+        assert_eq!(rewritten.model_data, imported.model_data);
+    }
+
+    #[test]
+    fn test_triangle() {
+        // A single triangle in a .gltf file, with its node moved up by one unit and a shape moving the last vertex. Its buffer view has a stride of 0, which means tightly packed.
+        let gltf = r#"{
+            "asset": {"version": "2.0"},
+            "scenes": [{"nodes": [0]}],
+            "nodes": [{"name": "LOD 0", "children": [1]}, {"mesh": 0, "translation": [0, 1, 0]}],
+            "meshes": [{"primitives": [{"attributes": {"POSITION": 0}, "targets": [{"POSITION": 1}]}], "extras": {"targetNames": ["shp_test"]}}],
+            "accessors": [
+                {"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"},
+                {"bufferView": 0, "byteOffset": 36, "componentType": 5126, "count": 3, "type": "VEC3"}
+            ],
+            "bufferViews": [{"buffer": 0, "byteLength": 72, "byteStride": 0}],
+            "buffers": [{"byteLength": 72, "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAIA/"}]
+        }"#;
+
+        let mdl = MDL::from_gltf(gltf.as_bytes()).unwrap();
+        assert_eq!(mdl.lods.len(), 1);
+        assert_eq!(mdl.material_names, vec!["/mt_default.mtrl"]);
+        assert!(mdl.affected_bone_names.is_empty());
+
+        let part = &mdl.lods[0].parts[0];
+        assert_eq!(part.indices, vec![0, 1, 2]);
+        assert_eq!(part.vertices[0].position, [0.0, 1.0, 0.0]);
+        assert_eq!(part.vertices[1].position, [1.0, 1.0, 0.0]);
+        assert_eq!(part.vertices[2].position, [0.0, 2.0, 0.0]);
+
+        assert_eq!(part.shapes.len(), 1);
+        assert_eq!(part.shapes[0].name, "shp_test");
+        assert_eq!(part.shapes[0].morphed_vertices[0].position, [0.0; 3]);
+        assert_eq!(part.shapes[0].morphed_vertices[2].position, [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_huge_accessor_count() {
+        // The counts don't match the 36 byte buffer, and would overflow or allocate far too much if trusted. A stride of 0 is the same as none, and strides smaller than an element aren't allowed.
+        for accessor in [
+            r#"{"bufferView": 0, "componentType": 5126, "count": 4611686018427387904, "type": "VEC3"}"#,
+            r#"{"componentType": 5126, "count": 4611686018427387904, "type": "VEC3"}"#,
+            r#"{"bufferView": 1, "componentType": 5126, "count": 4611686018427387904, "type": "VEC3"}"#,
+            r#"{"bufferView": 2, "componentType": 5126, "count": 3, "type": "VEC3"}"#,
+        ] {
+            let gltf = format!(
+                r#"{{
+                "asset": {{"version": "2.0"}},
+                "scenes": [{{"nodes": [0]}}],
+                "nodes": [{{"name": "LOD 0", "mesh": 0}}],
+                "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0}}}}]}}],
+                "accessors": [{accessor}],
+                "bufferViews": [{{"buffer": 0, "byteLength": 36}}, {{"buffer": 0, "byteLength": 36, "byteStride": 0}}, {{"buffer": 0, "byteLength": 36, "byteStride": 4}}],
+                "buffers": [{{"byteLength": 36, "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAA"}}]
+            }}"#
+            );
+            assert!(MDL::from_gltf(gltf.as_bytes()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_quantize_weights() {
+        assert_eq!(quantize_weights([1.0, 0.0, 0.0, 0.0]), [255, 0, 0, 0]);
+        assert_eq!(quantize_weights([1.0, 1.0, 1.0, 0.0]), [85, 85, 85, 0]);
+        assert_eq!(
+            quantize_weights([0.5, 0.5, 0.0, 0.0])
+                .iter()
+                .map(|x| *x as u32)
+                .sum::<u32>(),
+            255
+        );
+        assert_eq!(quantize_weights([0.0; 4]), [0; 4]);
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(decode_base64("Zm9vYmFy").unwrap(), b"foobar");
+        assert_eq!(decode_base64("Zm8=").unwrap(), b"fo");
+        assert_eq!(decode_base64("Zg==").unwrap(), b"f");
+        assert!(decode_base64("Z!==").is_none());
+    }
+}
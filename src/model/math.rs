@@ -0,0 +1,153 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Vector and matrix helpers shared by the model code.
+
+use crate::model::BoundingBox;
+
+/// Column-major 4x4 matrix, the same layout glTF uses.
+pub(super) type Matrix = [f32; 16];
+
+pub(super) const IDENTITY: Matrix = [
+    1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+];
+
+pub(super) fn normalize(vector: [f32; 3]) -> Option<[f32; 3]> {
+    let length = (vector[0] * vector[0] + vector[1] * vector[1] + vector[2] * vector[2]).sqrt();
+    (length > f32::EPSILON && length.is_finite()).then(|| vector.map(|x| x / length))
+}
+
+pub(super) fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+pub(super) fn compose(translation: [f32; 3], rotation: [f32; 4], scale: [f32; 3]) -> Matrix {
+    let [x, y, z, w] = rotation;
+    let (x2, y2, z2) = (x + x, y + y, z + z);
+    let (xx, xy, xz) = (x * x2, x * y2, x * z2);
+    let (yy, yz, zz) = (y * y2, y * z2, z * z2);
+    let (wx, wy, wz) = (w * x2, w * y2, w * z2);
+
+    [
+        (1.0 - (yy + zz)) * scale[0],
+        (xy + wz) * scale[0],
+        (xz - wy) * scale[0],
+        0.0,
+        (xy - wz) * scale[1],
+        (1.0 - (xx + zz)) * scale[1],
+        (yz + wx) * scale[1],
+        0.0,
+        (xz + wy) * scale[2],
+        (yz - wx) * scale[2],
+        (1.0 - (xx + yy)) * scale[2],
+        0.0,
+        translation[0],
+        translation[1],
+        translation[2],
+        1.0,
+    ]
+}
+
+pub(super) fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut result = [0.0; 16];
+    for column in 0..4 {
+        for row in 0..4 {
+            result[column * 4 + row] = (0..4).map(|k| a[k * 4 + row] * b[column * 4 + k]).sum();
+        }
+    }
+    result
+}
+
+/// Inverts a matrix that only contains a translation, rotation and scale.
+pub(super) fn inverse_affine(m: &Matrix) -> Matrix {
+    let a = |row: usize, column: usize| m[column * 4 + row];
+
+    let cofactors = [
+        a(1, 1) * a(2, 2) - a(1, 2) * a(2, 1),
+        a(1, 2) * a(2, 0) - a(1, 0) * a(2, 2),
+        a(1, 0) * a(2, 1) - a(1, 1) * a(2, 0),
+    ];
+    let determinant = a(0, 0) * cofactors[0] + a(0, 1) * cofactors[1] + a(0, 2) * cofactors[2];
+    if determinant.abs() <= f32::EPSILON {
+        return IDENTITY;
+    }
+    let inv_det = 1.0 / determinant;
+
+    // Inverse of the upper 3x3, indexed by [row][column]
+    let inverse = [
+        [
+            cofactors[0] * inv_det,
+            (a(0, 2) * a(2, 1) - a(0, 1) * a(2, 2)) * inv_det,
+            (a(0, 1) * a(1, 2) - a(0, 2) * a(1, 1)) * inv_det,
+        ],
+        [
+            cofactors[1] * inv_det,
+            (a(0, 0) * a(2, 2) - a(0, 2) * a(2, 0)) * inv_det,
+            (a(0, 2) * a(1, 0) - a(0, 0) * a(1, 2)) * inv_det,
+        ],
+        [
+            cofactors[2] * inv_det,
+            (a(0, 1) * a(2, 0) - a(0, 0) * a(2, 1)) * inv_det,
+            (a(0, 0) * a(1, 1) - a(0, 1) * a(1, 0)) * inv_det,
+        ],
+    ];
+
+    let translation = [a(0, 3), a(1, 3), a(2, 3)];
+
+    let mut result = IDENTITY;
+    for row in 0..3 {
+        for column in 0..3 {
+            result[column * 4 + row] = inverse[row][column];
+        }
+        result[12 + row] = -(0..3)
+            .map(|k| inverse[row][k] * translation[k])
+            .sum::<f32>();
+    }
+    result
+}
+
+/// The game stores the bitangent instead of the tangent, with the handedness in W.
+pub(super) fn bitangent(tangent: [f32; 3], handedness: f32, normal: [f32; 3]) -> [f32; 4] {
+    let handedness = if handedness < 0.0 { -1.0 } else { 1.0 };
+
+    let bitangent = normalize(cross(normal, tangent))
+        .or_else(|| {
+            // Without a usable tangent, any vector perpendicular to the normal will do
+            let axis = if normal[0].abs() < 0.9 {
+                [1.0, 0.0, 0.0]
+            } else {
+                [0.0, 1.0, 0.0]
+            };
+            normalize(cross(normal, axis))
+        })
+        .unwrap_or([0.0, 0.0, 1.0]);
+
+    [
+        bitangent[0] * handedness,
+        bitangent[1] * handedness,
+        bitangent[2] * handedness,
+        handedness,
+    ]
+}
+
+pub(super) fn expand(bounds: &mut Option<([f32; 3], [f32; 3])>, position: [f32; 3]) {
+    let (min, max) = bounds.get_or_insert((position, position));
+    for i in 0..3 {
+        min[i] = min[i].min(position[i]);
+        max[i] = max[i].max(position[i]);
+    }
+}
+
+pub(super) fn bounding_box(bounds: Option<([f32; 3], [f32; 3])>) -> BoundingBox {
+    match bounds {
+        Some((min, max)) => BoundingBox {
+            min: [min[0], min[1], min[2], 1.0],
+            max: [max[0], max[1], max[2], 1.0],
+        },
+        None => BoundingBox::default(),
+    }
+}
@@ -5,6 +5,26 @@
 
 mod file_operations;
 
+mod gltf;
+pub use gltf::{GltfMaterialTextures, GltfOptions};
+
+mod gltf_import;
+
+mod math;
+
+mod skinning;
+pub use skinning::{BoneTransform, Pose, SkinnedPart};
+
+mod deform;
+
+mod simplify;
+pub use simplify::LodOptions;
+
+mod finalize;
+
+mod validate;
+pub use validate::ModelIssue;
+
 pub mod vertex_declarations;
 
 use std::io::{Cursor, Seek, SeekFrom};
@@ -21,7 +41,7 @@ use crate::equipment::{CharacterCategory, EquipSlotCategory};
 use crate::race::{Gender, Race, Tribe, get_race_id};
 use crate::{ByteBuffer, ByteSpan, ReadableFile, WritableFile};
 use vertex_declarations::{
-    VERTEX_ELEMENT_SIZE, VertexDeclaration, VertexType, VertexUsage, vertex_element_parser,
+    MAX_VERTEX_STREAMS, VERTEX_ELEMENT_SIZE, VertexDeclaration, vertex_element_parser,
     vertex_element_writer,
 };
 
@@ -237,8 +257,8 @@ struct Mesh {
     bone_table_index: u16,
     start_index: u32,
 
-    vertex_buffer_offsets: [u32; 3],
-    vertex_buffer_strides: [u8; 3],
+    vertex_buffer_offsets: [u32; MAX_VERTEX_STREAMS],
+    vertex_buffer_strides: [u8; MAX_VERTEX_STREAMS],
 
     vertex_stream_count: u8,
 }
@@ -337,37 +357,40 @@ pub struct BoundingBox {
     pub max: [f32; 4],
 }
 
+/// A mesh for the shadows some bg models cast on terrain, which has its own vertices and indices.
 #[binrw]
 #[derive(Debug, Clone, PartialEq)]
-#[allow(dead_code)]
-struct TerrainShadowMesh {
-    index_count: u32,
-    start_index: u32,
-    vertex_buffer_offset: u32,
-    vertex_count: u16,
-    submesh_index: u16,
-    submesh_count: u16,
-    vertex_buffer_stride: u8,
+pub struct TerrainShadowMesh {
+    pub index_count: u32,
+    /// Relative to the index buffer of the LOD, like [Part] indices.
+    pub start_index: u32,
+    /// Relative to the vertex buffer of the LOD.
+    pub vertex_buffer_offset: u32,
+    pub vertex_count: u16,
+    pub submesh_index: u16,
+    pub submesh_count: u16,
+    pub vertex_buffer_stride: u8,
     padding: u8,
 }
 
+/// A range of indices in a [TerrainShadowMesh].
 #[binrw]
 #[derive(Debug, Clone, PartialEq)]
-#[allow(dead_code)]
-struct TerrainShadowSubmesh {
-    index_offset: u32,
-    index_count: u32,
-    unknown1: u16,
-    unknown2: u16,
+pub struct TerrainShadowSubmesh {
+    pub index_offset: u32,
+    pub index_count: u32,
+    pub unknown1: u16,
+    pub unknown2: u16,
 }
 
+/// Which shape meshes make up a shape in each LOD.
 #[binrw]
 #[derive(Debug, Clone, PartialEq)]
-#[allow(dead_code)]
-struct ShapeStruct {
-    string_offset: u32,
-    shape_mesh_start_index: [u16; 3],
-    shape_mesh_count: [u16; 3],
+pub struct ShapeStruct {
+    /// Offset of the shape's name in the model's strings.
+    pub string_offset: u32,
+    pub shape_mesh_start_index: [u16; 3],
+    pub shape_mesh_count: [u16; 3],
 }
 
 #[binrw]
@@ -396,7 +419,7 @@ pub struct ModelData {
     pub header: ModelHeader,
 
     #[br(count = header.element_id_count)]
-    element_ids: Vec<ElementId>,
+    pub element_ids: Vec<ElementId>,
 
     #[br(count = 3)]
     lods: Vec<MeshLod>,
@@ -411,13 +434,13 @@ pub struct ModelData {
     attribute_name_offsets: Vec<u32>,
 
     #[br(count = header.terrain_shadow_mesh_count)]
-    terrain_shadow_meshes: Vec<TerrainShadowMesh>,
+    pub terrain_shadow_meshes: Vec<TerrainShadowMesh>,
 
     #[br(count = header.submesh_count)]
     submeshes: Vec<Submesh>,
 
     #[br(count = header.terrain_shadow_submesh_count)]
-    terrain_shadow_submeshes: Vec<TerrainShadowSubmesh>,
+    pub terrain_shadow_submeshes: Vec<TerrainShadowSubmesh>,
 
     #[br(count = header.material_count)]
     material_name_offsets: Vec<u32>,
@@ -435,7 +458,7 @@ pub struct ModelData {
     bone_tables_v2: BoneTablesV2,
 
     #[br(count = header.shape_count)]
-    shapes: Vec<ShapeStruct>,
+    pub shapes: Vec<ShapeStruct>,
 
     #[br(count = header.shape_mesh_count)]
     shape_meshes: Vec<ShapeMesh>,
@@ -464,14 +487,15 @@ pub struct ModelData {
     bone_bounding_boxes: Vec<BoundingBox>,
 }
 
+/// A point attached to a bone, which the game uses to place things like VFX.
 #[binrw]
 #[derive(Debug, Clone, PartialEq)]
-#[allow(dead_code)]
-struct ElementId {
-    element_id: u32,
-    parent_bone_name: u32,
-    translate: [f32; 3],
-    rotate: [f32; 3],
+pub struct ElementId {
+    pub element_id: u32,
+    /// Offset of the bone's name in the model's strings, see [MDL::element_id_bone_name].
+    pub parent_bone_name: u32,
+    pub translate: [f32; 3],
+    pub rotate: [f32; 3],
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -480,13 +504,23 @@ pub struct Vertex {
     pub position: [f32; 3],
     pub uv0: [f32; 2],
     pub uv1: [f32; 2],
+    /// From the second UV element, which some Dawntrail models have.
+    pub uv2: [f32; 2],
+    /// From the second UV element, which some Dawntrail models have.
+    pub uv3: [f32; 2],
     pub normal: [f32; 3],
+    /// Only used by some models, like terrain.
+    pub tangent: [f32; 4],
     pub bitangent: [f32; 4],
-    //pub bitangent1: [f32; 4], // TODO: need to figure out what the heck this could be
+    /// From the second bitangent element, which some Dawntrail models have.
+    pub bitangent1: [f32; 4],
     pub color: [f32; 4],
+    /// From the second color element, which some Dawntrail models have.
+    pub color1: [f32; 4],
 
-    pub bone_weight: [f32; 4],
-    pub bone_id: [u8; 4],
+    /// Most models only use the first 4 influences, but Dawntrail models can have up to 8.
+    pub bone_weight: [f32; 8],
+    pub bone_id: [u8; 8],
 }
 
 impl Default for Vertex {
@@ -495,11 +529,16 @@ impl Default for Vertex {
             position: [0.0; 3],
             uv0: [0.0; 2],
             uv1: [0.0; 2],
+            uv2: [0.0; 2],
+            uv3: [0.0; 2],
             normal: [0.0; 3],
+            tangent: [0.0; 4],
             bitangent: [0.0; 4],
+            bitangent1: [0.0; 4],
             color: [0.0; 4],
-            bone_weight: [0.0; 4],
-            bone_id: [0u8; 4],
+            color1: [0.0; 4],
+            bone_weight: [0.0; 8],
+            bone_id: [0u8; 8],
         }
     }
 }
@@ -545,11 +584,26 @@ pub struct Part {
 pub enum PartType {
     Normal,
     Water,
+    /// Used for the vertical fog of some bg models.
+    VerticalFog,
+}
+
+/// Corresponds to a terrain shadow mesh in an LOD.
+#[derive(Debug, Clone)]
+pub struct TerrainShadowPart {
+    /// Index in `ModelData::terrain_shadow_meshes`.
+    pub mesh_index: u16,
+    /// The vertices as they're stored, in strides of the mesh's `vertex_buffer_stride`.
+    pub vertex_data: Vec<u8>,
+    pub indices: Vec<u16>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Lod {
     pub parts: Vec<Part>,
+    pub terrain_shadow_parts: Vec<TerrainShadowPart>,
+    /// The PS3 edge geometry of this LOD as it's stored, since its format isn't understood yet.
+    pub edge_geometry: ByteBuffer,
     pub model_lod_range: f32,
 }
 
@@ -669,6 +723,27 @@ impl MDL {
         self.update_headers();
     }
 
+    /// Returns the bone table used by `part`, which maps its vertices' `bone_id` to indices in `affected_bone_names`.
+    pub(crate) fn bone_table(&self, part: &Part) -> &[u16] {
+        let index = self.model_data.meshes[part.mesh_index as usize].bone_table_index as usize;
+        if let Some(table) = self.model_data.bone_tables.get(index) {
+            &table.bone_indices[..(table.bone_count as usize).min(table.bone_indices.len())]
+        } else if let Some(table) = self.model_data.bone_tables_v2.tables.get(index) {
+            &table.bone_indices
+        } else {
+            &[]
+        }
+    }
+
+    /// Returns the name of the bone `element_id` is attached to.
+    pub fn element_id_bone_name(&self, element_id: &ElementId) -> String {
+        let (name, _) = null_terminated_utf8(
+            &self.model_data.header.strings,
+            element_id.parent_bone_name as usize,
+        );
+        name
+    }
+
     pub(crate) fn update_headers(&mut self) {
         // update values
         for i in 0..self.file_header.lod_count {
@@ -689,14 +764,59 @@ impl MDL {
                     combined_index_count += mesh.index_count;
                 }
 
-                for i in 0..mesh.vertex_stream_count as usize {
-                    mesh.vertex_buffer_offsets[i] = vertex_offset;
-                    vertex_offset +=
-                        mesh.vertex_count as u32 * mesh.vertex_buffer_strides[i] as u32;
+                // Streams past the ones the mesh has room for directly follow the last one
+                for stream in 0..mesh.vertex_stream_count as usize {
+                    let (_, stride) = self.model_data.vertex_stream(j as usize, stream);
+                    let mesh = &mut self.model_data.meshes[j as usize];
+                    if stream < MAX_VERTEX_STREAMS {
+                        mesh.vertex_buffer_offsets[stream] = vertex_offset;
+                    }
+                    vertex_offset += mesh.vertex_count as u32 * stride;
                 }
             }
+
+            // Terrain shadow meshes come after the regular ones
+            let lod = &self.model_data.lods[i as usize];
+            let mut index_end = (lod.mesh_index..lod.mesh_index + lod.mesh_count)
+                .map(|j| {
+                    let mesh = &self.model_data.meshes[j as usize];
+                    mesh.start_index + mesh.index_count
+                })
+                .max()
+                .unwrap_or_default();
+            for part in self
+                .lods
+                .get(i as usize)
+                .map(|x| x.terrain_shadow_parts.as_slice())
+                .unwrap_or_default()
+            {
+                // Reported by validate, and writing the model fails
+                let Some(mesh) = self
+                    .model_data
+                    .terrain_shadow_meshes
+                    .get_mut(part.mesh_index as usize)
+                else {
+                    continue;
+                };
+                mesh.vertex_count =
+                    (part.vertex_data.len() / (mesh.vertex_buffer_stride.max(1) as usize)) as u16;
+                mesh.index_count = part.indices.len() as u32;
+
+                mesh.vertex_buffer_offset = vertex_offset;
+                vertex_offset += mesh.vertex_count as u32 * mesh.vertex_buffer_stride as u32;
+                mesh.start_index = index_end;
+                index_end += mesh.index_count;
+            }
         }
 
+        let vertex_strides: Vec<u32> = (0..self.model_data.meshes.len())
+            .map(|j| {
+                (0..self.model_data.meshes[j].vertex_stream_count as usize)
+                    .map(|stream| self.model_data.vertex_stream(j, stream).1)
+                    .sum()
+            })
+            .collect();
+
         for lod in &mut self.model_data.lods {
             let mut total_vertex_buffer_size = 0;
             let mut total_index_buffer_size = 0;
@@ -706,16 +826,20 @@ impl MDL {
                 let vertex_count = self.model_data.meshes[j as usize].vertex_count;
                 let index_count = self.model_data.meshes[j as usize].index_count;
 
-                let mut total_vertex_stride: u32 = 0;
-                for i in 0..self.model_data.meshes[j as usize].vertex_stream_count as usize {
-                    total_vertex_stride +=
-                        self.model_data.meshes[j as usize].vertex_buffer_strides[i] as u32;
-                }
-
-                total_vertex_buffer_size += vertex_count as u32 * total_vertex_stride;
+                total_vertex_buffer_size += vertex_count as u32 * vertex_strides[j as usize];
                 total_index_buffer_size += index_count * size_of::<u16>() as u32;
             }
 
+            for j in lod.terrain_shadow_mesh_index
+                ..lod.terrain_shadow_mesh_index + lod.terrain_shadow_mesh_count
+            {
+                if let Some(mesh) = self.model_data.terrain_shadow_meshes.get(j as usize) {
+                    total_vertex_buffer_size +=
+                        mesh.vertex_count as u32 * mesh.vertex_buffer_stride as u32;
+                    total_index_buffer_size += mesh.index_count * size_of::<u16>() as u32;
+                }
+            }
+
             // TODO: this can definitely be written better
             let mut index_padding = total_index_buffer_size % 16;
             if index_padding == 0 {
@@ -738,7 +862,11 @@ impl MDL {
 
         let mut overall_offset: u32 = 0;
 
-        for lod in &mut self.model_data.lods {
+        for (l, lod) in self.model_data.lods.iter_mut().enumerate() {
+            if let Some(edge_geometry) = self.lods.get(l).map(|x| &x.edge_geometry) {
+                lod.edge_geometry_size = edge_geometry.len() as u32;
+            }
+
             // vertex
             lod.vertex_data_offset = data_offset + overall_offset;
             overall_offset += lod.vertex_buffer_size;
@@ -747,11 +875,13 @@ impl MDL {
             lod.index_data_offset = data_offset + overall_offset;
             overall_offset += lod.index_buffer_size;
 
-            // edge, but unused?
-            //lod.edge_geometry_data_offset = data_offset + overall_offset;
-            //overall_offset += lod.edge_geometry_size;
-
-            lod.edge_geometry_data_offset = lod.index_data_offset;
+            // edge geometry, which only PS3 models have
+            if lod.edge_geometry_size > 0 {
+                lod.edge_geometry_data_offset = data_offset + overall_offset;
+                overall_offset += lod.edge_geometry_size;
+            } else {
+                lod.edge_geometry_data_offset = lod.index_data_offset;
+            }
         }
 
         for i in 0..self.lods.len() {
@@ -868,177 +998,16 @@ impl ReadableFile for MDL {
 
                 for k in 0..vertex_count {
                     for element in &declaration.elements {
+                        let (offset, stride) =
+                            model.vertex_stream(j as usize, element.stream as usize);
                         cursor.seek(SeekFrom::Start(
                             (model.lods[i as usize].vertex_data_offset
-                                + model.meshes[j as usize].vertex_buffer_offsets
-                                    [element.stream as usize]
+                                + offset
                                 + element.offset as u32
-                                + model.meshes[j as usize].vertex_buffer_strides
-                                    [element.stream as usize]
-                                    as u32
-                                    * k as u32) as u64,
+                                + stride * k as u32) as u64,
                         ))?;
 
-                        match element.vertex_usage {
-                            VertexUsage::Position => match element.vertex_type {
-                                VertexType::Single4 => {
-                                    vertices[k as usize].position.clone_from_slice(
-                                        &MDL::read_single4(&mut cursor, endianness)?[0..3],
-                                    );
-                                }
-                                VertexType::Half4 => {
-                                    vertices[k as usize].position.clone_from_slice(
-                                        &MDL::read_half4(&mut cursor, endianness)?[0..3],
-                                    );
-                                }
-                                VertexType::Single3 => {
-                                    vertices[k as usize].position =
-                                        MDL::read_single3(&mut cursor, endianness)?;
-                                }
-                                _ => {
-                                    panic!(
-                                        "Unexpected vertex type for position: {:#?}",
-                                        element.vertex_type
-                                    );
-                                }
-                            },
-                            VertexUsage::BlendWeights => match element.vertex_type {
-                                VertexType::ByteFloat4 => {
-                                    vertices[k as usize].bone_weight =
-                                        MDL::read_byte_float4(&mut cursor)?;
-                                }
-                                VertexType::Byte4 => {
-                                    vertices[k as usize].bone_weight =
-                                        MDL::read_byte_float4(&mut cursor)?;
-                                }
-                                VertexType::UnsignedShort4 => {
-                                    let bytes = MDL::read_unsigned_short4(&mut cursor, endianness)?;
-                                    vertices[k as usize].bone_weight = [
-                                        f32::from(bytes[0]),
-                                        f32::from(bytes[1]),
-                                        f32::from(bytes[2]),
-                                        f32::from(bytes[3]),
-                                    ];
-                                }
-                                _ => {
-                                    panic!(
-                                        "Unexpected vertex type for blendweight: {:#?}",
-                                        element.vertex_type
-                                    );
-                                }
-                            },
-                            VertexUsage::BlendIndices => match element.vertex_type {
-                                VertexType::Byte4 => {
-                                    vertices[k as usize].bone_id = MDL::read_byte4(&mut cursor)?;
-                                }
-                                VertexType::UnsignedShort4 => {
-                                    let shorts =
-                                        MDL::read_unsigned_short4(&mut cursor, endianness)?;
-                                    vertices[k as usize].bone_id = [
-                                        shorts[0] as u8,
-                                        shorts[1] as u8,
-                                        shorts[2] as u8,
-                                        shorts[3] as u8,
-                                    ];
-                                }
-                                _ => {
-                                    panic!(
-                                        "Unexpected vertex type for blendindice: {:#?}",
-                                        element.vertex_type
-                                    );
-                                }
-                            },
-                            VertexUsage::Normal => match element.vertex_type {
-                                VertexType::Half4 => {
-                                    vertices[k as usize].normal.clone_from_slice(
-                                        &MDL::read_half4(&mut cursor, endianness)?[0..3],
-                                    );
-                                }
-                                VertexType::Single3 => {
-                                    vertices[k as usize].normal =
-                                        MDL::read_single3(&mut cursor, endianness)?;
-                                }
-                                VertexType::UnkPS3 => {
-                                    // TODO: unsure
-                                    vertices[k as usize].normal.clone_from_slice(
-                                        &MDL::read_byte_float4(&mut cursor)?[0..3],
-                                    );
-                                }
-                                _ => {
-                                    panic!(
-                                        "Unexpected vertex type for normal: {:#?}",
-                                        element.vertex_type
-                                    );
-                                }
-                            },
-                            VertexUsage::UV => match element.vertex_type {
-                                VertexType::ByteFloat4 => {
-                                    let combined = MDL::read_byte_float4(&mut cursor)?;
-
-                                    vertices[k as usize].uv0.clone_from_slice(&combined[0..2]);
-                                    vertices[k as usize].uv1.clone_from_slice(&combined[2..4]);
-                                }
-                                VertexType::Half4 => {
-                                    let combined = MDL::read_half4(&mut cursor, endianness)?;
-
-                                    vertices[k as usize].uv0.clone_from_slice(&combined[0..2]);
-                                    vertices[k as usize].uv1.clone_from_slice(&combined[2..4]);
-                                }
-                                VertexType::Single4 => {
-                                    let combined = MDL::read_single4(&mut cursor, endianness)?;
-
-                                    vertices[k as usize].uv0.clone_from_slice(&combined[0..2]);
-                                    vertices[k as usize].uv1.clone_from_slice(&combined[2..4]);
-                                }
-                                VertexType::Half2 => {
-                                    let combined = MDL::read_half2(&mut cursor, endianness)?;
-
-                                    vertices[k as usize].uv0.clone_from_slice(&combined[0..2]);
-                                }
-                                _ => {
-                                    panic!(
-                                        "Unexpected vertex type for uv: {:#?}",
-                                        element.vertex_type
-                                    );
-                                }
-                            },
-                            VertexUsage::BiTangent => match element.vertex_type {
-                                VertexType::ByteFloat4 => {
-                                    vertices[k as usize].bitangent =
-                                        MDL::read_tangent(&mut cursor)?;
-                                }
-                                _ => {
-                                    panic!(
-                                        "Unexpected vertex type for bitangent: {:#?}",
-                                        element.vertex_type
-                                    );
-                                }
-                            },
-                            VertexUsage::Tangent => {
-                                match element.vertex_type {
-                                    // Used for... terrain..?
-                                    VertexType::ByteFloat4 => {}
-                                    _ => {
-                                        panic!(
-                                            "Unexpected vertex type for tangent: {:#?}",
-                                            element.vertex_type
-                                        );
-                                    }
-                                }
-                            }
-                            VertexUsage::Color => match element.vertex_type {
-                                VertexType::ByteFloat4 => {
-                                    vertices[k as usize].color =
-                                        MDL::read_byte_float4(&mut cursor)?;
-                                }
-                                _ => {
-                                    panic!(
-                                        "Unexpected vertex type for color: {:#?}",
-                                        element.vertex_type
-                                    );
-                                }
-                            },
-                        }
+                        vertices[k as usize].read_element(&mut cursor, endianness, element)?;
                     }
                 }
 
@@ -1103,11 +1072,13 @@ impl ReadableFile for MDL {
 
                     if !shape_values.is_empty() {
                         for shape_value in shape_values {
-                            let old_vertex =
-                                vertices[indices[shape_value.base_indices_index as usize] as usize];
+                            // The base index is relative to the whole LOD, not this mesh
+                            let base_index = indices[shape_value.base_indices_index as usize
+                                - model.meshes[j as usize].start_index as usize]
+                                as usize;
+                            let old_vertex = vertices[base_index];
                             let new_vertex = vertices[shape_value.replacing_vertex_index as usize];
-                            let vertex = &mut morphed_vertices
-                                [indices[shape_value.base_indices_index as usize] as usize];
+                            let vertex = &mut morphed_vertices[base_index];
 
                             vertex.position[0] = new_vertex.position[0] - old_vertex.position[0];
                             vertex.position[1] = new_vertex.position[1] - old_vertex.position[1];
@@ -1130,20 +1101,15 @@ impl ReadableFile for MDL {
                 let mut vertex_stream_strides = vec![];
                 let mesh = &model.meshes[j as usize];
                 for stream in 0..mesh.vertex_stream_count {
-                    if stream >= 3 {
-                        // TODO: extra strides aren't supported yet!
-                        continue;
-                    }
-
                     let mut vertex_data = vec![];
-                    let stride = mesh.vertex_buffer_strides[stream as usize];
+                    let (offset, stride) = model.vertex_stream(j as usize, stream as usize);
                     for z in 0..mesh.vertex_count {
                         // TODO: read the entire vertex data into a buffer
                         // Handle the offsets within Novus itself
                         cursor.seek(SeekFrom::Start(
                             (model.lods[i as usize].vertex_data_offset
-                                + model.meshes[j as usize].vertex_buffer_offsets[stream as usize]
-                                + (z as u32 * stride as u32)) as u64,
+                                + offset
+                                + (z as u32 * stride)) as u64,
                         ))?;
 
                         for _ in 0..stride {
@@ -1152,8 +1118,7 @@ impl ReadableFile for MDL {
                     }
 
                     vertex_streams.push(vertex_data);
-                    vertex_stream_strides
-                        .push(mesh.vertex_buffer_strides[stream as usize] as usize);
+                    vertex_stream_strides.push(stride as usize);
                 }
 
                 parts.push(Part {
@@ -1169,9 +1134,60 @@ impl ReadableFile for MDL {
                 });
             }
 
+            let mesh_lod = &model.lods[i as usize];
+
+            let mut terrain_shadow_parts = vec![];
+            for j in mesh_lod.terrain_shadow_mesh_index
+                ..mesh_lod.terrain_shadow_mesh_index + mesh_lod.terrain_shadow_mesh_count
+            {
+                let Some(mesh) = model.terrain_shadow_meshes.get(j as usize) else {
+                    continue;
+                };
+
+                cursor.seek(SeekFrom::Start(
+                    (mesh_lod.vertex_data_offset + mesh.vertex_buffer_offset) as u64,
+                ))?;
+                let vertex_data: Vec<u8> = cursor.read_type_args(
+                    endianness,
+                    VecArgs::builder()
+                        .count(mesh.vertex_count as usize * mesh.vertex_buffer_stride as usize)
+                        .finalize(),
+                )?;
+
+                cursor.seek(SeekFrom::Start(
+                    (model_file_header.index_offsets[i as usize]
+                        + mesh.start_index * size_of::<u16>() as u32) as u64,
+                ))?;
+                let indices: Vec<u16> = cursor.read_type_args(
+                    endianness,
+                    VecArgs::builder()
+                        .count(mesh.index_count as usize)
+                        .finalize(),
+                )?;
+
+                terrain_shadow_parts.push(TerrainShadowPart {
+                    mesh_index: j,
+                    vertex_data,
+                    indices,
+                });
+            }
+
+            let mut edge_geometry = ByteBuffer::new();
+            if mesh_lod.edge_geometry_size > 0 {
+                cursor.seek(SeekFrom::Start(mesh_lod.edge_geometry_data_offset as u64))?;
+                edge_geometry = cursor.read_type_args(
+                    endianness,
+                    VecArgs::builder()
+                        .count(mesh_lod.edge_geometry_size as usize)
+                        .finalize(),
+                )?;
+            }
+
             lods.push(Lod {
                 parts,
-                model_lod_range: model.lods[i as usize].model_lod_range,
+                terrain_shadow_parts,
+                edge_geometry,
+                model_lod_range: mesh_lod.model_lod_range,
             });
         }
 
@@ -1194,6 +1210,12 @@ fn get_part_type(lod: &MeshLod, index: u16) -> Option<PartType> {
         return Some(PartType::Water);
     }
 
+    if (lod.vertical_fog_mesh_index..lod.vertical_fog_mesh_index + lod.vertical_fog_mesh_count)
+        .contains(&index)
+    {
+        return Some(PartType::VerticalFog);
+    }
+
     None
 }
 
@@ -1219,167 +1241,17 @@ impl WritableFile for MDL {
 
                     for (k, vert) in part.vertices.iter().enumerate() {
                         for element in &declaration.elements {
+                            let (offset, stride) = self
+                                .model_data
+                                .vertex_stream(part.mesh_index as usize, element.stream as usize);
                             cursor.seek(SeekFrom::Start(
                                 (self.model_data.lods[l].vertex_data_offset
-                                    + self.model_data.meshes[part.mesh_index as usize]
-                                        .vertex_buffer_offsets
-                                        [element.stream as usize]
+                                    + offset
                                     + element.offset as u32
-                                    + self.model_data.meshes[part.mesh_index as usize]
-                                        .vertex_buffer_strides
-                                        [element.stream as usize]
-                                        as u32
-                                        * k as u32) as u64,
+                                    + stride * k as u32) as u64,
                             ))?;
 
-                            match element.vertex_usage {
-                                VertexUsage::Position => match element.vertex_type {
-                                    VertexType::Single4 => {
-                                        MDL::write_single4(
-                                            &mut cursor,
-                                            endianness,
-                                            &MDL::pad_slice(&vert.position, 1.0),
-                                        )?;
-                                    }
-                                    VertexType::Half4 => {
-                                        MDL::write_half4(
-                                            &mut cursor,
-                                            endianness,
-                                            &MDL::pad_slice(&vert.position, 1.0),
-                                        )?;
-                                    }
-                                    VertexType::Single3 => {
-                                        MDL::write_single3(
-                                            &mut cursor,
-                                            endianness,
-                                            &vert.position,
-                                        )?;
-                                    }
-                                    _ => {
-                                        panic!(
-                                            "Unexpected vertex type for position: {:#?}",
-                                            element.vertex_type
-                                        );
-                                    }
-                                },
-                                VertexUsage::BlendWeights => match element.vertex_type {
-                                    VertexType::ByteFloat4 => {
-                                        MDL::write_byte_float4(&mut cursor, &vert.bone_weight)?;
-                                    }
-                                    VertexType::Byte4 => {
-                                        MDL::write_byte_float42(&mut cursor, &vert.bone_weight)?; // TODO: WRONG!
-                                    }
-                                    _ => {
-                                        panic!(
-                                            "Unexpected vertex type for blendweight: {:#?}",
-                                            element.vertex_type
-                                        );
-                                    }
-                                },
-                                VertexUsage::BlendIndices => match element.vertex_type {
-                                    VertexType::Byte4 => {
-                                        MDL::write_byte4(&mut cursor, &vert.bone_id)?;
-                                    }
-                                    _ => {
-                                        panic!(
-                                            "Unexpected vertex type for blendindice: {:#?}",
-                                            element.vertex_type
-                                        );
-                                    }
-                                },
-                                VertexUsage::Normal => match element.vertex_type {
-                                    VertexType::Half4 => {
-                                        MDL::write_half4(
-                                            &mut cursor,
-                                            endianness,
-                                            &MDL::pad_slice(&vert.normal, 0.0),
-                                        )?;
-                                    }
-                                    VertexType::Single3 => {
-                                        MDL::write_single3(&mut cursor, endianness, &vert.normal)?;
-                                    }
-                                    VertexType::UnkPS3 => {
-                                        // TODO: unsure
-                                        MDL::write_byte_float4(
-                                            &mut cursor,
-                                            &MDL::pad_slice(&vert.normal, 0.0),
-                                        )?;
-                                    }
-                                    _ => {
-                                        panic!(
-                                            "Unexpected vertex type for normal: {:#?}",
-                                            element.vertex_type
-                                        );
-                                    }
-                                },
-                                VertexUsage::UV => match element.vertex_type {
-                                    VertexType::Half2 => {
-                                        MDL::write_half2(&mut cursor, endianness, &vert.uv0)?;
-                                    }
-                                    VertexType::Half4 => {
-                                        let combined =
-                                            [vert.uv0[0], vert.uv0[1], vert.uv1[0], vert.uv1[1]];
-
-                                        MDL::write_half4(&mut cursor, endianness, &combined)?;
-                                    }
-                                    VertexType::Single4 => {
-                                        let combined =
-                                            [vert.uv0[0], vert.uv0[1], vert.uv1[0], vert.uv1[1]];
-
-                                        MDL::write_single4(&mut cursor, endianness, &combined)?;
-                                    }
-                                    VertexType::ByteFloat4 => {
-                                        let combined =
-                                            [vert.uv0[0], vert.uv0[1], vert.uv1[0], vert.uv1[1]];
-
-                                        MDL::write_tangent(&mut cursor, &combined)?;
-                                    }
-                                    _ => {
-                                        panic!(
-                                            "Unexpected vertex type for uv: {:#?}",
-                                            element.vertex_type
-                                        );
-                                    }
-                                },
-                                VertexUsage::BiTangent => match element.vertex_type {
-                                    VertexType::ByteFloat4 => {
-                                        MDL::write_tangent(&mut cursor, &vert.bitangent)?;
-                                    }
-                                    _ => {
-                                        panic!(
-                                            "Unexpected vertex type for bitangent: {:#?}",
-                                            element.vertex_type
-                                        );
-                                    }
-                                },
-                                VertexUsage::Tangent => {
-                                    #[allow(clippy::match_single_binding)] // TODO
-                                    match element.vertex_type {
-                                        VertexType::ByteFloat4 => {
-                                            // TODO: restore
-                                            //MDL::write_tangent(&mut cursor, &vert.binormal)?;
-                                            MDL::write_tangent(&mut cursor, &[0.0, 0.0, 0.0, 0.0])?;
-                                        }
-                                        _ => {
-                                            panic!(
-                                                "Unexpected vertex type for tangent: {:#?}",
-                                                element.vertex_type
-                                            );
-                                        }
-                                    }
-                                }
-                                VertexUsage::Color => match element.vertex_type {
-                                    VertexType::ByteFloat4 => {
-                                        MDL::write_byte_float4(&mut cursor, &vert.color)?;
-                                    }
-                                    _ => {
-                                        panic!(
-                                            "Unexpected vertex type for color: {:#?}",
-                                            element.vertex_type
-                                        );
-                                    }
-                                },
-                            }
+                            vert.write_element(&mut cursor, endianness, element)?;
                         }
                     }
 
@@ -1391,9 +1263,50 @@ impl WritableFile for MDL {
 
                     cursor.write_type(&part.indices, endianness)?;
                 }
+
+                let mesh_lod = &self.model_data.lods[l];
+                for part in &lod.terrain_shadow_parts {
+                    let mesh = self
+                        .model_data
+                        .terrain_shadow_meshes
+                        .get(part.mesh_index as usize)
+                        .ok_or_else(|| crate::Error::InvalidData {
+                            reason: format!(
+                                "terrain shadow part uses mesh {}, but the model only has {}",
+                                part.mesh_index,
+                                self.model_data.terrain_shadow_meshes.len()
+                            ),
+                        })?;
+
+                    cursor.seek(SeekFrom::Start(
+                        (mesh_lod.vertex_data_offset + mesh.vertex_buffer_offset) as u64,
+                    ))?;
+                    cursor.write_type(&part.vertex_data, endianness)?;
+
+                    cursor.seek(SeekFrom::Start(
+                        (self.file_header.index_offsets[l]
+                            + mesh.start_index * size_of::<u16>() as u32)
+                            as u64,
+                    ))?;
+                    cursor.write_type(&part.indices, endianness)?;
+                }
+
+                if !lod.edge_geometry.is_empty() {
+                    cursor.seek(SeekFrom::Start(mesh_lod.edge_geometry_data_offset as u64))?;
+                    cursor.write_type(&lod.edge_geometry, endianness)?;
+                }
             }
         }
 
+        // Include the padding at the end of the index buffers
+        let end = (0..self.lods.len())
+            .map(|l| self.file_header.index_offsets[l] + self.file_header.index_buffer_size[l])
+            .max()
+            .unwrap_or_default() as usize;
+        if buffer.len() < end {
+            buffer.resize(end, 0);
+        }
+
         Ok(buffer)
     }
 }
@@ -1405,33 +1318,66 @@ impl ModelFileHeader {
     }
 }
 
-// TODO: From Xande, need to be cleaned up :)
+// Originally from Xande
 impl ModelData {
+    /// Returns the offset and stride of `stream` in mesh `mesh`.
+    ///
+    /// Meshes only have room for the offsets and strides of 3 streams. Any after that directly follow the previous stream, and their stride comes from the vertex declaration.
+    fn vertex_stream(&self, mesh: usize, stream: usize) -> (u32, u32) {
+        let Some(data) = self.meshes.get(mesh) else {
+            return (0, 0);
+        };
+        if stream < MAX_VERTEX_STREAMS {
+            return (
+                data.vertex_buffer_offsets[stream],
+                data.vertex_buffer_strides[stream] as u32,
+            );
+        }
+
+        let declaration = self.header.vertex_declarations.get(mesh);
+        let mut offset = data.vertex_buffer_offsets[MAX_VERTEX_STREAMS - 1];
+        let mut stride = data.vertex_buffer_strides[MAX_VERTEX_STREAMS - 1] as u32;
+        for stream in MAX_VERTEX_STREAMS..=stream {
+            offset += data.vertex_count as u32 * stride;
+            stride = declaration.map_or(0, |x| x.stride(stream as u8) as u32);
+        }
+        (offset, stride)
+    }
+
     pub fn calculate_runtime_size(&self) -> u32 {
-        2   //StringCount
-        + 2 // Unknown
-        + 4 //StringSize
-        + self.header.string_size
-        + 56 //ModelHeader
-        + (self.element_ids.len() as u32 * 32)
-        + (3 * 60) // 3 Lods
-        //+ ( /*file.ModelHeader.ExtraLodEnabled ? 40*/ 0 )
-        + self.meshes.len() as u32 * 36
-        + self.attribute_name_offsets.len() as u32 * size_of::<u32>() as u32
-        + self.header.terrain_shadow_mesh_count as u32 * 20
-        + self.header.submesh_count as u32 * 16
-        + self.header.terrain_shadow_submesh_count as u32 * 10
-        + self.material_name_offsets.len() as u32 * size_of::<u32>() as u32
-        + self.bone_name_offsets.len() as u32 * size_of::<u32>() as u32
-        + self.bone_tables.len() as u32 * 132
-        + self.header.shape_count as u32 * 16
-        + self.header.shape_mesh_count as u32 * 12
-        + self.header.shape_value_count as u32 * 4
-        + 4 // SubmeshBoneMapSize
-        + self.submesh_bone_map.len() as u32 * 2
-        + self.padding_amount as u32 + 1          // PaddingAmount and Padding
-        + (4 * 32) // 4 BoundingBoxes
-        + (self.header.bone_count as u32 * 32)
+        let mut size = 2 // StringCount
+            + 2 // Unknown
+            + 4 // StringSize
+            + self.header.strings.len() as u32
+            + 56 // ModelHeader
+            + self.element_ids.len() as u32 * 32
+            + self.lods.len() as u32 * 60
+            + self.extra_lods.len() as u32 * 40
+            + self.meshes.len() as u32 * 36
+            + self.attribute_name_offsets.len() as u32 * size_of::<u32>() as u32
+            + self.terrain_shadow_meshes.len() as u32 * 20
+            + self.submeshes.len() as u32 * 16
+            + self.terrain_shadow_submeshes.len() as u32 * 12
+            + self.material_name_offsets.len() as u32 * size_of::<u32>() as u32
+            + self.bone_name_offsets.len() as u32 * size_of::<u32>() as u32
+            + self.bone_tables.len() as u32 * 132;
+
+        // Each of the newer bone tables are padded to 4 bytes
+        size += self.bone_tables_v2.offset_counts.len() as u32 * 4;
+        for table in &self.bone_tables_v2.tables {
+            size += table.bone_indices.len() as u32 * size_of::<u16>() as u32;
+            size = size.next_multiple_of(4);
+        }
+
+        size + self.shapes.len() as u32 * 16
+            + self.shape_meshes.len() as u32 * 12
+            + self.shape_values.len() as u32 * 4
+            + 4 // SubmeshBoneMapSize
+            + self.submesh_bone_map.len() as u32 * 2
+            + self.unknown_72_padding.len() as u32
+            + 1 + self.unknown_padding.len() as u32 // PaddingAmount and Padding
+            + 4 * 32 // 4 BoundingBoxes
+            + self.bone_bounding_boxes.len() as u32 * 32
     }
 }
 
@@ -1441,7 +1387,7 @@ mod tests {
     use std::mem::size_of;
     use std::path::PathBuf;
 
-    use vertex_declarations::VERTEX_ELEMENT_SIZE;
+    use vertex_declarations::{VERTEX_ELEMENT_SIZE, VertexElement, VertexType, VertexUsage};
 
     use crate::pass_random_invalid;
 
@@ -1515,6 +1461,25 @@ mod tests {
         assert_eq!(mdl.model_data, old_mdl.model_data);
     }
 
+    #[test]
+    fn test_vertex_strides() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/tests");
+        d.push("c0201e0038_top_zeroed.mdl");
+
+        let mdl = MDL::from_existing(Platform::Win32, &read(d).unwrap()).unwrap();
+
+        for (declaration, mesh) in mdl
+            .model_data
+            .header
+            .vertex_declarations
+            .iter()
+            .zip(&mdl.model_data.meshes)
+        {
+            assert_eq!(declaration.strides(), mesh.vertex_buffer_strides);
+        }
+    }
+
     #[test]
     fn test_update_vertices() {
         let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -1573,6 +1538,129 @@ mod tests {
         assert_eq!(mdl.model_data.header.radius, 1.5340779);
     }
 
+    #[test]
+    fn test_extra_geometry_roundtrip() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/tests");
+        d.push("c0201e0038_top_zeroed.mdl");
+
+        let mut mdl = MDL::from_existing(Platform::Win32, &read(d).unwrap()).unwrap();
+
+        // Add a terrain shadow mesh, an element id and some edge geometry like a bg model could have
+        mdl.model_data
+            .terrain_shadow_meshes
+            .push(TerrainShadowMesh {
+                index_count: 0,
+                start_index: 0,
+                vertex_buffer_offset: 0,
+                vertex_count: 0,
+                submesh_index: 0,
+                submesh_count: 1,
+                vertex_buffer_stride: 8,
+                padding: 0,
+            });
+        mdl.model_data
+            .terrain_shadow_submeshes
+            .push(TerrainShadowSubmesh {
+                index_offset: 0,
+                index_count: 3,
+                unknown1: 0,
+                unknown2: 0,
+            });
+        mdl.model_data.lods[0].terrain_shadow_mesh_count = 1;
+        mdl.lods[0].terrain_shadow_parts.push(TerrainShadowPart {
+            mesh_index: 0,
+            vertex_data: (0..24).collect(),
+            indices: vec![0, 1, 2],
+        });
+        mdl.model_data.element_ids.push(ElementId {
+            element_id: 1,
+            parent_bone_name: mdl.model_data.bone_name_offsets[0],
+            translate: [1.0, 2.0, 3.0],
+            rotate: [0.0; 3],
+        });
+        mdl.lods[0].edge_geometry = vec![1, 2, 3, 4];
+        mdl.finalize();
+
+        let written = mdl.write_to_buffer(Platform::Win32).unwrap();
+        let reread = MDL::from_existing(Platform::Win32, &written).unwrap();
+        assert_eq!(reread.model_data, mdl.model_data);
+
+        let part = &reread.lods[0].terrain_shadow_parts[0];
+        assert_eq!(part.vertex_data, (0..24).collect::<Vec<u8>>());
+        assert_eq!(part.indices, vec![0, 1, 2]);
+        assert_eq!(reread.lods[0].edge_geometry, vec![1, 2, 3, 4]);
+        assert_eq!(
+            reread.element_id_bone_name(&reread.model_data.element_ids[0]),
+            reread.affected_bone_names[0]
+        );
+
+        // Writing it again shouldn't change anything
+        assert_eq!(reread.write_to_buffer(Platform::Win32).unwrap(), written);
+
+        // A terrain shadow part without a mesh is reported instead of panicking
+        mdl.lods[0].terrain_shadow_parts[0].mesh_index = 1;
+        assert!(
+            mdl.validate()
+                .contains(&ModelIssue::TerrainShadowMeshOutOfRange {
+                    lod: 0,
+                    part: 0,
+                    mesh_index: 1,
+                    mesh_count: 1,
+                })
+        );
+        mdl.update_headers();
+        assert!(mdl.write_to_buffer(Platform::Win32).is_err());
+    }
+
+    #[test]
+    fn test_extra_vertex_stream() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/tests");
+        d.push("c0201e0038_top_zeroed.mdl");
+
+        let mut mdl = MDL::from_existing(Platform::Win32, &read(d).unwrap()).unwrap();
+
+        // Put a second color into a fourth stream, which the mesh has no offset or stride for
+        let mesh_index = mdl.lods[0].parts[0].mesh_index as usize;
+        mdl.model_data.header.vertex_declarations[mesh_index]
+            .elements
+            .push(VertexElement {
+                stream: 3,
+                offset: 0,
+                vertex_type: VertexType::ByteFloat4,
+                vertex_usage: VertexUsage::Color,
+                usage_index: 1,
+            });
+        mdl.model_data.meshes[mesh_index].vertex_stream_count = 4;
+        for vertex in &mut mdl.lods[0].parts[0].vertices {
+            vertex.color1 = [1.0, 0.0, 1.0, 0.0];
+        }
+        let old_size = mdl.model_data.lods[0].vertex_buffer_size;
+        mdl.update_headers();
+        let vertex_count = mdl.lods[0].parts[0].vertices.len() as u32;
+        assert_eq!(
+            mdl.model_data.lods[0].vertex_buffer_size,
+            old_size + vertex_count * 4
+        );
+
+        let written = mdl.write_to_buffer(Platform::Win32).unwrap();
+        let reread = MDL::from_existing(Platform::Win32, &written).unwrap();
+        assert_eq!(reread.model_data, mdl.model_data);
+
+        let part = &reread.lods[0].parts[0];
+        assert_eq!(part.vertex_streams.len(), 4);
+        assert_eq!(part.vertex_stream_strides[3], 4);
+        assert_eq!(part.vertices, mdl.lods[0].parts[0].vertices);
+        // The other parts are still where they were
+        for (part, old_part) in reread.lods[0].parts.iter().zip(&mdl.lods[0].parts).skip(1) {
+            assert_eq!(part.vertices, old_part.vertices);
+        }
+
+        // Writing it again shouldn't change anything
+        assert_eq!(reread.write_to_buffer(Platform::Win32).unwrap(), written);
+    }
+
     #[test]
     fn test_equipment_path() {
         assert_eq!(
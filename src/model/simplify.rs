@@ -0,0 +1,1224 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::{
+    ByteBuffer, Error, ReadableFile, WritableFile,
+    common::Platform,
+    model::{
+        Lod, MDL, Mesh, Part, PartType, ShapeMesh, ShapeValue, SubMesh, Submesh, Vertex,
+        gltf_import::MESH_INDEX_ALIGNMENT,
+    },
+};
+
+/// How many LODs a model can have.
+const LOD_COUNT: usize = 3;
+
+/// The start index of a shape mesh in its LOD, and its values.
+type LodShapeMesh = (u32, Vec<ShapeValue>);
+
+/// Options for [MDL::generate_lods].
+#[derive(Debug, Clone)]
+pub struct LodOptions {
+    /// How much of the first LOD's triangles to keep in the second and third LOD, like `0.5` for half.
+    pub ratios: [f32; 2],
+    /// The distance at which the game switches from the first to the second LOD, and from the second to the third.
+    pub ranges: [f32; 2],
+}
+
+impl Default for LodOptions {
+    fn default() -> Self {
+        // Close to what retail gear uses
+        Self {
+            ratios: [0.6, 0.3],
+            ranges: [40.0, 125.0],
+        }
+    }
+}
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::InvalidData {
+        reason: reason.into(),
+    }
+}
+
+impl MDL {
+    /// Replaces the second and third LOD with simplified versions of the first one, and fills in their LOD ranges.
+    ///
+    /// Simplification collapses edges until enough triangles are removed, keeping the original vertices so UVs, skin weights and shapes stay intact. Vertices on UV seams only move along the seam, and vertices on open borders (like the neck of a top) never move, so gear still lines up with the body. Because of this, a LOD may keep more triangles than asked for.
+    ///
+    /// The first LOD is left as it is. Each of its regular meshes is copied into the other LODs with the same vertex layout, material, bone table, submesh attributes and shapes, only with fewer triangles. Returns an error if the second or third LOD has anything besides regular meshes, like water.
+    pub fn generate_lods(&mut self, options: &LodOptions) -> crate::Result<()> {
+        if self.lods.is_empty() {
+            return Err(invalid("model doesn't have any LODs to simplify"));
+        }
+
+        let mut mdl = self.clone();
+        mdl.remove_later_lods()?;
+
+        let first_parts: Vec<Part> = mdl.lods[0]
+            .parts
+            .iter()
+            .filter(|x| matches!(x.part_type, PartType::Normal))
+            .cloned()
+            .collect();
+
+        // Shape meshes of each LOD and shape, as their start index and values
+        let mut shape_meshes = vec![vec![Vec::new(); mdl.model_data.shapes.len()]; LOD_COUNT];
+        for (i, ratio) in options.ratios.iter().enumerate() {
+            let lod = i + 1;
+            let mesh_index = mdl.model_data.meshes.len();
+
+            let mut parts = Vec::with_capacity(first_parts.len());
+            let mut start_index = 0;
+            for part in &first_parts {
+                let (part, values) = mdl.push_simplified_mesh(part, *ratio, start_index)?;
+                for (shape, values) in values.into_iter().enumerate() {
+                    if !values.is_empty() {
+                        shape_meshes[lod][shape].push((start_index, values));
+                    }
+                }
+
+                // Index data of each mesh is aligned
+                start_index = (start_index + part.indices.len() as u32)
+                    .next_multiple_of(MESH_INDEX_ALIGNMENT);
+                parts.push(part);
+            }
+
+            let mesh_lod = &mut mdl.model_data.lods[lod];
+            let end = mdl.model_data.meshes.len() as u16;
+            mesh_lod.mesh_index = mesh_index as u16;
+            mesh_lod.mesh_count = end - mesh_index as u16;
+            mesh_lod.water_mesh_index = end;
+            mesh_lod.shadow_mesh_index = end;
+            mesh_lod.vertical_fog_mesh_index = end;
+
+            mdl.lods[lod].parts = parts;
+        }
+
+        mdl.push_shape_meshes(shape_meshes)?;
+
+        mdl.file_header.lod_count = LOD_COUNT as u8;
+        mdl.model_data.header.lod_count = LOD_COUNT as u8;
+        mdl.update_counts();
+        mdl.update_headers();
+
+        // Read it back, so everything derived from the file (like shapes) is filled in
+        let buffer = mdl.write_to_buffer(Platform::Win32)?;
+        *self = MDL::from_existing(Platform::Win32, &buffer)?;
+
+        // The last LOD is used at any distance
+        for (i, range) in options.ranges.iter().enumerate() {
+            self.model_data.lods[i].model_lod_range = *range;
+            self.lods[i].model_lod_range = *range;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the regular meshes of every LOD but the first, and any shape meshes using them.
+    fn remove_later_lods(&mut self) -> crate::Result<()> {
+        let lod_count = (self.file_header.lod_count as usize).min(LOD_COUNT);
+        for (lod, extra_lod) in
+            (1..lod_count).map(|x| (&self.model_data.lods[x], self.model_data.extra_lods.get(x)))
+        {
+            let has_extra_meshes = extra_lod.is_some_and(|x| {
+                x.light_shaft_mesh_count > 0
+                    || x.glass_mesh_count > 0
+                    || x.material_change_mesh_count > 0
+                    || x.crest_change_mesh_count > 0
+            });
+            if lod.water_mesh_count > 0
+                || lod.shadow_mesh_count > 0
+                || lod.vertical_fog_mesh_count > 0
+                || has_extra_meshes
+            {
+                return Err(invalid(
+                    "LODs can only be generated when the second and third LOD only have regular meshes",
+                ));
+            }
+        }
+
+        let data = &mut self.model_data;
+        let mut removed = vec![false; data.meshes.len()];
+        for lod in data.lods.iter().take(lod_count).skip(1) {
+            for j in lod.mesh_index..lod.mesh_index.saturating_add(lod.mesh_count) {
+                if let Some(removed) = removed.get_mut(j as usize) {
+                    *removed = true;
+                }
+            }
+        }
+
+        // Submeshes go along with their mesh
+        let mut removed_submeshes = vec![false; data.submeshes.len()];
+        for (mesh, removed) in data.meshes.iter().zip(&removed) {
+            if *removed {
+                for k in mesh.submesh_index..mesh.submesh_index.saturating_add(mesh.submesh_count) {
+                    if let Some(removed) = removed_submeshes.get_mut(k as usize) {
+                        *removed = true;
+                    }
+                }
+            }
+        }
+
+        for (mesh, removed) in data.meshes.iter_mut().zip(&removed) {
+            if !*removed {
+                shift_range(
+                    &removed_submeshes,
+                    &mut mesh.submesh_index,
+                    mesh.submesh_count,
+                )?;
+            }
+        }
+
+        let first_lod = &mut data.lods[0];
+        for (start, count) in [
+            (&mut first_lod.mesh_index, first_lod.mesh_count),
+            (&mut first_lod.water_mesh_index, first_lod.water_mesh_count),
+            (
+                &mut first_lod.shadow_mesh_index,
+                first_lod.shadow_mesh_count,
+            ),
+            (
+                &mut first_lod.vertical_fog_mesh_index,
+                first_lod.vertical_fog_mesh_count,
+            ),
+        ] {
+            shift_range(&removed, start, count)?;
+        }
+        if let Some(extra_lod) = data.extra_lods.first_mut() {
+            for (start, count) in [
+                (
+                    &mut extra_lod.light_shaft_mesh_index,
+                    extra_lod.light_shaft_mesh_count,
+                ),
+                (&mut extra_lod.glass_mesh_index, extra_lod.glass_mesh_count),
+                (
+                    &mut extra_lod.material_change_mesh_index,
+                    extra_lod.material_change_mesh_count,
+                ),
+                (
+                    &mut extra_lod.crest_change_mesh_index,
+                    extra_lod.crest_change_mesh_count,
+                ),
+            ] {
+                shift_range(&removed, start, count)?;
+            }
+        }
+        for part in &mut self.lods[0].parts {
+            part.mesh_index = shifted(&removed, part.mesh_index);
+        }
+
+        let mut kept = removed.iter().map(|x| !x);
+        data.meshes.retain(|_| kept.next().unwrap_or(true));
+        let mut kept = removed.iter().map(|x| !x);
+        data.header
+            .vertex_declarations
+            .retain(|_| kept.next().unwrap_or(true));
+        let mut kept = removed_submeshes.iter().map(|x| !x);
+        data.submeshes.retain(|_| kept.next().unwrap_or(true));
+
+        // Shape meshes of the first LOD are added back later, along with the new ones
+        for shape in &mut data.shapes {
+            for lod in 1..LOD_COUNT {
+                shape.shape_mesh_start_index[lod] = 0;
+                shape.shape_mesh_count[lod] = 0;
+            }
+        }
+
+        // Terrain shadows are kept, since they're separate from the regular meshes
+        for lod in self.lods.iter_mut().skip(1) {
+            lod.parts.clear();
+            lod.edge_geometry.clear();
+        }
+        for lod in self.model_data.lods.iter_mut().skip(lod_count.max(1)) {
+            lod.terrain_shadow_mesh_index = 0;
+            lod.terrain_shadow_mesh_count = 0;
+        }
+        while self.lods.len() < LOD_COUNT {
+            self.lods.push(Lod {
+                parts: Vec::new(),
+                terrain_shadow_parts: Vec::new(),
+                edge_geometry: ByteBuffer::new(),
+                model_lod_range: 0.0,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Adds a copy of `part` from the first LOD with its triangles simplified down to about `ratio`, with its index data starting at `start_index` in its LOD.
+    ///
+    /// Returns the new part, along with its values for each shape.
+    fn push_simplified_mesh(
+        &mut self,
+        part: &Part,
+        ratio: f32,
+        start_index: u32,
+    ) -> crate::Result<(Part, Vec<Vec<ShapeValue>>)> {
+        let data = &self.model_data;
+        let mesh = data.meshes[part.mesh_index as usize].clone();
+
+        // Vertices replaced by shapes come after the ones used by the indices
+        let vertex_count = part
+            .indices
+            .iter()
+            .max()
+            .map(|x| *x as usize + 1)
+            .unwrap_or_default()
+            .min(part.vertices.len());
+
+        let replacements: Vec<HashMap<usize, usize>> = self
+            .shape_replacements(0, part)
+            .into_iter()
+            .map(|x| {
+                x.into_iter()
+                    .filter(|(base, replacing)| {
+                        *base < vertex_count && *replacing < part.vertices.len()
+                    })
+                    .collect()
+            })
+            .collect();
+        let offsets: Vec<Vec<[f32; 3]>> = replacements
+            .iter()
+            .map(|replacements| {
+                let mut offsets = vec![[0.0; 3]; vertex_count];
+                for (base, replacing) in replacements {
+                    let [from, to] = [*base, *replacing].map(|x| part.vertices[x].position);
+                    offsets[*base] = std::array::from_fn(|i| to[i] - from[i]);
+                }
+                offsets
+            })
+            .collect();
+
+        // Submesh offsets are relative to the whole LOD
+        let submeshes: Vec<Submesh> = data
+            .submeshes
+            .iter()
+            .skip(mesh.submesh_index as usize)
+            .take(mesh.submesh_count as usize)
+            .cloned()
+            .collect();
+        let groups: Vec<Vec<u32>> = if submeshes.is_empty() {
+            vec![part.indices.iter().map(|x| *x as u32).collect()]
+        } else {
+            submeshes
+                .iter()
+                .map(|submesh| {
+                    let offset =
+                        (submesh.index_offset as usize).saturating_sub(mesh.start_index as usize);
+                    part.indices
+                        .iter()
+                        .skip(offset)
+                        .take(submesh.index_count as usize)
+                        .map(|x| *x as u32)
+                        .collect()
+                })
+                .collect()
+        };
+
+        let groups = simplify(&part.vertices[..vertex_count], &offsets, &groups, ratio);
+
+        // Only keep the vertices still in use, followed by the ones replacing them in shapes
+        let mut remap = vec![None; part.vertices.len()];
+        let mut vertices = Vec::new();
+        let mut keep = |vertex: usize, vertices: &mut Vec<Vertex>| -> u16 {
+            *remap[vertex].get_or_insert_with(|| {
+                vertices.push(part.vertices[vertex]);
+                (vertices.len() - 1) as u16
+            })
+        };
+
+        let indices: Vec<u16> = groups
+            .iter()
+            .flatten()
+            .map(|x| keep(*x as usize, &mut vertices))
+            .collect();
+
+        let mut shape_values = Vec::with_capacity(replacements.len());
+        for replacements in &replacements {
+            let mut values = Vec::new();
+            for (position, vertex) in groups.iter().flatten().enumerate() {
+                let Some(replacing) = replacements.get(&(*vertex as usize)) else {
+                    continue;
+                };
+
+                let base_index = u16::try_from(start_index as usize + position)
+                    .map_err(|_| invalid("a LOD has too many indices for its shapes"))?;
+                values.push(ShapeValue {
+                    base_indices_index: base_index,
+                    replacing_vertex_index: keep(*replacing, &mut vertices),
+                });
+            }
+            shape_values.push(values);
+        }
+
+        let data = &mut self.model_data;
+        let mesh_index =
+            u16::try_from(data.meshes.len()).map_err(|_| invalid("model has too many meshes"))?;
+        let submesh_index = data.submeshes.len();
+
+        let mut part_submeshes = Vec::with_capacity(submeshes.len());
+        let mut offset = start_index;
+        for (submesh, indices) in submeshes.iter().zip(&groups) {
+            part_submeshes.push(SubMesh {
+                submesh_index: data.submeshes.len(),
+                index_count: indices.len() as u32,
+                index_offset: offset,
+            });
+            // Only the indices change, so it still uses the same attributes and bones
+            data.submeshes.push(Submesh {
+                index_offset: offset,
+                index_count: indices.len() as u32,
+                ..submesh.clone()
+            });
+            offset += indices.len() as u32;
+        }
+
+        data.meshes.push(Mesh {
+            vertex_count: vertices.len() as u16,
+            index_count: indices.len() as u32,
+            submesh_index: submesh_index as u16,
+            start_index,
+            vertex_buffer_offsets: [0; 3],
+            ..mesh
+        });
+        let declaration = data.header.vertex_declarations[part.mesh_index as usize].clone();
+        data.header.vertex_declarations.push(declaration);
+
+        let part = Part {
+            mesh_index,
+            vertices,
+            vertex_streams: Vec::new(),
+            vertex_stream_strides: Vec::new(),
+            indices,
+            material_index: part.material_index,
+            submeshes: part_submeshes,
+            shapes: Vec::new(),
+            part_type: PartType::Normal,
+        };
+
+        Ok((part, shape_values))
+    }
+
+    /// Lays out the shape meshes again, keeping the ones of the first LOD and adding `lods`, the new shape meshes of each LOD and shape.
+    fn push_shape_meshes(&mut self, mut lods: Vec<Vec<Vec<LodShapeMesh>>>) -> crate::Result<()> {
+        let data = &mut self.model_data;
+        let old_meshes = std::mem::take(&mut data.shape_meshes);
+        let old_values = std::mem::take(&mut data.shape_values);
+
+        for (s, shape) in data.shapes.iter().enumerate() {
+            lods[0][s] = old_meshes
+                .iter()
+                .skip(shape.shape_mesh_start_index[0] as usize)
+                .take(shape.shape_mesh_count[0] as usize)
+                .map(|x| {
+                    let values = old_values
+                        .iter()
+                        .skip(x.shape_value_offset as usize)
+                        .take(x.shape_value_count as usize)
+                        .cloned()
+                        .collect();
+                    (x.mesh_index_offset, values)
+                })
+                .collect();
+        }
+
+        // Shape meshes of each shape and LOD have to be next to each other
+        for (s, shape) in data.shapes.iter_mut().enumerate() {
+            for (lod, meshes) in lods.iter_mut().enumerate() {
+                let meshes = std::mem::take(&mut meshes[s]);
+                shape.shape_mesh_start_index[lod] = if meshes.is_empty() {
+                    0
+                } else {
+                    data.shape_meshes.len() as u16
+                };
+                shape.shape_mesh_count[lod] = meshes.len() as u16;
+
+                for (start_index, values) in meshes {
+                    data.shape_meshes.push(ShapeMesh {
+                        mesh_index_offset: start_index,
+                        shape_value_count: values.len() as u32,
+                        shape_value_offset: data.shape_values.len() as u32,
+                    });
+                    data.shape_values.extend(values);
+                }
+            }
+        }
+
+        if data.shape_meshes.len() > u16::MAX as usize
+            || data.shape_values.len() > u16::MAX as usize
+        {
+            return Err(invalid("model has too many shape values"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns where `index` ends up once the meshes in `removed` are gone.
+fn shifted(removed: &[bool], index: u16) -> u16 {
+    index - removed.iter().take(index as usize).filter(|x| **x).count() as u16
+}
+
+/// Moves the range of `count` items from `start` to where it ends up once the items in `removed` are gone, or returns an error if it includes any of them.
+fn shift_range(removed: &[bool], start: &mut u16, count: u16) -> crate::Result<()> {
+    let range = *start as usize..*start as usize + count as usize;
+    if removed
+        .get(range.start.min(removed.len())..range.end.min(removed.len()))
+        .is_some_and(|x| x.contains(&true))
+    {
+        return Err(invalid(
+            "the first LOD shares meshes with the others, so they can't be replaced",
+        ));
+    }
+
+    *start = shifted(removed, *start);
+    Ok(())
+}
+
+/// How a vertex may be collapsed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum VertexKind {
+    /// Can be collapsed into any neighbor.
+    Manifold,
+    /// Split by a UV seam, so can only be collapsed along it together with the other side.
+    Seam,
+    /// On a border, or where seams meet, so never moves.
+    Locked,
+}
+
+/// A quadric measuring the squared distance to a set of planes.
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric {
+    a: [f64; 10],
+}
+
+impl Quadric {
+    fn from_plane(normal: [f64; 3], d: f64, weight: f64) -> Self {
+        let [x, y, z] = normal;
+        Self {
+            a: [
+                x * x,
+                x * y,
+                x * z,
+                x * d,
+                y * y,
+                y * z,
+                y * d,
+                z * z,
+                z * d,
+                d * d,
+            ]
+            .map(|v| v * weight),
+        }
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (a, b) in self.a.iter_mut().zip(other.a) {
+            *a += b;
+        }
+    }
+
+    fn error(&self, p: [f32; 3]) -> f64 {
+        let [x, y, z] = p.map(|v| v as f64);
+        let a = &self.a;
+        (a[0] * x * x
+            + 2.0 * a[1] * x * y
+            + 2.0 * a[2] * x * z
+            + 2.0 * a[3] * x
+            + a[4] * y * y
+            + 2.0 * a[5] * y * z
+            + 2.0 * a[6] * y
+            + a[7] * z * z
+            + 2.0 * a[8] * z
+            + a[9])
+            .max(0.0)
+    }
+}
+
+/// A possible collapse of `from` into `to`, and of `partner_from` into `partner_to` on the other side of a seam.
+#[derive(Debug, Clone, Copy)]
+struct Collapse {
+    cost: f64,
+    from: u32,
+    to: u32,
+    partner: Option<(u32, u32)>,
+    version: u32,
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed, so the heap gives the cheapest collapse first
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+struct Simplifier<'a> {
+    vertices: &'a [Vertex],
+    /// Position offsets of each vertex, for each shape.
+    shapes: &'a [Vec<[f32; 3]>],
+    triangles: Vec<[u32; 3]>,
+    alive: Vec<bool>,
+    /// Triangles around each vertex, some of which may no longer contain it.
+    vertex_triangles: Vec<Vec<usize>>,
+    kinds: Vec<VertexKind>,
+    /// The other vertex at the same position, for seam vertices.
+    partners: Vec<Option<u32>>,
+    /// Identifies vertices at the same position.
+    welded: Vec<u32>,
+    quadrics: Vec<Quadric>,
+    versions: Vec<u32>,
+    removed: Vec<bool>,
+}
+
+impl<'a> Simplifier<'a> {
+    fn new(vertices: &'a [Vertex], shapes: &'a [Vec<[f32; 3]>], triangles: Vec<[u32; 3]>) -> Self {
+        let vertex_count = vertices.len();
+
+        let mut positions = HashMap::new();
+        let welded: Vec<u32> = vertices
+            .iter()
+            .enumerate()
+            .map(|(i, vertex)| {
+                *positions
+                    .entry(vertex.position.map(f32::to_bits))
+                    .or_insert(i as u32)
+            })
+            .collect();
+
+        let mut groups: HashMap<u32, Vec<u32>> = HashMap::new();
+        for (i, welded) in welded.iter().enumerate() {
+            groups.entry(*welded).or_default().push(i as u32);
+        }
+
+        // Edges that aren't shared by exactly two triangles are borders
+        let mut edges: HashMap<(u32, u32), u32> = HashMap::new();
+        for triangle in &triangles {
+            for i in 0..3 {
+                let a = welded[triangle[i] as usize];
+                let b = welded[triangle[(i + 1) % 3] as usize];
+                *edges.entry((a.min(b), a.max(b))).or_default() += 1;
+            }
+        }
+        let mut locked_positions = vec![false; vertex_count];
+        for ((a, b), count) in edges {
+            if count != 2 {
+                locked_positions[a as usize] = true;
+                locked_positions[b as usize] = true;
+            }
+        }
+
+        let mut kinds = vec![VertexKind::Manifold; vertex_count];
+        let mut partners = vec![None; vertex_count];
+        for (i, kind) in kinds.iter_mut().enumerate() {
+            let group = &groups[&welded[i]];
+            *kind = if locked_positions[welded[i] as usize] || group.len() > 2 {
+                VertexKind::Locked
+            } else if group.len() == 2 {
+                partners[i] = group.iter().copied().find(|x| *x != i as u32);
+                VertexKind::Seam
+            } else {
+                VertexKind::Manifold
+            };
+        }
+
+        let mut vertex_triangles = vec![Vec::new(); vertex_count];
+        let mut quadrics = vec![Quadric::default(); vertex_count];
+        for (t, triangle) in triangles.iter().enumerate() {
+            let [a, b, c] = triangle.map(|x| vertices[x as usize].position.map(|v| v as f64));
+            let normal = cross64(sub64(b, a), sub64(c, a));
+            let length = dot64(normal, normal).sqrt();
+            let quadric = if length > 0.0 {
+                let normal = normal.map(|v| v / length);
+                // Weighted by area, so big triangles matter more
+                Quadric::from_plane(normal, -dot64(normal, a), length * 0.5)
+            } else {
+                Quadric::default()
+            };
+
+            for vertex in triangle {
+                vertex_triangles[*vertex as usize].push(t);
+                quadrics[*vertex as usize].add(&quadric);
+            }
+        }
+
+        Self {
+            vertices,
+            shapes,
+            alive: vec![true; triangles.len()],
+            triangles,
+            vertex_triangles,
+            kinds,
+            partners,
+            welded,
+            quadrics,
+            versions: vec![0; vertex_count],
+            removed: vec![false; vertex_count],
+        }
+    }
+
+    /// Returns the triangles that still contain `vertex`.
+    fn triangles_of(&self, vertex: u32) -> impl Iterator<Item = usize> + '_ {
+        self.vertex_triangles[vertex as usize]
+            .iter()
+            .copied()
+            .filter(move |t| self.alive[*t] && self.triangles[*t].contains(&vertex))
+    }
+
+    fn neighbors(&self, vertex: u32) -> Vec<u32> {
+        let mut neighbors = Vec::new();
+        for t in self.triangles_of(vertex) {
+            for other in self.triangles[t] {
+                if other != vertex && !neighbors.contains(&other) {
+                    neighbors.push(other);
+                }
+            }
+        }
+        neighbors
+    }
+
+    /// Whether moving `from` onto `to` keeps every remaining triangle facing the same way.
+    fn keeps_orientation(&self, from: u32, to: u32) -> bool {
+        let target = self.vertices[to as usize].position.map(|v| v as f64);
+        for t in self.triangles_of(from) {
+            let triangle = self.triangles[t];
+            if triangle.contains(&to) {
+                continue;
+            }
+
+            let positions = triangle.map(|x| self.vertices[x as usize].position.map(|v| v as f64));
+            let moved = triangle.map(|x| {
+                if x == from {
+                    target
+                } else {
+                    self.vertices[x as usize].position.map(|v| v as f64)
+                }
+            });
+
+            let before = cross64(
+                sub64(positions[1], positions[0]),
+                sub64(positions[2], positions[0]),
+            );
+            let after = cross64(sub64(moved[1], moved[0]), sub64(moved[2], moved[0]));
+            let lengths = dot64(before, before).sqrt() * dot64(after, after).sqrt();
+            if lengths <= 0.0 || dot64(before, after) < lengths * 0.25 {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// How much collapsing `from` into `to` changes the surface, its skinning and its shapes.
+    fn cost(&self, from: u32, to: u32) -> f64 {
+        let from_vertex = &self.vertices[from as usize];
+        let to_vertex = &self.vertices[to as usize];
+
+        let mut quadric = self.quadrics[from as usize];
+        quadric.add(&self.quadrics[to as usize]);
+        let mut cost = quadric.error(to_vertex.position);
+
+        let edge = sub64(
+            to_vertex.position.map(|v| v as f64),
+            from_vertex.position.map(|v| v as f64),
+        );
+        let length = dot64(edge, edge);
+
+        // Moving across differently weighted vertices would stretch the skinning
+        let influences = |vertex: u32| {
+            let vertex = &self.vertices[vertex as usize];
+            vertex
+                .bone_id
+                .into_iter()
+                .zip(vertex.bone_weight)
+                .filter(|(_, weight)| *weight > 0.0)
+        };
+        let weight_of = |vertex: u32, bone: u8| -> f64 {
+            influences(vertex)
+                .filter(|(id, _)| *id == bone)
+                .map(|(_, weight)| weight as f64)
+                .sum()
+        };
+        let mut skin_difference = 0.0;
+        for vertex in [from, to] {
+            for (bone, _) in influences(vertex) {
+                skin_difference += (weight_of(from, bone) - weight_of(to, bone)).abs();
+            }
+        }
+        cost += skin_difference * length;
+
+        // Likewise for shapes moving them differently
+        for offsets in self.shapes {
+            let difference = sub64(
+                offsets[from as usize].map(|v| v as f64),
+                offsets[to as usize].map(|v| v as f64),
+            );
+            cost += dot64(difference, difference);
+        }
+
+        cost
+    }
+
+    /// Finds the cheapest way to collapse `from`, if there is any.
+    fn best_collapse(&self, from: u32) -> Option<Collapse> {
+        let kind = self.kinds[from as usize];
+        if self.removed[from as usize] || kind == VertexKind::Locked {
+            return None;
+        }
+
+        let mut best: Option<Collapse> = None;
+        for to in self.neighbors(from) {
+            let mut partner = None;
+            if kind == VertexKind::Seam {
+                // The other side of the seam has to collapse along the same edge
+                let partner_from = self.partners[from as usize]?;
+                let welded_to = self.welded[to as usize];
+                let partner_to = self
+                    .neighbors(partner_from)
+                    .into_iter()
+                    .find(|x| self.welded[*x as usize] == welded_to);
+                let Some(partner_to) = partner_to else {
+                    continue;
+                };
+                if !self.keeps_orientation(partner_from, partner_to) {
+                    continue;
+                }
+                partner = Some((partner_from, partner_to));
+            }
+
+            if !self.keeps_orientation(from, to) {
+                continue;
+            }
+
+            let mut cost = self.cost(from, to);
+            if let Some((partner_from, partner_to)) = partner {
+                cost += self.cost(partner_from, partner_to);
+            }
+
+            if best.is_none_or(|x| cost < x.cost) {
+                best = Some(Collapse {
+                    cost,
+                    from,
+                    to,
+                    partner,
+                    version: self.versions[from as usize],
+                });
+            }
+        }
+
+        best
+    }
+
+    /// Moves `from` onto `to`, returning how many triangles were removed.
+    fn collapse(&mut self, from: u32, to: u32) -> usize {
+        let mut removed = 0;
+        for t in std::mem::take(&mut self.vertex_triangles[from as usize]) {
+            if !self.alive[t] || !self.triangles[t].contains(&from) {
+                continue;
+            }
+
+            if self.triangles[t].contains(&to) {
+                self.alive[t] = false;
+                removed += 1;
+            } else {
+                for vertex in &mut self.triangles[t] {
+                    if *vertex == from {
+                        *vertex = to;
+                    }
+                }
+                self.vertex_triangles[to as usize].push(t);
+            }
+        }
+
+        let quadric = self.quadrics[from as usize];
+        self.quadrics[to as usize].add(&quadric);
+        self.removed[from as usize] = true;
+
+        removed
+    }
+
+    fn run(&mut self, target: usize) {
+        let mut remaining = self.alive.iter().filter(|x| **x).count();
+
+        let mut heap = BinaryHeap::new();
+        for vertex in 0..self.vertices.len() as u32 {
+            if let Some(collapse) = self.best_collapse(vertex) {
+                heap.push(collapse);
+            }
+        }
+
+        while remaining > target {
+            let Some(collapse) = heap.pop() else {
+                break;
+            };
+            if self.removed[collapse.from as usize]
+                || collapse.version != self.versions[collapse.from as usize]
+            {
+                continue;
+            }
+
+            // Its neighborhood may have changed without it knowing, so make sure it's still the best
+            let Some(current) = self.best_collapse(collapse.from) else {
+                continue;
+            };
+            if current.cost > collapse.cost || current.to != collapse.to {
+                heap.push(current);
+                continue;
+            }
+
+            remaining -= self.collapse(current.from, current.to);
+            let mut touched = self.neighbors(current.to);
+            touched.push(current.to);
+            if let Some((partner_from, partner_to)) = current.partner {
+                remaining -= self.collapse(partner_from, partner_to);
+                touched.extend(self.neighbors(partner_to));
+                touched.push(partner_to);
+            }
+
+            for vertex in touched {
+                self.versions[vertex as usize] += 1;
+                // Seams depend on the other side too
+                let partner = self.partners[vertex as usize];
+                for vertex in std::iter::once(vertex).chain(partner) {
+                    if let Some(collapse) = self.best_collapse(vertex) {
+                        heap.push(collapse);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Simplifies the triangles of each submesh down to about `ratio` of them, returning the indices left in each.
+///
+/// `shapes` has the position offsets of each vertex for every shape, so vertices moving differently in them aren't collapsed together.
+fn simplify(
+    vertices: &[Vertex],
+    shapes: &[Vec<[f32; 3]>],
+    submeshes: &[Vec<u32>],
+    ratio: f32,
+) -> Vec<Vec<u32>> {
+    // The submesh of each triangle, so they can be split up again afterwards
+    let mut triangles = Vec::new();
+    let mut triangle_submeshes = Vec::new();
+    for (i, indices) in submeshes.iter().enumerate() {
+        for triangle in indices.chunks_exact(3) {
+            if triangle[0] != triangle[1]
+                && triangle[1] != triangle[2]
+                && triangle[0] != triangle[2]
+            {
+                triangles.push([triangle[0], triangle[1], triangle[2]]);
+                triangle_submeshes.push(i);
+            }
+        }
+    }
+
+    let target = (triangles.len() as f32 * ratio.clamp(0.0, 1.0)).ceil() as usize;
+    let mut simplifier = Simplifier::new(vertices, shapes, triangles);
+    simplifier.run(target);
+
+    let mut new_submeshes = vec![Vec::new(); submeshes.len()];
+    for (t, triangle) in simplifier.triangles.iter().enumerate() {
+        if simplifier.alive[t] {
+            new_submeshes[triangle_submeshes[t]].extend(triangle);
+        }
+    }
+    new_submeshes
+}
+
+fn sub64(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot64(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross64(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read;
+    use std::path::PathBuf;
+
+    use crate::model::gltf_import::{ImportedPart, Importer, MAX_INFLUENCES};
+
+    use super::*;
+
+    /// A flat grid of `size` by `size` quads, with a UV seam splitting the vertices at `seam`.
+    fn grid(size: u32, seam: u32) -> ImportedPart {
+        let mut vertices = Vec::new();
+        let mut index_of = HashMap::new();
+        let mut vertex = |x: u32, y: u32, right: bool| -> u32 {
+            *index_of.entry((x, y, right)).or_insert_with(|| {
+                let offset = if right { 1.0 } else { 0.0 };
+                vertices.push(Vertex {
+                    position: [x as f32, y as f32, 0.0],
+                    uv0: [x as f32 / size as f32 + offset, y as f32 / size as f32],
+                    normal: [0.0, 0.0, 1.0],
+                    ..Default::default()
+                });
+                vertices.len() as u32 - 1
+            })
+        };
+
+        let mut indices = Vec::new();
+        for y in 0..size {
+            for x in 0..size {
+                let right = x >= seam;
+                let [a, b, c, d] = [(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)]
+                    .map(|(x, y)| vertex(x, y, right));
+                indices.extend([a, b, c, b, d, c]);
+            }
+        }
+
+        // The left edge moves up in a shape
+        let shapes = vec![(
+            "shp_test".to_string(),
+            vertices
+                .iter()
+                .map(|x| {
+                    if x.position[0] == 0.0 {
+                        [0.0, 0.0, 1.0]
+                    } else {
+                        [0.0; 3]
+                    }
+                })
+                .collect(),
+        )];
+
+        ImportedPart {
+            bones: vec![[usize::MAX; MAX_INFLUENCES]; vertices.len()],
+            vertices,
+            submeshes: vec![indices],
+            shapes,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_simplify() {
+        let part = grid(8, 4);
+        let shapes: Vec<Vec<[f32; 3]>> = part.shapes.into_iter().map(|(_, x)| x).collect();
+        let simplified = simplify(&part.vertices, &shapes, &part.submeshes, 0.25);
+
+        let triangles = simplified[0].len() / 3;
+        assert!(triangles < 64);
+        assert!(triangles > 0);
+
+        let kept: Vec<&Vertex> = simplified[0]
+            .iter()
+            .map(|x| &part.vertices[*x as usize])
+            .collect();
+        // The corners are on the border, so they stay put
+        for corner in [
+            [0.0, 0.0, 0.0],
+            [8.0, 0.0, 0.0],
+            [0.0, 8.0, 0.0],
+            [8.0, 8.0, 0.0],
+        ] {
+            assert!(kept.iter().any(|x| x.position == corner));
+        }
+        // Neither side of the seam should cross it
+        for triangle in simplified[0].chunks_exact(3) {
+            let sides: Vec<bool> = triangle
+                .iter()
+                .map(|x| part.vertices[*x as usize].uv0[0] >= 1.0)
+                .collect();
+            assert!(sides.iter().all(|x| *x == sides[0]));
+            for vertex in triangle {
+                let x = part.vertices[*vertex as usize].position[0];
+                assert!(if sides[0] { x >= 4.0 } else { x <= 4.0 });
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_lods() {
+        let importer = Importer {
+            parts: vec![grid(8, 4)],
+            material_names: vec!["/mt_test.mtrl".to_string()],
+            ..Default::default()
+        };
+        let mut mdl = importer.build().unwrap();
+        assert_eq!(mdl.lods.len(), 1);
+        mdl.model_data.submeshes[0].attribute_index_mask = 1;
+        let first_indices = mdl.lods[0].parts[0].indices.clone();
+
+        mdl.generate_lods(&LodOptions::default()).unwrap();
+        assert_eq!(mdl.lods.len(), 3);
+        assert_eq!(mdl.lods[0].model_lod_range, 40.0);
+        assert_eq!(mdl.lods[1].model_lod_range, 125.0);
+        assert_eq!(mdl.lods[2].model_lod_range, 0.0);
+
+        let counts: Vec<usize> = mdl.lods.iter().map(|x| x.parts[0].indices.len()).collect();
+        assert_eq!(counts[0], 8 * 8 * 6);
+        assert!(counts[1] < counts[0]);
+        assert!(counts[2] <= counts[1]);
+        assert_eq!(mdl.lods[0].parts[0].indices, first_indices);
+        for lod in &mdl.lods {
+            assert_eq!(lod.parts[0].shapes.len(), 1);
+            assert_eq!(lod.parts[0].shapes[0].name, "shp_test");
+
+            let submesh = lod.parts[0].submeshes[0].submesh_index;
+            assert_eq!(mdl.model_data.submeshes[submesh].attribute_index_mask, 1);
+        }
+
+        assert_eq!(mdl.validate(), Vec::new());
+
+        // It should read back the same
+        let written = mdl.write_to_buffer(Platform::Win32).unwrap();
+        let reread = MDL::from_existing(Platform::Win32, &written).unwrap();
+        assert_eq!(reread.model_data, mdl.model_data);
+    }
+
+    #[test]
+    fn test_generate_lods_keeps_first_lod() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/tests");
+        // An open ended tube with a UV seam down one side, an attribute and a shape widening its top
+        d.push("tube.mdl");
+
+        let mut mdl = MDL::from_existing(Platform::Win32, &read(d).unwrap()).unwrap();
+        let old_mdl = mdl.clone();
+
+        mdl.generate_lods(&LodOptions::default()).unwrap();
+
+        // Everything about the first LOD stays the same
+        let first_meshes = |mdl: &MDL| {
+            let lod = &mdl.model_data.lods[0];
+            mdl.model_data.meshes
+                [lod.mesh_index as usize..(lod.mesh_index + lod.mesh_count) as usize]
+                .to_vec()
+        };
+        let first_submeshes = |mdl: &MDL| {
+            first_meshes(mdl)
+                .iter()
+                .flat_map(|x| {
+                    mdl.model_data.submeshes
+                        [x.submesh_index as usize..(x.submesh_index + x.submesh_count) as usize]
+                        .to_vec()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(first_meshes(&mdl), first_meshes(&old_mdl));
+        assert_eq!(first_submeshes(&mdl), first_submeshes(&old_mdl));
+        assert!(
+            first_submeshes(&mdl)
+                .iter()
+                .any(|x| x.attribute_index_mask != 0)
+        );
+        assert_eq!(
+            mdl.model_data.attribute_name_offsets,
+            old_mdl.model_data.attribute_name_offsets
+        );
+
+        assert_eq!(mdl.lods[0].parts.len(), old_mdl.lods[0].parts.len());
+        for (part, old_part) in mdl.lods[0].parts.iter().zip(&old_mdl.lods[0].parts) {
+            assert_eq!(part.indices, old_part.indices);
+            assert_eq!(part.vertices, old_part.vertices);
+            let names = |part: &Part| {
+                part.shapes
+                    .iter()
+                    .map(|x| x.name.clone())
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(names(part), names(old_part));
+            for (shape, old_shape) in part.shapes.iter().zip(&old_part.shapes) {
+                assert_eq!(shape.morphed_vertices, old_shape.morphed_vertices);
+            }
+        }
+        assert!(mdl.lods[0].parts.iter().any(|x| !x.shapes.is_empty()));
+
+        // Both ends of the tube are open borders, and the vertices replaced by the shape come after the others
+        let first_part = &mdl.lods[0].parts[0];
+        let vertex_count = *first_part.indices.iter().max().unwrap() as usize + 1;
+        let first_vertices = &first_part.vertices[..vertex_count];
+        let top = first_vertices
+            .iter()
+            .map(|x| x.position[1])
+            .fold(0.0, f32::max);
+        let borders: Vec<[f32; 3]> = first_vertices
+            .iter()
+            .map(|x| x.position)
+            .filter(|x| x[1] == 0.0 || x[1] == top)
+            .collect();
+        assert_eq!(borders.len(), 2 * 17);
+
+        // The other LODs are simplified, and keep the attributes
+        for lod in 1..LOD_COUNT {
+            let part = &mdl.lods[lod].parts[0];
+            let triangles = part.indices.len() / 3;
+            assert!(triangles > 0);
+            assert!(triangles < mdl.lods[lod - 1].parts[0].indices.len() / 3);
+
+            // The borders don't move
+            let kept: Vec<&Vertex> = part
+                .indices
+                .iter()
+                .map(|x| &part.vertices[*x as usize])
+                .collect();
+            for position in &borders {
+                assert!(kept.iter().any(|x| x.position == *position));
+            }
+            // No triangle crosses the UV seam
+            for triangle in part.indices.chunks_exact(3) {
+                let u: Vec<f32> = triangle
+                    .iter()
+                    .map(|x| part.vertices[*x as usize].uv0[0])
+                    .collect();
+                let min = u.iter().copied().fold(f32::MAX, f32::min);
+                let max = u.iter().copied().fold(f32::MIN, f32::max);
+                assert!(max - min < 0.5, "{u:?}");
+            }
+
+            assert_eq!(part.shapes.len(), 1);
+            assert_eq!(part.shapes[0].name, "shp_wide");
+
+            let mesh_lod = &mdl.model_data.lods[lod];
+            assert_eq!(mesh_lod.mesh_count, old_mdl.model_data.lods[0].mesh_count);
+            for (part, first_part) in mdl.lods[lod].parts.iter().zip(&mdl.lods[0].parts) {
+                let mesh = &mdl.model_data.meshes[part.mesh_index as usize];
+                let first_mesh = &mdl.model_data.meshes[first_part.mesh_index as usize];
+                assert_eq!(mesh.material_index, first_mesh.material_index);
+                assert_eq!(mesh.bone_table_index, first_mesh.bone_table_index);
+                assert_eq!(
+                    mdl.model_data.header.vertex_declarations[part.mesh_index as usize],
+                    mdl.model_data.header.vertex_declarations[first_part.mesh_index as usize]
+                );
+
+                let masks = |part: &Part| {
+                    part.submeshes
+                        .iter()
+                        .map(|x| mdl.model_data.submeshes[x.submesh_index].attribute_index_mask)
+                        .collect::<Vec<_>>()
+                };
+                assert_eq!(masks(part), masks(first_part));
+            }
+        }
+
+        assert_eq!(mdl.validate(), Vec::new());
+    }
+}
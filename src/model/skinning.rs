@@ -0,0 +1,399 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{
+    model::{
+        MDL, Part,
+        math::{IDENTITY, Matrix, compose, inverse_affine, multiply, normalize},
+    },
+    pap::Pap,
+    skeleton::Skeleton,
+};
+
+/// Local transform of a bone, relative to its parent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoneTransform {
+    pub position: [f32; 3],
+    /// Rotation quaternion in XYZW order.
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+}
+
+impl Default for BoneTransform {
+    fn default() -> Self {
+        Self {
+            position: [0.0; 3],
+            rotation: [0.0, 0.0, 0.0, 1.0],
+            scale: [1.0; 3],
+        }
+    }
+}
+
+impl BoneTransform {
+    fn matrix(&self) -> Matrix {
+        compose(self.position, self.rotation, self.scale)
+    }
+}
+
+#[cfg(feature = "glam")]
+impl From<BoneTransform> for glam::Affine3A {
+    fn from(t: BoneTransform) -> Self {
+        glam::Affine3A::from_scale_rotation_translation(
+            glam::Vec3::from(t.scale),
+            glam::Quat::from_array(t.rotation),
+            glam::Vec3::from(t.position),
+        )
+    }
+}
+
+#[cfg(feature = "glam")]
+impl From<glam::Affine3A> for BoneTransform {
+    fn from(a: glam::Affine3A) -> Self {
+        let (scale, rotation, translation) = a.to_scale_rotation_translation();
+
+        BoneTransform {
+            position: translation.into(),
+            rotation: rotation.to_array(),
+            scale: scale.into(),
+        }
+    }
+}
+
+/// A pose of a [Skeleton], to deform a model with [MDL::skin].
+#[derive(Debug, Clone)]
+pub struct Pose {
+    /// Local transform of each bone, in the same order as the skeleton's bones.
+    pub transforms: Vec<BoneTransform>,
+}
+
+impl Pose {
+    /// The reference pose of `skeleton`, which is the pose models are made in.
+    pub fn reference(skeleton: &Skeleton) -> Self {
+        Self {
+            transforms: skeleton
+                .bones
+                .iter()
+                .map(|bone| BoneTransform {
+                    position: bone.position,
+                    rotation: bone.rotation,
+                    scale: bone.scale,
+                })
+                .collect(),
+        }
+    }
+
+    /// Samples the animation at `index` in `pap` at `time` seconds.
+    ///
+    /// Bones that aren't animated stay in their reference pose. The animation has to be made for `skeleton`, otherwise the wrong bones are moved. Returns [None] if there's no animation at `index`.
+    pub fn from_animation(skeleton: &Skeleton, pap: &Pap, index: usize, time: f32) -> Option<Self> {
+        let mut pose = Self::reference(skeleton);
+        for (bone, transform) in pap.sample(index, time)? {
+            if let Some(existing) = pose.transforms.get_mut(bone) {
+                *existing = transform;
+            }
+        }
+
+        Some(pose)
+    }
+
+    /// Sets the local transform of the bone named `name`. Returns false if `skeleton` doesn't have it.
+    pub fn set(&mut self, skeleton: &Skeleton, name: &str, transform: BoneTransform) -> bool {
+        match skeleton
+            .bones
+            .iter()
+            .position(|bone| bone.name == name)
+            .and_then(|index| self.transforms.get_mut(index))
+        {
+            Some(existing) => {
+                *existing = transform;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Calculates the model space matrix of every bone.
+    fn world_matrices(&self, skeleton: &Skeleton) -> Vec<Matrix> {
+        (0..skeleton.bones.len())
+            .map(|i| {
+                // Walk up to the root, so this doesn't depend on the order of the bones
+                let mut world = IDENTITY;
+                let mut current = Some(i);
+                let mut depth = 0;
+                while let Some(index) = current
+                    && depth <= skeleton.bones.len()
+                {
+                    let local = self
+                        .transforms
+                        .get(index)
+                        .copied()
+                        .unwrap_or_default()
+                        .matrix();
+                    world = multiply(&local, &world);
+                    current = usize::try_from(skeleton.bones[index].parent_index)
+                        .ok()
+                        .filter(|x| *x < skeleton.bones.len());
+                    depth += 1;
+                }
+
+                world
+            })
+            .collect()
+    }
+}
+
+/// Vertex positions and normals of a [Part] after skinning, in the same order as its vertices.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SkinnedPart {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+}
+
+#[cfg(feature = "glam")]
+impl SkinnedPart {
+    /// The skinned positions as [glam::Vec3].
+    pub fn positions_vec3(&self) -> Vec<glam::Vec3> {
+        self.positions
+            .iter()
+            .map(|x| glam::Vec3::from(*x))
+            .collect()
+    }
+
+    /// The skinned normals as [glam::Vec3].
+    pub fn normals_vec3(&self) -> Vec<glam::Vec3> {
+        self.normals.iter().map(|x| glam::Vec3::from(*x)).collect()
+    }
+}
+
+impl MDL {
+    /// Deforms every part of every LOD with `skeleton`, posed in `pose`. Without a pose, the reference pose of the skeleton is used, which leaves the model unchanged.
+    ///
+    /// The result is indexed the same as [lods](Self::lods) and their parts.
+    pub fn skin(&self, skeleton: &Skeleton, pose: Option<&Pose>) -> Vec<Vec<SkinnedPart>> {
+        let matrices = self.bone_matrices(skeleton, pose);
+
+        self.lods
+            .iter()
+            .map(|lod| {
+                lod.parts
+                    .iter()
+                    .map(|part| self.skin_part_with(part, &matrices))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Deforms a single part with `skeleton`, posed in `pose`. See [skin](Self::skin).
+    pub fn skin_part(&self, part: &Part, skeleton: &Skeleton, pose: Option<&Pose>) -> SkinnedPart {
+        self.skin_part_with(part, &self.bone_matrices(skeleton, pose))
+    }
+
+    /// Calculates the skinning matrix of each bone used by `part`, indexed by the vertices' `bone_id`.
+    ///
+    /// These move a vertex from its bind pose into `pose`, and are column-major. Bones missing from `skeleton` aren't moved.
+    pub fn skinning_matrices(
+        &self,
+        part: &Part,
+        skeleton: &Skeleton,
+        pose: Option<&Pose>,
+    ) -> Vec<[f32; 16]> {
+        let matrices = self.bone_matrices(skeleton, pose);
+
+        self.bone_table(part)
+            .iter()
+            .map(|bone| matrices.get(*bone as usize).copied().unwrap_or(IDENTITY))
+            .collect()
+    }
+
+    /// Calculates the skinning matrix of each bone in `affected_bone_names`.
+    fn bone_matrices(&self, skeleton: &Skeleton, pose: Option<&Pose>) -> Vec<Matrix> {
+        let reference = Pose::reference(skeleton);
+        let bind = reference.world_matrices(skeleton);
+        let posed = match pose {
+            Some(pose) => pose.world_matrices(skeleton),
+            None => bind.clone(),
+        };
+
+        self.affected_bone_names
+            .iter()
+            .map(
+                |name| match skeleton.bones.iter().position(|bone| bone.name == *name) {
+                    Some(index) => multiply(&posed[index], &inverse_affine(&bind[index])),
+                    None => IDENTITY,
+                },
+            )
+            .collect()
+    }
+
+    fn skin_part_with(&self, part: &Part, matrices: &[Matrix]) -> SkinnedPart {
+        let bone_table = self.bone_table(part);
+
+        let mut skinned = SkinnedPart {
+            positions: Vec::with_capacity(part.vertices.len()),
+            normals: Vec::with_capacity(part.vertices.len()),
+        };
+        for vertex in &part.vertices {
+            let sum: f32 = vertex.bone_weight.iter().sum();
+
+            let mut position = [0.0; 3];
+            let mut normal = [0.0; 3];
+            for (weight, bone_id) in vertex.bone_weight.iter().zip(vertex.bone_id) {
+                if *weight <= 0.0 {
+                    continue;
+                }
+
+                let matrix = bone_table
+                    .get(bone_id as usize)
+                    .and_then(|bone| matrices.get(*bone as usize))
+                    .unwrap_or(&IDENTITY);
+                let weight = weight / sum;
+
+                let moved = transform(matrix, vertex.position, 1.0);
+                let turned = transform(matrix, vertex.normal, 0.0);
+                for i in 0..3 {
+                    position[i] += moved[i] * weight;
+                    normal[i] += turned[i] * weight;
+                }
+            }
+
+            // Vertices that aren't weighted to any bone stay where they are
+            if sum <= 0.0 {
+                position = vertex.position;
+                normal = vertex.normal;
+            }
+
+            skinned.positions.push(position);
+            skinned
+                .normals
+                .push(normalize(normal).unwrap_or(vertex.normal));
+        }
+
+        skinned
+    }
+}
+
+/// Transforms a point (`w` of 1) or direction (`w` of 0) by a column-major matrix.
+fn transform(m: &Matrix, v: [f32; 3], w: f32) -> [f32; 3] {
+    [
+        m[0] * v[0] + m[4] * v[1] + m[8] * v[2] + m[12] * w,
+        m[1] * v[0] + m[5] * v[1] + m[9] * v[2] + m[13] * w,
+        m[2] * v[0] + m[6] * v[1] + m[10] * v[2] + m[14] * w,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read;
+    use std::path::PathBuf;
+
+    use crate::{ReadableFile, common::Platform, skeleton::Bone};
+
+    use super::*;
+
+    fn read_mdl() -> MDL {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/tests");
+        d.push("c0201e0038_top_zeroed.mdl");
+
+        MDL::from_existing(Platform::Win32, &read(d).unwrap()).unwrap()
+    }
+
+    /// A skeleton with every bone of the test model, each one unit above its parent.
+    fn skeleton(mdl: &MDL) -> Skeleton {
+        Skeleton {
+            bones: mdl
+                .affected_bone_names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| Bone {
+                    name: name.clone(),
+                    parent_index: i as i32 - 1,
+                    position: [0.0, 1.0, 0.0],
+                    rotation: [0.0, 0.0, 0.0, 1.0],
+                    scale: [1.0; 3],
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_reference_pose() {
+        let mdl = read_mdl();
+        let skeleton = skeleton(&mdl);
+
+        // The reference pose shouldn't move anything
+        let skinned = mdl.skin(&skeleton, None);
+        for (lod, skinned_lod) in mdl.lods.iter().zip(&skinned) {
+            for (part, skinned_part) in lod.parts.iter().zip(skinned_lod) {
+                for (vertex, position) in part.vertices.iter().zip(&skinned_part.positions) {
+                    for (a, b) in vertex.position.iter().zip(position) {
+                        assert!((a - b).abs() < 1e-4);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_pose() {
+        let mut mdl = read_mdl();
+        let skeleton = skeleton(&mdl);
+
+        // Fully weight the first vertex to the first bone
+        let part = &mut mdl.lods[0].parts[0];
+        part.vertices[0].position = [1.0, 0.0, 0.0];
+        part.vertices[0].normal = [1.0, 0.0, 0.0];
+        part.vertices[0].bone_weight = [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+        part.vertices[0].bone_id = [0; 8];
+        let bone = mdl.bone_table(&mdl.lods[0].parts[0])[0] as usize;
+
+        // Turn that bone 90 degrees around Y, and move it up
+        let mut pose = Pose::reference(&skeleton);
+        assert!(pose.set(
+            &skeleton,
+            &mdl.affected_bone_names[bone],
+            BoneTransform {
+                position: [0.0, 2.0, 0.0],
+                rotation: [
+                    0.0,
+                    std::f32::consts::FRAC_1_SQRT_2,
+                    0.0,
+                    std::f32::consts::FRAC_1_SQRT_2
+                ],
+                scale: [1.0; 3],
+            },
+        ));
+        assert!(!pose.set(&skeleton, "missing", BoneTransform::default()));
+
+        let skinned = mdl.skin_part(&mdl.lods[0].parts[0], &skeleton, Some(&pose));
+        let expected = [0.0, 1.0, -1.0];
+        for i in 0..3 {
+            assert!((skinned.positions[0][i] - expected[i]).abs() < 1e-4);
+            assert!((skinned.normals[0][i] - [0.0, 0.0, -1.0][i]).abs() < 1e-4);
+        }
+    }
+
+    #[cfg(feature = "glam")]
+    #[test]
+    fn test_glam() {
+        let transform = BoneTransform {
+            position: [1.0, 2.0, 3.0],
+            rotation: [
+                0.0,
+                std::f32::consts::FRAC_1_SQRT_2,
+                0.0,
+                std::f32::consts::FRAC_1_SQRT_2,
+            ],
+            scale: [2.0; 3],
+        };
+
+        let affine: glam::Affine3A = transform.into();
+        let back: BoneTransform = affine.into();
+        for (a, b) in back.position.iter().zip(transform.position) {
+            assert!((a - b).abs() < 1e-5);
+        }
+        for (a, b) in back.rotation.iter().zip(transform.rotation) {
+            assert!((a - b).abs() < 1e-5);
+        }
+    }
+}
@@ -0,0 +1,751 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::model::{MDL, vertex_declarations::VertexUsage};
+
+/// The most bones a bone table can have.
+const MAX_BONES_PER_TABLE: usize = 64;
+
+/// A problem found by [MDL::validate], which would likely crash or glitch the game.
+///
+/// Parts are referred to by their `lod` and index in [Lod::parts](crate::model::Lod::parts), and submeshes by their index in [Part::submeshes](crate::model::Part::submeshes).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelIssue {
+    /// The model has more LODs than the game supports.
+    TooManyLods {
+        /// The number of LODs.
+        lod_count: usize,
+    },
+    /// The meshes of an LOD go past the end of the model's meshes.
+    LodMeshesOutOfRange {
+        /// Index of the LOD.
+        lod: usize,
+    },
+    /// The meshes of an LOD start before the ones of the previous LOD end.
+    LodMeshesOutOfOrder {
+        /// Index of the LOD.
+        lod: usize,
+    },
+    /// An LOD switches to the next one closer than the previous LOD does.
+    LodRangeOutOfOrder {
+        /// Index of the LOD.
+        lod: usize,
+        /// Its `model_lod_range`.
+        range: f32,
+        /// The `model_lod_range` of the previous LOD.
+        previous_range: f32,
+    },
+    /// A part has a different number of vertices than its mesh says.
+    VertexCountMismatch {
+        lod: usize,
+        part: usize,
+        /// How many vertices the part has.
+        vertex_count: usize,
+        /// How many vertices its mesh says it has.
+        mesh_vertex_count: usize,
+    },
+    /// A part has indices that don't make up whole triangles.
+    IncompleteTriangle {
+        lod: usize,
+        part: usize,
+        /// How many indices the part has.
+        index_count: usize,
+    },
+    /// A part has an index pointing past its vertices.
+    IndexOutOfRange {
+        lod: usize,
+        part: usize,
+        /// The biggest index that's out of range.
+        index: u16,
+        /// How many vertices the part has.
+        vertex_count: usize,
+    },
+    /// A submesh uses indices outside of its part.
+    SubmeshOutOfRange {
+        lod: usize,
+        part: usize,
+        submesh: usize,
+    },
+    /// The bones of a submesh go past the end of the submesh bone map.
+    SubmeshBonesOutOfRange {
+        lod: usize,
+        part: usize,
+        submesh: usize,
+    },
+    /// A part uses a material the model doesn't have.
+    MaterialOutOfRange {
+        lod: usize,
+        part: usize,
+        /// The index of the material.
+        material_index: u16,
+        /// How many materials the model has.
+        material_count: usize,
+    },
+    /// A bone table has more bones than the game supports.
+    BoneTableTooLarge {
+        /// Index of the bone table.
+        bone_table: usize,
+        /// How many bones it has.
+        bone_count: usize,
+    },
+    /// A bone table refers to a bone the model doesn't have.
+    BoneOutOfRange {
+        /// Index of the bone table.
+        bone_table: usize,
+        /// The index of the bone.
+        bone_index: u16,
+        /// How many bones the model has.
+        bone_count: usize,
+    },
+    /// A skinned part uses a bone table that doesn't exist.
+    BoneTableMissing {
+        lod: usize,
+        part: usize,
+        /// Index of the bone table.
+        bone_table: usize,
+    },
+    /// A vertex is weighted to a bone outside of its part's bone table.
+    VertexBoneOutOfRange {
+        lod: usize,
+        part: usize,
+        /// Index of the vertex in the part.
+        vertex: usize,
+        /// The bone in the bone table.
+        bone_id: u8,
+        /// How many bones are in the bone table.
+        bone_table_size: usize,
+    },
+    /// The shape meshes of a shape go past the end of the model's shape meshes.
+    ShapeMeshesOutOfRange {
+        /// Index of the shape.
+        shape: usize,
+        /// Index of the LOD.
+        lod: usize,
+    },
+    /// The values of a shape mesh go past the end of the model's shape values.
+    ShapeValuesOutOfRange {
+        /// Index of the shape mesh.
+        shape_mesh: usize,
+    },
+    /// A shape value points to an index outside of its part, or replaces it with a vertex that doesn't exist.
+    ShapeValueOutOfRange {
+        lod: usize,
+        part: usize,
+        /// Index of the shape value.
+        shape_value: usize,
+    },
+    /// A part's vertex declaration doesn't fit how its mesh's vertex streams are laid out.
+    VertexStreamMismatch {
+        lod: usize,
+        part: usize,
+        /// Index of the vertex stream.
+        stream: usize,
+        /// The stride the vertex declaration needs.
+        declaration_stride: usize,
+        /// The stride of the mesh's vertex stream.
+        mesh_stride: usize,
+    },
+    /// A vertex element is in a stream its mesh doesn't have.
+    VertexElementOutOfStream {
+        lod: usize,
+        part: usize,
+        /// The usage of the element.
+        usage: VertexUsage,
+        /// The stream of the element.
+        stream: u8,
+    },
+    /// A terrain shadow part uses a terrain shadow mesh the model doesn't have.
+    TerrainShadowMeshOutOfRange {
+        lod: usize,
+        /// Index of the part in [Lod::terrain_shadow_parts](crate::model::Lod::terrain_shadow_parts).
+        part: usize,
+        /// The index of the mesh.
+        mesh_index: u16,
+        /// How many terrain shadow meshes the model has.
+        mesh_count: usize,
+    },
+}
+
+impl std::fmt::Display for ModelIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ModelIssue::TooManyLods { lod_count } => {
+                write!(f, "model has {lod_count} LODs, but at most 3 are supported")
+            }
+            ModelIssue::LodMeshesOutOfRange { lod } => {
+                write!(f, "LOD {lod} has meshes past the end of the model")
+            }
+            ModelIssue::LodMeshesOutOfOrder { lod } => {
+                write!(
+                    f,
+                    "LOD {lod} has meshes before the ones of the previous LOD"
+                )
+            }
+            ModelIssue::LodRangeOutOfOrder {
+                lod,
+                range,
+                previous_range,
+            } => write!(
+                f,
+                "LOD {lod} has a range of {range}, which should be further than the previous LOD's {previous_range}"
+            ),
+            ModelIssue::VertexCountMismatch {
+                lod,
+                part,
+                vertex_count,
+                mesh_vertex_count,
+            } => write!(
+                f,
+                "part {part} in LOD {lod} has {vertex_count} vertices, but its mesh says it has {mesh_vertex_count}"
+            ),
+            ModelIssue::IncompleteTriangle {
+                lod,
+                part,
+                index_count,
+            } => write!(
+                f,
+                "part {part} in LOD {lod} has {index_count} indices, which isn't a multiple of 3"
+            ),
+            ModelIssue::IndexOutOfRange {
+                lod,
+                part,
+                index,
+                vertex_count,
+            } => write!(
+                f,
+                "part {part} in LOD {lod} has index {index}, but only {vertex_count} vertices"
+            ),
+            ModelIssue::SubmeshOutOfRange { lod, part, submesh } => write!(
+                f,
+                "submesh {submesh} of part {part} in LOD {lod} uses indices outside of its part"
+            ),
+            ModelIssue::SubmeshBonesOutOfRange { lod, part, submesh } => write!(
+                f,
+                "submesh {submesh} of part {part} in LOD {lod} has bones past the end of the submesh bone map"
+            ),
+            ModelIssue::MaterialOutOfRange {
+                lod,
+                part,
+                material_index,
+                material_count,
+            } => write!(
+                f,
+                "part {part} in LOD {lod} uses material {material_index}, but the model only has {material_count}"
+            ),
+            ModelIssue::BoneTableTooLarge {
+                bone_table,
+                bone_count,
+            } => write!(
+                f,
+                "bone table {bone_table} has {bone_count} bones, but at most {MAX_BONES_PER_TABLE} are supported"
+            ),
+            ModelIssue::BoneOutOfRange {
+                bone_table,
+                bone_index,
+                bone_count,
+            } => write!(
+                f,
+                "bone table {bone_table} uses bone {bone_index}, but the model only has {bone_count}"
+            ),
+            ModelIssue::BoneTableMissing {
+                lod,
+                part,
+                bone_table,
+            } => write!(
+                f,
+                "part {part} in LOD {lod} uses bone table {bone_table}, which doesn't exist"
+            ),
+            ModelIssue::VertexBoneOutOfRange {
+                lod,
+                part,
+                vertex,
+                bone_id,
+                bone_table_size,
+            } => write!(
+                f,
+                "vertex {vertex} of part {part} in LOD {lod} uses bone {bone_id}, but its bone table only has {bone_table_size}"
+            ),
+            ModelIssue::ShapeMeshesOutOfRange { shape, lod } => write!(
+                f,
+                "shape {shape} has shape meshes in LOD {lod} past the end of the model"
+            ),
+            ModelIssue::ShapeValuesOutOfRange { shape_mesh } => write!(
+                f,
+                "shape mesh {shape_mesh} has values past the end of the model"
+            ),
+            ModelIssue::ShapeValueOutOfRange {
+                lod,
+                part,
+                shape_value,
+            } => write!(
+                f,
+                "shape value {shape_value} for part {part} in LOD {lod} points outside of the part"
+            ),
+            ModelIssue::VertexStreamMismatch {
+                lod,
+                part,
+                stream,
+                declaration_stride,
+                mesh_stride,
+            } => write!(
+                f,
+                "stream {stream} of part {part} in LOD {lod} needs a stride of {declaration_stride}, but its mesh has {mesh_stride}"
+            ),
+            ModelIssue::VertexElementOutOfStream {
+                lod,
+                part,
+                usage,
+                stream,
+            } => write!(
+                f,
+                "the {usage:?} element of part {part} in LOD {lod} is in stream {stream}, which its mesh doesn't have"
+            ),
+            ModelIssue::TerrainShadowMeshOutOfRange {
+                lod,
+                part,
+                mesh_index,
+                mesh_count,
+            } => write!(
+                f,
+                "terrain shadow part {part} in LOD {lod} uses mesh {mesh_index}, but the model only has {mesh_count}"
+            ),
+        }
+    }
+}
+
+impl MDL {
+    /// Checks the model for problems that would likely crash or glitch the game, such as indices past the end of a part's vertices.
+    ///
+    /// Returns an empty list if none were found.
+    pub fn validate(&self) -> Vec<ModelIssue> {
+        let mut issues = Vec::new();
+
+        self.validate_lods(&mut issues);
+        self.validate_bone_tables(&mut issues);
+        for (l, lod) in self.lods.iter().enumerate() {
+            for p in 0..lod.parts.len() {
+                self.validate_part(l, p, &mut issues);
+            }
+
+            let mesh_count = self.model_data.terrain_shadow_meshes.len();
+            for (p, part) in lod.terrain_shadow_parts.iter().enumerate() {
+                if part.mesh_index as usize >= mesh_count {
+                    issues.push(ModelIssue::TerrainShadowMeshOutOfRange {
+                        lod: l,
+                        part: p,
+                        mesh_index: part.mesh_index,
+                        mesh_count,
+                    });
+                }
+            }
+        }
+        self.validate_shapes(&mut issues);
+
+        issues
+    }
+
+    fn validate_lods(&self, issues: &mut Vec<ModelIssue>) {
+        if self.lods.len() > 3 {
+            issues.push(ModelIssue::TooManyLods {
+                lod_count: self.lods.len(),
+            });
+        }
+
+        let mut previous_end = 0;
+        for (l, lod) in self
+            .model_data
+            .lods
+            .iter()
+            .take(self.lods.len())
+            .enumerate()
+        {
+            let end = lod.mesh_index as usize + lod.mesh_count as usize;
+            if end > self.model_data.meshes.len() {
+                issues.push(ModelIssue::LodMeshesOutOfRange { lod: l });
+            }
+            if lod.mesh_count > 0 && (lod.mesh_index as usize) < previous_end {
+                issues.push(ModelIssue::LodMeshesOutOfOrder { lod: l });
+            }
+            previous_end = previous_end.max(end);
+        }
+
+        // The last LOD is used at any distance, so its range doesn't matter
+        for l in 1..self.lods.len().saturating_sub(1) {
+            let range = self.lods[l].model_lod_range;
+            let previous_range = self.lods[l - 1].model_lod_range;
+            if range <= previous_range {
+                issues.push(ModelIssue::LodRangeOutOfOrder {
+                    lod: l,
+                    range,
+                    previous_range,
+                });
+            }
+        }
+    }
+
+    fn validate_bone_tables(&self, issues: &mut Vec<ModelIssue>) {
+        let bone_count = self.affected_bone_names.len();
+
+        let tables: Vec<Vec<u16>> = if self.model_data.bone_tables.is_empty() {
+            self.model_data
+                .bone_tables_v2
+                .tables
+                .iter()
+                .map(|x| x.bone_indices.clone())
+                .collect()
+        } else {
+            self.model_data
+                .bone_tables
+                .iter()
+                .map(|x| {
+                    x.bone_indices[..(x.bone_count as usize).min(x.bone_indices.len())].to_vec()
+                })
+                .collect()
+        };
+
+        for (i, table) in self.model_data.bone_tables.iter().enumerate() {
+            if table.bone_count as usize > MAX_BONES_PER_TABLE {
+                issues.push(ModelIssue::BoneTableTooLarge {
+                    bone_table: i,
+                    bone_count: table.bone_count as usize,
+                });
+            }
+        }
+
+        for (i, table) in tables.iter().enumerate() {
+            if table.len() > MAX_BONES_PER_TABLE {
+                issues.push(ModelIssue::BoneTableTooLarge {
+                    bone_table: i,
+                    bone_count: table.len(),
+                });
+            }
+            for bone_index in table {
+                if *bone_index as usize >= bone_count {
+                    issues.push(ModelIssue::BoneOutOfRange {
+                        bone_table: i,
+                        bone_index: *bone_index,
+                        bone_count,
+                    });
+                }
+            }
+        }
+    }
+
+    fn validate_part(&self, lod: usize, p: usize, issues: &mut Vec<ModelIssue>) {
+        let part = &self.lods[lod].parts[p];
+        let Some(mesh) = self.model_data.meshes.get(part.mesh_index as usize) else {
+            // Covered by the LOD checks
+            return;
+        };
+
+        if part.vertices.len() != mesh.vertex_count as usize {
+            issues.push(ModelIssue::VertexCountMismatch {
+                lod,
+                part: p,
+                vertex_count: part.vertices.len(),
+                mesh_vertex_count: mesh.vertex_count as usize,
+            });
+        }
+
+        if !part.indices.len().is_multiple_of(3) {
+            issues.push(ModelIssue::IncompleteTriangle {
+                lod,
+                part: p,
+                index_count: part.indices.len(),
+            });
+        }
+
+        if let Some(index) = part
+            .indices
+            .iter()
+            .copied()
+            .filter(|x| *x as usize >= part.vertices.len())
+            .max()
+        {
+            issues.push(ModelIssue::IndexOutOfRange {
+                lod,
+                part: p,
+                index,
+                vertex_count: part.vertices.len(),
+            });
+        }
+
+        let start = mesh.start_index as usize;
+        let end = start + part.indices.len();
+        for (s, submesh) in part.submeshes.iter().enumerate() {
+            let offset = submesh.index_offset as usize;
+            if offset < start || offset + submesh.index_count as usize > end {
+                issues.push(ModelIssue::SubmeshOutOfRange {
+                    lod,
+                    part: p,
+                    submesh: s,
+                });
+            }
+
+            if let Some(info) = self.model_data.submeshes.get(submesh.submesh_index)
+                && info.bone_start_index as usize + info.bone_count as usize
+                    > self.model_data.submesh_bone_map.len()
+            {
+                issues.push(ModelIssue::SubmeshBonesOutOfRange {
+                    lod,
+                    part: p,
+                    submesh: s,
+                });
+            }
+        }
+
+        if part.material_index as usize >= self.material_names.len() {
+            issues.push(ModelIssue::MaterialOutOfRange {
+                lod,
+                part: p,
+                material_index: part.material_index,
+                material_count: self.material_names.len(),
+            });
+        }
+
+        let declaration = self
+            .model_data
+            .header
+            .vertex_declarations
+            .get(part.mesh_index as usize);
+        if let Some(declaration) = declaration {
+            let stream_count = mesh.vertex_stream_count as usize;
+            for element in &declaration.elements {
+                if element.stream as usize >= stream_count {
+                    issues.push(ModelIssue::VertexElementOutOfStream {
+                        lod,
+                        part: p,
+                        usage: element.vertex_usage,
+                        stream: element.stream,
+                    });
+                }
+            }
+
+            for stream in 0..stream_count {
+                let declaration_stride = declaration.stride(stream as u8);
+                let (_, mesh_stride) = self
+                    .model_data
+                    .vertex_stream(part.mesh_index as usize, stream);
+                let mesh_stride = mesh_stride as usize;
+                if declaration_stride > mesh_stride {
+                    issues.push(ModelIssue::VertexStreamMismatch {
+                        lod,
+                        part: p,
+                        stream,
+                        declaration_stride,
+                        mesh_stride,
+                    });
+                }
+            }
+
+            let skinned = declaration
+                .elements
+                .iter()
+                .any(|x| x.vertex_usage == VertexUsage::BlendIndices);
+            let bone_table_count = self
+                .model_data
+                .bone_tables
+                .len()
+                .max(self.model_data.bone_tables_v2.tables.len());
+            if skinned && mesh.bone_table_index as usize >= bone_table_count {
+                issues.push(ModelIssue::BoneTableMissing {
+                    lod,
+                    part: p,
+                    bone_table: mesh.bone_table_index as usize,
+                });
+            } else if skinned {
+                let bone_table_size = self.bone_table(part).len();
+                for (v, vertex) in part.vertices.iter().enumerate() {
+                    let bad =
+                        vertex
+                            .bone_weight
+                            .iter()
+                            .zip(vertex.bone_id)
+                            .find(|(weight, bone_id)| {
+                                **weight > 0.0 && *bone_id as usize >= bone_table_size
+                            });
+                    if let Some((_, bone_id)) = bad {
+                        issues.push(ModelIssue::VertexBoneOutOfRange {
+                            lod,
+                            part: p,
+                            vertex: v,
+                            bone_id,
+                            bone_table_size,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    fn validate_shapes(&self, issues: &mut Vec<ModelIssue>) {
+        let data = &self.model_data;
+
+        for (i, shape_mesh) in data.shape_meshes.iter().enumerate() {
+            if shape_mesh.shape_value_offset as usize + shape_mesh.shape_value_count as usize
+                > data.shape_values.len()
+            {
+                issues.push(ModelIssue::ShapeValuesOutOfRange { shape_mesh: i });
+            }
+        }
+
+        for (s, shape) in data.shapes.iter().enumerate() {
+            for lod in 0..self.lods.len().min(3) {
+                let start = shape.shape_mesh_start_index[lod] as usize;
+                let count = shape.shape_mesh_count[lod] as usize;
+                if start + count > data.shape_meshes.len() {
+                    issues.push(ModelIssue::ShapeMeshesOutOfRange { shape: s, lod });
+                    continue;
+                }
+
+                for shape_mesh in &data.shape_meshes[start..start + count] {
+                    // Shape meshes refer to their part by where its indices start
+                    let part = self.lods[lod].parts.iter().position(|x| {
+                        data.meshes
+                            .get(x.mesh_index as usize)
+                            .is_some_and(|x| x.start_index == shape_mesh.mesh_index_offset)
+                    });
+                    let Some(p) = part else {
+                        continue;
+                    };
+                    let part = &self.lods[lod].parts[p];
+                    let start_index = shape_mesh.mesh_index_offset as usize;
+
+                    let first = shape_mesh.shape_value_offset as usize;
+                    let last = (first + shape_mesh.shape_value_count as usize)
+                        .min(data.shape_values.len());
+                    for v in first..last.max(first) {
+                        let value = &data.shape_values[v];
+                        let base = value.base_indices_index as usize;
+                        if base < start_index
+                            || base >= start_index + part.indices.len()
+                            || value.replacing_vertex_index as usize >= part.vertices.len()
+                        {
+                            issues.push(ModelIssue::ShapeValueOutOfRange {
+                                lod,
+                                part: p,
+                                shape_value: v,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read;
+    use std::path::PathBuf;
+
+    use crate::{
+        ReadableFile,
+        common::Platform,
+        model::vertex_declarations::{VertexElement, VertexType},
+    };
+
+    use super::*;
+
+    fn read_mdl() -> MDL {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/tests");
+        d.push("c0201e0038_top_zeroed.mdl");
+
+        MDL::from_existing(Platform::Win32, &read(d).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_valid() {
+        assert_eq!(read_mdl().validate(), Vec::new());
+    }
+
+    #[test]
+    fn test_issues() {
+        let mut mdl = read_mdl();
+
+        let part = &mut mdl.lods[0].parts[0];
+        part.indices[0] = u16::MAX;
+        part.indices.pop();
+        part.material_index = 5;
+        part.vertices[0].bone_weight[0] = 1.0;
+        part.vertices[0].bone_id[0] = 200;
+        mdl.lods[1].model_lod_range = 1.0;
+        mdl.model_data.bone_tables[0].bone_indices[0] = 1000;
+
+        let issues = mdl.validate();
+        let vertex_count = mdl.lods[0].parts[0].vertices.len();
+        let bone_table_size = mdl.bone_table(&mdl.lods[0].parts[0]).len();
+        for issue in [
+            ModelIssue::LodRangeOutOfOrder {
+                lod: 1,
+                range: 1.0,
+                previous_range: mdl.lods[0].model_lod_range,
+            },
+            ModelIssue::BoneOutOfRange {
+                bone_table: 0,
+                bone_index: 1000,
+                bone_count: mdl.affected_bone_names.len(),
+            },
+            ModelIssue::IncompleteTriangle {
+                lod: 0,
+                part: 0,
+                index_count: mdl.lods[0].parts[0].indices.len(),
+            },
+            ModelIssue::IndexOutOfRange {
+                lod: 0,
+                part: 0,
+                index: u16::MAX,
+                vertex_count,
+            },
+            ModelIssue::MaterialOutOfRange {
+                lod: 0,
+                part: 0,
+                material_index: 5,
+                material_count: 2,
+            },
+            ModelIssue::VertexBoneOutOfRange {
+                lod: 0,
+                part: 0,
+                vertex: 0,
+                bone_id: 200,
+                bone_table_size,
+            },
+        ] {
+            assert!(issues.contains(&issue), "{issue} wasn't found");
+        }
+        // The last submesh now goes past the end of the part
+        assert!(issues.contains(&ModelIssue::SubmeshOutOfRange {
+            lod: 0,
+            part: 0,
+            submesh: mdl.lods[0].parts[0].submeshes.len() - 1,
+        }));
+    }
+
+    #[test]
+    fn test_extra_vertex_stream() {
+        let mut mdl = read_mdl();
+
+        let mesh_index = mdl.lods[0].parts[0].mesh_index as usize;
+        mdl.model_data.header.vertex_declarations[mesh_index]
+            .elements
+            .push(VertexElement {
+                stream: 3,
+                offset: 0,
+                vertex_type: VertexType::ByteFloat4,
+                vertex_usage: VertexUsage::Color,
+                usage_index: 1,
+            });
+        let issue = ModelIssue::VertexElementOutOfStream {
+            lod: 0,
+            part: 0,
+            usage: VertexUsage::Color,
+            stream: 3,
+        };
+        assert!(mdl.validate().contains(&issue));
+
+        // Streams past the third have no stride in the mesh, so they always match
+        mdl.model_data.meshes[mesh_index].vertex_stream_count = 4;
+        assert_eq!(mdl.validate(), Vec::new());
+    }
+}
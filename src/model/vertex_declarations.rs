@@ -8,6 +8,9 @@ use std::io::SeekFrom;
 /// Marker for end of stream (0xFF)
 const END_OF_STREAM: u8 = 0xFF;
 
+/// How many vertex streams a mesh has room for the offsets and strides of. Any streams after these directly follow the last one.
+pub const MAX_VERTEX_STREAMS: usize = 3;
+
 /// The format of the vertex stream.
 #[binrw]
 #[brw(repr = u8)]
@@ -50,12 +53,13 @@ pub enum VertexType {
     /// 2 16-bit unsigned integers
     UnsignedShort2 = 16,
     /// 4 16-bit unsigned integers
+    ///
+    /// For blend weights and indices this is instead 8 bytes, which is how Dawntrail models store 8 bone influences.
     UnsignedShort4 = 17,
 }
 
 /// In bytes
 pub fn get_vertex_type_size(vertex_type: VertexType) -> usize {
-    // TODO: Most of these are wrong
     match vertex_type {
         VertexType::Single1 => 4,
         VertexType::Single2 => 8,
@@ -66,7 +70,7 @@ pub fn get_vertex_type_size(vertex_type: VertexType) -> usize {
         VertexType::Short4 => 8,
         VertexType::ByteFloat4 => 4,
         VertexType::Short2n => 4,
-        VertexType::Short4n => 4,
+        VertexType::Short4n => 8,
         VertexType::Half2 => 4,
         VertexType::Half4 => 8,
         VertexType::UnkPS3 => 4,
@@ -75,6 +79,27 @@ pub fn get_vertex_type_size(vertex_type: VertexType) -> usize {
     }
 }
 
+/// How many components an element of this type has.
+pub fn get_vertex_type_component_count(vertex_type: VertexType) -> usize {
+    match vertex_type {
+        VertexType::Single1 => 1,
+        VertexType::Single2
+        | VertexType::Short2
+        | VertexType::Short2n
+        | VertexType::Half2
+        | VertexType::UnsignedShort2 => 2,
+        VertexType::Single3 => 3,
+        VertexType::Single4
+        | VertexType::Byte4
+        | VertexType::Short4
+        | VertexType::ByteFloat4
+        | VertexType::Short4n
+        | VertexType::Half4
+        | VertexType::UnkPS3
+        | VertexType::UnsignedShort4 => 4,
+    }
+}
+
 /// What the vertex stream is used for.
 #[binrw]
 #[brw(repr = u8)]
@@ -114,6 +139,23 @@ pub struct VertexDeclaration {
     pub elements: Vec<VertexElement>,
 }
 
+impl VertexDeclaration {
+    /// Calculates the stride of `stream` in bytes, from the end of its last element.
+    pub fn stride(&self, stream: u8) -> usize {
+        self.elements
+            .iter()
+            .filter(|element| element.stream == stream)
+            .map(|element| element.offset as usize + get_vertex_type_size(element.vertex_type))
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Calculates the strides of every stream, in the same way as [stride](Self::stride).
+    pub fn strides(&self) -> [u8; MAX_VERTEX_STREAMS] {
+        std::array::from_fn(|stream| self.stride(stream as u8) as u8)
+    }
+}
+
 #[binrw::parser(reader, endian)]
 pub(crate) fn vertex_element_parser(count: u16) -> BinResult<Vec<VertexDeclaration>> {
     let mut vertex_declarations: Vec<VertexDeclaration> =
@@ -3,16 +3,21 @@
 
 #![allow(clippy::unnecessary_fallible_conversions)] // This wrongly trips on binrw code
 
+use std::collections::HashMap;
 use std::io::Cursor;
 
 use crate::common::Platform;
 use crate::common_file_operations::{Half1, Half2, Half3, null_terminated_utf8};
+use crate::json::JsonValue;
 use crate::mtrl::ColorDyeTable::{
     DawntrailColorDyeTable, LegacyColorDyeTable, OpaqueColorDyeTable,
 };
 use crate::mtrl::ColorTable::{DawntrailColorTable, LegacyColorTable, OpaqueColorTable};
+use crate::shpk::{Key, MaterialParameter, ShaderPackage};
+use crate::stm::Stm;
 use crate::{ByteBuffer, ByteSpan, ReadableFile, WritableFile};
 use binrw::{BinRead, BinResult, BinWrite, binrw};
+use half::f16;
 
 #[binrw]
 #[derive(Debug)]
@@ -53,57 +58,75 @@ struct ColorSet {
 #[allow(dead_code)]
 pub struct DawntrailColorTableRow {
     #[br(map = |x: Half3| { [x.r.to_f32(), x.g.to_f32(), x.b.to_f32()] })]
+    #[bw(map = write_halves)]
     pub diffuse_color: [f32; 3],
 
     #[br(map = |x: Half1| { x.value.to_f32() })]
+    #[bw(map = write_half)]
     pub unknown1: f32,
 
     #[br(map = |x: Half3| { [x.r.to_f32(), x.g.to_f32(), x.b.to_f32()] })]
+    #[bw(map = write_halves)]
     pub specular_color: [f32; 3],
 
     #[br(map = |x: Half1| { x.value.to_f32() })]
+    #[bw(map = write_half)]
     pub unknown2: f32,
 
     #[br(map = |x: Half3| { [x.r.to_f32(), x.g.to_f32(), x.b.to_f32()] })]
+    #[bw(map = write_halves)]
     pub emissive_color: [f32; 3],
 
     #[br(map = |x: Half1| { x.value.to_f32() })]
+    #[bw(map = write_half)]
     pub unknown3: f32,
 
     #[br(map = |x: Half1| { x.value.to_f32() })]
+    #[bw(map = write_half)]
     pub sheen_rate: f32,
 
     #[br(map = |x: Half1| { x.value.to_f32() })]
+    #[bw(map = write_half)]
     pub sheen_tint: f32,
 
     #[br(map = |x: Half1| { x.value.to_f32() })]
+    #[bw(map = write_half)]
     pub sheen_aperture: f32,
 
     #[br(map = |x: Half1| { x.value.to_f32() })]
+    #[bw(map = write_half)]
     pub unknown4: f32,
 
     #[br(map = |x: Half1| { x.value.to_f32() })]
+    #[bw(map = write_half)]
     pub roughness: f32,
 
     #[br(map = |x: Half1| { x.value.to_f32() })]
+    #[bw(map = write_half)]
     pub unknown5: f32,
 
     #[br(map = |x: Half1| { x.value.to_f32() })]
+    #[bw(map = write_half)]
     pub metalness: f32,
 
     #[br(map = |x: Half1| { x.value.to_f32() })]
+    #[bw(map = write_half)]
     pub anisotropy: f32,
 
     #[br(map = |x: Half1| { x.value.to_f32() })]
+    #[bw(map = write_half)]
     pub unknown6: f32,
 
     #[br(map = |x: Half1| { x.value.to_f32() })]
+    #[bw(map = write_half)]
     pub sphere_mask: f32,
 
     #[br(map = |x: Half1| { x.value.to_f32() })]
+    #[bw(map = write_half)]
     pub unknown7: f32,
 
     #[br(map = |x: Half1| { x.value.to_f32() })]
+    #[bw(map = write_half)]
     pub unknown8: f32,
 
     pub shader_index: u16,
@@ -111,14 +134,17 @@ pub struct DawntrailColorTableRow {
     pub tile_set: u16,
 
     #[br(map = |x: Half1| { x.value.to_f32() })]
+    #[bw(map = write_half)]
     pub tile_alpha: f32,
 
     pub sphere_index: u16,
 
     #[br(map = |x: Half2| { [x.x.to_f32(), x.y.to_f32()] })]
+    #[bw(map = write_halves)]
     pub material_repeat: [f32; 2],
 
     #[br(map = |x: Half2| { [x.x.to_f32(), x.y.to_f32()] })]
+    #[bw(map = write_halves)]
     pub material_skew: [f32; 2],
 }
 
@@ -128,29 +154,37 @@ pub struct DawntrailColorTableRow {
 #[allow(dead_code)]
 pub struct LegacyColorTableRow {
     #[br(map = |x: Half3| { [x.r.to_f32(), x.g.to_f32(), x.b.to_f32()] })]
+    #[bw(map = write_halves)]
     pub diffuse_color: [f32; 3],
 
     #[br(map = |x: Half1| { x.value.to_f32() })]
+    #[bw(map = write_half)]
     pub specular_strength: f32,
 
     #[br(map = |x: Half3| { [x.r.to_f32(), x.g.to_f32(), x.b.to_f32()] })]
+    #[bw(map = write_halves)]
     pub specular_color: [f32; 3],
 
     #[br(map = |x: Half1| { x.value.to_f32() })]
+    #[bw(map = write_half)]
     pub gloss_strength: f32,
 
     #[br(map = |x: Half3| { [x.r.to_f32(), x.g.to_f32(), x.b.to_f32()] })]
+    #[bw(map = write_halves)]
     pub emissive_color: [f32; 3],
 
     pub tile_set: u16,
 
     #[br(map = |x: Half1| { x.value.to_f32() })]
+    #[bw(map = write_half)]
     pub material_repeat_x: f32,
 
     #[br(map = |x: Half2| { [x.x.to_f32(), x.y.to_f32()] })]
+    #[bw(map = write_halves)]
     pub material_skew: [f32; 2],
 
     #[br(map = |x: Half1| { x.value.to_f32() })]
+    #[bw(map = write_half)]
     pub material_repeat_y: f32,
 }
 
@@ -186,12 +220,773 @@ pub enum ColorTable {
     OpaqueColorTable(OpaqueColorTableData),
 }
 
+impl ColorTable {
+    /// Returns a copy of this color table with `stain_id` applied from `stm`, the way the game renders dyed gear.
+    ///
+    /// Only rows that use dye `channel` are changed, and only the properties their row in `dye_table` allows. Legacy materials only have the first channel.
+    pub fn apply_dye(
+        &self,
+        dye_table: &ColorDyeTable,
+        stm: &Stm,
+        stain_id: u8,
+        channel: u8,
+    ) -> crate::Result<ColorTable> {
+        let mut table = self.clone();
+        match (&mut table, dye_table) {
+            (LegacyColorTable(table), LegacyColorDyeTable(dye_table)) => {
+                for (row, dye_row) in table.rows.iter_mut().zip(&dye_table.rows) {
+                    if channel != 0 {
+                        break;
+                    }
+                    let Some(dye) = stm.legacy_dye(dye_row.template.into(), stain_id) else {
+                        continue;
+                    };
+
+                    if dye_row.diffuse {
+                        row.diffuse_color = dye.diffuse;
+                    }
+                    if dye_row.specular {
+                        row.specular_color = dye.specular;
+                    }
+                    if dye_row.emissive {
+                        row.emissive_color = dye.emissive;
+                    }
+                    if dye_row.gloss {
+                        row.gloss_strength = dye.shininess;
+                    }
+                    if dye_row.specular_strength {
+                        row.specular_strength = dye.specular_mask;
+                    }
+                }
+            }
+            (DawntrailColorTable(table), DawntrailColorDyeTable(dye_table)) => {
+                for (row, dye_row) in table.rows.iter_mut().zip(&dye_table.rows) {
+                    if dye_row.channel != channel {
+                        continue;
+                    }
+                    let Some(dye) = stm.dye(dye_row.template.into(), stain_id) else {
+                        continue;
+                    };
+
+                    if dye_row.diffuse {
+                        row.diffuse_color = dye.diffuse;
+                    }
+                    if dye_row.specular {
+                        row.specular_color = dye.specular;
+                    }
+                    if dye_row.emissive {
+                        row.emissive_color = dye.emissive;
+                    }
+                    if dye_row.scalar3 {
+                        row.unknown1 = dye.scalar3;
+                    }
+                    if dye_row.metalness {
+                        row.metalness = dye.metalness;
+                    }
+                    if dye_row.roughness {
+                        row.roughness = dye.roughness;
+                    }
+                    if dye_row.sheen_rate {
+                        row.sheen_rate = dye.sheen_rate;
+                    }
+                    if dye_row.sheen_tint_rate {
+                        row.sheen_tint = dye.sheen_tint_rate;
+                    }
+                    if dye_row.sheen_aperture {
+                        row.sheen_aperture = dye.sheen_aperture;
+                    }
+                    if dye_row.anisotropy {
+                        row.anisotropy = dye.anisotropy;
+                    }
+                    if dye_row.sphere_map_index {
+                        row.sphere_index = dye.raw_sphere_map_index;
+                    }
+                    if dye_row.sphere_map_mask {
+                        row.sphere_mask = dye.sphere_map_mask;
+                    }
+                }
+            }
+            _ => {
+                return Err(crate::Error::InvalidData {
+                    reason: "the color table and dye table have different layouts".to_string(),
+                });
+            }
+        }
+
+        Ok(table)
+    }
+
+    /// Returns how many rows this table has, which is zero for tables in an unsupported layout.
+    pub fn row_count(&self) -> usize {
+        match self {
+            LegacyColorTable(table) => table.rows.len(),
+            DawntrailColorTable(table) => table.rows.len(),
+            OpaqueColorTable(_) => 0,
+        }
+    }
+
+    /// Returns the diffuse color of `row`.
+    pub fn diffuse_color(&self, row: usize) -> Option<[f32; 3]> {
+        self.row_value(row, |x| Some(x.diffuse_color), |x| Some(x.diffuse_color))
+    }
+
+    pub fn set_diffuse_color(&mut self, row: usize, color: [f32; 3]) -> crate::Result<()> {
+        self.set_row_value(
+            row,
+            "diffuse color",
+            |x| {
+                x.diffuse_color = color;
+                true
+            },
+            |x| {
+                x.diffuse_color = color;
+                true
+            },
+        )
+    }
+
+    /// Returns the specular color of `row`.
+    pub fn specular_color(&self, row: usize) -> Option<[f32; 3]> {
+        self.row_value(row, |x| Some(x.specular_color), |x| Some(x.specular_color))
+    }
+
+    pub fn set_specular_color(&mut self, row: usize, color: [f32; 3]) -> crate::Result<()> {
+        self.set_row_value(
+            row,
+            "specular color",
+            |x| {
+                x.specular_color = color;
+                true
+            },
+            |x| {
+                x.specular_color = color;
+                true
+            },
+        )
+    }
+
+    /// Returns the emissive color of `row`.
+    pub fn emissive_color(&self, row: usize) -> Option<[f32; 3]> {
+        self.row_value(row, |x| Some(x.emissive_color), |x| Some(x.emissive_color))
+    }
+
+    pub fn set_emissive_color(&mut self, row: usize, color: [f32; 3]) -> crate::Result<()> {
+        self.set_row_value(
+            row,
+            "emissive color",
+            |x| {
+                x.emissive_color = color;
+                true
+            },
+            |x| {
+                x.emissive_color = color;
+                true
+            },
+        )
+    }
+
+    /// Returns how strongly the specular color of `row` is applied. Only legacy tables have this.
+    pub fn specular_strength(&self, row: usize) -> Option<f32> {
+        self.row_value(row, |x| Some(x.specular_strength), |_| None)
+    }
+
+    pub fn set_specular_strength(&mut self, row: usize, strength: f32) -> crate::Result<()> {
+        self.set_row_value(
+            row,
+            "specular strength",
+            |x| {
+                x.specular_strength = strength;
+                true
+            },
+            |_| false,
+        )
+    }
+
+    /// Returns the gloss of `row`. Only legacy tables have this, Dawntrail ones use roughness instead.
+    pub fn gloss_strength(&self, row: usize) -> Option<f32> {
+        self.row_value(row, |x| Some(x.gloss_strength), |_| None)
+    }
+
+    pub fn set_gloss_strength(&mut self, row: usize, gloss: f32) -> crate::Result<()> {
+        self.set_row_value(
+            row,
+            "gloss",
+            |x| {
+                x.gloss_strength = gloss;
+                true
+            },
+            |_| false,
+        )
+    }
+
+    /// Returns the roughness of `row`. Only Dawntrail tables have this.
+    pub fn roughness(&self, row: usize) -> Option<f32> {
+        self.row_value(row, |_| None, |x| Some(x.roughness))
+    }
+
+    pub fn set_roughness(&mut self, row: usize, roughness: f32) -> crate::Result<()> {
+        self.set_row_value(
+            row,
+            "roughness",
+            |_| false,
+            |x| {
+                x.roughness = roughness;
+                true
+            },
+        )
+    }
+
+    /// Returns the metalness of `row`. Only Dawntrail tables have this.
+    pub fn metalness(&self, row: usize) -> Option<f32> {
+        self.row_value(row, |_| None, |x| Some(x.metalness))
+    }
+
+    pub fn set_metalness(&mut self, row: usize, metalness: f32) -> crate::Result<()> {
+        self.set_row_value(
+            row,
+            "metalness",
+            |_| false,
+            |x| {
+                x.metalness = metalness;
+                true
+            },
+        )
+    }
+
+    /// Returns the sheen of `row`. Only Dawntrail tables have this.
+    pub fn sheen(&self, row: usize) -> Option<Sheen> {
+        self.row_value(
+            row,
+            |_| None,
+            |x| {
+                Some(Sheen {
+                    rate: x.sheen_rate,
+                    tint: x.sheen_tint,
+                    aperture: x.sheen_aperture,
+                })
+            },
+        )
+    }
+
+    pub fn set_sheen(&mut self, row: usize, sheen: Sheen) -> crate::Result<()> {
+        self.set_row_value(
+            row,
+            "sheen",
+            |_| false,
+            |x| {
+                x.sheen_rate = sheen.rate;
+                x.sheen_tint = sheen.tint;
+                x.sheen_aperture = sheen.aperture;
+                true
+            },
+        )
+    }
+
+    /// Returns which tile from the material's tile textures `row` uses.
+    pub fn tile_index(&self, row: usize) -> Option<u16> {
+        self.row_value(
+            row,
+            |x| Some(decode_tile_index(x.tile_set)),
+            |x| Some(decode_tile_index(x.tile_set)),
+        )
+    }
+
+    pub fn set_tile_index(&mut self, row: usize, index: u16) -> crate::Result<()> {
+        self.set_row_value(
+            row,
+            "tile index",
+            |x| {
+                x.tile_set = encode_tile_index(index);
+                true
+            },
+            |x| {
+                x.tile_set = encode_tile_index(index);
+                true
+            },
+        )
+    }
+
+    /// Returns how the tile of `row` is repeated and skewed.
+    pub fn tile_transform(&self, row: usize) -> Option<TileTransform> {
+        self.row_value(
+            row,
+            |x| {
+                Some(TileTransform {
+                    repeat: [x.material_repeat_x, x.material_repeat_y],
+                    skew: x.material_skew,
+                })
+            },
+            |x| {
+                Some(TileTransform {
+                    repeat: x.material_repeat,
+                    skew: x.material_skew,
+                })
+            },
+        )
+    }
+
+    pub fn set_tile_transform(
+        &mut self,
+        row: usize,
+        transform: TileTransform,
+    ) -> crate::Result<()> {
+        self.set_row_value(
+            row,
+            "tile transform",
+            |x| {
+                [x.material_repeat_x, x.material_repeat_y] = transform.repeat;
+                x.material_skew = transform.skew;
+                true
+            },
+            |x| {
+                x.material_repeat = transform.repeat;
+                x.material_skew = transform.skew;
+                true
+            },
+        )
+    }
+
+    /// Reads a property from `row`, with `legacy` or `dawntrail` depending on the layout.
+    fn row_value<T>(
+        &self,
+        row: usize,
+        legacy: impl FnOnce(&LegacyColorTableRow) -> Option<T>,
+        dawntrail: impl FnOnce(&DawntrailColorTableRow) -> Option<T>,
+    ) -> Option<T> {
+        match self {
+            LegacyColorTable(table) => table.rows.get(row).and_then(legacy),
+            DawntrailColorTable(table) => table.rows.get(row).and_then(dawntrail),
+            OpaqueColorTable(_) => None,
+        }
+    }
+
+    /// Changes a property of `row`, with `legacy` or `dawntrail` depending on the layout. They return false if the layout doesn't have the property.
+    fn set_row_value(
+        &mut self,
+        row: usize,
+        property: &str,
+        legacy: impl FnOnce(&mut LegacyColorTableRow) -> bool,
+        dawntrail: impl FnOnce(&mut DawntrailColorTableRow) -> bool,
+    ) -> crate::Result<()> {
+        let supported = match self {
+            LegacyColorTable(table) => table.rows.get_mut(row).map(legacy),
+            DawntrailColorTable(table) => table.rows.get_mut(row).map(dawntrail),
+            OpaqueColorTable(_) => Some(false),
+        };
+
+        match supported {
+            Some(true) => Ok(()),
+            Some(false) => Err(crate::Error::InvalidData {
+                reason: format!("this color table layout doesn't have a {property}"),
+            }),
+            None => Err(crate::Error::InvalidData {
+                reason: format!("color table row {row} doesn't exist"),
+            }),
+        }
+    }
+
+    /// Converts this table to the 32-row layout introduced in Dawntrail. Tables that are already in that layout are returned as-is.
+    ///
+    /// Dawntrail blends between pairs of rows where older versions picked a single one, so each legacy row becomes two identical rows. Specular strength is folded into the specular color and gloss is approximated as roughness, since Dawntrail has neither. Everything else starts out from [`DawntrailColorTableRow::default`].
+    pub fn to_dawntrail(&self) -> crate::Result<ColorTable> {
+        match self {
+            LegacyColorTable(table) => {
+                let rows = table
+                    .rows
+                    .iter()
+                    .flat_map(|row| {
+                        let converted = DawntrailColorTableRow {
+                            diffuse_color: row.diffuse_color,
+                            specular_color: row.specular_color.map(|x| x * row.specular_strength),
+                            emissive_color: row.emissive_color,
+                            roughness: gloss_to_roughness(row.gloss_strength),
+                            tile_set: row.tile_set,
+                            material_repeat: [row.material_repeat_x, row.material_repeat_y],
+                            material_skew: row.material_skew,
+                            ..Default::default()
+                        };
+                        [converted; 2]
+                    })
+                    .collect();
+
+                Ok(DawntrailColorTable(DawntrailColorTableData { rows }))
+            }
+            DawntrailColorTable(_) => Ok(self.clone()),
+            OpaqueColorTable(_) => Err(crate::Error::InvalidData {
+                reason: "can't convert a color table in an unsupported layout".to_string(),
+            }),
+        }
+    }
+
+    /// Exports this table as JSON, which can be read back with [`ColorTable::from_json`].
+    ///
+    /// Each row is an object keyed by property name. Tile indices are stored decoded, along with the raw `tile_set` they came from.
+    pub fn to_json(&self) -> crate::Result<String> {
+        let (layout, rows) = match self {
+            LegacyColorTable(table) => ("legacy", table.rows.iter().map(|x| x.json()).collect()),
+            DawntrailColorTable(table) => {
+                ("dawntrail", table.rows.iter().map(|x| x.json()).collect())
+            }
+            OpaqueColorTable(_) => {
+                return Err(crate::Error::InvalidData {
+                    reason: "can't export a color table in an unsupported layout".to_string(),
+                });
+            }
+        };
+
+        Ok(JsonValue::Object(vec![
+            ("layout".to_string(), JsonValue::String(layout.to_string())),
+            ("rows".to_string(), JsonValue::Array(rows)),
+        ])
+        .to_pretty_string())
+    }
+
+    /// Imports a table exported with [`ColorTable::to_json`].
+    pub fn from_json(text: &str) -> crate::Result<ColorTable> {
+        let invalid = |reason: String| crate::Error::InvalidData { reason };
+
+        let root = JsonValue::parse(text)?;
+        let rows = root
+            .get("rows")
+            .and_then(|x| x.as_array())
+            .ok_or_else(|| invalid("color table is missing its rows".to_string()))?;
+        let rows = rows
+            .iter()
+            .enumerate()
+            .map(|(index, value)| JsonRow { value, index });
+
+        let (table, expected) = match root.get("layout").and_then(|x| x.as_str()) {
+            Some("legacy") => (
+                LegacyColorTable(LegacyColorTableData {
+                    rows: rows
+                        .map(|x| LegacyColorTableRow::from_json(&x))
+                        .collect::<crate::Result<_>>()?,
+                }),
+                16,
+            ),
+            Some("dawntrail") => (
+                DawntrailColorTable(DawntrailColorTableData {
+                    rows: rows
+                        .map(|x| DawntrailColorTableRow::from_json(&x))
+                        .collect::<crate::Result<_>>()?,
+                }),
+                32,
+            ),
+            _ => return Err(invalid("unknown color table layout".to_string())),
+        };
+
+        if table.row_count() != expected {
+            return Err(invalid(format!(
+                "color table has {} rows instead of {expected}",
+                table.row_count()
+            )));
+        }
+
+        Ok(table)
+    }
+}
+
+/// How the tile texture of a color table row is placed on the surface.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileTransform {
+    /// How many times the tile repeats horizontally and vertically.
+    pub repeat: [f32; 2],
+    /// How much the tile is skewed horizontally and vertically.
+    pub skew: [f32; 2],
+}
+
+/// The cloth-like sheen of a Dawntrail color table row.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sheen {
+    pub rate: f32,
+    /// How much the sheen takes on the diffuse color.
+    pub tint: f32,
+    pub aperture: f32,
+}
+
+/// Color table values are stored as halves, but exposed as floats.
+fn write_half(value: &f32) -> u16 {
+    f16::from_f32(*value).to_bits()
+}
+
+fn write_halves<const N: usize>(values: &[f32; N]) -> [u16; N] {
+    values.map(|x| f16::from_f32(x).to_bits())
+}
+
+/// Tile indices are stored as a half of `index / 64`, instead of an integer.
+fn decode_tile_index(raw: u16) -> u16 {
+    (f16::from_bits(raw).to_f32() * 64.0) as u16
+}
+
+fn encode_tile_index(index: u16) -> u16 {
+    // Aim for the middle, so rounding to a half doesn't land on the previous index
+    f16::from_f32((index as f32 + 0.5) / 64.0).to_bits()
+}
+
+/// Approximates roughness from a Blinn-Phong gloss exponent.
+fn gloss_to_roughness(gloss: f32) -> f32 {
+    (2.0 / (gloss.max(0.0) + 2.0)).sqrt().sqrt()
+}
+
+/// Writes a float to JSON, as a string if it can't be written as a number.
+fn json_float(value: f32) -> JsonValue {
+    if value.is_finite() {
+        JsonValue::number(value)
+    } else {
+        JsonValue::String(value.to_string())
+    }
+}
+
+fn json_floats(values: &[f32]) -> JsonValue {
+    JsonValue::Array(values.iter().map(|x| json_float(*x)).collect())
+}
+
+/// A row of a color table exported as JSON.
+struct JsonRow<'a> {
+    value: &'a JsonValue,
+    index: usize,
+}
+
+impl JsonRow<'_> {
+    fn missing(&self, key: &str) -> crate::Error {
+        crate::Error::InvalidData {
+            reason: format!("color table row {} has an invalid {key}", self.index),
+        }
+    }
+
+    fn number<T: std::str::FromStr>(&self, key: &str) -> crate::Result<T> {
+        self.value
+            .get(key)
+            .and_then(|x| x.as_number())
+            .ok_or_else(|| self.missing(key))
+    }
+
+    fn numbers<const N: usize>(&self, key: &str) -> crate::Result<[f32; N]> {
+        let values = self
+            .value
+            .get(key)
+            .and_then(|x| x.as_array())
+            .filter(|x| x.len() == N)
+            .ok_or_else(|| self.missing(key))?;
+
+        let mut result = [0.0; N];
+        for (result, value) in result.iter_mut().zip(values) {
+            *result = value.as_number().ok_or_else(|| self.missing(key))?;
+        }
+        Ok(result)
+    }
+
+    /// Reads the raw tile set if it still matches the tile index, so the bits the index doesn't cover survive a round trip. Otherwise the tile index was edited, and is encoded again.
+    fn tile_set(&self) -> crate::Result<u16> {
+        let index = self.number("tile_index")?;
+        match self.value.get("tile_set") {
+            Some(raw) => {
+                let raw = raw.as_number().ok_or_else(|| self.missing("tile_set"))?;
+                if decode_tile_index(raw) == index {
+                    Ok(raw)
+                } else {
+                    Ok(encode_tile_index(index))
+                }
+            }
+            None => Ok(encode_tile_index(index)),
+        }
+    }
+}
+
+impl LegacyColorTableRow {
+    fn json(&self) -> JsonValue {
+        JsonValue::Object(vec![
+            (
+                "diffuse_color".to_string(),
+                json_floats(&self.diffuse_color),
+            ),
+            (
+                "specular_strength".to_string(),
+                json_float(self.specular_strength),
+            ),
+            (
+                "specular_color".to_string(),
+                json_floats(&self.specular_color),
+            ),
+            (
+                "gloss_strength".to_string(),
+                json_float(self.gloss_strength),
+            ),
+            (
+                "emissive_color".to_string(),
+                json_floats(&self.emissive_color),
+            ),
+            (
+                "tile_index".to_string(),
+                JsonValue::number(decode_tile_index(self.tile_set)),
+            ),
+            ("tile_set".to_string(), JsonValue::number(self.tile_set)),
+            (
+                "material_repeat".to_string(),
+                json_floats(&[self.material_repeat_x, self.material_repeat_y]),
+            ),
+            (
+                "material_skew".to_string(),
+                json_floats(&self.material_skew),
+            ),
+        ])
+    }
+
+    fn from_json(row: &JsonRow) -> crate::Result<Self> {
+        let [material_repeat_x, material_repeat_y] = row.numbers("material_repeat")?;
+        Ok(Self {
+            diffuse_color: row.numbers("diffuse_color")?,
+            specular_strength: row.number("specular_strength")?,
+            specular_color: row.numbers("specular_color")?,
+            gloss_strength: row.number("gloss_strength")?,
+            emissive_color: row.numbers("emissive_color")?,
+            tile_set: row.tile_set()?,
+            material_repeat_x,
+            material_skew: row.numbers("material_skew")?,
+            material_repeat_y,
+        })
+    }
+}
+
+impl DawntrailColorTableRow {
+    fn json(&self) -> JsonValue {
+        let floats = [
+            ("unknown1", self.unknown1),
+            ("unknown2", self.unknown2),
+            ("unknown3", self.unknown3),
+            ("sheen_rate", self.sheen_rate),
+            ("sheen_tint", self.sheen_tint),
+            ("sheen_aperture", self.sheen_aperture),
+            ("unknown4", self.unknown4),
+            ("roughness", self.roughness),
+            ("unknown5", self.unknown5),
+            ("metalness", self.metalness),
+            ("anisotropy", self.anisotropy),
+            ("unknown6", self.unknown6),
+            ("sphere_mask", self.sphere_mask),
+            ("unknown7", self.unknown7),
+            ("unknown8", self.unknown8),
+            ("tile_alpha", self.tile_alpha),
+        ];
+
+        let mut entries = vec![
+            (
+                "diffuse_color".to_string(),
+                json_floats(&self.diffuse_color),
+            ),
+            (
+                "specular_color".to_string(),
+                json_floats(&self.specular_color),
+            ),
+            (
+                "emissive_color".to_string(),
+                json_floats(&self.emissive_color),
+            ),
+        ];
+        entries.extend(
+            floats
+                .iter()
+                .map(|(key, value)| (key.to_string(), json_float(*value))),
+        );
+        entries.extend([
+            (
+                "shader_index".to_string(),
+                JsonValue::number(self.shader_index),
+            ),
+            (
+                "tile_index".to_string(),
+                JsonValue::number(decode_tile_index(self.tile_set)),
+            ),
+            ("tile_set".to_string(), JsonValue::number(self.tile_set)),
+            (
+                "sphere_index".to_string(),
+                JsonValue::number(self.sphere_index),
+            ),
+            (
+                "material_repeat".to_string(),
+                json_floats(&self.material_repeat),
+            ),
+            (
+                "material_skew".to_string(),
+                json_floats(&self.material_skew),
+            ),
+        ]);
+        JsonValue::Object(entries)
+    }
+
+    fn from_json(row: &JsonRow) -> crate::Result<Self> {
+        Ok(Self {
+            diffuse_color: row.numbers("diffuse_color")?,
+            unknown1: row.number("unknown1")?,
+            specular_color: row.numbers("specular_color")?,
+            unknown2: row.number("unknown2")?,
+            emissive_color: row.numbers("emissive_color")?,
+            unknown3: row.number("unknown3")?,
+            sheen_rate: row.number("sheen_rate")?,
+            sheen_tint: row.number("sheen_tint")?,
+            sheen_aperture: row.number("sheen_aperture")?,
+            unknown4: row.number("unknown4")?,
+            roughness: row.number("roughness")?,
+            unknown5: row.number("unknown5")?,
+            metalness: row.number("metalness")?,
+            anisotropy: row.number("anisotropy")?,
+            unknown6: row.number("unknown6")?,
+            sphere_mask: row.number("sphere_mask")?,
+            unknown7: row.number("unknown7")?,
+            unknown8: row.number("unknown8")?,
+            shader_index: row.number("shader_index")?,
+            tile_set: row.tile_set()?,
+            tile_alpha: row.number("tile_alpha")?,
+            sphere_index: row.number("sphere_index")?,
+            material_repeat: row.numbers("material_repeat")?,
+            material_skew: row.numbers("material_skew")?,
+        })
+    }
+}
+
+impl Default for DawntrailColorTableRow {
+    /// A plain white, non-metallic row that tiles the same way legacy materials usually do.
+    fn default() -> Self {
+        Self {
+            diffuse_color: [1.0; 3],
+            unknown1: 1.0,
+            specular_color: [1.0; 3],
+            unknown2: 1.0,
+            emissive_color: [0.0; 3],
+            unknown3: 1.0,
+            sheen_rate: 0.1,
+            sheen_tint: 0.2,
+            sheen_aperture: 5.0,
+            unknown4: 0.0,
+            roughness: 0.5,
+            unknown5: 0.0,
+            metalness: 0.0,
+            anisotropy: 0.0,
+            unknown6: 0.0,
+            sphere_mask: 0.0,
+            unknown7: 0.0,
+            unknown8: 0.0,
+            shader_index: 0,
+            tile_set: 0,
+            tile_alpha: 1.0,
+            sphere_index: 0,
+            material_repeat: [16.0; 2],
+            material_skew: [0.0; 2],
+        }
+    }
+}
+
 #[binrw]
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct LegacyColorDyeTableRow {
     #[br(temp)]
-    #[bw(calc = 0)] // TODO: lol no
+    #[bw(calc = template << 5
+        | *diffuse as u16
+        | (*specular as u16) << 1
+        | (*emissive as u16) << 2
+        | (*gloss as u16) << 3
+        | (*specular_strength as u16) << 4)]
     data: u16,
 
     #[br(calc = data >> 5)]
@@ -224,7 +1019,20 @@ pub struct LegacyColorDyeTableRow {
 #[allow(dead_code)]
 pub struct DawntrailColorDyeTableRow {
     #[br(temp)]
-    #[bw(calc = 0)] // TODO: lol no
+    #[bw(calc = (*template as u32 & 0x7FF) << 16
+        | (*channel as u32 & 0x3) << 27
+        | *diffuse as u32
+        | (*specular as u32) << 1
+        | (*emissive as u32) << 2
+        | (*scalar3 as u32) << 3
+        | (*metalness as u32) << 4
+        | (*roughness as u32) << 5
+        | (*sheen_rate as u32) << 6
+        | (*sheen_tint_rate as u32) << 7
+        | (*sheen_aperture as u32) << 8
+        | (*anisotropy as u32) << 9
+        | (*sphere_map_index as u32) << 10
+        | (*sphere_map_mask as u32) << 11)]
     data: u32,
 
     #[br(calc = ((data >> 16) & 0x7FF) as u16)]
@@ -316,6 +1124,47 @@ pub enum ColorDyeTable {
     OpaqueColorDyeTable(OpaqueColorDyeTableData),
 }
 
+impl ColorDyeTable {
+    /// Converts this dye table to the layout introduced in Dawntrail, to go along with [`ColorTable::to_dawntrail`]. Tables that are already in that layout are returned as-is.
+    ///
+    /// Like the color table, each legacy row becomes a pair of rows on the first channel. Gloss dyeing becomes roughness dyeing, and specular strength dyeing is dropped since Dawntrail doesn't have it. The templates are kept, so they may need to be pointed at a Dawntrail staining template afterwards.
+    pub fn to_dawntrail(&self) -> crate::Result<ColorDyeTable> {
+        match self {
+            LegacyColorDyeTable(table) => {
+                let rows = table
+                    .rows
+                    .iter()
+                    .flat_map(|row| {
+                        let converted = DawntrailColorDyeTableRow {
+                            template: row.template,
+                            channel: 0,
+                            diffuse: row.diffuse,
+                            specular: row.specular,
+                            emissive: row.emissive,
+                            scalar3: false,
+                            metalness: false,
+                            roughness: row.gloss,
+                            sheen_rate: false,
+                            sheen_tint_rate: false,
+                            sheen_aperture: false,
+                            anisotropy: false,
+                            sphere_map_index: false,
+                            sphere_map_mask: false,
+                        };
+                        [converted.clone(), converted]
+                    })
+                    .collect();
+
+                Ok(DawntrailColorDyeTable(DawntrailColorDyeTableData { rows }))
+            }
+            DawntrailColorDyeTable(_) => Ok(self.clone()),
+            OpaqueColorDyeTable(_) => Err(crate::Error::InvalidData {
+                reason: "can't convert a dye table in an unsupported layout".to_string(),
+            }),
+        }
+    }
+}
+
 #[binrw]
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
@@ -355,6 +1204,25 @@ pub struct Sampler {
     texture_index: u8,
 }
 
+impl Constant {
+    /// CRC32 of the constant name, see [MaterialResolver] to get it back.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// The values of this constant, of which only the first four are kept. See [MaterialResolver::constants] to get all of them.
+    pub fn values(&self) -> &[f32] {
+        &self.values[..(self.num_values as usize).min(self.values.len())]
+    }
+}
+
+impl Sampler {
+    /// Index into [Material::texture_paths] of the texture bound to this sampler.
+    pub fn texture_index(&self) -> u8 {
+        self.texture_index
+    }
+}
+
 #[binrw::parser(reader, endian)]
 fn parse_color_table(table_dimension_logs: u8) -> BinResult<Option<ColorTable>> {
     // NOTE: the unwrap_or_default is intentional, as some materials (like `bg/ffxiv/fst_f1/fld/common/material/f1f0_treesdw04a.mtrl`) have a color table but its not actually written.
@@ -457,6 +1325,61 @@ struct MaterialData {
     shader_values: Vec<f32>,
 }
 
+impl MaterialData {
+    /// Stores new table flags, and resizes the file for a table that went from `old_size` to `new_size` bytes.
+    fn set_table_data(
+        &mut self,
+        flags: u32,
+        old_size: usize,
+        new_size: usize,
+    ) -> crate::Result<()> {
+        let too_large = || crate::Error::InvalidData {
+            reason: "material is too large for this color table".to_string(),
+        };
+
+        // The flags live in the additional data, which some materials don't have
+        let added_data = 4usize.saturating_sub(self.additional_data.len());
+        let resize = |size: u16, added: usize| {
+            (size as usize + added + new_size)
+                .checked_sub(old_size)
+                .and_then(|x| u16::try_from(x).ok())
+                .ok_or_else(too_large)
+        };
+        let file_size = resize(self.file_header.file_size, added_data)?;
+        let data_set_size = resize(self.file_header.data_set_size, 0)?;
+
+        if added_data > 0 {
+            self.additional_data.resize(4, 0);
+            self.file_header.additional_data_size = 4;
+        }
+        self.additional_data[0..4].copy_from_slice(&flags.to_le_bytes());
+        self.file_header.file_size = file_size;
+        self.file_header.data_set_size = data_set_size;
+
+        self.table_flags = flags;
+        self.has_table = (flags & 0x4) != 0;
+        self.has_dye_table = (flags & 0x8) != 0;
+        self.table_width_log = ((flags >> 4) & 0xF) as u8;
+        self.table_height_log = ((flags >> 8) & 0xF) as u8;
+        self.table_dimension_logs = (flags >> 4) as u8;
+        self.is_dawntrail =
+            !self.has_table || self.table_width_log != 0 && self.table_height_log != 0;
+
+        Ok(())
+    }
+}
+
+/// Returns how many bytes `value` takes up in a file.
+fn written_size<T>(value: &T) -> usize
+where
+    T: for<'a> BinWrite<Args<'a> = ()>,
+{
+    let mut cursor = Cursor::new(Vec::new());
+    // Writing to memory can't fail
+    value.write_le(&mut cursor).unwrap();
+    cursor.into_inner().len()
+}
+
 /// Material file, usually with the `.mtrl` file extension.
 ///
 /// Contains general information about a material, such as which textures it uses.
@@ -468,7 +1391,9 @@ pub struct Material {
     pub shader_keys: Vec<ShaderKey>,
     pub constants: Vec<Constant>,
     pub samplers: Vec<Sampler>,
+    /// A copy of the color table, which is changed with [Material::set_color_table].
     pub color_table: Option<ColorTable>,
+    /// A copy of the dye table, which is changed with [Material::set_color_dye_table].
     pub color_dye_table: Option<ColorDyeTable>,
 }
 
@@ -507,7 +1432,11 @@ impl ReadableFile for Material {
                 // TODO: use mem::size_of
                 let num_floats = constant.value_size / VALUE_SIZE;
                 for (i, value) in values.iter_mut().enumerate().take(num_floats as usize) {
-                    *value = mat_data.shader_values[(constant.value_offset as usize / 4) + i];
+                    *value = mat_data
+                        .shader_values
+                        .get((constant.value_offset as usize / 4) + i)
+                        .copied()
+                        .unwrap_or_default();
                 }
 
                 constants.push(Constant {
@@ -551,11 +1480,217 @@ impl WritableFile for Material {
 }
 
 impl Material {
+    /// Sets the values of constant `id`, adding it if the material doesn't have it yet.
+    ///
+    /// Only up to 4 values are supported, since that's all [Constant] can hold.
+    fn set_constant_values(&mut self, id: u32, values: &[f32]) -> crate::Result<()> {
+        const VALUE_SIZE: u16 = std::mem::size_of::<f32>() as u16;
+
+        if values.len() > 4 {
+            return Err(crate::Error::InvalidData {
+                reason: format!("constants can have up to 4 values, not {}", values.len()),
+            });
+        }
+
+        let data = &mut self.mat_data;
+        match data.constants.iter().find(|x| x.constant_id == id) {
+            Some(constant) => {
+                if constant.value_size != values.len() as u16 * VALUE_SIZE {
+                    return Err(crate::Error::InvalidData {
+                        reason: format!(
+                            "constant {id:#X} has {} values, not {}",
+                            constant.value_size / VALUE_SIZE,
+                            values.len()
+                        ),
+                    });
+                }
+
+                let start = constant.value_offset as usize / VALUE_SIZE as usize;
+                data.shader_values
+                    .get_mut(start..start + values.len())
+                    .ok_or_else(|| crate::Error::InvalidData {
+                        reason: format!("constant {id:#X} points outside of the shader values"),
+                    })?
+                    .copy_from_slice(values);
+            }
+            None => {
+                let value_offset = data.header.shader_value_list_size;
+                let value_size = values.len() as u16 * VALUE_SIZE;
+                let too_large = || crate::Error::InvalidData {
+                    reason: "material is too large to add another constant".to_string(),
+                };
+                let shader_value_list_size =
+                    value_offset.checked_add(value_size).ok_or_else(too_large)?;
+                let file_size = data
+                    .file_header
+                    .file_size
+                    .checked_add(std::mem::size_of::<ConstantStruct>() as u16 + value_size)
+                    .ok_or_else(too_large)?;
+
+                data.constants.push(ConstantStruct {
+                    constant_id: id,
+                    value_offset,
+                    value_size,
+                });
+                data.shader_values.extend_from_slice(values);
+
+                data.header.constant_count += 1;
+                data.header.shader_value_list_size = shader_value_list_size;
+                data.file_header.file_size = file_size;
+            }
+        }
+
+        let mut constant_values = [0.0; 4];
+        constant_values[..values.len()].copy_from_slice(values);
+        let constant = Constant {
+            id,
+            num_values: values.len() as u32,
+            values: constant_values,
+        };
+        match self.constants.iter_mut().find(|x| x.id == id) {
+            Some(existing) => *existing = constant,
+            None => self.constants.push(constant),
+        }
+
+        Ok(())
+    }
+
+    /// Returns every value of constant `id`, which unlike [Constant::values] can be more than 4.
+    fn constant_values(&self, id: u32) -> Option<&[f32]> {
+        let constant = self
+            .mat_data
+            .constants
+            .iter()
+            .find(|x| x.constant_id == id)?;
+        let start = constant.value_offset as usize / std::mem::size_of::<f32>();
+        let count = constant.value_size as usize / std::mem::size_of::<f32>();
+        self.mat_data.shader_values.get(start..start + count)
+    }
+
+    /// Binds `texture_index` to the sampler `id`, adding it if the material doesn't have it yet.
+    fn set_sampler_texture(&mut self, id: u32, texture_index: u8) -> crate::Result<()> {
+        if texture_index as usize >= self.texture_paths.len() {
+            return Err(crate::Error::InvalidData {
+                reason: format!(
+                    "texture index {texture_index} is out of range, there are only {} textures",
+                    self.texture_paths.len()
+                ),
+            });
+        }
+
+        let data = &mut self.mat_data;
+        match data.samplers.iter_mut().find(|x| x.texture_usage == id) {
+            Some(sampler) => sampler.texture_index = texture_index,
+            None => {
+                data.file_header.file_size = data
+                    .file_header
+                    .file_size
+                    .checked_add(std::mem::size_of::<Sampler>() as u16)
+                    .ok_or_else(|| crate::Error::InvalidData {
+                        reason: "material is too large to add another sampler".to_string(),
+                    })?;
+
+                // We don't know what the flags mean yet, so borrow them from an existing sampler
+                let flags = data.samplers.first().map(|x| x.flags).unwrap_or_default();
+                data.samplers.push(Sampler {
+                    texture_usage: id,
+                    flags,
+                    texture_index,
+                });
+
+                data.header.sampler_count += 1;
+            }
+        }
+
+        self.samplers = data.samplers.clone();
+
+        Ok(())
+    }
+
+    /// Replaces the color table, adding one if the material doesn't have it yet. This also updates [Material::color_table].
+    ///
+    /// If the material has a dye table, it has to be in the same layout as `table`. To change the layout of both, remove the dye table with [Material::set_color_dye_table] first.
+    pub fn set_color_table(&mut self, table: ColorTable) -> crate::Result<()> {
+        let dimension_logs = match (&table, &self.mat_data.color_dye_table) {
+            (OpaqueColorTable(_), _) => {
+                return Err(crate::Error::InvalidData {
+                    reason: "can't write a color table in an unsupported layout".to_string(),
+                });
+            }
+            (LegacyColorTable(_), None | Some(LegacyColorDyeTable(_))) => 0,
+            (DawntrailColorTable(_), None | Some(DawntrailColorDyeTable(_))) => 0x53,
+            _ => {
+                return Err(crate::Error::InvalidData {
+                    reason: "the color table and dye table have different layouts".to_string(),
+                });
+            }
+        };
+
+        let old_size = self.mat_data.color_table.as_ref().map_or(0, written_size);
+        let flags = (self.mat_data.table_flags & !0xFF0) | 0x4 | dimension_logs << 4;
+        self.mat_data
+            .set_table_data(flags, old_size, written_size(&table))?;
+
+        self.mat_data.color_table = Some(table.clone());
+        self.color_table = Some(table);
+
+        Ok(())
+    }
+
+    /// Replaces the dye table, adding one if the material doesn't have it yet, or removes it if `table` is `None`. This also updates [Material::color_dye_table].
+    ///
+    /// The material has to have a color table in the same layout as `table` first, see [Material::set_color_table].
+    pub fn set_color_dye_table(&mut self, table: Option<ColorDyeTable>) -> crate::Result<()> {
+        match (&self.mat_data.color_table, &table) {
+            (_, None)
+            | (Some(LegacyColorTable(_)), Some(LegacyColorDyeTable(_)))
+            | (Some(DawntrailColorTable(_)), Some(DawntrailColorDyeTable(_))) => {}
+            (None, _) => {
+                return Err(crate::Error::InvalidData {
+                    reason: "the material needs a color table before it can have a dye table"
+                        .to_string(),
+                });
+            }
+            _ => {
+                return Err(crate::Error::InvalidData {
+                    reason: "the color table and dye table have different layouts".to_string(),
+                });
+            }
+        }
+
+        let old_size = self
+            .mat_data
+            .color_dye_table
+            .as_ref()
+            .map_or(0, written_size);
+        let (flags, new_size) = match &table {
+            Some(table) => (self.mat_data.table_flags | 0x8, written_size(table)),
+            None => (self.mat_data.table_flags & !0x8, 0),
+        };
+        self.mat_data.set_table_data(flags, old_size, new_size)?;
+
+        self.mat_data.color_dye_table = table.clone();
+        self.color_dye_table = table;
+
+        Ok(())
+    }
+
     /// Builds a material path for a specific gear
     pub fn gear_material_path(gear_id: i32, gear_version: i32, material_name: &str) -> String {
         format!("chara/equipment/e{gear_id:04}/material/v{gear_version:04}{material_name}")
     }
 
+    /// Builds a material path for a specific accessory
+    pub fn accessory_material_path(
+        accessory_id: i32,
+        accessory_version: i32,
+        material_name: &str,
+    ) -> String {
+        format!(
+            "chara/accessory/a{accessory_id:04}/material/v{accessory_version:04}{material_name}"
+        )
+    }
+
     /// Builds a skin material path for a character
     pub fn skin_material_path(race_code: i32, body_code: i32, material_name: &str) -> String {
         format!(
@@ -588,6 +1723,296 @@ impl Material {
     }
 }
 
+/// Names of material constants and shader keys that aren't stored in shader packages, but are known from the game's shaders.
+const KNOWN_NAMES: &[&str] = &[
+    "g_AlphaAperture",
+    "g_AlphaOffset",
+    "g_AlphaThreshold",
+    "g_AmbientOcclusionMask",
+    "g_AngleClip",
+    "g_Color",
+    "g_ColorUVScale",
+    "g_DiffuseColor",
+    "g_EmissiveColor",
+    "g_EnvMapPower",
+    "g_Fresnel",
+    "g_FresnelValue0",
+    "g_GlassIOR",
+    "g_GlassThicknessMax",
+    "g_HairBackScatterRoughnessOffsetRate",
+    "g_HairScatterColorShift",
+    "g_HairSecondaryRoughnessOffsetRate",
+    "g_HairSpecularBackScatterShift",
+    "g_HairSpecularPrimaryShift",
+    "g_HairSpecularSecondaryShift",
+    "g_InclusionAperture",
+    "g_IrisRingColor",
+    "g_IrisRingEmissiveIntensity",
+    "g_IrisUvRadius",
+    "g_LipRoughnessScale",
+    "g_NormalScale",
+    "g_OutlineColor",
+    "g_OutlineWidth",
+    "g_ScatteringLevel",
+    "g_ShaderID",
+    "g_SheenAperture",
+    "g_SheenRate",
+    "g_SheenTintRate",
+    "g_Shininess",
+    "g_SpecularColor",
+    "g_SpecularColorMask",
+    "g_SSAOMask",
+    "g_SubSurfaceProfileID",
+    "g_SubSurfaceWidth",
+    "g_TextureMipBias",
+    "g_TileAlpha",
+    "g_TileIndex",
+    "g_TileScale",
+    "g_ToonIndex",
+    "g_ToonSpecIndex",
+    "g_WaveSpeed",
+    "g_WaveTime",
+    "CategoryFlowMapType",
+    "CategoryHairType",
+    "CategorySkinType",
+    "CategorySpecularType",
+    "CategoryTextureType",
+    "ValueBody",
+    "ValueBodyJJM",
+    "ValueFace",
+    "ValueHair",
+    "ValueFur",
+    "ValueMask",
+];
+
+/// A material constant, along with its name if it could be resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedConstant {
+    /// CRC32 of the name.
+    pub id: u32,
+    pub name: Option<String>,
+    pub values: Vec<f32>,
+    /// Whether the material doesn't set this constant, so `values` comes from the shader package.
+    pub is_default: bool,
+}
+
+/// A material sampler, along with its name if it could be resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedSampler {
+    /// CRC32 of the name.
+    pub id: u32,
+    pub name: Option<String>,
+    /// Index into [Material::texture_paths].
+    pub texture_index: u8,
+}
+
+/// A material shader key, along with the names of its category and value if they could be resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedShaderKey {
+    /// CRC32 of the category name.
+    pub category: u32,
+    pub category_name: Option<String>,
+    /// CRC32 of the value name.
+    pub value: u32,
+    pub value_name: Option<String>,
+    /// Whether the material doesn't set this key, so `value` comes from the shader package.
+    pub is_default: bool,
+}
+
+/// Resolves the CRC32 IDs in a [Material] to names, and fills in missing values using the [ShaderPackage] it references.
+///
+/// Sampler names are read from the shader package. Constants and shader keys are only stored as IDs, so their names come from a list of known ones, which can be extended with [MaterialResolver::add_name].
+#[derive(Debug, Clone)]
+pub struct MaterialResolver {
+    names: HashMap<u32, String>,
+    parameters: Vec<MaterialParameter>,
+    defaults: Vec<f32>,
+    keys: Vec<Key>,
+}
+
+impl MaterialResolver {
+    pub fn new(shader_package: &ShaderPackage) -> Self {
+        let mut resolver = Self {
+            names: HashMap::new(),
+            parameters: shader_package.material_parameters.clone(),
+            defaults: shader_package.mat_param_defaults.clone(),
+            keys: shader_package.material_keys.clone(),
+        };
+
+        for name in KNOWN_NAMES {
+            resolver.add_name(name);
+        }
+        for parameter in shader_package
+            .sampler_parameters
+            .iter()
+            .chain(&shader_package.texture_parameters)
+        {
+            resolver
+                .names
+                .insert(parameter.crc32, parameter.name.clone());
+        }
+
+        resolver
+    }
+
+    /// Makes `name` resolvable, for constants and keys that aren't known yet.
+    pub fn add_name(&mut self, name: &str) {
+        self.names
+            .insert(ShaderPackage::crc(name), name.to_string());
+    }
+
+    /// Returns the name for `id`, if it's known.
+    pub fn name(&self, id: u32) -> Option<&str> {
+        self.names.get(&id).map(|x| x.as_str())
+    }
+
+    /// Returns the ID for `name`, which is either a hexadecimal ID like `0x380CAED0` or a name to hash.
+    fn id(name: &str) -> u32 {
+        name.strip_prefix("0x")
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .unwrap_or_else(|| ShaderPackage::crc(name))
+    }
+
+    fn default_values(&self, parameter: &MaterialParameter) -> Vec<f32> {
+        let start = parameter.byte_offset as usize / 4;
+        let count = parameter.byte_size as usize / 4;
+        self.defaults
+            .get(start..start + count)
+            .map(|x| x.to_vec())
+            .unwrap_or_else(|| vec![0.0; count])
+    }
+
+    /// Lists every constant the shader package has, using the values from `material` when it sets them. Constants only the material has are included at the end.
+    pub fn constants(&self, material: &Material) -> Vec<NamedConstant> {
+        let find = |id: u32| material.constants.iter().find(|x| x.id == id);
+        let values = |constant: &Constant| {
+            material
+                .constant_values(constant.id)
+                .unwrap_or(constant.values())
+                .to_vec()
+        };
+
+        let mut constants: Vec<NamedConstant> = self
+            .parameters
+            .iter()
+            .map(|parameter| {
+                let constant = find(parameter.id);
+                NamedConstant {
+                    id: parameter.id,
+                    name: self.name(parameter.id).map(str::to_string),
+                    values: match constant {
+                        Some(constant) => values(constant),
+                        None => self.default_values(parameter),
+                    },
+                    is_default: constant.is_none(),
+                }
+            })
+            .collect();
+
+        for constant in &material.constants {
+            if !self.parameters.iter().any(|x| x.id == constant.id) {
+                constants.push(NamedConstant {
+                    id: constant.id,
+                    name: self.name(constant.id).map(str::to_string),
+                    values: values(constant),
+                    is_default: false,
+                });
+            }
+        }
+
+        constants
+    }
+
+    /// Returns the values of constant `name`, falling back to the default from the shader package.
+    pub fn constant(&self, material: &Material, name: &str) -> Option<Vec<f32>> {
+        let id = Self::id(name);
+        self.constants(material)
+            .into_iter()
+            .find(|x| x.id == id)
+            .map(|x| x.values)
+    }
+
+    /// Sets the values of constant `name`, adding it to `material` if needed.
+    ///
+    /// If the shader package knows about this constant, `values` has to be the same size. Only constants with up to 4 values can be set.
+    pub fn set_constant(
+        &self,
+        material: &mut Material,
+        name: &str,
+        values: &[f32],
+    ) -> crate::Result<()> {
+        let id = Self::id(name);
+        if let Some(parameter) = self.parameters.iter().find(|x| x.id == id)
+            && parameter.byte_size as usize != values.len() * 4
+        {
+            return Err(crate::Error::InvalidData {
+                reason: format!(
+                    "{name} expects {} values, not {}",
+                    parameter.byte_size / 4,
+                    values.len()
+                ),
+            });
+        }
+
+        material.set_constant_values(id, values)
+    }
+
+    /// Lists the samplers in `material`.
+    pub fn samplers(&self, material: &Material) -> Vec<NamedSampler> {
+        material
+            .samplers
+            .iter()
+            .map(|sampler| NamedSampler {
+                id: sampler.texture_usage,
+                name: self.name(sampler.texture_usage).map(str::to_string),
+                texture_index: sampler.texture_index,
+            })
+            .collect()
+    }
+
+    /// Binds the texture at `texture_index` to sampler `name`, adding the sampler to `material` if needed.
+    ///
+    /// What the flags of a sampler mean isn't known yet, so a new sampler copies them from the first one in `material`, or leaves them zeroed if there isn't any. This is only a guess, and may not be what the game expects for that sampler.
+    pub fn set_sampler(
+        &self,
+        material: &mut Material,
+        name: &str,
+        texture_index: u8,
+    ) -> crate::Result<()> {
+        material.set_sampler_texture(Self::id(name), texture_index)
+    }
+
+    /// Lists every material key the shader package has, using the values from `material` when it sets them.
+    pub fn shader_keys(&self, material: &Material) -> Vec<NamedShaderKey> {
+        let named = |category: u32, value: u32, is_default: bool| NamedShaderKey {
+            category,
+            category_name: self.name(category).map(str::to_string),
+            value,
+            value_name: self.name(value).map(str::to_string),
+            is_default,
+        };
+
+        let mut keys: Vec<NamedShaderKey> = self
+            .keys
+            .iter()
+            .map(
+                |key| match material.shader_keys.iter().find(|x| x.category == key.id) {
+                    Some(set) => named(key.id, set.value, false),
+                    None => named(key.id, key.default_value, true),
+                },
+            )
+            .collect();
+
+        for key in &material.shader_keys {
+            if !self.keys.iter().any(|x| x.id == key.category) {
+                keys.push(named(key.category, key.value, false));
+            }
+        }
+
+        keys
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::pass_random_invalid;
@@ -598,4 +2023,601 @@ mod tests {
     fn test_invalid() {
         pass_random_invalid::<Material>();
     }
+
+    /// Builds a staining template file with a single `template`. Each color and scalar either has one value for every stain, or a value per stain.
+    fn stm(version: u16, template: u32, colors: &[Vec<[f32; 3]>], scalars: &[Vec<f32>]) -> Stm {
+        let half = |x: f32| f16::from_f32(x).to_bits().to_le_bytes();
+
+        let mut arrays: Vec<Vec<u8>> = colors
+            .iter()
+            .map(|values| values.iter().flatten().flat_map(|x| half(*x)).collect())
+            .collect();
+        arrays.extend(
+            scalars
+                .iter()
+                .map(|values| values.iter().flat_map(|x| half(*x)).collect()),
+        );
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&0x534Du16.to_le_bytes());
+        data.extend_from_slice(&version.to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data.extend_from_slice(&template.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+
+        // Where each array ends, in halves
+        let mut end = 0;
+        for array in &arrays {
+            end += array.len() as u16 / 2;
+            data.extend_from_slice(&end.to_le_bytes());
+        }
+        for array in arrays {
+            data.extend(array);
+        }
+
+        Stm::from_existing(Platform::Win32, &data).unwrap()
+    }
+
+    fn legacy_row() -> LegacyColorTableRow {
+        LegacyColorTableRow {
+            diffuse_color: [1.0; 3],
+            specular_strength: 1.0,
+            specular_color: [1.0; 3],
+            gloss_strength: 1.0,
+            emissive_color: [0.0; 3],
+            tile_set: 0,
+            material_repeat_x: 1.0,
+            material_skew: [0.0; 2],
+            material_repeat_y: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_apply_legacy_dye() {
+        // Every stain has a different diffuse color
+        let diffuse: Vec<[f32; 3]> = (0..254).map(|i| [i as f32 / 256.0, 0.0, 0.0]).collect();
+        let stm = stm(
+            0x101,
+            5,
+            &[diffuse, vec![[0.5; 3]], vec![[0.25; 3]]],
+            &[vec![2.0], vec![0.5]],
+        );
+
+        let table = LegacyColorTable(LegacyColorTableData {
+            rows: vec![legacy_row(); 16],
+        });
+        let dye_row = |template, diffuse, gloss| LegacyColorDyeTableRow {
+            template,
+            diffuse,
+            specular: false,
+            emissive: false,
+            gloss,
+            specular_strength: false,
+        };
+        let mut rows = vec![dye_row(0, false, false); 16];
+        rows[0] = dye_row(5, true, true);
+        rows[1] = dye_row(5, false, false);
+        // This template doesn't exist, so it should be left alone
+        rows[2] = dye_row(6, true, true);
+        let dye_table = LegacyColorDyeTable(LegacyColorDyeTableData { rows });
+
+        let LegacyColorTable(dyed) = table.apply_dye(&dye_table, &stm, 9, 0).unwrap() else {
+            panic!("dyeing changed the layout");
+        };
+        assert_eq!(dyed.rows[0].diffuse_color, [8.0 / 256.0, 0.0, 0.0]);
+        assert_eq!(dyed.rows[0].gloss_strength, 2.0);
+        assert_eq!(dyed.rows[0].specular_color, [1.0; 3]);
+        assert_eq!(dyed.rows[1].diffuse_color, [1.0; 3]);
+        assert_eq!(dyed.rows[2].diffuse_color, [1.0; 3]);
+
+        // Legacy materials only have one channel, and stain zero is no dye at all
+        for (stain_id, channel) in [(9, 1), (0, 0)] {
+            let LegacyColorTable(undyed) = table
+                .apply_dye(&dye_table, &stm, stain_id, channel)
+                .unwrap()
+            else {
+                panic!("dyeing changed the layout");
+            };
+            assert_eq!(undyed.rows[0].diffuse_color, [1.0; 3]);
+        }
+
+        let dawntrail = DawntrailColorDyeTable(DawntrailColorDyeTableData { rows: vec![] });
+        assert!(table.apply_dye(&dawntrail, &stm, 9, 0).is_err());
+    }
+
+    #[test]
+    fn test_apply_dawntrail_dye() {
+        let mut scalars = vec![vec![0.0]; 9];
+        scalars[1] = vec![0.75]; // metalness
+        scalars[7] = vec![f16::from_bits(3).to_f32()]; // sphere map index, stored raw
+        let stm = stm(
+            0x201,
+            1000,
+            &[vec![[0.5, 0.25, 0.0]], vec![[0.0; 3]], vec![[0.0; 3]]],
+            &scalars,
+        );
+
+        let row = DawntrailColorTableRow {
+            diffuse_color: [1.0; 3],
+            unknown1: 0.0,
+            specular_color: [1.0; 3],
+            unknown2: 0.0,
+            emissive_color: [0.0; 3],
+            unknown3: 0.0,
+            sheen_rate: 0.0,
+            sheen_tint: 0.0,
+            sheen_aperture: 0.0,
+            unknown4: 0.0,
+            roughness: 0.5,
+            unknown5: 0.0,
+            metalness: 0.0,
+            anisotropy: 0.0,
+            unknown6: 0.0,
+            sphere_mask: 0.0,
+            unknown7: 0.0,
+            unknown8: 0.0,
+            shader_index: 0,
+            tile_set: 0,
+            tile_alpha: 1.0,
+            sphere_index: 0,
+            material_repeat: [1.0; 2],
+            material_skew: [0.0; 2],
+        };
+        let table = DawntrailColorTable(DawntrailColorTableData {
+            rows: vec![row; 32],
+        });
+
+        let dye_row = |channel| DawntrailColorDyeTableRow {
+            template: 1000,
+            channel,
+            diffuse: true,
+            specular: false,
+            emissive: false,
+            scalar3: false,
+            metalness: true,
+            roughness: false,
+            sheen_rate: false,
+            sheen_tint_rate: false,
+            sheen_aperture: false,
+            anisotropy: false,
+            sphere_map_index: true,
+            sphere_map_mask: false,
+        };
+        let mut rows = vec![dye_row(0); 32];
+        rows[1] = dye_row(1);
+        let dye_table = DawntrailColorDyeTable(DawntrailColorDyeTableData { rows });
+
+        // The first channel only touches rows using it
+        let DawntrailColorTable(dyed) = table.apply_dye(&dye_table, &stm, 1, 0).unwrap() else {
+            panic!("dyeing changed the layout");
+        };
+        assert_eq!(dyed.rows[0].diffuse_color, [0.5, 0.25, 0.0]);
+        assert_eq!(dyed.rows[0].metalness, 0.75);
+        assert_eq!(dyed.rows[0].sphere_index, 3);
+        assert_eq!(dyed.rows[0].roughness, 0.5);
+        assert_eq!(dyed.rows[1].diffuse_color, [1.0; 3]);
+
+        // And the second channel only touches the other rows
+        let DawntrailColorTable(dyed) = table.apply_dye(&dye_table, &stm, 1, 1).unwrap() else {
+            panic!("dyeing changed the layout");
+        };
+        assert_eq!(dyed.rows[0].diffuse_color, [1.0; 3]);
+        assert_eq!(dyed.rows[1].diffuse_color, [0.5, 0.25, 0.0]);
+    }
+
+    #[test]
+    fn test_color_table_accessors() {
+        let mut table = LegacyColorTable(LegacyColorTableData {
+            rows: vec![legacy_row(); 16],
+        });
+        assert_eq!(table.row_count(), 16);
+
+        table.set_diffuse_color(3, [0.5, 0.25, 0.0]).unwrap();
+        table.set_gloss_strength(3, 20.0).unwrap();
+        table.set_tile_index(3, 7).unwrap();
+        table
+            .set_tile_transform(
+                3,
+                TileTransform {
+                    repeat: [4.0, 8.0],
+                    skew: [0.5, 0.0],
+                },
+            )
+            .unwrap();
+        assert_eq!(table.diffuse_color(3), Some([0.5, 0.25, 0.0]));
+        assert_eq!(table.gloss_strength(3), Some(20.0));
+        assert_eq!(table.tile_index(3), Some(7));
+        assert_eq!(table.tile_transform(3).unwrap().repeat, [4.0, 8.0]);
+        assert_eq!(table.diffuse_color(16), None);
+
+        // Legacy tables don't have any of the physically based properties
+        assert_eq!(table.roughness(0), None);
+        assert!(table.set_roughness(0, 0.5).is_err());
+        assert!(table.set_diffuse_color(16, [0.0; 3]).is_err());
+
+        let mut table = DawntrailColorTable(DawntrailColorTableData {
+            rows: vec![DawntrailColorTableRow::default(); 32],
+        });
+        let sheen = Sheen {
+            rate: 0.5,
+            tint: 1.0,
+            aperture: 2.0,
+        };
+        table.set_sheen(31, sheen).unwrap();
+        table.set_metalness(31, 1.0).unwrap();
+        assert_eq!(table.sheen(31), Some(sheen));
+        assert_eq!(table.metalness(31), Some(1.0));
+        assert_eq!(table.gloss_strength(31), None);
+    }
+
+    #[test]
+    fn test_upgrade_legacy_table() {
+        let mut rows = vec![legacy_row(); 16];
+        rows[1].diffuse_color = [0.5; 3];
+        rows[1].specular_strength = 0.5;
+        rows[1].gloss_strength = 0.0;
+        rows[1].material_repeat_y = 2.0;
+        let table = LegacyColorTable(LegacyColorTableData { rows });
+
+        let DawntrailColorTable(upgraded) = table.to_dawntrail().unwrap() else {
+            panic!("the table wasn't upgraded");
+        };
+        assert_eq!(upgraded.rows.len(), 32);
+        // The second legacy row becomes the second pair
+        for row in &upgraded.rows[2..4] {
+            assert_eq!(row.diffuse_color, [0.5; 3]);
+            assert_eq!(row.specular_color, [0.5; 3]);
+            assert_eq!(row.roughness, 1.0);
+            assert_eq!(row.material_repeat, [1.0, 2.0]);
+        }
+        assert_eq!(upgraded.rows[1].diffuse_color, [1.0; 3]);
+
+        let dye_row = LegacyColorDyeTableRow {
+            template: 5,
+            diffuse: true,
+            specular: false,
+            emissive: false,
+            gloss: true,
+            specular_strength: true,
+        };
+        let dye_table = LegacyColorDyeTable(LegacyColorDyeTableData {
+            rows: vec![dye_row; 16],
+        });
+        let DawntrailColorDyeTable(upgraded) = dye_table.to_dawntrail().unwrap() else {
+            panic!("the dye table wasn't upgraded");
+        };
+        assert_eq!(upgraded.rows.len(), 32);
+        assert_eq!(upgraded.rows[31].template, 5);
+        assert!(upgraded.rows[31].diffuse && upgraded.rows[31].roughness);
+        assert!(!upgraded.rows[31].specular);
+    }
+
+    #[test]
+    fn test_color_table_json() {
+        let mut rows = vec![legacy_row(); 16];
+        rows[15].emissive_color = [0.25, 0.5, 1.0];
+        let mut table = LegacyColorTable(LegacyColorTableData { rows });
+        table.set_tile_index(15, 12).unwrap();
+
+        let LegacyColorTable(imported) = ColorTable::from_json(&table.to_json().unwrap()).unwrap()
+        else {
+            panic!("the layout changed");
+        };
+        assert_eq!(imported.rows[15].emissive_color, [0.25, 0.5, 1.0]);
+        assert_eq!(imported.rows[15].material_repeat_y, 1.0);
+        assert_eq!(
+            ColorTable::LegacyColorTable(imported).tile_index(15),
+            Some(12)
+        );
+
+        let table = table.to_dawntrail().unwrap();
+        let imported = ColorTable::from_json(&table.to_json().unwrap()).unwrap();
+        assert_eq!(imported.row_count(), 32);
+        assert_eq!(imported.emissive_color(31), Some([0.25, 0.5, 1.0]));
+        assert_eq!(imported.sheen(0), table.sheen(0));
+
+        // Bits the tile index doesn't cover are kept
+        let mut table = table;
+        let DawntrailColorTable(data) = &mut table else {
+            unreachable!()
+        };
+        data.rows[0].tile_set = encode_tile_index(12) + 1;
+        assert_eq!(table.tile_index(0), Some(12));
+        let DawntrailColorTable(imported) =
+            ColorTable::from_json(&table.to_json().unwrap()).unwrap()
+        else {
+            panic!("the layout changed");
+        };
+        assert_eq!(imported.rows[0].tile_set, encode_tile_index(12) + 1);
+
+        assert!(ColorTable::from_json(r#"{"layout": "legacy", "rows": []}"#).is_err());
+        assert!(ColorTable::from_json(r#"{"layout": "dawntrail", "rows": [{}]}"#).is_err());
+    }
+
+    /// A material with a single texture, shader key, constant and sampler.
+    fn material() -> Material {
+        let strings = b"a.tex\0character.shpk\0\0\0";
+
+        let mut data = Vec::new();
+        // File header
+        data.extend_from_slice(&0x01030000u32.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes()); // file size, filled in below
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data.extend_from_slice(&(strings.len() as u16).to_le_bytes());
+        data.extend_from_slice(&6u16.to_le_bytes());
+        data.extend_from_slice(&[1, 0, 0, 0]);
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(strings);
+        // Material header
+        for value in [4u16, 1, 1, 1] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&0u32.to_le_bytes());
+        // Shader key, constant and sampler
+        for value in [
+            ShaderPackage::crc("CategorySkinType"),
+            ShaderPackage::crc("ValueFace"),
+            ShaderPackage::crc("g_AlphaThreshold"),
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data.extend_from_slice(&4u16.to_le_bytes());
+        data.extend_from_slice(&ShaderPackage::crc("g_SamplerNormal").to_le_bytes());
+        data.extend_from_slice(&0x000F8340u32.to_le_bytes());
+        data.extend_from_slice(&[0, 0, 0, 0]);
+        data.extend_from_slice(&0.5f32.to_le_bytes());
+
+        let size = data.len() as u16;
+        data[4..6].copy_from_slice(&size.to_le_bytes());
+
+        Material::from_existing(Platform::Win32, &data).unwrap()
+    }
+
+    fn resolver() -> MaterialResolver {
+        let mut resolver = MaterialResolver {
+            names: HashMap::new(),
+            parameters: vec![
+                MaterialParameter {
+                    id: ShaderPackage::crc("g_AlphaThreshold"),
+                    byte_offset: 0,
+                    byte_size: 4,
+                },
+                MaterialParameter {
+                    id: ShaderPackage::crc("g_DiffuseColor"),
+                    byte_offset: 4,
+                    byte_size: 12,
+                },
+            ],
+            defaults: vec![0.0, 1.0, 0.5, 0.25],
+            keys: vec![
+                Key {
+                    id: ShaderPackage::crc("CategorySkinType"),
+                    default_value: ShaderPackage::crc("ValueBody"),
+                },
+                Key {
+                    id: 0x12345678,
+                    default_value: 0x9ABCDEF0,
+                },
+            ],
+        };
+        for name in KNOWN_NAMES {
+            resolver.add_name(name);
+        }
+        resolver.add_name("g_SamplerNormal");
+        resolver
+    }
+
+    #[test]
+    fn test_resolve_names() {
+        let material = material();
+        let resolver = resolver();
+
+        let constants = resolver.constants(&material);
+        assert_eq!(
+            constants,
+            [
+                NamedConstant {
+                    id: ShaderPackage::crc("g_AlphaThreshold"),
+                    name: Some("g_AlphaThreshold".to_string()),
+                    values: vec![0.5],
+                    is_default: false,
+                },
+                NamedConstant {
+                    id: ShaderPackage::crc("g_DiffuseColor"),
+                    name: Some("g_DiffuseColor".to_string()),
+                    values: vec![1.0, 0.5, 0.25],
+                    is_default: true,
+                },
+            ]
+        );
+        assert_eq!(
+            resolver.constant(&material, "g_DiffuseColor"),
+            Some(vec![1.0, 0.5, 0.25])
+        );
+
+        let samplers = resolver.samplers(&material);
+        assert_eq!(samplers[0].name.as_deref(), Some("g_SamplerNormal"));
+
+        let keys = resolver.shader_keys(&material);
+        assert_eq!(keys[0].category_name.as_deref(), Some("CategorySkinType"));
+        assert_eq!(keys[0].value_name.as_deref(), Some("ValueFace"));
+        assert!(!keys[0].is_default);
+        // Unknown keys still get their default value
+        assert_eq!(keys[1].category_name, None);
+        assert_eq!(keys[1].value, 0x9ABCDEF0);
+        assert!(keys[1].is_default);
+    }
+
+    #[test]
+    fn test_set_by_name() {
+        let mut material = material();
+        let resolver = resolver();
+
+        resolver
+            .set_constant(&mut material, "g_AlphaThreshold", &[0.25])
+            .unwrap();
+        resolver
+            .set_constant(&mut material, "g_DiffuseColor", &[1.0, 0.0, 0.0])
+            .unwrap();
+        resolver
+            .set_sampler(&mut material, "g_SamplerMask", 0)
+            .unwrap();
+
+        // Sizes have to match the shader package, and textures have to exist
+        assert!(
+            resolver
+                .set_constant(&mut material, "g_DiffuseColor", &[1.0])
+                .is_err()
+        );
+        assert!(
+            resolver
+                .set_sampler(&mut material, "g_SamplerMask", 1)
+                .is_err()
+        );
+
+        // The changes should survive being written out
+        let buffer = material.write_to_buffer(Platform::Win32).unwrap();
+        assert_eq!(
+            u16::from_le_bytes([buffer[4], buffer[5]]) as usize,
+            buffer.len()
+        );
+        let material = Material::from_existing(Platform::Win32, &buffer).unwrap();
+        assert_eq!(
+            resolver.constant(&material, "g_AlphaThreshold"),
+            Some(vec![0.25])
+        );
+        assert_eq!(
+            resolver.constant(&material, "g_DiffuseColor"),
+            Some(vec![1.0, 0.0, 0.0])
+        );
+        let samplers = resolver.samplers(&material);
+        assert_eq!(samplers.len(), 2);
+        assert_eq!(samplers[1].id, ShaderPackage::crc("g_SamplerMask"));
+        assert_eq!(material.samplers[1].flags, 0x000F8340);
+    }
+
+    #[test]
+    fn test_set_color_table() {
+        let mut material = material();
+
+        let mut table = LegacyColorTable(LegacyColorTableData {
+            rows: vec![legacy_row(); 16],
+        });
+        table.set_diffuse_color(2, [0.5, 0.25, 0.0]).unwrap();
+        table.set_tile_index(2, 7).unwrap();
+        let dye_row = LegacyColorDyeTableRow {
+            template: 5,
+            diffuse: true,
+            specular: false,
+            emissive: false,
+            gloss: true,
+            specular_strength: false,
+        };
+        let dye_table = LegacyColorDyeTable(LegacyColorDyeTableData {
+            rows: vec![dye_row; 16],
+        });
+
+        // A dye table needs a color table to go along with it
+        assert!(
+            material
+                .set_color_dye_table(Some(dye_table.clone()))
+                .is_err()
+        );
+        material.set_color_table(table.clone()).unwrap();
+        material.set_color_dye_table(Some(dye_table)).unwrap();
+        assert!(
+            material
+                .set_color_table(table.to_dawntrail().unwrap())
+                .is_err()
+        );
+
+        let buffer = material.write_to_buffer(Platform::Win32).unwrap();
+        assert_eq!(
+            buffer.len(),
+            material.mat_data.file_header.file_size as usize
+        );
+        let material = Material::from_existing(Platform::Win32, &buffer).unwrap();
+        let table = material.color_table.as_ref().unwrap();
+        assert_eq!(table.diffuse_color(2), Some([0.5, 0.25, 0.0]));
+        assert_eq!(table.tile_index(2), Some(7));
+        let Some(LegacyColorDyeTable(dye_table)) = &material.color_dye_table else {
+            panic!("the dye table wasn't written");
+        };
+        assert_eq!(dye_table.rows[15].template, 5);
+        assert!(dye_table.rows[15].diffuse && dye_table.rows[15].gloss);
+        assert!(!dye_table.rows[15].specular);
+        // Nothing after the tables moved
+        assert_eq!(material.constants[0].values[0], 0.5);
+        assert_eq!(material.texture_paths, vec!["a.tex".to_string()]);
+
+        // Upgrading replaces both tables, which is done by removing the dye table first
+        let mut material = material;
+        let table = material
+            .color_table
+            .clone()
+            .unwrap()
+            .to_dawntrail()
+            .unwrap();
+        let dye_table = material
+            .color_dye_table
+            .clone()
+            .unwrap()
+            .to_dawntrail()
+            .unwrap();
+        material.set_color_dye_table(None).unwrap();
+        material.set_color_table(table).unwrap();
+        material.set_color_dye_table(Some(dye_table)).unwrap();
+
+        let buffer = material.write_to_buffer(Platform::Win32).unwrap();
+        assert_eq!(
+            buffer.len(),
+            material.mat_data.file_header.file_size as usize
+        );
+        let material = Material::from_existing(Platform::Win32, &buffer).unwrap();
+        let table = material.color_table.as_ref().unwrap();
+        assert_eq!(table.row_count(), 32);
+        assert_eq!(table.diffuse_color(5), Some([0.5, 0.25, 0.0]));
+        assert_eq!(table.tile_index(5), Some(7));
+        let Some(DawntrailColorDyeTable(dye_table)) = &material.color_dye_table else {
+            panic!("the dye table wasn't upgraded");
+        };
+        assert_eq!(dye_table.rows[31].template, 5);
+        assert!(dye_table.rows[31].diffuse && dye_table.rows[31].roughness);
+        assert_eq!(material.constants[0].values[0], 0.5);
+    }
+
+    #[test]
+    fn test_large_constants() {
+        let mut material = material();
+        let resolver = resolver();
+
+        assert!(
+            resolver
+                .set_constant(&mut material, "g_Unknown", &[0.0; 5])
+                .is_err()
+        );
+
+        // Some retail materials have constants with more than 4 values
+        let values: Vec<f32> = (0..9).map(|x| x as f32).collect();
+        material.mat_data.constants.push(ConstantStruct {
+            constant_id: 0x12345678,
+            value_offset: material.mat_data.header.shader_value_list_size,
+            value_size: 9 * 4,
+        });
+        material.mat_data.shader_values.extend(&values);
+        material.constants.push(Constant {
+            id: 0x12345678,
+            num_values: 9,
+            values: [0.0, 1.0, 2.0, 3.0],
+        });
+
+        assert_eq!(resolver.constant(&material, "0x12345678"), Some(values));
+        assert!(
+            resolver
+                .set_constant(&mut material, "0x12345678", &[0.0; 9])
+                .is_err()
+        );
+    }
 }
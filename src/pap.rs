@@ -13,6 +13,8 @@ use crate::common_file_operations::read_bool_from;
 use crate::common_file_operations::read_string;
 use crate::common_file_operations::write_bool_as;
 use crate::common_file_operations::write_string;
+use crate::havok::{HavokAnimationContainer, HavokBinaryTagFileReader};
+use crate::model::BoneTransform;
 use crate::string_heap::StringHeap;
 use crate::tmb::Tmb;
 use binrw::BinRead;
@@ -106,6 +108,56 @@ impl ReadableFile for Pap {
     }
 }
 
+impl Pap {
+    /// Samples the animation at `index` at `time` seconds.
+    ///
+    /// Returns the local transform of each animated bone, keyed by its index in the skeleton the animation was made for. Returns [None] if there's no animation at `index`, or its Havok data can't be read.
+    pub(crate) fn sample(&self, index: usize, time: f32) -> Option<Vec<(usize, BoneTransform)>> {
+        let havok_index = usize::try_from(self.animations.get(index)?.havok_index).ok()?;
+
+        let root = HavokBinaryTagFileReader::read(&self.havok_data)?;
+        let container =
+            HavokAnimationContainer::new(root.find_object_by_type("hkaAnimationContainer")?)?;
+        let binding = container.bindings.get(havok_index)?;
+
+        let duration = binding.animation.duration();
+        let time = if duration > 0.0 {
+            time.clamp(0.0, duration)
+        } else {
+            0.0
+        };
+
+        Some(
+            binding
+                .animation
+                // The sampler takes milliseconds
+                .sample(time * 1000.0)?
+                .into_iter()
+                .enumerate()
+                .map(|(track, transform)| {
+                    // Without a mapping, each track animates the bone with the same index
+                    let bone = binding
+                        .transform_track_to_bone_indices
+                        .get(track)
+                        .map(|x| *x as usize)
+                        .unwrap_or(track);
+                    let [x, y, z, _] = transform.translation;
+                    let [sx, sy, sz, _] = transform.scale;
+
+                    (
+                        bone,
+                        BoneTransform {
+                            position: [x, y, z],
+                            rotation: transform.rotation,
+                            scale: [sx, sy, sz],
+                        },
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
 impl WritableFile for Pap {
     fn write_to_buffer(&self, platform: Platform) -> crate::Result<ByteBuffer> {
         let mut buffer = ByteBuffer::new();
@@ -121,7 +173,12 @@ impl WritableFile for Pap {
 
 #[cfg(test)]
 mod tests {
+    use std::fs::read;
+    use std::path::PathBuf;
+
+    use crate::model::Pose;
     use crate::pass_random_invalid;
+    use crate::skeleton::{Bone, Skeleton};
 
     use super::*;
 
@@ -129,4 +186,87 @@ mod tests {
     fn test_invalid() {
         pass_random_invalid::<Pap>();
     }
+
+    /// One second long animation where the only track holds bone 5 still.
+    fn havok_data() -> Vec<u8> {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/tests");
+        d.push("hold_bone.hkx");
+
+        read(d).unwrap()
+    }
+
+    fn pap(havok_data: Vec<u8>) -> Pap {
+        Pap {
+            version: 0,
+            num_animations: 1,
+            model_id: 0,
+            model_type: SkeletonType::Human,
+            variant: 0,
+            info_offset: 0,
+            havok_position: 0,
+            tmb_offset: havok_data.len() as i32,
+            animations: vec![PapAnimation {
+                name: "idle".to_string(),
+                animation_type: 0,
+                havok_index: 0,
+                face: false,
+            }],
+            havok_data,
+            tmbs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_sample() {
+        let pap = pap(havok_data());
+
+        let sample = pap.sample(0, 0.5).unwrap();
+        assert_eq!(sample.len(), 1);
+
+        let (bone, transform) = sample[0];
+        assert_eq!(bone, 5);
+        assert_eq!(transform.position, [1.0, 2.0, 3.0]);
+        assert_eq!(transform.scale, [1.0, 1.0, 1.0]);
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        for (value, expected) in transform.rotation.iter().zip([0.0, 0.0, half, half]) {
+            assert!((value - expected).abs() < 1e-4, "{:?}", transform.rotation);
+        }
+
+        // There's only one animation
+        assert!(pap.sample(1, 0.5).is_none());
+
+        let skeleton = Skeleton {
+            bones: (0..6)
+                .map(|i| Bone {
+                    name: format!("j_{i}"),
+                    parent_index: i - 1,
+                    position: [0.0; 3],
+                    rotation: [0.0, 0.0, 0.0, 1.0],
+                    scale: [1.0; 3],
+                })
+                .collect(),
+        };
+        let pose = Pose::from_animation(&skeleton, &pap, 0, 0.5).unwrap();
+        assert_eq!(pose.transforms[4], BoneTransform::default());
+        assert_eq!(pose.transforms[5], transform);
+    }
+
+    #[test]
+    fn test_sample_garbage() {
+        let havok_data = havok_data();
+
+        assert!(pap(Vec::new()).sample(0, 0.0).is_none());
+        assert!(pap(vec![0xff; 64]).sample(0, 0.0).is_none());
+
+        // A valid signature followed by garbage
+        let mut garbage = havok_data[..8].to_vec();
+        garbage.extend_from_slice(&[0xff; 64]);
+        assert!(pap(garbage).sample(0, 0.0).is_none());
+
+        // The file end tag is missing from every truncated file
+        for len in 0..havok_data.len() {
+            assert!(pap(havok_data[..len].to_vec()).sample(0, 0.0).is_none());
+        }
+    }
 }
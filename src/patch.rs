@@ -1054,6 +1054,9 @@ mod tests {
             .map(|x| x.strip_prefix(&data_dir).unwrap())
             .collect();
 
-        assert_eq!(old_relative_files.sort(), new_relative_files.sort());
+        old_relative_files.sort();
+        new_relative_files.sort();
+
+        assert_eq!(old_relative_files, new_relative_files);
     }
 }
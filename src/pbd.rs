@@ -33,7 +33,7 @@ pub struct RacialDeformer {
     #[bw(ignore)]
     _padding: u16,
 
-    /// A 4x3 transformation matrix.
+    /// A 3x4 row-major transformation matrix for each bone, with the translation in the last column.
     #[br(count = bone_count)]
     pub transform: Vec<[f32; 12]>,
 }
@@ -83,7 +83,7 @@ pub struct PreBoneDeformer {
 pub struct PreBoneDeformBone {
     /// Name of the affected bone
     pub name: String,
-    /// The deform matrix
+    /// The deform matrix, in the same layout as [RacialDeformer::transform].
     pub deform: [f32; 12],
 }
 
@@ -113,6 +113,13 @@ impl WritableFile for PreBoneDeformer {
     }
 }
 
+/// The identity in the same 3x4 row-major layout as [RacialDeformer::transform].
+const IDENTITY: [f32; 12] = [
+    1.0, 0.0, 0.0, 0.0, //
+    0.0, 1.0, 0.0, 0.0, //
+    0.0, 0.0, 1.0, 0.0,
+];
+
 impl PreBoneDeformer {
     /// Calculates the deform matrices between two races
     pub fn get_deform_matrices(
@@ -159,6 +166,167 @@ impl PreBoneDeformer {
 
         Some(PreBoneDeformMatrices { bones })
     }
+
+    /// Calculates the deform matrices to turn a model made for `from_body_id` into one for `to_body_id`.
+    ///
+    /// Unlike [Self::get_deform_matrices], which lists the raw matrices of each deformer on the way, this combines them into one matrix per bone. Each deformer only changes the body of its parent in the tree, so this walks up from both bodies to their common parent. Returns [None] if the bodies are the same, or either isn't in this file.
+    ///
+    /// The scale stored next to each deformer isn't applied. What it scales is unknown, and the matrices already hold the complete transform of each bone, including its scale.
+    pub fn get_deform_matrices_between(
+        &self,
+        from_body_id: u16,
+        to_body_id: u16,
+    ) -> Option<PreBoneDeformMatrices> {
+        if from_body_id == to_body_id {
+            return None;
+        }
+
+        let mut from_path = self.path_to_root(from_body_id)?;
+        let mut to_path = self.path_to_root(to_body_id)?;
+
+        // The deformers both bodies share cancel each other out
+        while let (Some(a), Some(b)) = (from_path.last(), to_path.last())
+            && a == b
+        {
+            from_path.pop();
+            to_path.pop();
+        }
+
+        let mut names: Vec<&String> = Vec::new();
+        for item in from_path.iter().chain(&to_path) {
+            if let Some(deformer) = &self.items[*item].deformer {
+                for name in &deformer.bone_names {
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+            }
+        }
+
+        let bones = names
+            .into_iter()
+            .map(|name| {
+                // Undo the deformers of the first body, then apply the ones of the second
+                let from = self.combined_transform(&from_path, name);
+                let to = self.combined_transform(&to_path, name);
+
+                PreBoneDeformBone {
+                    name: name.clone(),
+                    deform: multiply(&to, &invert(&from).unwrap_or(IDENTITY)),
+                }
+            })
+            .collect();
+
+        Some(PreBoneDeformMatrices { bones })
+    }
+
+    /// Returns the indices of the items from `body_id` up to the root of the tree.
+    fn path_to_root(&self, body_id: u16) -> Option<Vec<usize>> {
+        let mut item = self.items.iter().position(|x| x.body_id == body_id)?;
+        let mut link = self.links.get(self.items[item].link_index as usize)?;
+
+        let mut path = vec![item];
+        while let Ok(parent) = usize::try_from(link.parent_index) {
+            // Guard against cycles in malformed files
+            if path.len() > self.links.len() {
+                return None;
+            }
+
+            link = self.links.get(parent)?;
+            item = link.deformer_index as usize;
+            if item >= self.items.len() {
+                return None;
+            }
+            path.push(item);
+        }
+
+        Some(path)
+    }
+
+    /// Combines the transforms of the deformers in `path` for bone `name`, starting from the root.
+    fn combined_transform(&self, path: &[usize], name: &str) -> [f32; 12] {
+        let mut combined = IDENTITY;
+        for item in path.iter().rev() {
+            let Some(deformer) = &self.items[*item].deformer else {
+                continue;
+            };
+
+            if let Some(index) = deformer.bone_names.iter().position(|x| x == name)
+                && let Some(transform) = deformer.transform.get(index)
+            {
+                combined = multiply(transform, &combined);
+            }
+        }
+
+        combined
+    }
+}
+
+impl PreBoneDeformMatrices {
+    /// Returns the deform matrix for the bone named `name`, if it has one.
+    pub fn get(&self, name: &str) -> Option<&[f32; 12]> {
+        self.bones
+            .iter()
+            .find(|bone| bone.name == name)
+            .map(|bone| &bone.deform)
+    }
+}
+
+/// Multiplies two 3x4 row-major affine matrices, so `b` is applied first.
+fn multiply(a: &[f32; 12], b: &[f32; 12]) -> [f32; 12] {
+    let mut result = [0.0; 12];
+    for row in 0..3 {
+        for column in 0..4 {
+            let mut value = if column == 3 { a[row * 4 + 3] } else { 0.0 };
+            for k in 0..3 {
+                value += a[row * 4 + k] * b[k * 4 + column];
+            }
+            result[row * 4 + column] = value;
+        }
+    }
+    result
+}
+
+/// Inverts a 3x4 row-major affine matrix, or returns [None] if it can't be.
+fn invert(m: &[f32; 12]) -> Option<[f32; 12]> {
+    let a = |row: usize, column: usize| m[row * 4 + column];
+
+    let determinant = a(0, 0) * (a(1, 1) * a(2, 2) - a(1, 2) * a(2, 1))
+        - a(0, 1) * (a(1, 0) * a(2, 2) - a(1, 2) * a(2, 0))
+        + a(0, 2) * (a(1, 0) * a(2, 1) - a(1, 1) * a(2, 0));
+    if determinant.abs() <= f32::EPSILON {
+        return None;
+    }
+    let inv_det = 1.0 / determinant;
+
+    let inverse = [
+        [
+            (a(1, 1) * a(2, 2) - a(1, 2) * a(2, 1)) * inv_det,
+            (a(0, 2) * a(2, 1) - a(0, 1) * a(2, 2)) * inv_det,
+            (a(0, 1) * a(1, 2) - a(0, 2) * a(1, 1)) * inv_det,
+        ],
+        [
+            (a(1, 2) * a(2, 0) - a(1, 0) * a(2, 2)) * inv_det,
+            (a(0, 0) * a(2, 2) - a(0, 2) * a(2, 0)) * inv_det,
+            (a(0, 2) * a(1, 0) - a(0, 0) * a(1, 2)) * inv_det,
+        ],
+        [
+            (a(1, 0) * a(2, 1) - a(1, 1) * a(2, 0)) * inv_det,
+            (a(0, 1) * a(2, 0) - a(0, 0) * a(2, 1)) * inv_det,
+            (a(0, 0) * a(1, 1) - a(0, 1) * a(1, 0)) * inv_det,
+        ],
+    ];
+
+    let mut result = [0.0; 12];
+    for row in 0..3 {
+        let mut translation = 0.0;
+        for column in 0..3 {
+            result[row * 4 + column] = inverse[row][column];
+            translation -= inverse[row][column] * a(column, 3);
+        }
+        result[row * 4 + 3] = translation;
+    }
+    Some(result)
 }
 
 #[cfg(test)]
@@ -171,4 +339,79 @@ mod tests {
     fn test_invalid() {
         pass_random_invalid::<PreBoneDeformer>();
     }
+
+    fn item(body_id: u16, link_index: u16, transform: Option<[f32; 12]>) -> PreBoneDeformerItem {
+        PreBoneDeformerItem {
+            body_id,
+            link_index,
+            data_offset: 0,
+            unk_scale: 1.0,
+            deformer: transform.map(|transform| RacialDeformer {
+                bone_count: 1,
+                bone_name_offsets: vec![0],
+                bone_names: vec!["j_kosi".to_string()],
+                transform: vec![transform],
+            }),
+        }
+    }
+
+    fn link(parent_index: i16, deformer_index: u16) -> PreBoneDeformerLink {
+        PreBoneDeformerLink {
+            parent_index,
+            first_child_index: -1,
+            next_sibling_index: -1,
+            deformer_index,
+        }
+    }
+
+    #[test]
+    fn test_deform_matrices() {
+        let translate = [
+            1.0, 0.0, 0.0, 1.0, //
+            0.0, 1.0, 0.0, 0.0, //
+            0.0, 0.0, 1.0, 0.0,
+        ];
+        let scale = [
+            2.0, 0.0, 0.0, 0.0, //
+            0.0, 2.0, 0.0, 0.0, //
+            0.0, 0.0, 2.0, 0.0,
+        ];
+
+        // 101 is the root, 201 is its child, and 301 and 401 are children of 201
+        let pbd = PreBoneDeformer {
+            items: vec![
+                item(101, 0, None),
+                item(201, 1, Some(translate)),
+                item(301, 2, Some(scale)),
+                item(401, 3, Some(translate)),
+            ],
+            links: vec![link(-1, 0), link(0, 1), link(1, 2), link(1, 3)],
+        };
+
+        assert!(pbd.get_deform_matrices_between(101, 101).is_none());
+        assert!(pbd.get_deform_matrices_between(101, 999).is_none());
+
+        // Going down the tree applies each deformer
+        let matrices = pbd.get_deform_matrices_between(101, 301).unwrap();
+        assert_eq!(matrices.get("j_kosi"), Some(&multiply(&scale, &translate)));
+        assert!(matrices.get("j_mune").is_none());
+
+        // Going up the tree undoes them
+        let matrices = pbd.get_deform_matrices_between(301, 201).unwrap();
+        assert_eq!(matrices.get("j_kosi"), invert(&scale).as_ref());
+
+        // Going between siblings skips their shared parent
+        let matrices = pbd.get_deform_matrices_between(301, 401).unwrap();
+        assert_eq!(
+            matrices.get("j_kosi"),
+            Some(&multiply(&translate, &invert(&scale).unwrap()))
+        );
+
+        // The raw matrices are still available, starting from the first body
+        let mut pbd = pbd;
+        pbd.links[2].next_sibling_index = 3;
+        let matrices = pbd.get_deform_matrices(301, 201).unwrap();
+        assert_eq!(matrices.bones.len(), 1);
+        assert_eq!(matrices.get("j_kosi"), Some(&scale));
+    }
 }
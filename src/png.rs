@@ -0,0 +1,443 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! A minimal PNG reader and writer, so we don't have to depend on an image library for importing and exporting textures.
+
+use crate::{
+    ByteBuffer,
+    compression::{header_compress, header_decompress},
+};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+const COLOR_TYPE_GRAYSCALE: u8 = 0;
+const COLOR_TYPE_RGB: u8 = 2;
+const COLOR_TYPE_PALETTE: u8 = 3;
+const COLOR_TYPE_GRAYSCALE_ALPHA: u8 = 4;
+/// Color type for 8-bit RGBA.
+const COLOR_TYPE_RGBA: u8 = 6;
+
+/// Starting column, starting row, column step and row step for each of the Adam7 interlacing passes.
+const ADAM7_PASSES: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// Encodes `rgba` (8 bits per channel) as a PNG image.
+pub(crate) fn write_png(width: u32, height: u32, rgba: &[u8]) -> crate::Result<ByteBuffer> {
+    let stride = width as usize * 4;
+
+    // Each scanline is prefixed with its filter type, which is always none
+    let mut scanlines = Vec::with_capacity((stride + 1) * height as usize);
+    for line in rgba.chunks_exact(stride).take(height as usize) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(line);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.extend_from_slice(&[8, COLOR_TYPE_RGBA, 0, 0, 0]);
+
+    let mut output = SIGNATURE.to_vec();
+    write_chunk(&mut output, b"IHDR", &header);
+    write_chunk(&mut output, b"IDAT", &header_compress(&scanlines)?);
+    write_chunk(&mut output, b"IEND", &[]);
+
+    Ok(output)
+}
+
+/// Decodes a PNG image to 8-bit RGBA, returning its width, height and pixels.
+///
+/// Every standard color type, bit depth and interlacing method is supported. 16-bit channels are truncated to 8 bits.
+pub(crate) fn read_png(buffer: &[u8]) -> crate::Result<(u32, u32, Vec<u8>)> {
+    let invalid = |reason: &str| crate::Error::InvalidData {
+        reason: format!("invalid PNG: {reason}"),
+    };
+
+    if buffer.get(..8) != Some(&SIGNATURE) {
+        return Err(invalid("missing signature"));
+    }
+
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut transparency: &[u8] = &[];
+    let mut compressed = Vec::new();
+
+    let mut position = 8;
+    loop {
+        let length = buffer
+            .get(position..position + 4)
+            .map(|x| u32::from_be_bytes(x.try_into().unwrap()) as usize)
+            .ok_or_else(|| invalid("missing IEND chunk"))?;
+        let chunk = buffer
+            .get(position + 4..position + 12 + length)
+            .ok_or_else(|| invalid("truncated chunk"))?;
+        position += 12 + length;
+
+        let (kind, rest) = chunk.split_at(4);
+        let (data, crc) = rest.split_at(length);
+        if crc32(&chunk[..4 + length]) != u32::from_be_bytes(crc.try_into().unwrap()) {
+            return Err(invalid("chunk CRC mismatch"));
+        }
+
+        match kind {
+            b"IHDR" => header = Some(data),
+            b"PLTE" => palette = data,
+            b"tRNS" => transparency = data,
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            // Ancillary chunks can be safely ignored, but critical ones can't
+            _ if kind[0].is_ascii_uppercase() => return Err(invalid("unknown critical chunk")),
+            _ => {}
+        }
+    }
+
+    let header = header
+        .filter(|header| header.len() == 13)
+        .ok_or_else(|| invalid("missing IHDR chunk"))?;
+    let width = u32::from_be_bytes(header[0..4].try_into().unwrap());
+    let height = u32::from_be_bytes(header[4..8].try_into().unwrap());
+    let (bit_depth, color_type, interlaced) = (header[8], header[9], header[12] == 1);
+
+    let channels = match (color_type, bit_depth) {
+        (COLOR_TYPE_GRAYSCALE, 1 | 2 | 4 | 8 | 16) => 1,
+        (COLOR_TYPE_RGB, 8 | 16) => 3,
+        (COLOR_TYPE_PALETTE, 1 | 2 | 4 | 8) => 1,
+        (COLOR_TYPE_GRAYSCALE_ALPHA, 8 | 16) => 2,
+        (COLOR_TYPE_RGBA, 8 | 16) => 4,
+        _ => return Err(invalid("unsupported color type or bit depth")),
+    };
+    if width == 0 || height == 0 {
+        return Err(invalid("image dimensions can't be zero"));
+    }
+    // Textures can't be any bigger, so don't try to allocate room for it
+    if width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(invalid("image dimensions can't be larger than 65535"));
+    }
+
+    let (width, height) = (width as usize, height as usize);
+    let bits_per_pixel = channels * bit_depth as usize;
+    let passes: &[(usize, usize, usize, usize)] = if interlaced {
+        &ADAM7_PASSES
+    } else {
+        &[(0, 0, 1, 1)]
+    };
+
+    // Figure out how big each pass is, skipping empty ones which aren't stored at all
+    let pass_sizes: Vec<(usize, usize)> = passes
+        .iter()
+        .map(|(x, y, x_step, y_step)| {
+            (
+                width.saturating_sub(*x).div_ceil(*x_step),
+                height.saturating_sub(*y).div_ceil(*y_step),
+            )
+        })
+        .collect();
+    let too_large = || invalid("image is too large");
+    let expected_size = pass_sizes
+        .iter()
+        .filter(|(pass_width, pass_height)| *pass_width != 0 && *pass_height != 0)
+        .try_fold(0usize, |size, (pass_width, pass_height)| {
+            let stride = pass_width.checked_mul(bits_per_pixel)?.div_ceil(8);
+            size.checked_add(stride.checked_add(1)?.checked_mul(*pass_height)?)
+        })
+        .ok_or_else(too_large)?;
+    let rgba_size = width
+        .checked_mul(height)
+        .and_then(|x| x.checked_mul(4))
+        .ok_or_else(too_large)?;
+    // Deflate can't expand data by more than about 1032 times, so there can't be enough of it. Each byte of it becomes at most 32 bytes of RGBA, for 1-bit pixels.
+    if expected_size / 1032 > compressed.len() || rgba_size / (1032 * 32) > compressed.len() {
+        return Err(invalid("not enough image data"));
+    }
+
+    let mut filtered = vec![0; expected_size];
+    if header_decompress(&mut compressed, &mut filtered)? != expected_size {
+        return Err(invalid("not enough image data"));
+    }
+
+    let mut rgba = vec![0; rgba_size];
+    let mut position = 0;
+    for ((x, y, x_step, y_step), (pass_width, pass_height)) in passes.iter().zip(pass_sizes) {
+        if pass_width == 0 || pass_height == 0 {
+            continue;
+        }
+
+        let stride = (pass_width * bits_per_pixel).div_ceil(8);
+        let pass = &mut filtered[position..position + (stride + 1) * pass_height];
+        position += pass.len();
+        unfilter(pass, stride, bits_per_pixel.div_ceil(8))?;
+
+        for (row, line) in pass.chunks_exact(stride + 1).enumerate() {
+            let line = &line[1..];
+            for column in 0..pass_width {
+                let sample = |channel: usize| -> u16 {
+                    let index = column * channels + channel;
+                    match bit_depth {
+                        16 => u16::from_be_bytes([line[index * 2], line[index * 2 + 1]]),
+                        8 => u16::from(line[index]),
+                        _ => {
+                            let bit = index * bit_depth as usize;
+                            let shift = 8 - bit_depth as usize - bit % 8;
+                            u16::from(line[bit / 8] >> shift) & ((1 << bit_depth) - 1)
+                        }
+                    }
+                };
+                let pixel = to_rgba(color_type, bit_depth, sample, palette, transparency)
+                    .ok_or_else(|| invalid("palette index out of range"))?;
+
+                let offset = ((y + row * y_step) * width + x + column * x_step) * 4;
+                rgba[offset..offset + 4].copy_from_slice(&pixel);
+            }
+        }
+    }
+
+    Ok((width as u32, height as u32, rgba))
+}
+
+/// Converts a single pixel to 8-bit RGBA, using `sample` to read each of its channels.
+fn to_rgba(
+    color_type: u8,
+    bit_depth: u8,
+    sample: impl Fn(usize) -> u16,
+    palette: &[u8],
+    transparency: &[u8],
+) -> Option<[u8; 4]> {
+    let max = (1u32 << bit_depth) - 1;
+    let scale = |value: u16| (u32::from(value) * 255 / max) as u8;
+    // Grayscale and RGB images have a single color that is considered transparent
+    let transparent = |channel: usize, value: u16| {
+        transparency
+            .get(channel * 2..channel * 2 + 2)
+            .map(|x| u16::from_be_bytes([x[0], x[1]]) == value)
+    };
+
+    Some(match color_type {
+        COLOR_TYPE_GRAYSCALE => {
+            let gray = scale(sample(0));
+            let alpha = if transparent(0, sample(0)) == Some(true) {
+                0
+            } else {
+                255
+            };
+            [gray, gray, gray, alpha]
+        }
+        COLOR_TYPE_RGB => {
+            let key = (0..3).all(|c| transparent(c, sample(c)) == Some(true));
+            [
+                scale(sample(0)),
+                scale(sample(1)),
+                scale(sample(2)),
+                if key { 0 } else { 255 },
+            ]
+        }
+        COLOR_TYPE_PALETTE => {
+            let index = sample(0) as usize;
+            let color = palette.get(index * 3..index * 3 + 3)?;
+            [
+                color[0],
+                color[1],
+                color[2],
+                transparency.get(index).copied().unwrap_or(255),
+            ]
+        }
+        COLOR_TYPE_GRAYSCALE_ALPHA => {
+            let gray = scale(sample(0));
+            [gray, gray, gray, scale(sample(1))]
+        }
+        _ => [
+            scale(sample(0)),
+            scale(sample(1)),
+            scale(sample(2)),
+            scale(sample(3)),
+        ],
+    })
+}
+
+/// Reverses the filter applied to each scanline, in place. `data` contains every scanline prefixed by its filter type.
+fn unfilter(data: &mut [u8], stride: usize, bytes_per_pixel: usize) -> crate::Result<()> {
+    let mut previous = vec![0; stride];
+    for line in data.chunks_exact_mut(stride + 1) {
+        let (filter, line) = line.split_first_mut().unwrap();
+        for i in 0..stride {
+            let left = if i >= bytes_per_pixel {
+                line[i - bytes_per_pixel]
+            } else {
+                0
+            };
+            let up = previous[i];
+            let upper_left = if i >= bytes_per_pixel {
+                previous[i - bytes_per_pixel]
+            } else {
+                0
+            };
+
+            let predictor = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((u16::from(left) + u16::from(up)) / 2) as u8,
+                4 => paeth(left, up, upper_left),
+                _ => {
+                    return Err(crate::Error::InvalidData {
+                        reason: format!("invalid PNG: unknown filter type {filter}"),
+                    });
+                }
+            };
+            line[i] = line[i].wrapping_add(predictor);
+        }
+        previous.copy_from_slice(line);
+    }
+
+    Ok(())
+}
+
+fn paeth(left: u8, up: u8, upper_left: u8) -> u8 {
+    let estimate = i16::from(left) + i16::from(up) - i16::from(upper_left);
+    let distance = |x: u8| (estimate - i16::from(x)).abs();
+    if distance(left) <= distance(up) && distance(left) <= distance(upper_left) {
+        left
+    } else if distance(up) <= distance(upper_left) {
+        up
+    } else {
+        upper_left
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    unsafe { libz_rs_sys::crc32(0, data.as_ptr(), data.len() as libz_rs_sys::uInt) as u32 }
+}
+
+fn write_chunk(output: &mut ByteBuffer, kind: &[u8; 4], data: &[u8]) {
+    output.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let start = output.len();
+    output.extend_from_slice(kind);
+    output.extend_from_slice(data);
+
+    // The CRC covers the chunk type and data, but not the length
+    let crc = crc32(&output[start..]);
+    output.extend_from_slice(&crc.to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a PNG out of already filtered scanlines.
+    fn png(header: [u8; 13], chunks: &[(&[u8; 4], &[u8])], scanlines: &[u8]) -> ByteBuffer {
+        let mut output = SIGNATURE.to_vec();
+        write_chunk(&mut output, b"IHDR", &header);
+        for (kind, data) in chunks {
+            write_chunk(&mut output, kind, data);
+        }
+        write_chunk(&mut output, b"IDAT", &header_compress(scanlines).unwrap());
+        write_chunk(&mut output, b"IEND", &[]);
+        output
+    }
+
+    fn header(width: u32, height: u32, bit_depth: u8, color_type: u8, interlace: u8) -> [u8; 13] {
+        let mut header = [0; 13];
+        header[0..4].copy_from_slice(&width.to_be_bytes());
+        header[4..8].copy_from_slice(&height.to_be_bytes());
+        header[8] = bit_depth;
+        header[9] = color_type;
+        header[12] = interlace;
+        header
+    }
+
+    #[test]
+    fn test_png_round_trip() {
+        let rgba: Vec<u8> = (0..=255).collect();
+        let png = write_png(8, 8, &rgba).unwrap();
+        assert_eq!(read_png(&png).unwrap(), (8, 8, rgba));
+
+        // Any change should be caught by the CRC
+        let mut corrupted = png.clone();
+        corrupted[20] ^= 1;
+        assert!(read_png(&corrupted).is_err());
+        assert!(read_png(&png[..png.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_read_png_filters() {
+        // A 2x2 RGB image, where the first row uses the sub filter and the second uses paeth
+        let scanlines = [
+            1, 10, 20, 30, 5, 5, 5, //
+            4, 1, 1, 1, 1, 1, 1,
+        ];
+        let png = png(header(2, 2, 8, COLOR_TYPE_RGB, 0), &[], &scanlines);
+        let (_, _, rgba) = read_png(&png).unwrap();
+        assert_eq!(
+            rgba,
+            [
+                10, 20, 30, 255, 15, 25, 35, 255, //
+                11, 21, 31, 255, 16, 26, 36, 255
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_png_palette() {
+        // Four 2-bit indices packed into a single byte, with the first entry being transparent
+        let palette = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+        let png = png(
+            header(4, 1, 2, COLOR_TYPE_PALETTE, 0),
+            &[(b"PLTE", &palette), (b"tRNS", &[0])],
+            &[0, 0b00_01_10_11],
+        );
+        let (_, _, rgba) = read_png(&png).unwrap();
+        assert_eq!(
+            rgba,
+            [
+                255, 0, 0, 0, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 255
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_png_interlaced() {
+        // 2x2 grayscale, where Adam7 stores the top-left pixel in the first pass, the top-right in the sixth and the bottom row in the seventh
+        let png = png(
+            header(2, 2, 8, COLOR_TYPE_GRAYSCALE, 1),
+            &[],
+            &[0, 10, 0, 20, 0, 30, 40],
+        );
+        let (_, _, rgba) = read_png(&png).unwrap();
+        let gray: Vec<u8> = rgba.chunks_exact(4).map(|x| x[0]).collect();
+        assert_eq!(gray, [10, 20, 30, 40]);
+    }
+
+    #[test]
+    fn test_read_png_too_large() {
+        for (width, height) in [(u32::MAX, 1), (1, 65536), (u32::MAX, u32::MAX)] {
+            let png = png(header(width, height, 8, COLOR_TYPE_RGBA, 0), &[], &[0; 5]);
+            assert!(read_png(&png).is_err());
+        }
+
+        // These would need gigabytes, which a few bytes of data can't decompress to. 1-bit pixels take up 32 times more space once decoded.
+        for header in [
+            header(65535, 65535, 16, COLOR_TYPE_RGBA, 1),
+            header(65535, 65535, 1, COLOR_TYPE_GRAYSCALE, 0),
+        ] {
+            assert!(read_png(&png(header, &[], &[0; 9])).is_err());
+        }
+    }
+
+    #[test]
+    fn test_write_png() {
+        let png = write_png(2, 1, &[255, 0, 0, 255, 0, 255, 0, 128]).unwrap();
+
+        assert_eq!(png[..8], SIGNATURE);
+        assert_eq!(&png[12..16], b"IHDR");
+        // IHDR CRC for a 2x1 RGBA image
+        assert_eq!(png[29..33], [0xF4, 0x22, 0x7F, 0x8A]);
+        assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+    }
+}
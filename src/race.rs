@@ -81,8 +81,8 @@ pub fn get_race_id(race: Race, tribe: Tribe, gender: Gender) -> Option<i32> {
             Gender::Female => Some(601),
         },
         Race::Lalafell => match gender {
-            Gender::Male => Some(501),
-            Gender::Female => Some(601),
+            Gender::Male => Some(1101),
+            Gender::Female => Some(1201),
         },
         Race::Miqote => match gender {
             Gender::Male => Some(701),
@@ -108,6 +108,47 @@ pub fn get_race_id(race: Race, tribe: Tribe, gender: Gender) -> Option<i32> {
     }
 }
 
+/// The inverse of [get_race_id], returning the race, tribe and gender of a race identifier like 101.
+///
+/// Races that share an identifier between tribes return the first of [get_supported_tribes].
+pub fn get_race_from_id(race_id: i32) -> Option<(Race, Tribe, Gender)> {
+    let race = match race_id {
+        101 | 201 | 301 | 401 => Race::Hyur,
+        501 | 601 => Race::Elezen,
+        701 | 801 => Race::Miqote,
+        901 | 1001 => Race::Roegadyn,
+        1101 | 1201 => Race::Lalafell,
+        1301 | 1401 => Race::AuRa,
+        1501 | 1601 => Race::Hrothgar,
+        1701 | 1801 => Race::Viera,
+        _ => return None,
+    };
+    let tribe = match race_id {
+        301 | 401 => Tribe::Highlander,
+        _ => get_supported_tribes(race)[0],
+    };
+    let gender = if (race_id / 100) % 2 == 1 {
+        Gender::Male
+    } else {
+        Gender::Female
+    };
+
+    Some((race, tribe, gender))
+}
+
+/// Returns the race identifier the game falls back to when `race_id` doesn't have its own model, such as 201 for 801.
+///
+/// Follow this repeatedly to reach 101, which has no fallback.
+pub fn get_fallback_race_id(race_id: i32) -> Option<i32> {
+    match race_id {
+        101 => None,
+        201 => Some(101),
+        1201 => Some(1101),
+        _ if get_race_from_id(race_id)?.2 == Gender::Male => Some(101),
+        _ => Some(201),
+    }
+}
+
 /// Builds the path to the skeleton (sklb) file for a given `race`, `tribe` and `gender`.
 pub fn build_skeleton_path(race: Race, tribe: Tribe, gender: Gender) -> String {
     format!(
@@ -126,7 +167,7 @@ pub fn get_supported_tribes(race: Race) -> [Tribe; 2] {
         Race::Roegadyn => [Tribe::SeaWolf, Tribe::Hellsguard],
         Race::AuRa => [Tribe::Raen, Tribe::Xaela],
         Race::Hrothgar => [Tribe::Hellion, Tribe::Lost],
-        Race::Viera => [Tribe::Raen, Tribe::Veena],
+        Race::Viera => [Tribe::Rava, Tribe::Veena],
         _ => [Tribe::Unknown, Tribe::Unknown],
     }
 }
@@ -148,4 +189,69 @@ mod tests {
             None
         );
     }
+
+    #[test]
+    fn test_lalafell_race_id() {
+        assert_eq!(
+            get_race_id(Race::Lalafell, Tribe::Plainsfolk, Gender::Male),
+            Some(1101)
+        );
+        assert_eq!(
+            get_race_id(Race::Lalafell, Tribe::Dunesfolk, Gender::Female),
+            Some(1201)
+        );
+    }
+
+    #[test]
+    fn test_viera_tribes() {
+        assert_eq!(
+            get_supported_tribes(Race::Viera),
+            [Tribe::Rava, Tribe::Veena]
+        );
+        assert_eq!(
+            get_race_id(Race::Viera, Tribe::Rava, Gender::Female),
+            Some(1801)
+        );
+    }
+
+    #[test]
+    fn test_race_from_id() {
+        for race in [
+            Race::Hyur,
+            Race::Elezen,
+            Race::Lalafell,
+            Race::Miqote,
+            Race::Roegadyn,
+            Race::AuRa,
+            Race::Hrothgar,
+            Race::Viera,
+        ] {
+            for tribe in get_supported_tribes(race) {
+                for gender in [Gender::Male, Gender::Female] {
+                    let race_id = get_race_id(race, tribe, gender).unwrap();
+                    let (found_race, found_tribe, found_gender) =
+                        get_race_from_id(race_id).unwrap();
+                    assert_eq!(found_race, race);
+                    assert_eq!(found_gender, gender);
+                    assert_eq!(
+                        get_race_id(found_race, found_tribe, found_gender),
+                        Some(race_id)
+                    );
+                }
+            }
+        }
+
+        assert_eq!(get_race_from_id(0), None);
+    }
+
+    #[test]
+    fn test_fallback_race_id() {
+        assert_eq!(get_fallback_race_id(101), None);
+        assert_eq!(get_fallback_race_id(201), Some(101));
+        assert_eq!(get_fallback_race_id(801), Some(201));
+        assert_eq!(get_fallback_race_id(1301), Some(101));
+        assert_eq!(get_fallback_race_id(1201), Some(1101));
+        assert_eq!(get_fallback_race_id(1101), Some(101));
+        assert_eq!(get_fallback_race_id(42), None);
+    }
 }
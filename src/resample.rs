@@ -0,0 +1,218 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Image resampling, used for resizing textures and generating their mips.
+
+use std::f32::consts::PI;
+
+use crate::tex::{MipFilter, MipOptions};
+
+/// Radius of the Kaiser filter, in destination pixels.
+const KAISER_WIDTH: f32 = 3.0;
+/// How quickly the Kaiser window falls off, higher values trade sharpness for less ringing.
+const KAISER_ALPHA: f32 = 4.0;
+
+/// Zeroth-order modified Bessel function of the first kind, used by the Kaiser window.
+fn bessel_i0(x: f32) -> f32 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half = x / 2.0;
+    for k in 1..32 {
+        term *= half / k as f32;
+        let squared = term * term;
+        sum += squared;
+        if squared < sum * 1e-8 {
+            break;
+        }
+    }
+    sum
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-6 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+fn filter_support(filter: MipFilter) -> f32 {
+    match filter {
+        MipFilter::Box => 0.5,
+        MipFilter::Kaiser => KAISER_WIDTH,
+    }
+}
+
+fn filter_weight(filter: MipFilter, x: f32) -> f32 {
+    match filter {
+        MipFilter::Box => {
+            if x.abs() <= 0.5 {
+                1.0
+            } else {
+                0.0
+            }
+        }
+        MipFilter::Kaiser => {
+            if x.abs() >= KAISER_WIDTH {
+                return 0.0;
+            }
+
+            let t = x / KAISER_WIDTH;
+            sinc(x) * bessel_i0(KAISER_ALPHA * (1.0 - t * t).sqrt()) / bessel_i0(KAISER_ALPHA)
+        }
+    }
+}
+
+/// Calculates which source pixels contribute to each destination pixel along one axis, and by how much.
+fn contributions(source: usize, destination: usize, filter: MipFilter) -> Vec<Vec<(usize, f32)>> {
+    let scale = source as f32 / destination as f32;
+    // When shrinking, the filter is stretched so every source pixel is accounted for
+    let filter_scale = scale.max(1.0);
+    let radius = filter_support(filter) * filter_scale;
+
+    (0..destination)
+        .map(|x| {
+            let center = (x as f32 + 0.5) * scale;
+            let first = (center - radius).floor() as isize;
+            let last = (center + radius).ceil() as isize;
+
+            let mut weights: Vec<(usize, f32)> = (first..=last)
+                .map(|i| {
+                    let distance = (i as f32 + 0.5 - center) / filter_scale;
+                    (
+                        i.clamp(0, source as isize - 1) as usize,
+                        filter_weight(filter, distance),
+                    )
+                })
+                .filter(|(_, weight)| *weight != 0.0)
+                .collect();
+
+            let total: f32 = weights.iter().map(|(_, weight)| weight).sum();
+            if total.abs() < f32::EPSILON {
+                // Fall back to the nearest pixel, which can only happen when enlarging with a box filter
+                return vec![((center as usize).min(source - 1), 1.0)];
+            }
+            for (_, weight) in &mut weights {
+                *weight /= total;
+            }
+            weights
+        })
+        .collect()
+}
+
+/// Resamples linear RGBA `pixels` from `width` by `height` to `new_width` by `new_height`.
+fn resample(
+    pixels: &[[f32; 4]],
+    width: usize,
+    height: usize,
+    new_width: usize,
+    new_height: usize,
+    filter: MipFilter,
+) -> Vec<[f32; 4]> {
+    let accumulate = |pixels: &[[f32; 4]], contributions: &[(usize, f32)], stride: usize| {
+        let mut result = [0.0; 4];
+        for (index, weight) in contributions {
+            for c in 0..4 {
+                result[c] += pixels[index * stride][c] * weight;
+            }
+        }
+        result
+    };
+
+    // Filter each row first, and then each column
+    let horizontal = contributions(width, new_width, filter);
+    let mut rows = Vec::with_capacity(new_width * height);
+    for y in 0..height {
+        let row = &pixels[y * width..(y + 1) * width];
+        rows.extend(horizontal.iter().map(|x| accumulate(row, x, 1)));
+    }
+
+    let vertical = contributions(height, new_height, filter);
+    let mut result = vec![[0.0; 4]; new_width * new_height];
+    for (y, contributions) in vertical.iter().enumerate() {
+        for x in 0..new_width {
+            result[y * new_width + x] = accumulate(&rows[x..], contributions, new_width);
+        }
+    }
+
+    result
+}
+
+fn srgb_to_linear(x: f32) -> f32 {
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(x: f32) -> f32 {
+    if x <= 0.0031308 {
+        x * 12.92
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Resizes 8-bit `rgba`, treating the color channels the way `options` describes.
+pub(crate) fn resize_rgba(
+    rgba: &[u8],
+    width: usize,
+    height: usize,
+    new_width: usize,
+    new_height: usize,
+    options: &MipOptions,
+) -> Vec<u8> {
+    // Normals aren't colors, so they are never gamma corrected
+    let srgb = options.srgb && !options.normal_map;
+
+    let pixels: Vec<[f32; 4]> = rgba
+        .chunks_exact(4)
+        .map(|pixel| {
+            let mut pixel = pixel.iter().map(|x| f32::from(*x) / 255.0);
+            std::array::from_fn(|c| {
+                let value = pixel.next().unwrap();
+                if srgb && c < 3 {
+                    srgb_to_linear(value)
+                } else {
+                    value
+                }
+            })
+        })
+        .collect();
+
+    let resized = resample(
+        &pixels,
+        width,
+        height,
+        new_width,
+        new_height,
+        options.filter,
+    );
+
+    resized
+        .iter()
+        .flat_map(|pixel| {
+            let mut pixel = *pixel;
+            if options.normal_map {
+                let normal: [f32; 3] = std::array::from_fn(|c| pixel[c] * 2.0 - 1.0);
+                let length = normal.iter().map(|x| x * x).sum::<f32>().sqrt();
+                if length > f32::EPSILON {
+                    for c in 0..3 {
+                        pixel[c] = (normal[c] / length + 1.0) / 2.0;
+                    }
+                }
+            }
+
+            std::array::from_fn::<u8, 4, _>(|c| {
+                let value = pixel[c].clamp(0.0, 1.0);
+                let value = if srgb && c < 3 {
+                    linear_to_srgb(value)
+                } else {
+                    value
+                };
+                (value * 255.0).round() as u8
+            })
+        })
+        .collect()
+}
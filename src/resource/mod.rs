@@ -19,6 +19,8 @@
 //! * To read and parse file at once, use [ResourceResolver::parsed]/[SqPackResource::parsed].
 //! * To read an Excel sheet header (`.exh`), use [ResourceResolver::read_excel_sheet_header]/[SqPackResource::read_excel_sheet_header].
 //! * To read an Excel sheet, use [ResourceResolver::read_excel_sheet]/[SqPackResource::read_excel_sheet].
+//! * To read an Excel sheet in every language at once, use [ResourceResolver::read_excel_sheet_all_languages]/[SqPackResource::read_excel_sheet_all_languages].
+//! * To read an Excel sheet only as rows are requested, use [ResourceResolver::read_lazy_excel_sheet]/[SqPackResource::read_lazy_excel_sheet].
 //!
 //! # Deriving from Resource
 //!
@@ -58,7 +60,7 @@ pub use unpacked::UnpackedResource;
 use crate::{
     ByteBuffer, ReadableFile,
     common::{Language, Platform},
-    excel::{Page, Sheet},
+    excel::{MultiLanguageSheet, Page, Sheet},
     exd::EXD,
     exh::EXH,
     exl::EXL,
@@ -186,6 +188,35 @@ pub fn generic_read_excel_sheet<R: Resource + ?Sized>(
     })
 }
 
+/// Read an excel sheet by name (e.g. "Achievement") in every language it supports.
+///
+/// Language-agnostic sheets are read with [Language::None].
+///
+/// You most likely want to use the method in `ResourceResolver.`
+pub fn generic_read_excel_sheet_all_languages<R: Resource + ?Sized>(
+    resource: &mut R,
+    exh: &EXH,
+    name: &str,
+) -> crate::Result<MultiLanguageSheet> {
+    let mut languages = exh.languages.clone();
+    if languages.is_empty() {
+        languages.push(Language::None);
+    }
+
+    let sheets = languages
+        .into_iter()
+        .map(|language| {
+            Ok((
+                language,
+                generic_read_excel_sheet(resource, exh, name, language)?,
+            ))
+        })
+        .collect::<crate::Result<Vec<_>>>()?;
+
+    // There is always at least one language, so this is never None
+    Ok(MultiLanguageSheet::from_sheets(exh, sheets).unwrap())
+}
+
 /// Returns all known sheet names listed in the root list.
 ///
 /// You most likely want to use the method in `ResourceResolver.`
@@ -200,7 +231,7 @@ pub fn generic_get_all_sheet_names<R: Resource + ?Sized>(
         .collect())
 }
 
-fn generic_read_excel_exd<R: Resource + ?Sized>(
+pub(crate) fn generic_read_excel_exd<R: Resource + ?Sized>(
     resource: &mut R,
     name: &str,
     exh: &EXH,
@@ -214,3 +245,47 @@ fn generic_read_excel_exd<R: Resource + ?Sized>(
 
     generic_parsed::<R, EXD>(resource, &exd_path)
 }
+
+/// Serves files from memory, used for testing.
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub(crate) struct MemoryResource {
+    files: std::collections::HashMap<String, ByteBuffer>,
+}
+
+#[cfg(test)]
+impl MemoryResource {
+    /// Creates a resource with the files written by each [SheetBuilder](crate::excel::SheetBuilder), including the root list.
+    pub(crate) fn from_sheets(builders: &[crate::excel::SheetBuilder]) -> Self {
+        let mut resource = Self::default();
+        let mut root_exl = EXL {
+            version: 2,
+            entries: Vec::new(),
+        };
+        for builder in builders {
+            resource.files.extend(builder.write(&mut root_exl).unwrap());
+        }
+        resource
+    }
+
+    /// Adds a file at `path`, replacing any existing one.
+    pub(crate) fn add_file(&mut self, path: &str, data: ByteBuffer) {
+        self.files.insert(path.to_lowercase(), data);
+    }
+}
+
+#[cfg(test)]
+impl Resource for MemoryResource {
+    fn read(&mut self, path: &str) -> crate::Result<ByteBuffer> {
+        self.files
+            .get(&path.to_lowercase())
+            .cloned()
+            .ok_or(crate::Error::FileNotFound {
+                path: path.to_string(),
+            })
+    }
+
+    fn exists(&mut self, path: &str) -> bool {
+        self.files.contains_key(&path.to_lowercase())
+    }
+}
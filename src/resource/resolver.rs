@@ -3,11 +3,11 @@
 
 use crate::{
     ByteBuffer, Error, Language, ReadableFile,
-    excel::Sheet,
+    excel::{LazySheet, MultiLanguageSheet, Sheet},
     exh::EXH,
     resource::{
         generic_get_all_sheet_names, generic_parsed, generic_read_excel_sheet,
-        generic_read_excel_sheet_header,
+        generic_read_excel_sheet_all_languages, generic_read_excel_sheet_header,
     },
 };
 
@@ -103,6 +103,25 @@ impl ResourceResolver {
         )
     }
 
+    /// Read an excel sheet by name (e.g. "Achievement") in every language it supports.
+    pub fn read_excel_sheet_all_languages(
+        &mut self,
+        exh: &EXH,
+        name: &str,
+    ) -> crate::Result<MultiLanguageSheet> {
+        self.execute_first_found(
+            |resource| generic_read_excel_sheet_all_languages(resource, exh, name),
+            Error::ResolverFailed,
+        )
+    }
+
+    /// Read an excel sheet by name (e.g. "Achievement"), but only read pages as rows are requested.
+    ///
+    /// The returned sheet holds a copy of this resolver, so sources added afterwards won't be seen.
+    pub fn read_lazy_excel_sheet(&self, exh: &EXH, name: &str, language: Language) -> LazySheet {
+        LazySheet::new(self.clone(), exh, name, language)
+    }
+
     /// Returns all known sheet names listed in the root list.
     pub fn get_all_sheet_names(&mut self) -> crate::Result<Vec<String>> {
         self.execute_first_found(generic_get_all_sheet_names, Error::ResolverFailed)
@@ -140,3 +159,13 @@ impl ResourceResolver {
         Err(error)
     }
 }
+
+impl Resource for ResourceResolver {
+    fn read(&mut self, path: &str) -> crate::Result<ByteBuffer> {
+        ResourceResolver::read(self, path)
+    }
+
+    fn exists(&mut self, path: &str) -> bool {
+        ResourceResolver::exists(self, path)
+    }
+}
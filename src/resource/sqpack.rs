@@ -12,12 +12,12 @@ use strum::IntoEnumIterator;
 use crate::{
     ByteBuffer, Error, ReadableFile,
     common::{Language, Platform, read_version},
-    excel::Sheet,
+    excel::{LazySheet, MultiLanguageSheet, Sheet},
     exh::EXH,
     repository::{Category, Repository, RepositoryType, string_to_category},
     resource::{
         generic_get_all_sheet_names, generic_parsed, generic_read_excel_sheet,
-        generic_read_excel_sheet_header,
+        generic_read_excel_sheet_all_languages, generic_read_excel_sheet_header,
     },
     sqpack::{Hash, IndexEntry, IndexType, SqPackData, SqPackIndex},
 };
@@ -470,6 +470,22 @@ impl SqPackResource {
         generic_read_excel_sheet(self, exh, name, language)
     }
 
+    /// Read an excel sheet by name (e.g. "Achievement") in every language it supports.
+    pub fn read_excel_sheet_all_languages(
+        &mut self,
+        exh: &EXH,
+        name: &str,
+    ) -> Result<MultiLanguageSheet, Error> {
+        generic_read_excel_sheet_all_languages(self, exh, name)
+    }
+
+    /// Read an excel sheet by name (e.g. "Achievement"), but only read pages as rows are requested.
+    ///
+    /// The returned sheet holds a copy of this resource.
+    pub fn read_lazy_excel_sheet(&self, exh: &EXH, name: &str, language: Language) -> LazySheet {
+        LazySheet::new(self.clone(), exh, name, language)
+    }
+
     /// Returns all known sheet names listed in the root list.
     pub fn get_all_sheet_names(&mut self) -> Result<Vec<String>, Error> {
         generic_get_all_sheet_names(self)
@@ -569,7 +585,7 @@ mod tests {
 
     #[test]
     fn repository_repair_okay() {
-        let mut d = PathBuf::from(std::env::temp_dir());
+        let mut d = std::env::temp_dir();
         d.push("test_sqpack");
 
         if d.exists() {
@@ -592,7 +608,7 @@ mod tests {
 
     #[test]
     fn repository_repair_extra_spacing() {
-        let mut d = PathBuf::from(std::env::temp_dir());
+        let mut d = std::env::temp_dir();
         d.push("test_sqpack_bad");
 
         if d.exists() {
@@ -63,7 +63,7 @@ mod tests {
     fn exist_files() {
         let mut data = common_setup_data();
 
-        assert_eq!(data.exists("empty_planlive.lgb"), true);
-        assert_eq!(data.exists("non_existent.lgb"), false);
+        assert!(data.exists("empty_planlive.lgb"));
+        assert!(!data.exists("non_existent.lgb"));
     }
 }
@@ -20,7 +20,7 @@ use binrw::{BinRead, BinWrite, binrw};
 #[allow(unused)]
 pub struct ResourceParameter {
     /// CRC32 of `name`.
-    crc32: u32,
+    pub crc32: u32,
     #[br(temp)]
     #[bw(ignore)]
     local_string_offset: u32,
@@ -88,9 +88,12 @@ pub struct Shader {
 #[repr(C)]
 #[allow(unused)]
 pub struct MaterialParameter {
-    id: u32,
-    byte_offset: u16,
-    byte_size: u16,
+    /// CRC32 of the parameter name, which matches the ID of a material constant.
+    pub id: u32,
+    /// Offset into the material parameter buffer. Divide it by four to index into `mat_param_defaults`.
+    pub byte_offset: u16,
+    /// Size of the parameter, which is always a multiple of four since it's made of floats.
+    pub byte_size: u16,
 }
 
 #[binrw]
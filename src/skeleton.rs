@@ -81,28 +81,42 @@ impl ReadableFile for Skeleton {
 
         let sklb = SKLB::read_options(&mut cursor, platform.endianness(), ())?;
 
-        let root = HavokBinaryTagFileReader::read(&sklb.raw_data);
-        let raw_animation_container = root.find_object_by_type("hkaAnimationContainer");
-        let animation_container = HavokAnimationContainer::new(raw_animation_container);
-
-        let havok_skeleton = &animation_container.skeletons[0];
+        let animation_container = HavokBinaryTagFileReader::read(&sklb.raw_data)
+            .and_then(|root| root.find_object_by_type("hkaAnimationContainer"))
+            .and_then(HavokAnimationContainer::new)
+            .ok_or_else(|| crate::Error::InvalidData {
+                reason: "the skeleton's Havok data couldn't be read".to_string(),
+            })?;
+
+        let havok_skeleton =
+            animation_container
+                .skeletons
+                .first()
+                .ok_or_else(|| crate::Error::InvalidData {
+                    reason: "the skeleton's Havok data has no skeletons".to_string(),
+                })?;
 
         let mut skeleton = Skeleton { bones: vec![] };
 
-        for (index, bone) in havok_skeleton.bone_names.iter().enumerate() {
+        for ((bone, parent_index), reference_pose) in havok_skeleton
+            .bone_names
+            .iter()
+            .zip(&havok_skeleton.parent_indices)
+            .zip(&havok_skeleton.reference_pose)
+        {
             skeleton.bones.push(Bone {
                 name: bone.clone(),
-                parent_index: havok_skeleton.parent_indices[index] as i32,
+                parent_index: *parent_index as i32,
                 position: [
-                    havok_skeleton.reference_pose[index].translation[0],
-                    havok_skeleton.reference_pose[index].translation[1],
-                    havok_skeleton.reference_pose[index].translation[2],
+                    reference_pose.translation[0],
+                    reference_pose.translation[1],
+                    reference_pose.translation[2],
                 ],
-                rotation: havok_skeleton.reference_pose[index].rotation,
+                rotation: reference_pose.rotation,
                 scale: [
-                    havok_skeleton.reference_pose[index].scale[0],
-                    havok_skeleton.reference_pose[index].scale[1],
-                    havok_skeleton.reference_pose[index].scale[2],
+                    reference_pose.scale[0],
+                    reference_pose.scale[1],
+                    reference_pose.scale[2],
                 ],
             });
         }
@@ -121,6 +135,9 @@ impl Skeleton {
 
 #[cfg(test)]
 mod tests {
+    use std::fs::read;
+    use std::path::PathBuf;
+
     use crate::pass_random_invalid;
 
     use super::*;
@@ -129,4 +146,28 @@ mod tests {
     fn test_invalid() {
         pass_random_invalid::<Skeleton>();
     }
+
+    #[test]
+    fn test_read() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/tests");
+        d.push("skl_c0801b0001.sklb");
+
+        let data = read(d).unwrap();
+
+        let skeleton = Skeleton::from_existing(Platform::Win32, &data).unwrap();
+        assert_eq!(skeleton.bones.len(), 2);
+        assert_eq!(skeleton.bones[0].name, "n_root");
+        assert_eq!(skeleton.bones[0].parent_index, -1);
+        assert_eq!(skeleton.bones[1].name, "j_kosi");
+        assert_eq!(skeleton.bones[1].parent_index, 0);
+        assert_eq!(skeleton.bones[1].position, [0.0, 1.0, 0.0]);
+        assert_eq!(skeleton.bones[1].rotation, [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(skeleton.bones[1].scale, [1.0, 1.0, 1.0]);
+
+        // Bad Havok data is an error instead of a panic
+        for len in 28..data.len() {
+            assert!(Skeleton::from_existing(Platform::Win32, &data[..len]).is_err());
+        }
+    }
 }
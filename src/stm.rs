@@ -25,16 +25,43 @@ struct StainingTemplateEntry {
     #[br(map = calc_byte_counts, count = num_colors + num_scalars)]
     byte_counts: Vec<u16>,
 
+    /// Each color, with a value for every stain.
     #[br(parse_with = read_color_array, args(&byte_counts, num_colors))]
     #[bw(ignore)]
-    colors: Vec<Half3>,
+    colors: Vec<Vec<Half3>>,
+    /// Each scalar, with a value for every stain.
     #[br(parse_with = read_scalar_array, args(&byte_counts, num_colors, num_scalars))]
     #[bw(ignore)]
-    scalars: Vec<u16>,
+    scalars: Vec<Vec<u16>>,
+}
+
+impl StainingTemplateEntry {
+    /// The number of stains this template has values for.
+    fn stain_count(&self) -> usize {
+        self.colors
+            .iter()
+            .map(Vec::len)
+            .chain(self.scalars.iter().map(Vec::len))
+            .min()
+            .unwrap_or_default()
+    }
+
+    fn color(&self, color: usize, index: usize) -> [f32; 3] {
+        self.colors[color][index].into()
+    }
+
+    fn scalar(&self, scalar: usize, index: usize) -> f32 {
+        f16::from_bits(self.scalars[scalar][index]).to_f32_const()
+    }
+}
+
+/// Dye information for a single stain, which can be read from a staining template.
+trait StainingTemplateDye {
+    fn from_entry(entry: &StainingTemplateEntry, index: usize) -> Self;
 }
 
 /// Legacy dye information.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LegacyDye {
     pub diffuse: [f32; 3],
     pub specular: [f32; 3],
@@ -43,25 +70,25 @@ pub struct LegacyDye {
     pub specular_mask: f32,
 }
 
-impl From<StainingTemplateEntry> for LegacyDye {
-    fn from(value: StainingTemplateEntry) -> Self {
+impl StainingTemplateDye for LegacyDye {
+    fn from_entry(entry: &StainingTemplateEntry, index: usize) -> Self {
         Self {
-            diffuse: value.colors[0].into(),
-            specular: value.colors[1].into(),
-            emissive: value.colors[2].into(),
-            shininess: f16::from_bits(value.scalars[0]).to_f32_const(),
-            specular_mask: f16::from_bits(value.scalars[1]).to_f32_const(),
+            diffuse: entry.color(0, index),
+            specular: entry.color(1, index),
+            emissive: entry.color(2, index),
+            shininess: entry.scalar(0, index),
+            specular_mask: entry.scalar(1, index),
         }
     }
 }
 
 /// Dawntrail-era dye information.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Dye {
     pub diffuse: [f32; 3],
     pub specular: [f32; 3],
     pub emissive: [f32; 3],
-    scalar3: f32, // TODO: what is this?
+    pub scalar3: f32, // TODO: what is this?
     pub metalness: f32,
     pub roughness: f32,
     pub sheen_rate: f32,
@@ -72,21 +99,21 @@ pub struct Dye {
     pub sphere_map_mask: f32,
 }
 
-impl From<StainingTemplateEntry> for Dye {
-    fn from(value: StainingTemplateEntry) -> Self {
+impl StainingTemplateDye for Dye {
+    fn from_entry(entry: &StainingTemplateEntry, index: usize) -> Self {
         Self {
-            diffuse: value.colors[0].into(),
-            specular: value.colors[1].into(),
-            emissive: value.colors[2].into(),
-            scalar3: f16::from_bits(value.scalars[9]).to_f32_const(),
-            metalness: f16::from_bits(value.scalars[1]).to_f32_const(),
-            roughness: f16::from_bits(value.scalars[2]).to_f32_const(),
-            sheen_rate: f16::from_bits(value.scalars[3]).to_f32_const(),
-            sheen_tint_rate: f16::from_bits(value.scalars[4]).to_f32_const(),
-            sheen_aperture: f16::from_bits(value.scalars[5]).to_f32_const(),
-            anisotropy: f16::from_bits(value.scalars[6]).to_f32_const(),
-            raw_sphere_map_index: value.scalars[7],
-            sphere_map_mask: f16::from_bits(value.scalars[8]).to_f32_const(),
+            diffuse: entry.color(0, index),
+            specular: entry.color(1, index),
+            emissive: entry.color(2, index),
+            scalar3: entry.scalar(0, index),
+            metalness: entry.scalar(1, index),
+            roughness: entry.scalar(2, index),
+            sheen_rate: entry.scalar(3, index),
+            sheen_tint_rate: entry.scalar(4, index),
+            sheen_aperture: entry.scalar(5, index),
+            anisotropy: entry.scalar(6, index),
+            raw_sphere_map_index: entry.scalars[7][index],
+            sphere_map_mask: entry.scalar(8, index),
         }
     }
 }
@@ -104,15 +131,12 @@ fn calc_byte_counts(ends: Vec<u16>) -> Vec<u16> {
 }
 
 #[binrw::parser(reader, endian)]
-fn read_color_array(byte_counts: &[u16], num_colors: usize) -> BinResult<Vec<Half3>> {
-    let mut entries = Vec::with_capacity(num_colors);
-
-    for count in byte_counts.iter().take(num_colors) {
-        let mut array: Vec<Half3> = read_array(reader, endian, *count);
-        entries.append(&mut array); // TODO: wrong
-    }
-
-    Ok(entries)
+fn read_color_array(byte_counts: &[u16], num_colors: usize) -> BinResult<Vec<Vec<Half3>>> {
+    Ok(byte_counts
+        .iter()
+        .take(num_colors)
+        .map(|count| read_array(reader, endian, *count))
+        .collect())
 }
 
 #[binrw::parser(reader, endian)]
@@ -120,15 +144,13 @@ fn read_scalar_array(
     byte_counts: &[u16],
     num_colors: usize,
     num_scalars: usize,
-) -> BinResult<Vec<u16>> {
-    let mut entries = Vec::with_capacity(num_scalars);
-
-    for count in byte_counts.iter().skip(num_colors).take(num_scalars) {
-        let mut array: Vec<u16> = read_array(reader, endian, *count);
-        entries.append(&mut array); // TODO: wrong
-    }
-
-    Ok(entries)
+) -> BinResult<Vec<Vec<u16>>> {
+    Ok(byte_counts
+        .iter()
+        .skip(num_colors)
+        .take(num_scalars)
+        .map(|count| read_array(reader, endian, *count))
+        .collect())
 }
 
 fn read_array<
@@ -173,7 +195,7 @@ fn read_array<
         vec
     } else if array_size == MAX_ELEMENTS {
         let mut vec = vec![];
-        for _ in 0..size {
+        for _ in 0..array_size {
             vec.push(cursor.read_type::<T>(endian).unwrap());
         }
         vec
@@ -207,22 +229,24 @@ pub struct Stm {
     #[bw(ignore)]
     num_scalars: usize,
 
+    /// Dyes for each template, indexed by stain ID minus one. See [Stm::dye].
     #[br(if(version != 0x101), parse_with = read_entries, args(&keys, &offsets, num_colors, num_scalars))]
     #[bw(ignore)] // TODO: support writing
-    pub dyes: HashMap<u32, Dye>,
+    pub dyes: HashMap<u32, Vec<Dye>>,
 
+    /// Dyes for each template, indexed by stain ID minus one. See [Stm::legacy_dye].
     #[br(if(version == 0x101), parse_with = read_entries, args(&keys, &offsets, num_colors, num_scalars))]
     #[bw(ignore)] // TODO: support writing
-    pub legacy_dyes: HashMap<u32, LegacyDye>,
+    pub legacy_dyes: HashMap<u32, Vec<LegacyDye>>,
 }
 
 #[binrw::parser(reader, endian)]
-fn read_entries<T: From<StainingTemplateEntry>>(
+fn read_entries<T: StainingTemplateDye>(
     keys: &[u32],
     offsets: &[u32],
     num_colors: usize,
     num_scalars: usize,
-) -> BinResult<HashMap<u32, T>> {
+) -> BinResult<HashMap<u32, Vec<T>>> {
     let mut entries = HashMap::with_capacity(keys.len());
 
     let start_position = reader.stream_position()?;
@@ -231,7 +255,10 @@ fn read_entries<T: From<StainingTemplateEntry>>(
         reader.seek(SeekFrom::Start(start_position + *offset as u64 * 2))?;
         let entry: StainingTemplateEntry =
             reader.read_type_args(endian, (num_colors, num_scalars))?;
-        entries.insert(*key, T::from(entry));
+        let dyes = (0..entry.stain_count())
+            .map(|index| T::from_entry(&entry, index))
+            .collect();
+        entries.insert(*key, dyes);
     }
 
     Ok(entries)
@@ -257,6 +284,22 @@ impl WritableFile for Stm {
     }
 }
 
+impl Stm {
+    /// Returns the Dawntrail-era dye for `stain_id` in `template`. Stain zero means no dye, so it never has one.
+    pub fn dye(&self, template: u32, stain_id: u8) -> Option<&Dye> {
+        self.dyes
+            .get(&template)?
+            .get(usize::from(stain_id).checked_sub(1)?)
+    }
+
+    /// Returns the legacy dye for `stain_id` in `template`. Stain zero means no dye, so it never has one.
+    pub fn legacy_dye(&self, template: u32, stain_id: u8) -> Option<&LegacyDye> {
+        self.legacy_dyes
+            .get(&template)?
+            .get(usize::from(stain_id).checked_sub(1)?)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::pass_random_invalid;
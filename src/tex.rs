@@ -10,15 +10,30 @@ use crate::ByteSpan;
 use crate::ReadableFile;
 use crate::WritableFile;
 use crate::bcn::decode_bc1;
+use crate::bcn::decode_bc2;
 use crate::bcn::decode_bc3;
+use crate::bcn::decode_bc4;
 use crate::bcn::decode_bc5;
+use crate::bcn::decode_bc6h;
 use crate::bcn::decode_bc7;
+use crate::bcn::encode_bc1;
+use crate::bcn::encode_bc3;
+use crate::bcn::encode_bc4;
+use crate::bcn::encode_bc5;
+use crate::bcn::encode_bc7;
 use crate::common::Platform;
+use crate::dds::{self, DdsHeader, DdsPixelFormat, Dx10Header};
+use crate::png::{read_png, write_png};
+use crate::tga::{read_tga, write_tga};
+use crate::tiling::Tiling;
 use binrw::BinRead;
 use binrw::BinWrite;
 use binrw::binrw;
 use binrw::helpers::until_eof;
 use bitflags::bitflags;
+use half::f16;
+
+pub use crate::bcn::CompressionQuality;
 
 #[binrw]
 #[repr(C)]
@@ -105,6 +120,273 @@ pub enum TextureFormat {
     D24_UNORM_S8_UINT_3 = 0x8250,
 }
 
+impl TextureFormat {
+    /// Returns whether this format is compressed in 4x4 blocks.
+    pub fn is_block_compressed(&self) -> bool {
+        matches!(
+            self,
+            TextureFormat::BC1_UNORM
+                | TextureFormat::BC2_UNORM
+                | TextureFormat::BC3_UNORM
+                | TextureFormat::BC4_UNORM
+                | TextureFormat::BC5_UNORM
+                | TextureFormat::BC6H_SF16
+                | TextureFormat::BC7_UNORM
+        )
+    }
+
+    /// Returns the size of a pixel in bytes, or the size of a 4x4 block if [TextureFormat::is_block_compressed].
+    pub fn bytes_per_element(&self) -> usize {
+        match self {
+            TextureFormat::L8_UNORM
+            | TextureFormat::A8_UNORM
+            | TextureFormat::R8_UNORM
+            | TextureFormat::R8_UINT => 1,
+            TextureFormat::R16_UINT
+            | TextureFormat::R8G8_UNORM
+            | TextureFormat::B4G4R4A4_UNORM
+            | TextureFormat::B5G5R5A1_UNORM
+            | TextureFormat::R16_FLOAT
+            | TextureFormat::D16_UNORM
+            | TextureFormat::D16_UNORM_2
+            | TextureFormat::R16_UNORM => 2,
+            TextureFormat::R32_UINT
+            | TextureFormat::B8G8R8A8_UNORM
+            | TextureFormat::B8G8R8X8_UNORM
+            | TextureFormat::R32_FLOAT
+            | TextureFormat::R16G16_FLOAT
+            | TextureFormat::R11G11B10_FLOAT
+            | TextureFormat::D24_UNORM_S8_UINT
+            | TextureFormat::D24_UNORM_S8_UINT_2
+            | TextureFormat::D24_UNORM_S8_UINT_3
+            | TextureFormat::R16G16_UNORM
+            | TextureFormat::R10G10B10A2_UNORM
+            | TextureFormat::R10G10B10A2_UNORM_2 => 4,
+            TextureFormat::R32G32_FLOAT | TextureFormat::R16G16B16A16_FLOAT => 8,
+            TextureFormat::R32G32B32A32_FLOAT => 16,
+            TextureFormat::BC1_UNORM | TextureFormat::BC4_UNORM => 8,
+            TextureFormat::BC2_UNORM
+            | TextureFormat::BC3_UNORM
+            | TextureFormat::BC5_UNORM
+            | TextureFormat::BC6H_SF16
+            | TextureFormat::BC7_UNORM => 16,
+        }
+    }
+
+    /// Returns the size in bytes of a `width` by `height` image in this format.
+    pub fn surface_size(&self, width: usize, height: usize) -> usize {
+        if self.is_block_compressed() {
+            width.div_ceil(4) * height.div_ceil(4) * self.bytes_per_element()
+        } else {
+            width * height * self.bytes_per_element()
+        }
+    }
+}
+
+/// Identifies a single 2D image inside of a [Texture].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TextureSurface {
+    /// The mip level, where zero is the full size image.
+    pub mip_level: u8,
+    /// The array layer, or the cube face for cubemaps. See [Texture::layers].
+    pub layer: u8,
+    /// The depth slice, which is only non-zero for 3D textures. See [Texture::mip_depth].
+    pub slice: u16,
+}
+
+/// A decoded [TextureSurface].
+#[derive(Debug, Clone)]
+pub struct TextureImage<T = u8> {
+    /// The surface this image was decoded from.
+    pub surface: TextureSurface,
+    /// Width of the image in pixels.
+    pub width: u16,
+    /// Height of the image in pixels.
+    pub height: u16,
+    /// RGBA pixels, in rows from top to bottom.
+    pub pixels: Vec<T>,
+}
+
+impl TextureImage {
+    /// Reads a PNG image, which can then be turned back into a texture with [Texture::from_rgba_with_mips].
+    pub fn from_png(buffer: ByteSpan) -> crate::Result<Self> {
+        let (width, height, pixels) = read_png(buffer)?;
+        match (u16::try_from(width), u16::try_from(height)) {
+            (Ok(width), Ok(height)) => Ok(Self {
+                surface: TextureSurface::default(),
+                width,
+                height,
+                pixels,
+            }),
+            _ => Err(crate::Error::InvalidData {
+                reason: format!("{width}x{height} is too large for a texture"),
+            }),
+        }
+    }
+
+    /// Reads a TGA image, which can then be turned back into a texture with [Texture::from_rgba_with_mips].
+    pub fn from_tga(buffer: ByteSpan) -> crate::Result<Self> {
+        let (width, height, pixels) = read_tga(buffer)?;
+        Ok(Self {
+            surface: TextureSurface::default(),
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Encodes this image as a PNG.
+    pub fn to_png(&self) -> crate::Result<ByteBuffer> {
+        write_png(self.width.into(), self.height.into(), &self.pixels)
+    }
+
+    /// Encodes this image as an uncompressed TGA.
+    pub fn to_tga(&self) -> crate::Result<ByteBuffer> {
+        write_tga(self.width, self.height, &self.pixels)
+    }
+}
+
+/// Filter used to downsample mips and resize images.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MipFilter {
+    /// Averages the pixels covered by each new pixel. Fast, but slightly blurry.
+    Box,
+    /// Windowed sinc filter, which keeps smaller mips sharper.
+    #[default]
+    Kaiser,
+}
+
+/// Options for [generate_mips] and [resize].
+#[derive(Debug, Clone, Copy)]
+pub struct MipOptions {
+    /// Filter used for downsampling.
+    pub filter: MipFilter,
+    /// Whether the color channels are sRGB, in which case they're filtered in linear space. Alpha is always filtered as-is.
+    pub srgb: bool,
+    /// Whether the image is a normal map, in which case the color channels are renormalized after filtering.
+    pub normal_map: bool,
+    /// The maximum number of mip levels including the full size image, or [None] to generate every level down to 1x1.
+    pub max_levels: Option<u8>,
+    /// Whether [Texture::from_rgba_with_mips] first resizes the image to the nearest power of two.
+    pub power_of_two: bool,
+}
+
+impl Default for MipOptions {
+    fn default() -> Self {
+        Self {
+            filter: MipFilter::default(),
+            srgb: true,
+            normal_map: false,
+            max_levels: None,
+            power_of_two: false,
+        }
+    }
+}
+
+fn check_image_size(rgba: &[u8], width: u16, height: u16) -> crate::Result<()> {
+    if width == 0 || height == 0 {
+        return Err(crate::Error::InvalidData {
+            reason: "image dimensions can't be zero".to_string(),
+        });
+    }
+    if rgba.len() != width as usize * height as usize * 4 {
+        return Err(crate::Error::InvalidData {
+            reason: format!(
+                "a {width}x{height} image should have {} bytes, but has {}",
+                width as usize * height as usize * 4,
+                rgba.len()
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Resizes RGBA data from `width` by `height` to `new_width` by `new_height`.
+pub fn resize(
+    rgba: &[u8],
+    width: u16,
+    height: u16,
+    new_width: u16,
+    new_height: u16,
+    options: &MipOptions,
+) -> crate::Result<Vec<u8>> {
+    check_image_size(rgba, width, height)?;
+    if new_width == 0 || new_height == 0 {
+        return Err(crate::Error::InvalidData {
+            reason: "image dimensions can't be zero".to_string(),
+        });
+    }
+
+    if (width, height) == (new_width, new_height) {
+        return Ok(rgba.to_vec());
+    }
+
+    Ok(crate::resample::resize_rgba(
+        rgba,
+        width as usize,
+        height as usize,
+        new_width as usize,
+        new_height as usize,
+        options,
+    ))
+}
+
+/// Resizes RGBA data so both dimensions are the nearest power of two, and returns it along with the new width and height.
+pub fn resize_to_power_of_two(
+    rgba: &[u8],
+    width: u16,
+    height: u16,
+    options: &MipOptions,
+) -> crate::Result<(Vec<u8>, u16, u16)> {
+    fn nearest_power_of_two(x: u16) -> u16 {
+        let Some(up) = x.checked_next_power_of_two() else {
+            return 1 << (u16::BITS - 1);
+        };
+        let down = (up / 2).max(1);
+        if x - down < up - x { down } else { up }
+    }
+
+    let new_width = nearest_power_of_two(width);
+    let new_height = nearest_power_of_two(height);
+    let rgba = resize(rgba, width, height, new_width, new_height, options)?;
+    Ok((rgba, new_width, new_height))
+}
+
+/// Generates mips for RGBA data, starting with the full size image and halving the size each level.
+///
+/// The result can be passed to [Texture::from_rgba].
+pub fn generate_mips(
+    rgba: &[u8],
+    width: u16,
+    height: u16,
+    options: &MipOptions,
+) -> crate::Result<Vec<Vec<u8>>> {
+    check_image_size(rgba, width, height)?;
+
+    let full_chain = (u16::BITS - width.max(height).leading_zeros()) as u8;
+    let levels = full_chain
+        .min(options.max_levels.unwrap_or(u8::MAX))
+        .clamp(1, Texture::MAX_MIP_LEVELS);
+
+    Ok((0..levels)
+        .map(|level| {
+            if level == 0 {
+                return rgba.to_vec();
+            }
+
+            // Each level is filtered from the full size image, so errors don't build up
+            crate::resample::resize_rgba(
+                rgba,
+                width as usize,
+                height as usize,
+                (width >> level).max(1) as usize,
+                (height >> level).max(1) as usize,
+                options,
+            )
+        })
+        .collect())
+}
+
 /// Texture file, usually with the `.tex` file extension.
 ///
 /// Contains a texture, which optionally be compressed or represent a more complex type like a 3D image.
@@ -136,21 +418,40 @@ pub struct Texture {
 }
 
 type DecodeFunction = fn(&[u8], usize, usize, &mut [u32]) -> Result<(), &'static str>;
+type PixelFunction<T> = fn(&[u8]) -> [T; 4];
+type EncodeFunction = fn(&[u8], usize, usize, CompressionQuality) -> Result<Vec<u8>, &'static str>;
 
 impl ReadableFile for Texture {
+    /// Reads a texture, detiling the data if `platform` stores it in a tiled layout so it's always linear afterwards.
     fn from_existing(platform: Platform, buffer: ByteSpan) -> crate::Result<Self> {
         let mut cursor = Cursor::new(buffer);
-        Ok(Self::read_options(&mut cursor, platform.endianness(), ())?)
+        let mut texture = Self::read_options(&mut cursor, platform.endianness(), ())?;
+
+        if let Some((data, offset_to_surface)) = texture.convert_layout(platform, true)? {
+            texture.data = data;
+            texture.offset_to_surface = offset_to_surface;
+        }
+
+        Ok(texture)
     }
 }
 
 impl WritableFile for Texture {
+    /// Writes the texture, tiling the data again if `platform` expects it.
     fn write_to_buffer(&self, platform: Platform) -> crate::Result<ByteBuffer> {
         let mut buffer = ByteBuffer::new();
 
         {
             let mut cursor = Cursor::new(&mut buffer);
-            self.write_options(&mut cursor, platform.endianness(), ())?;
+            match self.convert_layout(platform, false)? {
+                Some((data, offset_to_surface)) => Texture {
+                    data,
+                    offset_to_surface,
+                    ..*self
+                }
+                .write_options(&mut cursor, platform.endianness(), ())?,
+                None => self.write_options(&mut cursor, platform.endianness(), ())?,
+            }
         }
 
         Ok(buffer)
@@ -159,6 +460,345 @@ impl WritableFile for Texture {
 
 impl Texture {
     const HEADER_SIZE: usize = 80;
+    /// The number of mip levels that fit in `offset_to_surface`.
+    const MAX_MIP_LEVELS: u8 = 13;
+
+    /// Creates a 2D texture from RGBA data, encoding it to `format`.
+    ///
+    /// `mips` contains the data for each mip level, starting with the full size image. Each level has to be the size given by [Texture::mip_size].
+    pub fn from_rgba<T: AsRef<[u8]>>(
+        width: u16,
+        height: u16,
+        format: TextureFormat,
+        mips: &[T],
+    ) -> crate::Result<Self> {
+        Self::from_rgba_with_quality(width, height, format, mips, CompressionQuality::default())
+    }
+
+    /// Creates a 2D texture from a single RGBA image, generating the rest of the mips with [generate_mips].
+    pub fn from_rgba_with_mips(
+        width: u16,
+        height: u16,
+        format: TextureFormat,
+        rgba: &[u8],
+        options: &MipOptions,
+    ) -> crate::Result<Self> {
+        let (rgba, width, height) = if options.power_of_two {
+            resize_to_power_of_two(rgba, width, height, options)?
+        } else {
+            (rgba.to_vec(), width, height)
+        };
+
+        let mips = generate_mips(&rgba, width, height, options)?;
+        Self::from_rgba(width, height, format, &mips)
+    }
+
+    /// Same as [Texture::from_rgba], but lets you pick how much effort is spent on block compression.
+    pub fn from_rgba_with_quality<T: AsRef<[u8]>>(
+        width: u16,
+        height: u16,
+        format: TextureFormat,
+        mips: &[T],
+        quality: CompressionQuality,
+    ) -> crate::Result<Self> {
+        let mut texture = Texture {
+            attribute: TextureAttribute::TEXTURE_TYPE2_D,
+            format,
+            width,
+            height,
+            depth: 1,
+            mip_levels: 0,
+            array_size: 0,
+            lod_offsets: [0; 3],
+            offset_to_surface: [0; 13],
+            data: Vec::new(),
+        };
+
+        if width == 0 || height == 0 {
+            return Err(crate::Error::InvalidData {
+                reason: "texture dimensions can't be zero".to_string(),
+            });
+        }
+        if mips.is_empty() || mips.len() > texture.offset_to_surface.len() {
+            return Err(crate::Error::InvalidData {
+                reason: format!(
+                    "textures need between 1 and {} mip levels, not {}",
+                    texture.offset_to_surface.len(),
+                    mips.len()
+                ),
+            });
+        }
+
+        for (level, mip) in mips.iter().enumerate() {
+            let (mip_width, mip_height) = texture.mip_size(level as u8);
+            let (mip_width, mip_height) = (mip_width as usize, mip_height as usize);
+
+            let mip = mip.as_ref();
+            if mip.len() != mip_width * mip_height * 4 {
+                return Err(crate::Error::InvalidData {
+                    reason: format!(
+                        "mip level {level} should be {mip_width}x{mip_height}, but has {} bytes",
+                        mip.len()
+                    ),
+                });
+            }
+
+            texture.offset_to_surface[level] = (Self::HEADER_SIZE + texture.data.len()) as u32;
+            texture
+                .data
+                .extend(Self::encode(mip, mip_width, mip_height, format, quality)?);
+        }
+
+        texture.mip_levels = mips.len() as u8;
+        // Each quality setting starts from a smaller mip, as long as there is one
+        texture.lod_offsets = std::array::from_fn(|i| (i as u32).min(mips.len() as u32 - 1));
+
+        Ok(texture)
+    }
+
+    /// Reads a DirectDraw Surface (`.dds`) file, keeping the encoded data as-is.
+    ///
+    /// Files with and without the DX10 header are supported, as long as their format can be stored in a texture.
+    /// Formats that share a DXGI format (such as [TextureFormat::L8_UNORM] and [TextureFormat::R8_UNORM]) are only told apart in files written by [Texture::to_dds].
+    pub fn from_dds(buffer: ByteSpan) -> crate::Result<Self> {
+        let invalid = |reason: String| crate::Error::InvalidData { reason };
+
+        let mut cursor = Cursor::new(buffer);
+        let header = DdsHeader::read(&mut cursor)?;
+        let data = &buffer[cursor.position() as usize..];
+
+        let (format, dimension, cube, array_size) = match &header.dx10 {
+            Some(dx10) => (
+                dds::texture_format(dx10.dxgi_format).ok_or_else(|| {
+                    invalid(format!("DXGI format {} is not supported", dx10.dxgi_format))
+                })?,
+                dx10.resource_dimension,
+                dx10.misc_flag & dds::MISC_TEXTURECUBE != 0,
+                dx10.array_size.max(1),
+            ),
+            None => (
+                dds::legacy_texture_format(&header.pixel_format)
+                    .ok_or_else(|| invalid("DDS pixel format is not supported".to_string()))?,
+                if header.caps2 & dds::DDSCAPS2_VOLUME != 0 {
+                    dds::DIMENSION_TEXTURE3D
+                } else {
+                    dds::DIMENSION_TEXTURE2D
+                },
+                header.caps2 & dds::DDSCAPS2_CUBEMAP != 0,
+                1,
+            ),
+        };
+
+        // Prefer the exact format we wrote, as long as the file wasn't changed to something else since
+        let format = match header.reserved1 {
+            [dds::FORMAT_HINT_MAGIC, hint, ..] => {
+                TextureFormat::read_le(&mut Cursor::new(hint.to_le_bytes()))
+                    .ok()
+                    .filter(|hint| dds::dxgi_format(*hint) == dds::dxgi_format(format))
+                    .unwrap_or(format)
+            }
+            _ => format,
+        };
+
+        let attribute = match (dimension, cube, array_size) {
+            (dds::DIMENSION_TEXTURE1D, _, 1) => TextureAttribute::TEXTURE_TYPE1_D,
+            (dds::DIMENSION_TEXTURE2D, true, 1) => TextureAttribute::TEXTURE_TYPE_CUBE,
+            (dds::DIMENSION_TEXTURE2D, false, 1) => TextureAttribute::TEXTURE_TYPE2_D,
+            (dds::DIMENSION_TEXTURE2D, false, 2..=255) => TextureAttribute::TEXTURE_TYPE2_D_ARRAY,
+            (dds::DIMENSION_TEXTURE3D, _, 1) => TextureAttribute::TEXTURE_TYPE3_D,
+            _ => {
+                return Err(invalid(format!(
+                    "DDS with dimension {dimension}, {array_size} layers and cube set to {cube} can't be stored in a texture"
+                )));
+            }
+        };
+
+        let size = |value: u32, name: &str| {
+            u16::try_from(value)
+                .ok()
+                .filter(|value| *value != 0)
+                .ok_or_else(|| invalid(format!("DDS {name} of {value} is out of range")))
+        };
+        let mip_levels = header.mip_map_count.max(1);
+        if mip_levels > u32::from(Self::MAX_MIP_LEVELS) {
+            return Err(invalid(format!(
+                "textures can have up to {} mip levels, not {mip_levels}",
+                Self::MAX_MIP_LEVELS
+            )));
+        }
+
+        let mut texture = Texture {
+            attribute,
+            format,
+            width: size(header.width, "width")?,
+            height: size(header.height, "height")?,
+            depth: if attribute == TextureAttribute::TEXTURE_TYPE3_D {
+                size(header.depth, "depth")?
+            } else {
+                1
+            },
+            mip_levels: mip_levels as u8,
+            array_size: if attribute == TextureAttribute::TEXTURE_TYPE2_D_ARRAY {
+                array_size as u8
+            } else {
+                0
+            },
+            lod_offsets: std::array::from_fn(|i| (i as u32).min(mip_levels - 1)),
+            offset_to_surface: [0; 13],
+            data: Vec::new(),
+        };
+
+        // DDS stores every mip of a layer before moving onto the next layer, which is the opposite of textures
+        let layer_stride: usize = (0..texture.mip_levels)
+            .map(|level| texture.layer_size(level))
+            .sum();
+        for level in 0..texture.mip_levels {
+            let layer_size = texture.layer_size(level);
+            let mip_offset: usize = (0..level).map(|level| texture.layer_size(level)).sum();
+
+            texture.offset_to_surface[level as usize] =
+                (Self::HEADER_SIZE + texture.data.len()) as u32;
+            for layer in 0..texture.layers() as usize {
+                let offset = layer * layer_stride + mip_offset;
+                let surface = data
+                    .get(offset..offset + layer_size)
+                    .ok_or_else(|| invalid("DDS file is missing surface data".to_string()))?;
+                texture.data.extend_from_slice(surface);
+            }
+        }
+
+        Ok(texture)
+    }
+
+    /// Writes this texture as a DirectDraw Surface (`.dds`) file with a DX10 header, without re-encoding it.
+    pub fn to_dds(&self) -> crate::Result<ByteBuffer> {
+        let volume = self.attribute.contains(TextureAttribute::TEXTURE_TYPE3_D);
+        let cube = self.attribute.contains(TextureAttribute::TEXTURE_TYPE_CUBE);
+        let dimension = if volume {
+            dds::DIMENSION_TEXTURE3D
+        } else if self.attribute.contains(TextureAttribute::TEXTURE_TYPE1_D) {
+            dds::DIMENSION_TEXTURE1D
+        } else {
+            dds::DIMENSION_TEXTURE2D
+        };
+
+        let mut flags = dds::DDSD_CAPS
+            | dds::DDSD_HEIGHT
+            | dds::DDSD_WIDTH
+            | dds::DDSD_PIXELFORMAT
+            | dds::DDSD_MIPMAPCOUNT;
+        let pitch_or_linear_size = if self.format.is_block_compressed() {
+            flags |= dds::DDSD_LINEARSIZE;
+            self.format
+                .surface_size(self.width as usize, self.height as usize)
+        } else {
+            flags |= dds::DDSD_PITCH;
+            self.width as usize * self.format.bytes_per_element()
+        };
+        if volume {
+            flags |= dds::DDSD_DEPTH;
+        }
+
+        let mut caps = dds::DDSCAPS_TEXTURE;
+        if self.mip_levels > 1 {
+            caps |= dds::DDSCAPS_COMPLEX | dds::DDSCAPS_MIPMAP;
+        }
+        let mut caps2 = 0;
+        if cube {
+            caps |= dds::DDSCAPS_COMPLEX;
+            caps2 |= dds::DDSCAPS2_CUBEMAP | dds::DDSCAPS2_CUBEMAP_ALLFACES;
+        }
+        if volume {
+            caps |= dds::DDSCAPS_COMPLEX;
+            caps2 |= dds::DDSCAPS2_VOLUME;
+        }
+
+        let mut reserved1 = [0; 11];
+        reserved1[0] = dds::FORMAT_HINT_MAGIC;
+        reserved1[1] = self.format as u32;
+
+        let header = DdsHeader {
+            size: 124,
+            flags,
+            height: u32::from(self.height),
+            width: u32::from(self.width),
+            pitch_or_linear_size: pitch_or_linear_size as u32,
+            depth: if volume { u32::from(self.depth) } else { 0 },
+            mip_map_count: u32::from(self.mip_levels),
+            reserved1,
+            pixel_format: DdsPixelFormat {
+                size: 32,
+                flags: dds::DDPF_FOURCC,
+                four_cc: dds::FOURCC_DX10,
+                ..Default::default()
+            },
+            caps,
+            caps2,
+            caps3: 0,
+            caps4: 0,
+            reserved2: 0,
+            dx10: Some(Dx10Header {
+                dxgi_format: dds::dxgi_format(self.format),
+                resource_dimension: dimension,
+                misc_flag: if cube { dds::MISC_TEXTURECUBE } else { 0 },
+                // Cubemaps count the number of cubes, not faces
+                array_size: if cube { 1 } else { u32::from(self.layers()) },
+                misc_flags2: 0,
+            }),
+        };
+
+        let mut buffer = ByteBuffer::new();
+        header.write(&mut Cursor::new(&mut buffer))?;
+
+        for layer in 0..self.layers() as usize {
+            for level in 0..self.mip_levels {
+                let layer_size = self.layer_size(level);
+                let surface = self
+                    .mip_data(level)
+                    .map(|(start, end)| (start + layer * layer_size, end))
+                    .filter(|(offset, end)| offset + layer_size <= *end)
+                    .and_then(|(offset, _)| self.data.get(offset..offset + layer_size))
+                    .ok_or_else(|| crate::Error::InvalidData {
+                        reason: format!("mip level {level} is missing data for layer {layer}"),
+                    })?;
+                buffer.extend_from_slice(surface);
+            }
+        }
+
+        Ok(buffer)
+    }
+
+    fn encode(
+        rgba: &[u8],
+        width: usize,
+        height: usize,
+        format: TextureFormat,
+        quality: CompressionQuality,
+    ) -> crate::Result<Vec<u8>> {
+        let encode_func: EncodeFunction = match format {
+            TextureFormat::B8G8R8A8_UNORM | TextureFormat::B8G8R8X8_UNORM => {
+                let opaque = format == TextureFormat::B8G8R8X8_UNORM;
+                return Ok(rgba
+                    .chunks_exact(4)
+                    .flat_map(|x| [x[2], x[1], x[0], if opaque { 255 } else { x[3] }])
+                    .collect());
+            }
+            TextureFormat::BC1_UNORM => encode_bc1,
+            TextureFormat::BC3_UNORM => encode_bc3,
+            TextureFormat::BC4_UNORM => encode_bc4,
+            TextureFormat::BC5_UNORM => encode_bc5,
+            TextureFormat::BC7_UNORM => encode_bc7,
+            _ => {
+                return Err(crate::Error::InvalidData {
+                    reason: format!("encoding {format:?} textures is not supported"),
+                });
+            }
+        };
+
+        encode_func(rgba, width, height, quality).map_err(|reason| crate::Error::InvalidData {
+            reason: reason.to_string(),
+        })
+    }
 
     pub fn layers(&self) -> u8 {
         if self
@@ -196,100 +836,383 @@ impl Texture {
         ((self.width >> shift).max(1), (self.height >> shift).max(1))
     }
 
-    fn decode(src: &[u8], width: usize, height: usize, decode_func: DecodeFunction) -> Vec<u8> {
+    /// Returns the number of depth slices in a mip level, which is always 1 unless this is a 3D texture.
+    pub fn mip_depth(&self, level: u8) -> u16 {
+        if !self.attribute.contains(TextureAttribute::TEXTURE_TYPE3_D) {
+            return 1;
+        }
+
+        let shift = u32::from(level).min(u16::BITS - 1);
+        (self.depth >> shift).max(1)
+    }
+
+    /// Returns the size in bytes of a single layer in a mip level, including all of its depth slices.
+    fn layer_size(&self, level: u8) -> usize {
+        let (width, height) = self.mip_size(level);
+        self.format.surface_size(width as usize, height as usize) * self.mip_depth(level) as usize
+    }
+
+    /// Converts the data between the linear layout and the one used by `platform`, along with the new surface offsets.
+    ///
+    /// Returns [None] if `platform` uses the linear layout for this texture, so there's nothing to do.
+    fn convert_layout(
+        &self,
+        platform: Platform,
+        to_linear: bool,
+    ) -> crate::Result<Option<(Vec<u8>, [u32; 13])>> {
+        let element_size = self.format.bytes_per_element();
+        let elements = |level: u8| {
+            let (width, height) = self.mip_size(level);
+            let (width, height) = (width as usize, height as usize);
+            if self.format.is_block_compressed() {
+                (width.div_ceil(4), height.div_ceil(4))
+            } else {
+                (width, height)
+            }
+        };
+        let tiling = |level: u8| {
+            let (width, height) = elements(level);
+            Tiling::new(
+                platform,
+                self.format.is_block_compressed(),
+                width,
+                height,
+                !self
+                    .attribute
+                    .contains(TextureAttribute::TEXTURE_NO_SWIZZLE),
+                !self.attribute.contains(TextureAttribute::TEXTURE_NO_TILED),
+            )
+        };
+
+        if (0..self.mip_levels).all(|level| tiling(level) == Tiling::Linear) {
+            return Ok(None);
+        }
+
+        let mut data = Vec::with_capacity(self.data.len());
+        let mut offset_to_surface = [0; 13];
+        for level in 0..self.mip_levels {
+            let tiling = tiling(level);
+            let (width, height) = elements(level);
+            let linear_size = width * height * element_size;
+            let tiled_size = tiling.surface_size(width, height, element_size);
+            let source_size = if to_linear { tiled_size } else { linear_size };
+
+            // Every layer and depth slice is tiled on its own
+            let (start, _) = self
+                .mip_data(level)
+                .ok_or_else(|| crate::Error::InvalidData {
+                    reason: format!("mip level {level} has no data"),
+                })?;
+            let surfaces = self.layers() as usize * self.mip_depth(level) as usize;
+
+            offset_to_surface[level as usize] = (Self::HEADER_SIZE + data.len()) as u32;
+            for surface in 0..surfaces {
+                let offset = start + surface * source_size;
+                let source = self.data.get(offset..offset + source_size).ok_or_else(|| {
+                    crate::Error::InvalidData {
+                        reason: format!("mip level {level} is missing data for surface {surface}"),
+                    }
+                })?;
+
+                if to_linear {
+                    data.extend(tiling.detile(source, width, height, element_size));
+                } else {
+                    data.extend(tiling.tile(source, width, height, element_size));
+                }
+            }
+        }
+
+        Ok(Some((data, offset_to_surface)))
+    }
+
+    /// Lists every surface in this texture, ordered by mip level, then layer and then depth slice.
+    pub fn surfaces(&self) -> impl Iterator<Item = TextureSurface> + '_ {
+        (0..self.mip_levels).flat_map(move |mip_level| {
+            (0..self.layers()).flat_map(move |layer| {
+                (0..self.mip_depth(mip_level)).map(move |slice| TextureSurface {
+                    mip_level,
+                    layer,
+                    slice,
+                })
+            })
+        })
+    }
+
+    /// Returns the encoded data for `surface`, or [None] if it's out of bounds.
+    pub fn surface_data(&self, surface: TextureSurface) -> Option<&[u8]> {
+        if surface.layer >= self.layers() || surface.slice >= self.mip_depth(surface.mip_level) {
+            return None;
+        }
+
+        // Each mip level stores every layer one after another, and each layer stores every slice
+        let (start, end) = self.mip_data(surface.mip_level)?;
+        let layer_size = self.layer_size(surface.mip_level);
+        let slice_size = layer_size / self.mip_depth(surface.mip_level) as usize;
+
+        let offset =
+            start + surface.layer as usize * layer_size + surface.slice as usize * slice_size;
+        if offset + slice_size > end {
+            return None;
+        }
+        self.data.get(offset..offset + slice_size)
+    }
+
+    /// Decode a single surface to RGBA data. See [Texture::to_rgba] for how HDR formats are handled.
+    pub fn decode_surface(&self, surface: TextureSurface) -> crate::Result<TextureImage> {
+        let (data, width, height) = self.surface_and_size(surface)?;
+        Ok(TextureImage {
+            surface,
+            width,
+            height,
+            pixels: Self::decode_rgba(self.format, data, width as usize, height as usize)?,
+        })
+    }
+
+    /// Decode a single surface to RGBA floats. See [Texture::to_rgba_f32].
+    pub fn decode_surface_f32(&self, surface: TextureSurface) -> crate::Result<TextureImage<f32>> {
+        let (data, width, height) = self.surface_and_size(surface)?;
+        Ok(TextureImage {
+            surface,
+            width,
+            height,
+            pixels: Self::decode_rgba_f32(self.format, data, width as usize, height as usize)?,
+        })
+    }
+
+    /// Decodes the first mip level and layer as a PNG image. Use [TextureImage::to_png] for the other surfaces.
+    pub fn to_png(&self) -> crate::Result<ByteBuffer> {
+        self.decode_surface(TextureSurface::default())?.to_png()
+    }
+
+    /// Decodes the first mip level and layer as a TGA image. Use [TextureImage::to_tga] for the other surfaces.
+    pub fn to_tga(&self) -> crate::Result<ByteBuffer> {
+        self.decode_surface(TextureSurface::default())?.to_tga()
+    }
+
+    /// Decodes every surface in this texture, in the same order as [Texture::surfaces].
+    pub fn images(&self) -> impl Iterator<Item = crate::Result<TextureImage>> + '_ {
+        self.surfaces().map(|surface| self.decode_surface(surface))
+    }
+
+    fn surface_and_size(&self, surface: TextureSurface) -> crate::Result<(&[u8], u16, u16)> {
+        let data = self
+            .surface_data(surface)
+            .ok_or_else(|| crate::Error::InvalidData {
+                reason: format!("{surface:?} is out of bounds"),
+            })?;
+        let (width, height) = self.mip_size(surface.mip_level);
+        Ok((data, width, height))
+    }
+
+    fn decode(
+        src: &[u8],
+        width: usize,
+        height: usize,
+        decode_func: DecodeFunction,
+    ) -> crate::Result<Vec<u8>> {
         let mut image: Vec<u32> = vec![0; width * height];
-        decode_func(src, width, height, &mut image).unwrap();
+        decode_func(src, width, height, &mut image).map_err(|reason| {
+            crate::Error::InvalidData {
+                reason: reason.to_string(),
+            }
+        })?;
 
-        image
+        Ok(image
             .iter()
             .flat_map(|x| {
                 let v = x.to_le_bytes();
                 [v[2], v[1], v[0], v[3]]
             })
-            .collect::<Vec<u8>>()
+            .collect::<Vec<u8>>())
     }
 
-    /// Decode this texture to RGBA data, if we know how to decode it in software.
-    pub fn to_rgba(&self) -> Option<Vec<u8>> {
-        match self.format {
-            TextureFormat::B4G4R4A4_UNORM => {
-                let mut dst =
-                    vec![0u8; self.width as usize * self.height as usize * self.depth as usize * 4];
-
-                let mut offset = 0;
-                let mut dst_offset = 0;
-
-                for _ in 0..self.width as usize * self.height as usize {
-                    let short: u16 =
-                        ((self.data[offset] as u16) << 8) | self.data[offset + 1] as u16;
-
-                    let src_b = short & 0xF;
-                    let src_g = (short >> 4) & 0xF;
-                    let src_r = (short >> 8) & 0xF;
-                    let src_a = (short >> 12) & 0xF;
-
-                    dst[dst_offset] = (17 * src_r) as u8;
-                    dst[dst_offset + 1] = (17 * src_g) as u8;
-                    dst[dst_offset + 2] = (17 * src_b) as u8;
-                    dst[dst_offset + 3] = (17 * src_a) as u8;
+    /// Decode this texture to RGBA data.
+    ///
+    /// Only the first mip level and layer are decoded, use [Texture::decode_surface] for the others. For 3D textures, every depth slice of it is decoded one after the other.
+    /// Floating point and BC6H textures are clamped to 0-1 first, use [Texture::to_rgba_f32] to keep their full range.
+    pub fn to_rgba(&self) -> crate::Result<Vec<u8>> {
+        Self::decode_rgba(
+            self.format,
+            &self.data,
+            self.width as usize,
+            self.height as usize * self.depth as usize,
+        )
+    }
 
-                    offset += 2;
-                    dst_offset += 4;
-                }
+    /// Decode this texture to RGBA floats, which keeps the full range of floating point and BC6H textures.
+    ///
+    /// Like [Texture::to_rgba], only the first mip level and layer are decoded.
+    pub fn to_rgba_f32(&self) -> crate::Result<Vec<f32>> {
+        Self::decode_rgba_f32(
+            self.format,
+            &self.data,
+            self.width as usize,
+            self.height as usize * self.depth as usize,
+        )
+    }
 
-                Some(dst)
+    fn decode_rgba(
+        format: TextureFormat,
+        data: &[u8],
+        width: usize,
+        height: usize,
+    ) -> crate::Result<Vec<u8>> {
+        let decode_pixel: PixelFunction<u8> = match format {
+            TextureFormat::BC1_UNORM => return Self::decode(data, width, height, decode_bc1),
+            TextureFormat::BC2_UNORM => return Self::decode(data, width, height, decode_bc2),
+            TextureFormat::BC3_UNORM => return Self::decode(data, width, height, decode_bc3),
+            TextureFormat::BC4_UNORM => return Self::decode(data, width, height, decode_bc4),
+            TextureFormat::BC5_UNORM => return Self::decode(data, width, height, decode_bc5),
+            TextureFormat::BC7_UNORM => return Self::decode(data, width, height, decode_bc7),
+            TextureFormat::R16_FLOAT
+            | TextureFormat::R32_FLOAT
+            | TextureFormat::R16G16_FLOAT
+            | TextureFormat::R32G32_FLOAT
+            | TextureFormat::R11G11B10_FLOAT
+            | TextureFormat::R16G16B16A16_FLOAT
+            | TextureFormat::R32G32B32A32_FLOAT
+            | TextureFormat::BC6H_SF16 => {
+                return Ok(Self::decode_rgba_f32(format, data, width, height)?
+                    .iter()
+                    .map(|x| (x.clamp(0.0, 1.0) * 255.0).round() as u8)
+                    .collect());
             }
-            TextureFormat::B8G8R8A8_UNORM => {
-                let mut dst =
-                    vec![0u8; self.width as usize * self.height as usize * self.depth as usize * 4];
+            TextureFormat::L8_UNORM => |p| [p[0], p[0], p[0], 255],
+            TextureFormat::A8_UNORM => |p| [0, 0, 0, p[0]],
+            TextureFormat::R8_UNORM | TextureFormat::R8_UINT => |p| [p[0], 0, 0, 255],
+            // Only the most significant byte of wider formats is kept
+            TextureFormat::R16_UNORM | TextureFormat::R16_UINT => |p| [p[1], 0, 0, 255],
+            TextureFormat::R32_UINT => |p| [p[3], 0, 0, 255],
+            TextureFormat::R8G8_UNORM => |p| [p[0], p[1], 0, 255],
+            TextureFormat::R16G16_UNORM => |p| [p[1], p[3], 0, 255],
+            TextureFormat::B4G4R4A4_UNORM => |p| {
+                let short = u16::from_be_bytes([p[0], p[1]]);
+                [short >> 8, short >> 4, short, short >> 12].map(|x| (17 * (x & 0xF)) as u8)
+            },
+            TextureFormat::B5G5R5A1_UNORM => |p| {
+                let short = u16::from_le_bytes([p[0], p[1]]);
+                let expand = |x: u16| (((x & 0x1F) << 3) | ((x & 0x1F) >> 2)) as u8;
+                [
+                    expand(short >> 10),
+                    expand(short >> 5),
+                    expand(short),
+                    if short >> 15 != 0 { 255 } else { 0 },
+                ]
+            },
+            TextureFormat::B8G8R8A8_UNORM => |p| [p[2], p[1], p[0], p[3]],
+            TextureFormat::B8G8R8X8_UNORM => |p| [p[2], p[1], p[0], 255],
+            TextureFormat::R10G10B10A2_UNORM | TextureFormat::R10G10B10A2_UNORM_2 => |p| {
+                let value = u32::from_le_bytes([p[0], p[1], p[2], p[3]]);
+                [value >> 2, value >> 12, value >> 22, (value >> 30) * 85].map(|x| x as u8)
+            },
+            TextureFormat::D16_UNORM | TextureFormat::D16_UNORM_2 => |p| [p[1], p[1], p[1], 255],
+            // The depth is in the lower 24 bits, and the stencil is ignored
+            TextureFormat::D24_UNORM_S8_UINT
+            | TextureFormat::D24_UNORM_S8_UINT_2
+            | TextureFormat::D24_UNORM_S8_UINT_3 => |p| [p[2], p[2], p[2], 255],
+        };
 
-                let mut offset = 0;
+        Self::decode_pixels(
+            data,
+            width * height,
+            format.bytes_per_element(),
+            decode_pixel,
+        )
+    }
 
-                for _ in 0..self.width as usize * self.height as usize * self.depth as usize {
-                    let src_b = self.data[offset];
-                    let src_g = self.data[offset + 1];
-                    let src_r = self.data[offset + 2];
-                    let src_a = self.data[offset + 3];
+    fn decode_rgba_f32(
+        format: TextureFormat,
+        data: &[u8],
+        width: usize,
+        height: usize,
+    ) -> crate::Result<Vec<f32>> {
+        fn half(p: &[u8]) -> f32 {
+            f16::from_le_bytes([p[0], p[1]]).to_f32()
+        }
 
-                    dst[offset] = src_r;
-                    dst[offset + 1] = src_g;
-                    dst[offset + 2] = src_b;
-                    dst[offset + 3] = src_a;
+        fn single(p: &[u8]) -> f32 {
+            f32::from_le_bytes([p[0], p[1], p[2], p[3]])
+        }
 
-                    offset += 4;
-                }
+        /// Unsigned floats with a 5 bit exponent, which are half floats without the sign and some of the mantissa.
+        fn small_float(bits: u32, mantissa_bits: u32) -> f32 {
+            let exponent = (bits >> mantissa_bits) & 0x1F;
+            let mantissa = bits & ((1 << mantissa_bits) - 1);
+            f16::from_bits(((exponent << 10) | (mantissa << (10 - mantissa_bits))) as u16).to_f32()
+        }
 
-                Some(dst)
+        let decode_pixel: PixelFunction<f32> = match format {
+            TextureFormat::R16_FLOAT => |p| [half(p), 0.0, 0.0, 1.0],
+            TextureFormat::R32_FLOAT => |p| [single(p), 0.0, 0.0, 1.0],
+            TextureFormat::R16G16_FLOAT => |p| [half(p), half(&p[2..]), 0.0, 1.0],
+            TextureFormat::R32G32_FLOAT => |p| [single(p), single(&p[4..]), 0.0, 1.0],
+            TextureFormat::R11G11B10_FLOAT => |p| {
+                let value = u32::from_le_bytes([p[0], p[1], p[2], p[3]]);
+                [
+                    small_float(value & 0x7FF, 6),
+                    small_float((value >> 11) & 0x7FF, 6),
+                    small_float(value >> 22, 5),
+                    1.0,
+                ]
+            },
+            TextureFormat::R16G16B16A16_FLOAT => {
+                |p| [half(p), half(&p[2..]), half(&p[4..]), half(&p[6..])]
+            }
+            TextureFormat::R32G32B32A32_FLOAT => |p| {
+                [
+                    single(p),
+                    single(&p[4..]),
+                    single(&p[8..]),
+                    single(&p[12..]),
+                ]
+            },
+            TextureFormat::BC6H_SF16 => {
+                let mut image = vec![0.0; width * height * 4];
+                decode_bc6h(data, width, height, &mut image).map_err(|reason| {
+                    crate::Error::InvalidData {
+                        reason: reason.to_string(),
+                    }
+                })?;
+                return Ok(image);
             }
-            TextureFormat::BC1_UNORM => Some(Texture::decode(
-                &self.data,
-                self.width as usize,
-                self.height as usize * self.depth as usize,
-                decode_bc1,
-            )),
-            TextureFormat::BC3_UNORM => Some(Texture::decode(
-                &self.data,
-                self.width as usize,
-                self.height as usize * self.depth as usize,
-                decode_bc3,
-            )),
-            TextureFormat::BC5_UNORM => Some(Texture::decode(
-                &self.data,
-                self.width as usize,
-                self.height as usize * self.depth as usize,
-                decode_bc5,
-            )),
-            TextureFormat::BC7_UNORM => Some(Texture::decode(
-                &self.data,
-                self.width as usize,
-                self.height as usize * self.depth as usize,
-                decode_bc7,
-            )),
             _ => {
-                println!("Unsupported texture format {:?}!", self.format);
-                None
+                return Ok(Self::decode_rgba(format, data, width, height)?
+                    .iter()
+                    .map(|x| f32::from(*x) / 255.0)
+                    .collect());
             }
+        };
+
+        Self::decode_pixels(
+            data,
+            width * height,
+            format.bytes_per_element(),
+            decode_pixel,
+        )
+    }
+
+    /// Decodes formats where each pixel is stored separately, in `pixel_size` bytes.
+    fn decode_pixels<T>(
+        data: &[u8],
+        pixel_count: usize,
+        pixel_size: usize,
+        decode_pixel: PixelFunction<T>,
+    ) -> crate::Result<Vec<T>> {
+        if data.len() < pixel_count * pixel_size {
+            return Err(crate::Error::InvalidData {
+                reason: format!(
+                    "expected {} bytes of pixel data, but only found {}",
+                    pixel_count * pixel_size,
+                    data.len()
+                ),
+            });
         }
+
+        Ok(data
+            .chunks_exact(pixel_size)
+            .take(pixel_count)
+            .flat_map(decode_pixel)
+            .collect())
     }
 }
 
@@ -313,7 +1236,7 @@ mod tests {
         d.push("grid.tex");
 
         let file = &read(d).unwrap();
-        let tex = Texture::from_existing(Platform::Win32, &file).unwrap();
+        let tex = Texture::from_existing(Platform::Win32, file).unwrap();
         assert_eq!(tex.attribute, TextureAttribute::TEXTURE_TYPE2_D);
         assert_eq!(tex.format, TextureFormat::B8G8R8A8_UNORM);
         assert_eq!(tex.width, 256);
@@ -324,4 +1247,540 @@ mod tests {
         // round-trip
         assert_eq!(*file, tex.write_to_buffer(Platform::Win32).unwrap());
     }
+
+    fn texture(format: TextureFormat, width: u16, height: u16, data: Vec<u8>) -> Texture {
+        Texture {
+            attribute: TextureAttribute::TEXTURE_TYPE2_D,
+            format,
+            width,
+            height,
+            depth: 1,
+            mip_levels: 1,
+            array_size: 0,
+            lod_offsets: [0; 3],
+            offset_to_surface: [
+                Texture::HEADER_SIZE as u32,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+            ],
+            data,
+        }
+    }
+
+    #[test]
+    fn test_decode_pixels() {
+        let decode = |format, data: &[u8]| texture(format, 1, 1, data.to_vec()).to_rgba().unwrap();
+
+        assert_eq!(
+            decode(TextureFormat::L8_UNORM, &[0x80]),
+            [128, 128, 128, 255]
+        );
+        assert_eq!(decode(TextureFormat::A8_UNORM, &[0x80]), [0, 0, 0, 128]);
+        assert_eq!(
+            decode(TextureFormat::B5G5R5A1_UNORM, &[0x1F, 0x80]),
+            [0, 0, 255, 255]
+        );
+        assert_eq!(
+            decode(
+                TextureFormat::R10G10B10A2_UNORM,
+                &0xC00FFC00u32.to_le_bytes()
+            ),
+            [0, 255, 0, 255]
+        );
+        assert_eq!(
+            decode(TextureFormat::D24_UNORM_S8_UINT, &[0, 0, 0x40, 0xFF]),
+            [64, 64, 64, 255]
+        );
+
+        // Not enough data for a single pixel
+        assert!(
+            texture(TextureFormat::R32_FLOAT, 1, 1, vec![0; 2])
+                .to_rgba()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_decode_hdr() {
+        let half = [0x4000u16, 0x3800, 0x0000, 0x3C00];
+        let tex = texture(
+            TextureFormat::R16G16B16A16_FLOAT,
+            1,
+            1,
+            half.iter().flat_map(|x| x.to_le_bytes()).collect(),
+        );
+        assert_eq!(tex.to_rgba_f32().unwrap(), [2.0, 0.5, 0.0, 1.0]);
+        // Values outside of 0-1 are clamped
+        assert_eq!(tex.to_rgba().unwrap(), [255, 128, 0, 255]);
+
+        // 1.0, 2.0 and 0.5
+        let packed = 0x3C0u32 | (0x400 << 11) | (0x1C0 << 22);
+        let tex = texture(
+            TextureFormat::R11G11B10_FLOAT,
+            1,
+            1,
+            packed.to_le_bytes().to_vec(),
+        );
+        assert_eq!(tex.to_rgba_f32().unwrap(), [1.0, 2.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn test_decode_bc2() {
+        // Red with explicit alpha, which is 0xF, 0x0 and then 0x8 for every other pixel
+        let mut block = 0x8888_8888_8888_880Fu64.to_le_bytes().to_vec();
+        block.extend_from_slice(&[0x00, 0xF8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+
+        let rgba = texture(TextureFormat::BC2_UNORM, 4, 4, block)
+            .to_rgba()
+            .unwrap();
+        assert_eq!(rgba[0..4], [255, 0, 0, 255]);
+        assert_eq!(rgba[4..8], [255, 0, 0, 0]);
+        assert_eq!(rgba[8..12], [255, 0, 0, 136]);
+    }
+
+    #[test]
+    fn test_decode_bc6h() {
+        // Mode 11 stores both endpoints directly as 10-bit signed values, and every index is zero
+        let endpoint = 248u128;
+        let negative = (1024 - 248) as u128;
+        let block = 0b00011 | (endpoint << 5) | (endpoint << 15) | (negative << 25);
+
+        let rgba = texture(TextureFormat::BC6H_SF16, 4, 4, block.to_le_bytes().to_vec())
+            .to_rgba_f32()
+            .unwrap();
+        let expected = f16::from_bits(0x3C2F).to_f32();
+        for pixel in rgba.chunks_exact(4) {
+            assert_eq!(pixel, [expected, expected, -expected, 1.0]);
+        }
+    }
+
+    #[test]
+    fn test_array_surfaces() {
+        // Two layers of 2x2 in the first mip, then two layers of 1x1 in the second
+        let colors: [[u8; 4]; 4] = [
+            [0, 0, 255, 255],
+            [0, 255, 0, 255],
+            [255, 0, 0, 255],
+            [255; 4],
+        ];
+        let mut data = Vec::new();
+        for (i, color) in colors.iter().enumerate() {
+            let pixels = if i < 2 { 4 } else { 1 };
+            data.extend(color.repeat(pixels));
+        }
+
+        let mut tex = texture(TextureFormat::B8G8R8A8_UNORM, 2, 2, data);
+        tex.attribute = TextureAttribute::TEXTURE_TYPE2_D_ARRAY;
+        tex.array_size = 2;
+        tex.mip_levels = 2;
+        tex.offset_to_surface[1] = Texture::HEADER_SIZE as u32 + 32;
+
+        let images: Vec<TextureImage> = tex.images().collect::<crate::Result<_>>().unwrap();
+        assert_eq!(images.len(), 4);
+        for (image, color) in images.iter().zip(colors) {
+            let expected = (image.width, image.height);
+            assert_eq!(expected, tex.mip_size(image.surface.mip_level));
+            assert_eq!(image.pixels[..4], [color[2], color[1], color[0], color[3]]);
+        }
+        assert_eq!(
+            images[3].surface,
+            TextureSurface {
+                mip_level: 1,
+                layer: 1,
+                slice: 0
+            }
+        );
+
+        // There are only two layers
+        let surface = TextureSurface {
+            layer: 2,
+            ..Default::default()
+        };
+        assert!(tex.surface_data(surface).is_none());
+        assert!(tex.decode_surface(surface).is_err());
+    }
+
+    #[test]
+    fn test_volume_surfaces() {
+        let mut tex = texture(TextureFormat::L8_UNORM, 1, 1, vec![10, 20, 30]);
+        tex.attribute = TextureAttribute::TEXTURE_TYPE3_D;
+        tex.depth = 3;
+
+        let slices: Vec<u8> = tex.images().map(|image| image.unwrap().pixels[0]).collect();
+        assert_eq!(slices, [10, 20, 30]);
+    }
+
+    #[test]
+    fn test_grid_mips() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/tests");
+        d.push("grid.tex");
+
+        let tex = Texture::from_existing(Platform::Win32, &read(d).unwrap()).unwrap();
+        assert_eq!(tex.surfaces().count(), 9);
+
+        let surface = TextureSurface {
+            mip_level: 3,
+            ..Default::default()
+        };
+        let image = tex.decode_surface(surface).unwrap();
+        assert_eq!((image.width, image.height), (32, 32));
+        assert_eq!(image.pixels.len(), 32 * 32 * 4);
+    }
+
+    #[test]
+    fn test_dds_grid() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/tests");
+        d.push("grid.tex");
+
+        let tex = Texture::from_existing(Platform::Win32, &read(d).unwrap()).unwrap();
+        let dds = tex.to_dds().unwrap();
+        // Magic, header and the DX10 header
+        assert_eq!(dds.len(), 4 + 124 + 20 + tex.data.len());
+        assert_eq!(dds[..4], *b"DDS ");
+
+        let imported = Texture::from_dds(&dds).unwrap();
+        assert_eq!(imported.attribute, tex.attribute);
+        assert_eq!(imported.format, tex.format);
+        assert_eq!(
+            (imported.width, imported.height, imported.mip_levels),
+            (256, 256, 9)
+        );
+        assert_eq!(imported.offset_to_surface, tex.offset_to_surface);
+        assert_eq!(imported.data, tex.data);
+    }
+
+    #[test]
+    fn test_dds_cubemap() {
+        // Each face has a 2x2 mip followed by a 1x1 one
+        let mut data = Vec::new();
+        for level in 0..2u8 {
+            for face in 0..6u8 {
+                let pixels = if level == 0 { 4 } else { 1 };
+                data.extend(std::iter::repeat_n(face * 10 + level, pixels));
+            }
+        }
+
+        let mut tex = texture(TextureFormat::L8_UNORM, 2, 2, data);
+        tex.attribute = TextureAttribute::TEXTURE_TYPE_CUBE;
+        tex.mip_levels = 2;
+        tex.offset_to_surface[1] = Texture::HEADER_SIZE as u32 + 24;
+
+        let dds = tex.to_dds().unwrap();
+        // Faces are stored with all of their mips together
+        assert_eq!(dds[148..158], [0, 0, 0, 0, 1, 10, 10, 10, 10, 11]);
+
+        let imported = Texture::from_dds(&dds).unwrap();
+        assert_eq!(imported.attribute, TextureAttribute::TEXTURE_TYPE_CUBE);
+        // L8 shares a DXGI format with R8, but it should still be preserved
+        assert_eq!(imported.format, TextureFormat::L8_UNORM);
+        assert_eq!(imported.offset_to_surface[..3], [80, 104, 0]);
+        assert_eq!(imported.data, tex.data);
+
+        // Not enough data for the last face
+        assert!(Texture::from_dds(&dds[..dds.len() - 1]).is_err());
+        assert!(Texture::from_dds(&dds[..100]).is_err());
+    }
+
+    #[test]
+    fn test_dds_legacy() {
+        let header = DdsHeader {
+            size: 124,
+            flags: dds::DDSD_CAPS | dds::DDSD_HEIGHT | dds::DDSD_WIDTH | dds::DDSD_PIXELFORMAT,
+            height: 4,
+            width: 8,
+            pitch_or_linear_size: 16,
+            depth: 0,
+            mip_map_count: 0,
+            reserved1: [0; 11],
+            pixel_format: DdsPixelFormat {
+                size: 32,
+                flags: dds::DDPF_FOURCC,
+                four_cc: u32::from_le_bytes(*b"DXT1"),
+                ..Default::default()
+            },
+            caps: dds::DDSCAPS_TEXTURE,
+            caps2: 0,
+            caps3: 0,
+            caps4: 0,
+            reserved2: 0,
+            dx10: None,
+        };
+        let mut dds = ByteBuffer::new();
+        header.write(&mut Cursor::new(&mut dds)).unwrap();
+        dds.extend((0..16).collect::<Vec<u8>>());
+
+        let tex = Texture::from_dds(&dds).unwrap();
+        assert_eq!(tex.attribute, TextureAttribute::TEXTURE_TYPE2_D);
+        assert_eq!(tex.format, TextureFormat::BC1_UNORM);
+        assert_eq!((tex.width, tex.height, tex.mip_levels), (8, 4, 1));
+        assert_eq!(tex.data.len(), 16);
+    }
+
+    #[test]
+    fn test_png_tga_round_trip() {
+        let rgba = gradient(16, 8);
+        let tex = Texture::from_rgba(16, 8, TextureFormat::B8G8R8A8_UNORM, &[&rgba]).unwrap();
+
+        let png = TextureImage::from_png(&tex.to_png().unwrap()).unwrap();
+        let tga = TextureImage::from_tga(&tex.to_tga().unwrap()).unwrap();
+        for image in [png, tga] {
+            assert_eq!((image.width, image.height), (16, 8));
+            assert_eq!(image.pixels, rgba);
+
+            let imported = Texture::from_rgba_with_mips(
+                image.width,
+                image.height,
+                TextureFormat::B8G8R8A8_UNORM,
+                &image.pixels,
+                &MipOptions::default(),
+            )
+            .unwrap();
+            assert_eq!(imported.data[..tex.data.len()], tex.data);
+        }
+    }
+
+    #[test]
+    fn test_console_tiling() {
+        let mips = [gradient(12, 4), gradient(6, 2)];
+        let tex = Texture::from_rgba(12, 4, TextureFormat::B8G8R8A8_UNORM, &mips).unwrap();
+        let linear = tex.write_to_buffer(Platform::Win32).unwrap();
+
+        // Every mip is padded to 8x8 tiles, so 16x8 and then 8x8
+        let tiled = tex.write_to_buffer(Platform::PS4).unwrap();
+        assert_eq!(tiled.len(), Texture::HEADER_SIZE + (16 * 8 + 8 * 8) * 4);
+        // The first 2x2 pixels are stored together
+        let first_pixels = |data: &[u8]| data[Texture::HEADER_SIZE..][..16].to_vec();
+        assert_eq!(first_pixels(&tiled)[8..12], tex.data[12 * 4..12 * 4 + 4]);
+
+        for platform in [Platform::PS4, Platform::PS5] {
+            let detiled =
+                Texture::from_existing(platform, &tex.write_to_buffer(platform).unwrap()).unwrap();
+            assert_eq!(detiled.offset_to_surface, tex.offset_to_surface);
+            assert_eq!(detiled.data, tex.data);
+        }
+
+        // Opting out of tiling keeps the data as-is
+        let mut untiled = Texture::from_existing(Platform::Win32, &linear).unwrap();
+        untiled.attribute |= TextureAttribute::TEXTURE_NO_TILED;
+        let untiled_data = untiled.write_to_buffer(Platform::PS4).unwrap();
+        assert_eq!(untiled_data[Texture::HEADER_SIZE..], tex.data);
+        let reread = Texture::from_existing(Platform::PS4, &untiled_data).unwrap();
+        assert_eq!(reread.data, tex.data);
+
+        // The Xbox and Switch 2 read and write linear data as-is
+        for platform in [Platform::Xbox, Platform::NS2] {
+            let written = tex.write_to_buffer(platform).unwrap();
+            assert_eq!(written[Texture::HEADER_SIZE..], tex.data);
+            let reread = Texture::from_existing(platform, &written).unwrap();
+            assert_eq!(reread.offset_to_surface, tex.offset_to_surface);
+            assert_eq!(reread.data, tex.data);
+        }
+
+        // The PS3 can't swizzle non-power of two textures
+        let swizzled = tex.write_to_buffer(Platform::PS3).unwrap();
+        assert_eq!(swizzled[Texture::HEADER_SIZE..], tex.data);
+
+        let tex =
+            Texture::from_rgba(4, 4, TextureFormat::B8G8R8A8_UNORM, &[gradient(4, 4)]).unwrap();
+        let swizzled = tex.write_to_buffer(Platform::PS3).unwrap();
+        assert_eq!(swizzled[Texture::HEADER_SIZE + 8..][..4], tex.data[16..20]);
+        let unswizzled = Texture::from_existing(Platform::PS3, &swizzled).unwrap();
+        assert_eq!(unswizzled.data, tex.data);
+    }
+
+    #[test]
+    fn test_generate_mips() {
+        // Alternating black and white columns
+        let rgba: Vec<u8> = (0..8 * 4)
+            .flat_map(|i| if i % 2 == 0 { [0, 0, 0, 255] } else { [255; 4] })
+            .collect();
+
+        let linear = MipOptions {
+            filter: MipFilter::Box,
+            srgb: false,
+            ..Default::default()
+        };
+        let mips = generate_mips(&rgba, 8, 4, &linear).unwrap();
+        let sizes: Vec<usize> = mips.iter().map(|mip| mip.len() / 4).collect();
+        assert_eq!(sizes, [32, 8, 2, 1]);
+        assert_eq!(mips[1][..4], [128, 128, 128, 255]);
+
+        // Averaging in linear space makes the result brighter once it's back in sRGB
+        let srgb = MipOptions {
+            filter: MipFilter::Box,
+            ..Default::default()
+        };
+        let mips = generate_mips(&rgba, 8, 4, &srgb).unwrap();
+        assert_eq!(mips[1][..4], [188, 188, 188, 255]);
+
+        let limited = MipOptions {
+            max_levels: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(generate_mips(&rgba, 8, 4, &limited).unwrap().len(), 2);
+
+        // The Kaiser filter should keep a solid color as-is
+        let solid = [10, 100, 200, 255].repeat(16 * 16);
+        let mips = generate_mips(&solid, 16, 16, &MipOptions::default()).unwrap();
+        for mip in mips {
+            assert!(mip.chunks_exact(4).all(|x| x == [10, 100, 200, 255]));
+        }
+    }
+
+    #[test]
+    fn test_normal_map_mips() {
+        // Normals pointing right and towards the viewer, which average to a shorter vector
+        let rgba: Vec<u8> = (0..4)
+            .flat_map(|i| {
+                if i % 2 == 0 {
+                    [255, 128, 128, 255]
+                } else {
+                    [128, 128, 255, 255]
+                }
+            })
+            .collect();
+
+        let options = MipOptions {
+            filter: MipFilter::Box,
+            normal_map: true,
+            ..Default::default()
+        };
+        let mips = generate_mips(&rgba, 2, 2, &options).unwrap();
+        let normal: Vec<f32> = mips[1][..3]
+            .iter()
+            .map(|x| f32::from(*x) / 255.0 * 2.0 - 1.0)
+            .collect();
+        let length = normal.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((length - 1.0).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_from_rgba_with_mips() {
+        let options = MipOptions {
+            power_of_two: true,
+            ..Default::default()
+        };
+        let tex =
+            Texture::from_rgba_with_mips(5, 3, TextureFormat::BC1_UNORM, &gradient(5, 3), &options)
+                .unwrap();
+        assert_eq!((tex.width, tex.height), (4, 4));
+        assert_eq!(tex.mip_levels, 3);
+        assert_eq!(tex.offset_to_surface[..4], [80, 88, 96, 0]);
+
+        let (rgba, width, height) =
+            resize_to_power_of_two(&gradient(5, 3), 5, 3, &options).unwrap();
+        assert_eq!((width, height, rgba.len()), (4, 4, 64));
+        assert!(resize(&rgba, 4, 4, 0, 2, &options).is_err());
+    }
+
+    /// A smooth gradient with varying alpha, which every encoder should handle well.
+    fn gradient(width: usize, height: usize) -> Vec<u8> {
+        (0..width * height)
+            .flat_map(|i| {
+                let (x, y) = (i % width, i / width);
+                [
+                    (x * 12) as u8,
+                    (y * 20) as u8,
+                    255 - (x * 6) as u8,
+                    128 + (y * 8) as u8,
+                ]
+            })
+            .collect()
+    }
+
+    fn mean_difference(a: &[u8], b: &[u8], channels: &[usize]) -> f32 {
+        let total: u32 = a
+            .chunks_exact(4)
+            .zip(b.chunks_exact(4))
+            .flat_map(|(a, b)| channels.iter().map(|c| u32::from(a[*c].abs_diff(b[*c]))))
+            .sum();
+        total as f32 / (a.len() / 4 * channels.len()) as f32
+    }
+
+    #[test]
+    fn test_from_rgba() {
+        let formats: [(TextureFormat, &[usize], f32); 6] = [
+            (TextureFormat::B8G8R8A8_UNORM, &[0, 1, 2, 3], 0.0),
+            (TextureFormat::BC1_UNORM, &[0, 1, 2], 8.0),
+            (TextureFormat::BC3_UNORM, &[0, 1, 2, 3], 6.0),
+            (TextureFormat::BC4_UNORM, &[0], 1.0),
+            (TextureFormat::BC5_UNORM, &[0, 1], 1.5),
+            (TextureFormat::BC7_UNORM, &[0, 1, 2, 3], 3.5),
+        ];
+
+        // Not a multiple of the block size on purpose
+        let mips = [gradient(18, 10), gradient(9, 5)];
+        for (format, channels, tolerance) in formats {
+            let tex = Texture::from_rgba(18, 10, format, &mips).unwrap();
+            assert_eq!(tex.attribute, TextureAttribute::TEXTURE_TYPE2_D);
+            assert_eq!(tex.mip_levels, 2);
+            assert_eq!(tex.lod_offsets, [0, 1, 1]);
+            assert_eq!(tex.offset_to_surface[0], 80);
+            assert_eq!(
+                tex.mip_data(0),
+                Some((0, tex.offset_to_surface[1] as usize - 80))
+            );
+
+            let buffer = tex.write_to_buffer(Platform::Win32).unwrap();
+            let tex = Texture::from_existing(Platform::Win32, &buffer).unwrap();
+            assert_eq!(tex.format, format);
+            assert_eq!(tex.mip_size(1), (9, 5));
+
+            let rgba = tex.to_rgba().unwrap();
+            assert!(
+                mean_difference(&rgba, &mips[0], channels) <= tolerance,
+                "{format:?} is too lossy"
+            );
+        }
+    }
+
+    #[test]
+    fn test_compression_quality() {
+        // Noise is hard to compress, so the extra effort should show
+        let mut state = 1u32;
+        let rgba: Vec<u8> = (0..16 * 16 * 4)
+            .map(|_| {
+                state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                (state >> 24) as u8
+            })
+            .collect();
+
+        let error = |quality| {
+            let data = crate::bcn::encode_bc7(&rgba, 16, 16, quality).unwrap();
+            let tex = texture(TextureFormat::BC7_UNORM, 16, 16, data);
+            tex.to_rgba()
+                .unwrap()
+                .iter()
+                .zip(&rgba)
+                .map(|(a, b)| (i32::from(*a) - i32::from(*b)).pow(2))
+                .sum::<i32>()
+        };
+
+        let fast = error(CompressionQuality::Fast);
+        let normal = error(CompressionQuality::Normal);
+        let high = error(CompressionQuality::High);
+        assert!(normal <= fast);
+        assert!(high <= normal);
+    }
+
+    #[test]
+    fn test_from_rgba_invalid() {
+        // The second mip should be 2x2
+        let mips = [vec![0; 4 * 4 * 4], vec![0; 4]];
+        assert!(Texture::from_rgba(4, 4, TextureFormat::BC1_UNORM, &mips).is_err());
+        assert!(Texture::from_rgba(4, 4, TextureFormat::D16_UNORM, &mips[..1]).is_err());
+        assert!(Texture::from_rgba::<Vec<u8>>(4, 4, TextureFormat::BC1_UNORM, &[]).is_err());
+    }
 }
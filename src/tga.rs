@@ -0,0 +1,288 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! A minimal Truevision TGA reader and writer, for the same reasons as the PNG one.
+
+use std::io::Cursor;
+
+use binrw::{BinRead, BinWrite, binrw};
+
+use crate::ByteBuffer;
+
+const IMAGE_TYPE_COLOR_MAPPED: u8 = 1;
+const IMAGE_TYPE_TRUE_COLOR: u8 = 2;
+const IMAGE_TYPE_GRAYSCALE: u8 = 3;
+/// Added to the image type when the pixels are run-length encoded.
+const IMAGE_TYPE_RLE: u8 = 8;
+
+/// Set in the image descriptor when the first row is the top of the image, instead of the bottom.
+const DESCRIPTOR_TOP_TO_BOTTOM: u8 = 0x20;
+/// Set in the image descriptor when each row starts from the right of the image.
+const DESCRIPTOR_RIGHT_TO_LEFT: u8 = 0x10;
+/// The number of alpha bits in each pixel.
+const DESCRIPTOR_ALPHA_BITS: u8 = 0xF;
+
+#[binrw]
+#[brw(little)]
+#[derive(Debug)]
+struct TgaHeader {
+    id_length: u8,
+    color_map_type: u8,
+    image_type: u8,
+    color_map_first: u16,
+    color_map_length: u16,
+    color_map_depth: u8,
+    x_origin: u16,
+    y_origin: u16,
+    width: u16,
+    height: u16,
+    pixel_depth: u8,
+    descriptor: u8,
+}
+
+/// Encodes `rgba` (8 bits per channel) as an uncompressed 32-bit TGA image.
+pub(crate) fn write_tga(width: u16, height: u16, rgba: &[u8]) -> crate::Result<ByteBuffer> {
+    let header = TgaHeader {
+        id_length: 0,
+        color_map_type: 0,
+        image_type: IMAGE_TYPE_TRUE_COLOR,
+        color_map_first: 0,
+        color_map_length: 0,
+        color_map_depth: 0,
+        x_origin: 0,
+        y_origin: 0,
+        width,
+        height,
+        pixel_depth: 32,
+        descriptor: DESCRIPTOR_TOP_TO_BOTTOM | 8,
+    };
+
+    let mut output = ByteBuffer::new();
+    header.write(&mut Cursor::new(&mut output))?;
+    output.extend(
+        rgba.chunks_exact(4)
+            .take(width as usize * height as usize)
+            .flat_map(|x| [x[2], x[1], x[0], x[3]]),
+    );
+
+    Ok(output)
+}
+
+/// Decodes a TGA image to 8-bit RGBA, returning its width, height and pixels.
+///
+/// Color-mapped, true color and grayscale images are supported, with or without run-length encoding.
+pub(crate) fn read_tga(buffer: &[u8]) -> crate::Result<(u16, u16, Vec<u8>)> {
+    let invalid = |reason: &str| crate::Error::InvalidData {
+        reason: format!("invalid TGA: {reason}"),
+    };
+
+    let mut cursor = Cursor::new(buffer);
+    let header = TgaHeader::read(&mut cursor)?;
+    let mut position = cursor.position() as usize + header.id_length as usize;
+
+    let alpha_bits = header.descriptor & DESCRIPTOR_ALPHA_BITS;
+    let image_type = header.image_type & !IMAGE_TYPE_RLE;
+    let pixel_bytes = (header.pixel_depth as usize).div_ceil(8);
+    match (image_type, header.pixel_depth) {
+        (IMAGE_TYPE_COLOR_MAPPED, 8 | 16)
+        | (IMAGE_TYPE_TRUE_COLOR, 15 | 16 | 24 | 32)
+        | (IMAGE_TYPE_GRAYSCALE, 8 | 16) => {}
+        _ => return Err(invalid("unsupported image type or pixel depth")),
+    }
+    if header.width == 0 || header.height == 0 {
+        return Err(invalid("image dimensions can't be zero"));
+    }
+
+    let mut color_map = Vec::new();
+    if header.color_map_type == 1 {
+        let entry_bytes = (header.color_map_depth as usize).div_ceil(8);
+        if !matches!(header.color_map_depth, 15 | 16 | 24 | 32) {
+            return Err(invalid("unsupported color map depth"));
+        }
+        let size = header.color_map_length as usize * entry_bytes;
+        let entries = buffer
+            .get(position..position + size)
+            .ok_or_else(|| invalid("truncated color map"))?;
+        position += size;
+
+        for entry in entries.chunks_exact(entry_bytes) {
+            color_map.push(true_color(entry, header.color_map_depth, alpha_bits).unwrap());
+        }
+    }
+
+    let pixel_count = header.width as usize * header.height as usize;
+    let pixels = if header.image_type & IMAGE_TYPE_RLE != 0 {
+        // Each byte left can expand to at most a full run, so don't trust the header for more than that
+        let remaining = buffer.len().saturating_sub(position);
+        let mut pixels = Vec::with_capacity(
+            (pixel_count * pixel_bytes).min(remaining.saturating_mul(128 * pixel_bytes)),
+        );
+        while pixels.len() < pixel_count * pixel_bytes {
+            let packet = *buffer
+                .get(position)
+                .ok_or_else(|| invalid("truncated pixel data"))?;
+            let count = (packet & 0x7F) as usize + 1;
+            position += 1;
+
+            // Run-length packets repeat a single pixel, raw packets store each one
+            let size = if packet & 0x80 != 0 {
+                pixel_bytes
+            } else {
+                count * pixel_bytes
+            };
+            let data = buffer
+                .get(position..position + size)
+                .ok_or_else(|| invalid("truncated pixel data"))?;
+            position += size;
+
+            if packet & 0x80 != 0 {
+                pixels.extend(data.repeat(count));
+            } else {
+                pixels.extend_from_slice(data);
+            }
+        }
+        pixels.truncate(pixel_count * pixel_bytes);
+        pixels
+    } else {
+        buffer
+            .get(position..position + pixel_count * pixel_bytes)
+            .ok_or_else(|| invalid("truncated pixel data"))?
+            .to_vec()
+    };
+
+    let (width, height) = (header.width as usize, header.height as usize);
+    let mut rgba = vec![0; pixel_count * 4];
+    for (i, pixel) in pixels.chunks_exact(pixel_bytes).enumerate() {
+        let color = match image_type {
+            IMAGE_TYPE_COLOR_MAPPED => {
+                let index = if pixel_bytes == 2 {
+                    u16::from_le_bytes([pixel[0], pixel[1]])
+                } else {
+                    u16::from(pixel[0])
+                };
+                index
+                    .checked_sub(header.color_map_first)
+                    .and_then(|index| color_map.get(index as usize).copied())
+                    .ok_or_else(|| invalid("color map index out of range"))?
+            }
+            IMAGE_TYPE_GRAYSCALE => {
+                let alpha = if pixel_bytes == 2 { pixel[1] } else { 255 };
+                [pixel[0], pixel[0], pixel[0], alpha]
+            }
+            _ => true_color(pixel, header.pixel_depth, alpha_bits).unwrap(),
+        };
+
+        // Put the pixel where it belongs, since rows are stored from the bottom up unless stated otherwise
+        let (mut x, mut y) = (i % width, i / width);
+        if header.descriptor & DESCRIPTOR_RIGHT_TO_LEFT != 0 {
+            x = width - 1 - x;
+        }
+        if header.descriptor & DESCRIPTOR_TOP_TO_BOTTOM == 0 {
+            y = height - 1 - y;
+        }
+        let offset = (y * width + x) * 4;
+        rgba[offset..offset + 4].copy_from_slice(&color);
+    }
+
+    Ok((header.width, header.height, rgba))
+}
+
+/// Converts a little-endian BGR(A) color with `depth` bits to RGBA.
+fn true_color(data: &[u8], depth: u8, alpha_bits: u8) -> Option<[u8; 4]> {
+    Some(match depth {
+        15 | 16 => {
+            let value = u16::from_le_bytes([data[0], data[1]]);
+            let expand = |x: u16| ((x & 0x1F) * 255 / 31) as u8;
+            let alpha = if depth == 16 && alpha_bits != 0 && value & 0x8000 == 0 {
+                0
+            } else {
+                255
+            };
+            [
+                expand(value >> 10),
+                expand(value >> 5),
+                expand(value),
+                alpha,
+            ]
+        }
+        24 => [data[2], data[1], data[0], 255],
+        32 => [
+            data[2],
+            data[1],
+            data[0],
+            if alpha_bits != 0 { data[3] } else { 255 },
+        ],
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tga_round_trip() {
+        let rgba = [255, 0, 0, 255, 0, 255, 0, 128, 0, 0, 255, 0];
+        let tga = write_tga(3, 1, &rgba).unwrap();
+        assert_eq!(tga.len(), 18 + 12);
+        assert_eq!(read_tga(&tga).unwrap(), (3, 1, rgba.to_vec()));
+    }
+
+    #[test]
+    fn test_read_rle_bottom_up() {
+        let header = TgaHeader {
+            id_length: 0,
+            color_map_type: 0,
+            image_type: IMAGE_TYPE_TRUE_COLOR | IMAGE_TYPE_RLE,
+            color_map_first: 0,
+            color_map_length: 0,
+            color_map_depth: 0,
+            x_origin: 0,
+            y_origin: 0,
+            width: 2,
+            height: 2,
+            pixel_depth: 24,
+            descriptor: 0,
+        };
+        let mut tga = ByteBuffer::new();
+        header.write(&mut Cursor::new(&mut tga)).unwrap();
+        // Two blue pixels in a run, and then a raw red and green pixel
+        tga.extend_from_slice(&[0x81, 255, 0, 0, 0x01, 0, 0, 255, 0, 255, 0]);
+
+        let (_, _, rgba) = read_tga(&tga).unwrap();
+        // The bottom row comes first
+        assert_eq!(
+            rgba,
+            [
+                255, 0, 0, 255, 0, 255, 0, 255, //
+                0, 0, 255, 255, 0, 0, 255, 255
+            ]
+        );
+
+        assert!(read_tga(&tga[..tga.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_read_rle_huge_header() {
+        let header = TgaHeader {
+            id_length: 0,
+            color_map_type: 0,
+            image_type: IMAGE_TYPE_TRUE_COLOR | IMAGE_TYPE_RLE,
+            color_map_first: 0,
+            color_map_length: 0,
+            color_map_depth: 0,
+            x_origin: 0,
+            y_origin: 0,
+            width: u16::MAX,
+            height: u16::MAX,
+            pixel_depth: 32,
+            descriptor: 0,
+        };
+        let mut tga = ByteBuffer::new();
+        header.write(&mut Cursor::new(&mut tga)).unwrap();
+        // A single run of blue pixels, nowhere near enough for the whole image
+        tga.extend_from_slice(&[0xFF, 255, 0, 0, 255]);
+
+        assert!(read_tga(&tga).is_err());
+    }
+}
@@ -0,0 +1,208 @@
+// SPDX-FileCopyrightText: 2026 Joshua Goins <josh@redstrate.com>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Converting texture surfaces between the linear layout used on Windows and the tiled layouts used by consoles.
+//!
+//! Everything here works on "elements", which are pixels for uncompressed formats and 4x4 blocks for compressed ones.
+
+use crate::common::Platform;
+
+/// Width and height of a tile on the PS4 and PS5, in elements.
+const TILE_SIZE: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Tiling {
+    /// Rows are stored one after another.
+    Linear,
+    /// The whole surface is stored in Z-order, where the bits of the X and Y coordinates are interleaved. Used on the PS3.
+    Morton,
+    /// The surface is split up into 8x8 tiles stored row by row, and the elements inside each tile are in Z-order. Used on the PS4 and PS5.
+    Tiled,
+}
+
+impl Tiling {
+    /// Returns how `platform` lays out a surface that's `width` by `height` elements.
+    ///
+    /// `swizzled` and `tiled` come from the texture attributes, which can opt out of either.
+    pub(crate) fn new(
+        platform: Platform,
+        block_compressed: bool,
+        width: usize,
+        height: usize,
+        swizzled: bool,
+        tiled: bool,
+    ) -> Self {
+        match platform {
+            // The RSX can only swizzle uncompressed textures with power of two dimensions
+            Platform::PS3
+                if swizzled
+                    && !block_compressed
+                    && width.is_power_of_two()
+                    && height.is_power_of_two() =>
+            {
+                Tiling::Morton
+            }
+            Platform::PS4 | Platform::PS5 if tiled => Tiling::Tiled,
+            _ => Tiling::Linear,
+        }
+    }
+
+    /// Returns the size in bytes of a surface in this layout, which can be larger than the linear size because of padding.
+    pub(crate) fn surface_size(&self, width: usize, height: usize, element_size: usize) -> usize {
+        match self {
+            Tiling::Linear | Tiling::Morton => width * height * element_size,
+            Tiling::Tiled => {
+                width.next_multiple_of(TILE_SIZE)
+                    * height.next_multiple_of(TILE_SIZE)
+                    * element_size
+            }
+        }
+    }
+
+    /// Returns the index of the element at `x` and `y` in this layout.
+    fn element_index(&self, x: usize, y: usize, width: usize, height: usize) -> usize {
+        match self {
+            Tiling::Linear => y * width + x,
+            Tiling::Morton => morton(x, y, width, height),
+            Tiling::Tiled => {
+                let tiles_per_row = width.div_ceil(TILE_SIZE);
+                let tile = (y / TILE_SIZE) * tiles_per_row + x / TILE_SIZE;
+                tile * TILE_SIZE * TILE_SIZE
+                    + morton(x % TILE_SIZE, y % TILE_SIZE, TILE_SIZE, TILE_SIZE)
+            }
+        }
+    }
+
+    /// Converts a surface in this layout to a linear one.
+    pub(crate) fn detile(
+        &self,
+        data: &[u8],
+        width: usize,
+        height: usize,
+        element_size: usize,
+    ) -> Vec<u8> {
+        let mut output = vec![0; width * height * element_size];
+        self.copy_elements(width, height, element_size, |linear, tiled| {
+            output[linear..linear + element_size]
+                .copy_from_slice(&data[tiled..tiled + element_size]);
+        });
+        output
+    }
+
+    /// Converts a linear surface to this layout, leaving any padding zeroed.
+    pub(crate) fn tile(
+        &self,
+        data: &[u8],
+        width: usize,
+        height: usize,
+        element_size: usize,
+    ) -> Vec<u8> {
+        let mut output = vec![0; self.surface_size(width, height, element_size)];
+        self.copy_elements(width, height, element_size, |linear, tiled| {
+            output[tiled..tiled + element_size]
+                .copy_from_slice(&data[linear..linear + element_size]);
+        });
+        output
+    }
+
+    /// Calls `copy` with the byte offset of each element in the linear and tiled layouts.
+    fn copy_elements(
+        &self,
+        width: usize,
+        height: usize,
+        element_size: usize,
+        mut copy: impl FnMut(usize, usize),
+    ) {
+        for y in 0..height {
+            for x in 0..width {
+                let tiled = self.element_index(x, y, width, height) * element_size;
+                copy((y * width + x) * element_size, tiled);
+            }
+        }
+    }
+}
+
+/// Interleaves the bits of `x` and `y`, starting with `x`. Once the smaller dimension runs out of bits, the rest come from the larger one.
+fn morton(x: usize, y: usize, width: usize, height: usize) -> usize {
+    let (mut x, mut y) = (x, y);
+    let (mut width, mut height) = (width, height);
+
+    let mut index = 0;
+    let mut bit = 0;
+    while width > 1 || height > 1 {
+        if width > 1 {
+            index |= (x & 1) << bit;
+            x >>= 1;
+            width >>= 1;
+            bit += 1;
+        }
+        if height > 1 {
+            index |= (y & 1) << bit;
+            y >>= 1;
+            height >>= 1;
+            bit += 1;
+        }
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_morton() {
+        // The first 2x2 square, and then the next one to the right
+        let order: Vec<usize> = [(0, 0), (1, 0), (0, 1), (1, 1), (2, 0), (3, 0)]
+            .iter()
+            .map(|(x, y)| morton(*x, *y, 4, 4))
+            .collect();
+        assert_eq!(order, [0, 1, 2, 3, 4, 5]);
+
+        // Once Y runs out of bits, X fills in the rest
+        assert_eq!(morton(2, 0, 4, 1), 2);
+        assert_eq!(morton(1, 1, 4, 2), 3);
+        assert_eq!(morton(2, 1, 4, 2), 6);
+    }
+
+    #[test]
+    fn test_tiled_round_trip() {
+        let linear: Vec<u8> = (0..10 * 3 * 2).map(|x| x as u8).collect();
+        let tiled = Tiling::Tiled.tile(&linear, 10, 3, 2);
+        // Padded up to two 8x8 tiles
+        assert_eq!(tiled.len(), 16 * 8 * 2);
+        // The first element of the second tile is X = 8
+        assert_eq!(tiled[128..130], linear[16..18]);
+        assert_eq!(Tiling::Tiled.detile(&tiled, 10, 3, 2), linear);
+    }
+
+    #[test]
+    fn test_platform_tiling() {
+        assert_eq!(
+            Tiling::new(Platform::PS3, false, 16, 8, true, true),
+            Tiling::Morton
+        );
+        assert_eq!(
+            Tiling::new(Platform::PS3, false, 12, 8, true, true),
+            Tiling::Linear
+        );
+        assert_eq!(
+            Tiling::new(Platform::PS3, true, 16, 8, true, true),
+            Tiling::Linear
+        );
+        assert_eq!(
+            Tiling::new(Platform::PS5, true, 3, 3, true, true),
+            Tiling::Tiled
+        );
+        assert_eq!(
+            Tiling::new(Platform::PS4, true, 3, 3, true, false),
+            Tiling::Linear
+        );
+        for platform in [Platform::Win32, Platform::Xbox, Platform::NS2] {
+            assert_eq!(
+                Tiling::new(platform, false, 16, 16, true, true),
+                Tiling::Linear
+            );
+        }
+    }
+}